@@ -0,0 +1,267 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::app_config_dir;
+use crate::app_state::AppState;
+use crate::pagination::paragraph_pages;
+use crate::private_books;
+
+// Past this size the log drops its older half, so it stays quick to read whole.
+const MAX_LOG_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    BookOpened,
+    WordSaved,
+    PageTranslated,
+    HighlightAdded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    at: DateTime<Utc>,
+    kind: ActivityKind,
+    #[serde(default)]
+    book_id: Option<String>,
+    #[serde(default)]
+    page: Option<u32>,
+    // The word saved, the highlighted text and the like.
+    #[serde(default)]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelineEntry {
+    #[serde(flatten)]
+    event: ActivityEvent,
+    book_title: Option<String>,
+}
+
+// One day of the timeline, newest event first.
+#[derive(Debug, Serialize)]
+pub struct TimelineDay {
+    date: NaiveDate,
+    entries: Vec<TimelineEntry>,
+}
+
+// Both ends optional and inclusive.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct ActivityPeriod {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+// Serializes appends, so lines from concurrent events never interleave.
+#[derive(Default)]
+pub struct EventLog {
+    lock: Mutex<()>,
+}
+
+fn log_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("activity_log.jsonl"))
+}
+
+fn append(handle: &tauri::AppHandle, events: &[ActivityEvent]) -> Result<(), String> {
+    let path = log_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event).map_err(|e| e.to_string())?);
+        lines.push('\n');
+    }
+    let log = handle.state::<EventLog>();
+    let _lock = log.lock.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = OpenOptions::new().create(true).append(true).open(&path).map_err(|e| e.to_string())?;
+    file.write_all(lines.as_bytes()).map_err(|e| e.to_string())?;
+    if file.metadata().is_ok_and(|metadata| metadata.len() > MAX_LOG_BYTES) {
+        drop(file);
+        let mut events = load_events(handle)?;
+        events.drain(..events.len() / 2);
+        write_events(&path, &events)?;
+    }
+    Ok(())
+}
+
+// Events are one JSON object per line, oldest first. A line that does not parse, such as one
+// cut short by a crash, is skipped rather than losing the rest of the log.
+fn load_events(handle: &tauri::AppHandle) -> Result<Vec<ActivityEvent>, String> {
+    let path = log_file_path(handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let mut skipped = 0;
+    let events = data
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let event = serde_json::from_str(line).ok();
+            if event.is_none() {
+                skipped += 1;
+            }
+            event
+        })
+        .collect();
+    if skipped > 0 {
+        log::warn!("Skipped {} unreadable lines in {}", skipped, path.display());
+    }
+    Ok(events)
+}
+
+fn write_events(path: &Path, events: &[ActivityEvent]) -> Result<(), String> {
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event).map_err(|e| e.to_string())?);
+        lines.push('\n');
+    }
+    let partial = path.with_extension("jsonl.tmp");
+    fs::write(&partial, lines).map_err(|e| e.to_string())?;
+    fs::rename(&partial, path).map_err(|e| e.to_string())
+}
+
+// Rewrites the whole log, holding off appends meanwhile.
+fn rewrite(handle: &tauri::AppHandle, f: impl FnOnce(&mut Vec<ActivityEvent>)) -> Result<(), String> {
+    let log = handle.state::<EventLog>();
+    let _lock = log.lock.lock().unwrap_or_else(|e| e.into_inner());
+    let mut events = load_events(handle)?;
+    f(&mut events);
+    write_events(&log_file_path(handle)?, &events)
+}
+
+// A book's events, so they can be sealed with its other private data.
+pub fn book_events(handle: &tauri::AppHandle, book_id: &str) -> Result<Vec<ActivityEvent>, String> {
+    let mut events = load_events(handle)?;
+    events.retain(|event| event.book_id.as_deref() == Some(book_id));
+    Ok(events)
+}
+
+// Drops a book's events up to `until`, once they are sealed.
+pub fn remove_book_events(handle: &tauri::AppHandle, book_id: &str, until: DateTime<Utc>) -> Result<(), String> {
+    rewrite(handle, |events| events.retain(|event| event.book_id.as_deref() != Some(book_id) || event.at > until))
+}
+
+// Puts sealed events back in time order.
+pub fn restore_events(handle: &tauri::AppHandle, restored: Vec<ActivityEvent>) -> Result<(), String> {
+    if restored.is_empty() {
+        return Ok(());
+    }
+    rewrite(handle, |events| {
+        events.extend(restored);
+        events.sort_by_key(|event| event.at);
+    })
+}
+
+// Adds an event to the log. Logging never fails the action it records.
+pub fn record(handle: &tauri::AppHandle, kind: ActivityKind, book_id: Option<&str>, detail: Option<&str>) {
+    let event = ActivityEvent {
+        at: Utc::now(),
+        kind,
+        book_id: book_id.map(str::to_string),
+        page: None,
+        detail: detail.map(str::to_string),
+    };
+    if let Err(e) = append(handle, &[event]) {
+        log::error!("Failed to record activity: {}", e);
+    }
+}
+
+// The page a sid belongs to, from its `<doc_id>:p<page>:` prefix.
+fn sid_page(sid: &str) -> Option<(&str, u32)> {
+    let mut parts = sid.split(':');
+    let doc_id = parts.next()?;
+    let page = parts.next()?.strip_prefix('p')?.parse().ok()?;
+    Some((doc_id, page))
+}
+
+// One `page_translated` event per page among newly translated sids, on the page each sid is
+// on now for documents paged here.
+pub fn record_translated_pages<'a>(handle: &tauri::AppHandle, sids: impl IntoIterator<Item = &'a str>) {
+    let mut paged: HashMap<&str, Option<HashMap<String, u32>>> = HashMap::new();
+    let pages: BTreeSet<(&str, u32)> = sids
+        .into_iter()
+        .filter_map(|sid| {
+            let (doc_id, page) = sid_page(sid)?;
+            let current = paged.entry(doc_id).or_insert_with(|| paragraph_pages(handle, doc_id));
+            // Sentence-mode sids are the paragraph's with `#s<n>` added.
+            let paragraph = sid.split('#').next().unwrap_or(sid);
+            Some((doc_id, current.as_ref().and_then(|pages| pages.get(paragraph)).copied().unwrap_or(page)))
+        })
+        .collect();
+    if pages.is_empty() {
+        return;
+    }
+    let at = Utc::now();
+    let events: Vec<ActivityEvent> = pages
+        .into_iter()
+        .map(|(book_id, page)| ActivityEvent {
+            at,
+            kind: ActivityKind::PageTranslated,
+            book_id: Some(book_id.to_string()),
+            page: Some(page),
+            detail: None,
+        })
+        .collect();
+    if let Err(e) = append(handle, &events) {
+        log::error!("Failed to record activity: {}", e);
+    }
+}
+
+// Records an event the frontend owns, such as a highlight being added.
+#[tauri::command(rename_all = "camelCase")]
+pub fn log_activity(
+    handle: tauri::AppHandle,
+    kind: ActivityKind,
+    book_id: Option<String>,
+    page: Option<u32>,
+    detail: Option<String>,
+) -> Result<(), String> {
+    let event = ActivityEvent { at: Utc::now(), kind, book_id, page, detail };
+    append(&handle, &[event])
+}
+
+// The activity log by day, newest first, for one book or all of them, within `period`. Backs
+// the reading journal, and shows what happened in which order when sync or merges go wrong.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_activity_timeline(
+    handle: tauri::AppHandle,
+    book_id: Option<String>,
+    period: Option<ActivityPeriod>,
+) -> Result<Vec<TimelineDay>, String> {
+    let period = period.unwrap_or_default();
+    // Private books are sealed with their events when locked; any logged since stay hidden.
+    let unlocked = private_books::is_unlocked(&handle);
+    let (titles, hidden): (HashMap<String, String>, HashSet<String>) =
+        handle.state::<AppState>().recent_books.read(&handle, |data| {
+            let titles = data.books.iter().map(|b| (b.id.clone(), b.title.clone())).collect();
+            let hidden = data.books.iter().filter(|b| b.private_lock && !unlocked).map(|b| b.id.clone()).collect();
+            (titles, hidden)
+        })?;
+    let mut days: Vec<TimelineDay> = Vec::new();
+    for event in load_events(&handle)?.into_iter().rev() {
+        if book_id.as_ref().is_some_and(|id| event.book_id.as_ref() != Some(id))
+            || event.book_id.as_ref().is_some_and(|id| hidden.contains(id))
+            || period.from.is_some_and(|from| event.at < from)
+            || period.to.is_some_and(|to| event.at > to)
+        {
+            continue;
+        }
+        let date = event.at.date_naive();
+        let book_title = event.book_id.as_ref().and_then(|id| titles.get(id).cloned());
+        let entry = TimelineEntry { event, book_title };
+        match days.last_mut() {
+            Some(day) if day.date == date => day.entries.push(entry),
+            _ => days.push(TimelineDay { date, entries: vec![entry] }),
+        }
+    }
+    Ok(days)
+}
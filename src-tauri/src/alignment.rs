@@ -0,0 +1,489 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::book_text::{load_book_text, PageParagraph};
+use crate::encryption::{read_store_file, write_store_file};
+use crate::epub::{chapter_text, spine_hrefs};
+use crate::granularity::{sentence_separator, split_sentences};
+use crate::language::detect_language;
+use crate::quarantine::parse_or_quarantine;
+use crate::segmentation::segment;
+use crate::{
+    book_data_file_path, cache_target, read_cache, translation_cache_key, update_cache, GenerationInfo, TargetLanguage,
+    TranslateSentence,
+};
+
+// Gale–Church bead types as (source sentences, target sentences, prior probability).
+const BEADS: [(usize, usize, f64); 6] = [
+    (1, 1, 0.89),
+    (1, 0, 0.0099 / 2.0),
+    (0, 1, 0.0099 / 2.0),
+    (2, 1, 0.089 / 2.0),
+    (1, 2, 0.089 / 2.0),
+    (2, 2, 0.011),
+];
+// Variance of the target/source length difference per source character (Gale & Church).
+const LENGTH_VARIANCE: f64 = 6.8;
+// How far either side of the diagonal the alignment may wander, in sentences.
+const MIN_BAND: usize = 50;
+const MAX_BAND: usize = 200;
+// Most cells the alignment table may hold (16 bytes each); longer books get a narrower band.
+const MAX_CELLS: usize = 4_000_000;
+// Beads less likely than this under the length model are reported but not seeded.
+const MIN_SEED_CONFIDENCE: f64 = 0.2;
+// Recorded as the provider of seeded cache entries.
+const ALIGNED_PROVIDER: &str = "aligned translation";
+
+// One alignment bead: source sentences and the target sentences they were matched with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignedPair {
+    // Source sentence sids, `<paragraph sid>#s<n>` where a paragraph splits into several;
+    // empty for target text matched with nothing.
+    pub sids: Vec<String>,
+    pub source: String,
+    pub target: String,
+    // How well the two lengths agree, from 0 to 1.
+    pub confidence: f64,
+}
+
+// The last alignment of a book with a translation file.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct BookAlignment {
+    pub translation_file: String,
+    pub model: String,
+    pub target_code: String,
+    pub aligned_at: Option<DateTime<Utc>>,
+    pub pairs: Vec<AlignedPair>,
+}
+
+// One row of the side-by-side view.
+#[derive(Debug, Serialize)]
+pub struct AlignmentRow {
+    sids: Vec<String>,
+    source: String,
+    // None where nothing is cached yet.
+    target: Option<String>,
+    // Only for rows from an aligned translation; cached model translations are paired by sid.
+    confidence: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlignmentReport {
+    source_sentences: usize,
+    target_sentences: usize,
+    pairs: usize,
+    mean_confidence: f64,
+    seeded_paragraphs: usize,
+    seeded_sentences: usize,
+}
+
+// Source sentences, target sentences and how well their lengths agree.
+type Bead = (Range<usize>, Range<usize>, f64);
+
+struct SourceSentence {
+    sid: String,
+    paragraph: usize,
+    text: String,
+}
+
+struct SourceParagraph {
+    sid: String,
+    text: String,
+}
+
+fn alignment_file_path(handle: &tauri::AppHandle, book_id: &str) -> Result<PathBuf, String> {
+    book_data_file_path(handle, "alignments", book_id)
+}
+
+fn load_alignment(handle: &tauri::AppHandle, book_id: &str) -> Result<BookAlignment, String> {
+    let path = alignment_file_path(handle, book_id)?;
+    if !path.exists() {
+        return Ok(BookAlignment::default());
+    }
+    let data = read_store_file(handle, &path)?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_alignment(handle: &tauri::AppHandle, book_id: &str, alignment: &BookAlignment) -> Result<(), String> {
+    let path = alignment_file_path(handle, book_id)?;
+    let data = serde_json::to_string(alignment).map_err(|e| e.to_string())?;
+    write_store_file(handle, &path, &data)
+}
+
+// The translation's paragraphs: an EPUB's blocks in spine order, or a text file's lines.
+fn translation_paragraphs(path: &Path) -> Result<Vec<String>, String> {
+    let is_epub = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("epub")) || path.is_dir();
+    let text = if is_epub {
+        let mut chapters = Vec::new();
+        for href in spine_hrefs(path)? {
+            chapters.push(chapter_text(path, &href)?);
+        }
+        chapters.join("\n")
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+    };
+    Ok(text.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string).collect())
+}
+
+fn language_of(paragraphs: &[&str]) -> String {
+    let sample: Vec<&str> = paragraphs.iter().take(50).copied().collect();
+    detect_language(&sample.join(" ")).unwrap_or_else(|| "en".to_string())
+}
+
+// The book's paragraphs and their sentences, split as sentence-mode translation splits them so
+// seeded sentence entries are the ones the reader looks up.
+fn source_sentences(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+) -> Result<(Vec<SourceParagraph>, Vec<SourceSentence>), String> {
+    let text = load_book_text(handle, book_id)?;
+    let paragraphs: Vec<SourceParagraph> = text
+        .pages
+        .iter()
+        .flat_map(|page| &page.paragraphs)
+        .filter(|p| !p.kind.is_verbatim())
+        .map(|p| SourceParagraph { sid: p.sid.clone(), text: p.text.clone() })
+        .collect();
+    let units: Vec<TranslateSentence> = paragraphs
+        .iter()
+        .map(|p| TranslateSentence { sid: p.sid.clone(), text: p.text.clone(), ..Default::default() })
+        .collect();
+    let (split, counts) = split_sentences(&units);
+    let owners = counts.iter().enumerate().flat_map(|(index, &count)| std::iter::repeat_n(index, count));
+    let sentences = split
+        .into_iter()
+        .zip(owners)
+        .map(|(sentence, paragraph)| SourceSentence { sid: sentence.sid, paragraph, text: sentence.text })
+        .collect();
+    Ok((paragraphs, sentences))
+}
+
+// Abramowitz & Stegun 7.1.26; plenty for a length model.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    (1.0 - poly * (-x * x).exp()).copysign(x)
+}
+
+// Probability of a length difference at least this large between true translations, where
+// `ratio` is the expected target characters per source character.
+fn length_match_probability(source_chars: usize, target_chars: usize, ratio: f64) -> f64 {
+    if source_chars == 0 && target_chars == 0 {
+        return 1.0;
+    }
+    let (l1, l2) = (source_chars as f64, target_chars as f64);
+    let mean = (l1 + l2 / ratio) / 2.0;
+    let delta = (l2 - l1 * ratio) / (mean * LENGTH_VARIANCE).sqrt();
+    let cdf = 0.5 * (1.0 + erf(delta.abs() / std::f64::consts::SQRT_2));
+    2.0 * (1.0 - cdf)
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+    cost: f64,
+    bead: usize,
+}
+
+// Gale–Church length-based alignment, restricted to a band around the diagonal so whole books
+// stay tractable.
+fn gale_church(source: &[usize], target: &[usize]) -> Option<Vec<Bead>> {
+    let (n, m) = (source.len(), target.len());
+    if n == 0 || m == 0 {
+        return None;
+    }
+    let prefix = |lengths: &[usize]| {
+        let mut sums = vec![0];
+        for length in lengths {
+            sums.push(sums[sums.len() - 1] + length);
+        }
+        sums
+    };
+    let (source_sums, target_sums) = (prefix(source), prefix(target));
+    let ratio = (target_sums[m] as f64 / source_sums[n].max(1) as f64).max(0.01);
+    let band = (n.max(m) / 10).clamp(MIN_BAND, MAX_BAND).min(MAX_CELLS / (2 * (n + 1))).max(1);
+    let low = |i: usize| (i * m / n).saturating_sub(band);
+    let high = |i: usize| (i * m / n + band).min(m);
+
+    let unreached = Cell { cost: f64::INFINITY, bead: usize::MAX };
+    let mut rows: Vec<Vec<Cell>> = (0..=n).map(|i| vec![unreached; high(i) - low(i) + 1]).collect();
+    rows[0][0] = Cell { cost: 0.0, bead: usize::MAX };
+    for i in 0..=n {
+        for j in low(i)..=high(i) {
+            if i == 0 && j == 0 {
+                continue;
+            }
+            let mut best = unreached;
+            for (bead, &(di, dj, prior)) in BEADS.iter().enumerate() {
+                if di > i || dj > j {
+                    continue;
+                }
+                let (pi, pj) = (i - di, j - dj);
+                if pj < low(pi) || pj > high(pi) {
+                    continue;
+                }
+                let previous = rows[pi][pj - low(pi)].cost;
+                if previous.is_infinite() {
+                    continue;
+                }
+                let probability = length_match_probability(
+                    source_sums[i] - source_sums[pi],
+                    target_sums[j] - target_sums[pj],
+                    ratio,
+                );
+                let cost = previous - prior.ln() - probability.max(f64::MIN_POSITIVE).ln();
+                if cost < best.cost {
+                    best = Cell { cost, bead };
+                }
+            }
+            rows[i][j - low(i)] = best;
+        }
+    }
+
+    let mut beads = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let cell = rows[i][j - low(i)];
+        let &(di, dj, _) = BEADS.get(cell.bead)?;
+        let (pi, pj) = (i - di, j - dj);
+        let confidence =
+            length_match_probability(source_sums[i] - source_sums[pi], target_sums[j] - target_sums[pj], ratio);
+        beads.push((pi..i, pj..j, confidence));
+        (i, j) = (pi, pj);
+    }
+    beads.reverse();
+    Some(beads)
+}
+
+fn seed(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    translation_path: &Path,
+    model: &str,
+    target_language: &TargetLanguage,
+) -> Result<AlignmentReport, String> {
+    let (paragraphs, sentences) = source_sentences(handle, book_id)?;
+    if sentences.is_empty() {
+        return Err(format!("No extracted text stored for book: {}", book_id));
+    }
+    let target_paragraphs = translation_paragraphs(translation_path)?;
+    let target_lang = language_of(&target_paragraphs.iter().map(String::as_str).collect::<Vec<_>>());
+    let targets: Vec<String> =
+        target_paragraphs.iter().flat_map(|p| segment(p, &target_lang, None)).map(|s| s.text).collect();
+
+    let lengths = |texts: Vec<&str>| texts.into_iter().map(|t| t.chars().count()).collect::<Vec<_>>();
+    let beads = gale_church(
+        &lengths(sentences.iter().map(|s| s.text.as_str()).collect()),
+        &lengths(targets.iter().map(String::as_str).collect()),
+    )
+    .ok_or_else(|| "The translation has no text to align.".to_string())?;
+
+    let target_code = target_language.tag();
+    let key_target = cache_target(handle, &target_code);
+    let separator = sentence_separator(&target_code);
+    let mut pairs = Vec::with_capacity(beads.len());
+    // Target text per paragraph, from the beads that start in it; None once any of them is
+    // too doubtful to seed.
+    let mut paragraph_targets: HashMap<usize, Option<Vec<String>>> = HashMap::new();
+    let mut sentence_entries = Vec::new();
+    for (source_range, target_range, confidence) in beads {
+        let bead_sources = &sentences[source_range];
+        let target = targets[target_range].join(separator);
+        let confident = confidence >= MIN_SEED_CONFIDENCE;
+        if let Some(first) = bead_sources.first() {
+            let parts = paragraph_targets.entry(first.paragraph).or_insert_with(|| Some(Vec::new()));
+            match parts {
+                Some(_) if !confident => *parts = None,
+                Some(parts) if !target.is_empty() => parts.push(target.clone()),
+                _ => {}
+            }
+            if confident && bead_sources.len() == 1 && !target.is_empty() && first.sid.contains("#s") {
+                sentence_entries.push((first.sid.clone(), first.text.clone(), target.clone()));
+            }
+        }
+        pairs.push(AlignedPair {
+            sids: bead_sources.iter().map(|s| s.sid.clone()).collect(),
+            source: bead_sources.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+            target,
+            confidence,
+        });
+    }
+
+    let mut entries: Vec<(String, String, String)> = paragraphs
+        .iter()
+        .enumerate()
+        .filter_map(|(index, p)| {
+            let parts = paragraph_targets.get(&index)?.as_ref().filter(|parts| !parts.is_empty())?;
+            Some((p.sid.clone(), p.text.clone(), parts.join(separator)))
+        })
+        .collect();
+    let paragraph_count = entries.len();
+    entries.extend(sentence_entries);
+    // Only fills gaps: a translation already cached, by a model or by hand, is kept.
+    let (seeded_paragraphs, seeded_sentences) = update_cache(handle, |cache| {
+        let (mut paragraphs, mut sentences) = (0, 0);
+        for (index, (sid, text, translation)) in entries.into_iter().enumerate() {
+            let key = translation_cache_key(&sid, &text, model, &key_target);
+            let Entry::Vacant(entry) = cache.entries.entry(key.clone()) else {
+                continue;
+            };
+            entry.insert(translation);
+            let generation = GenerationInfo {
+                generation_id: None,
+                provider: Some(ALIGNED_PROVIDER.to_string()),
+                model: Some(model.to_string()),
+                created_at: Some(Utc::now()),
+                ..Default::default()
+            };
+            cache.generations.insert(key, generation);
+            if index < paragraph_count {
+                paragraphs += 1;
+            } else {
+                sentences += 1;
+            }
+        }
+        (paragraphs, sentences)
+    })?;
+
+    let mean_confidence = pairs.iter().map(|p| p.confidence).sum::<f64>() / pairs.len().max(1) as f64;
+    let report = AlignmentReport {
+        source_sentences: sentences.len(),
+        target_sentences: targets.len(),
+        pairs: pairs.len(),
+        mean_confidence,
+        seeded_paragraphs,
+        seeded_sentences,
+    };
+    let alignment = BookAlignment {
+        translation_file: translation_path.to_string_lossy().to_string(),
+        model: model.to_string(),
+        target_code,
+        aligned_at: Some(Utc::now()),
+        pairs,
+    };
+    save_alignment(handle, book_id, &alignment)?;
+    Ok(report)
+}
+
+// Aligns the book's extracted text sentence by sentence with a translation the user already
+// has (an EPUB or plain text file) and puts the result in the translation cache under `model`
+// and `target_language`, so the reader shows that translation instead of requesting one.
+// Only confident matches are seeded, and entries already cached for the same text are kept.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn align_and_seed_cache(
+    handle: tauri::AppHandle,
+    book_id: String,
+    translation_path: String,
+    model: String,
+    target_language: TargetLanguage,
+) -> Result<AlignmentReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        seed(&handle, &book_id, Path::new(&translation_path), &model, &target_language)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn without_whitespace(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+// Stored pairs per paragraph sid. Target text matched with no source goes with the paragraph
+// before it.
+fn pairs_by_paragraph(pairs: Vec<AlignedPair>) -> HashMap<String, Vec<AlignedPair>> {
+    let mut by_paragraph: HashMap<String, Vec<AlignedPair>> = HashMap::new();
+    let mut current: Option<String> = None;
+    for pair in pairs {
+        if let Some(sid) = pair.sids.first() {
+            current = Some(sid.split('#').next().unwrap_or(sid).to_string());
+        }
+        if let Some(paragraph) = &current {
+            by_paragraph.entry(paragraph.clone()).or_default().push(pair);
+        }
+    }
+    by_paragraph
+}
+
+// Cached translations of a paragraph, sentence by sentence when the reader translated it that
+// way, otherwise as a whole.
+fn cached_rows(
+    paragraph: &PageParagraph,
+    model: &str,
+    target_code: &str,
+    entries: &HashMap<String, String>,
+) -> Vec<AlignmentRow> {
+    let unit = TranslateSentence { sid: paragraph.sid.clone(), text: paragraph.text.clone(), ..Default::default() };
+    let (sentences, _) = split_sentences(std::slice::from_ref(&unit));
+    let lookup = |sid: &str, text: &str| entries.get(&translation_cache_key(sid, text, model, target_code)).cloned();
+    let by_sentence: Vec<Option<String>> = sentences.iter().map(|s| lookup(&s.sid, &s.text)).collect();
+    if sentences.len() > 1 && by_sentence.iter().all(Option::is_some) {
+        return sentences
+            .into_iter()
+            .zip(by_sentence)
+            .map(|(sentence, target)| AlignmentRow {
+                sids: vec![sentence.sid],
+                source: sentence.text,
+                target,
+                confidence: None,
+            })
+            .collect();
+    }
+    vec![AlignmentRow {
+        sids: vec![unit.sid.clone()],
+        target: lookup(&unit.sid, &unit.text),
+        source: unit.text,
+        confidence: None,
+    }]
+}
+
+// Source and target sentence pairs for one page, for reading the two side by side and for
+// checking how well an alignment went. Paragraphs aligned by `align_and_seed_cache` come with
+// confidences; the rest come from the translation cache under `model` and `target_language`,
+// which default to the ones the book was aligned for.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_alignment(
+    handle: tauri::AppHandle,
+    book_id: String,
+    page: u32,
+    model: Option<String>,
+    target_language: Option<TargetLanguage>,
+) -> Result<Vec<AlignmentRow>, String> {
+    let text = load_book_text(&handle, &book_id)?;
+    let Some(page) = text.page(page) else {
+        return Ok(Vec::new());
+    };
+    let stored = load_alignment(&handle, &book_id)?;
+    let model = model.unwrap_or_else(|| stored.model.clone());
+    let target_code = target_language.map(|t| t.tag()).unwrap_or_else(|| stored.target_code.clone());
+    let mut aligned = if stored.model == model && stored.target_code == target_code {
+        pairs_by_paragraph(stored.pairs)
+    } else {
+        HashMap::new()
+    };
+    let key_target = cache_target(&handle, &target_code);
+
+    read_cache(&handle, |cache| {
+        let mut rows = Vec::new();
+        for paragraph in page.paragraphs.iter().filter(|p| !p.kind.is_verbatim()) {
+            // Stored pairs only count while the paragraph still reads as it did when aligned.
+            let pairs = aligned.remove(&paragraph.sid).filter(|pairs| {
+                let source: String = pairs.iter().map(|p| p.source.as_str()).collect();
+                without_whitespace(&source) == without_whitespace(&paragraph.text)
+            });
+            match pairs {
+                Some(pairs) => rows.extend(pairs.into_iter().map(|pair| AlignmentRow {
+                    sids: pair.sids,
+                    source: pair.source,
+                    target: Some(pair.target),
+                    confidence: Some(pair.confidence),
+                })),
+                None => rows.extend(cached_rows(paragraph, &model, &key_target, &cache.entries)),
+            }
+        }
+        rows
+    })
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::quota;
+use crate::{
+    ensure_cloud_allowed, extract_json_object, load_openrouter_credentials, request_openrouter,
+    truncate_for_error, TargetLanguage,
+};
+
+const MAX_ALTERNATIVES: u32 = 5;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlternativeTranslation {
+    translation: String,
+    // Short note on register or nuance, e.g. "more formal", "closer to the literal wording".
+    #[serde(default)]
+    note: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlternativesResponse {
+    alternatives: Vec<AlternativeTranslation>,
+}
+
+fn build_alternatives_system_prompt() -> String {
+    [
+        "You are a translation engine that offers several distinct renderings of one sentence.",
+        "Each rendering must differ meaningfully in wording, register or interpretation.",
+        "Output STRICT JSON ONLY.",
+        "No markdown, no explanations, no extra text.",
+    ]
+    .join(" ")
+}
+
+fn build_alternatives_prompt(sentence: &str, target_language: &TargetLanguage, count: u32) -> String {
+    format!(
+        r#"Give {} distinct translations of this sentence into {} ({}): "{}"
+Return JSON in this exact format:
+{{"alternatives": [{{"translation": "...", "note": "..."}}]}}
+- note: one short phrase in {} describing how this rendering differs (register, tone, literalness)"#,
+        count, target_language.label, target_language.prompt_tag(), sentence, target_language.label
+    )
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_alternative_translations(
+    handle: tauri::AppHandle,
+    model: String,
+    target_language: TargetLanguage,
+    sentence: String,
+    count: u32,
+    book_id: String,
+) -> Result<Vec<AlternativeTranslation>, String> {
+    ensure_cloud_allowed(&handle, &book_id)?;
+    let count = count.clamp(1, MAX_ALTERNATIVES);
+    let credentials = load_openrouter_credentials(&handle)?.for_feature(quota::FEATURE_ALTERNATIVES);
+    let system_prompt = build_alternatives_system_prompt();
+    let user_prompt = build_alternatives_prompt(&sentence, &target_language, count);
+
+    // A little temperature helps the candidates actually differ.
+    let content = request_openrouter(&credentials, &model, 0.7, &system_prompt, &user_prompt).await?;
+    let json_content = extract_json_object(&content);
+    let parsed: AlternativesResponse = serde_json::from_str(&json_content).map_err(|e| {
+        format!("Failed to parse alternatives JSON: {} (content: {})", e, truncate_for_error(&json_content))
+    })?;
+
+    let mut alternatives: Vec<AlternativeTranslation> = Vec::new();
+    for candidate in parsed.alternatives {
+        let duplicate = alternatives
+            .iter()
+            .any(|a| a.translation.trim() == candidate.translation.trim());
+        if !duplicate && !candidate.translation.trim().is_empty() {
+            alternatives.push(candidate);
+        }
+    }
+    alternatives.truncate(count as usize);
+    Ok(alternatives)
+}
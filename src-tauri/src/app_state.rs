@@ -0,0 +1,262 @@
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::cloud_policy::{read_cloud_policy_file, write_cloud_policy_file, CloudPolicyData};
+use crate::job_state::{read_pending_jobs_file, write_pending_jobs_file, PendingJobsData};
+use crate::lookup_history::{read_lookup_history_file, write_lookup_history_file, LookupHistoryData};
+use crate::private_books::{read_private_books_file, write_private_books_file, PrivateBooksData};
+use crate::quota::{read_usage_file, write_usage_file, UsageData};
+use crate::reading_activity::{read_reading_activity_file, write_reading_activity_file, ReadingActivity};
+use crate::response_cache::{read_response_cache_file, write_response_cache_file, ResponseCacheData};
+use crate::settings::{read_settings_file, write_settings_file, AppSettings};
+use crate::sync_conflicts::merge_pending_conflicts;
+use crate::transliteration::{
+    read_transliteration_cache_file, write_transliteration_cache_file, TransliterationCacheData,
+};
+use crate::{
+    read_cache_file, read_recent_books_file, read_vocabulary_file, write_cache_file, write_recent_books_file,
+    write_vocabulary_file, CachedTranslations, RecentBooksData, VocabularyData,
+};
+
+// Dirty stores are checked this often.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+// Progress and reading activity change on every page turn; coalesce them into one write per
+// this window.
+const RECENT_BOOKS_DEBOUNCE: Duration = Duration::from_secs(10);
+
+type LoadFn<T> = fn(&tauri::AppHandle) -> Result<T, String>;
+type SaveFn<T> = fn(&tauri::AppHandle, &T) -> Result<(), String>;
+
+// One JSON store kept in memory. Loaded on first access; changes mark it dirty and are
+// written back by the background flusher once `debounce` has passed since the first
+// unsaved change (or right away by an explicit `flush`).
+pub struct Store<T> {
+    data: RwLock<Option<T>>,
+    dirty_since: Mutex<Option<Instant>>,
+    debounce: Duration,
+    load: LoadFn<T>,
+    save: SaveFn<T>,
+}
+
+impl<T: Clone> Store<T> {
+    fn new(load: LoadFn<T>, save: SaveFn<T>) -> Self {
+        Self {
+            data: RwLock::new(None),
+            dirty_since: Mutex::new(None),
+            debounce: Duration::ZERO,
+            load,
+            save,
+        }
+    }
+
+    fn debounced(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    fn mark_dirty(&self) {
+        self.dirty_since
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(Instant::now);
+    }
+
+    fn take_dirty(&self, force: bool) -> bool {
+        let mut dirty_since = self.dirty_since.lock().unwrap_or_else(|e| e.into_inner());
+        match *dirty_since {
+            Some(since) if force || since.elapsed() >= self.debounce => {
+                *dirty_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn loaded<'a>(&self, handle: &tauri::AppHandle, data: &'a mut Option<T>) -> Result<&'a mut T, String> {
+        match data {
+            Some(value) => Ok(value),
+            slot @ None => Ok(slot.insert((self.load)(handle)?)),
+        }
+    }
+
+    pub fn read<R>(&self, handle: &tauri::AppHandle, f: impl FnOnce(&T) -> R) -> Result<R, String> {
+        {
+            let data = self.data.read().map_err(|e| e.to_string())?;
+            if let Some(value) = data.as_ref() {
+                return Ok(f(value));
+            }
+        }
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        Ok(f(self.loaded(handle, &mut data)?))
+    }
+
+    pub fn get(&self, handle: &tauri::AppHandle) -> Result<T, String> {
+        self.read(handle, T::clone)
+    }
+
+    pub fn update<R>(&self, handle: &tauri::AppHandle, f: impl FnOnce(&mut T) -> R) -> Result<R, String> {
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        let result = f(self.loaded(handle, &mut data)?);
+        self.mark_dirty();
+        Ok(result)
+    }
+
+    pub fn set(&self, value: T) -> Result<(), String> {
+        let mut data = self.data.write().map_err(|e| e.to_string())?;
+        *data = Some(value);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Drops the loaded data so the next access reads the file again.
+    fn unload(&self) {
+        *self.data.write().unwrap_or_else(|e| e.into_inner()) = None;
+        *self.dirty_since.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    // With `force`, writes unsaved changes now regardless of the debounce window.
+    fn write_back(&self, handle: &tauri::AppHandle, force: bool) -> Result<(), String> {
+        if !self.take_dirty(force) {
+            return Ok(());
+        }
+        let data = self.data.read().map_err(|e| e.to_string())?;
+        if let Some(value) = data.as_ref() {
+            if let Err(e) = (self.save)(handle, value) {
+                self.mark_dirty();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The stores hit on nearly every command, shared across the app instead of re-read from disk.
+pub struct AppState {
+    pub settings: Store<AppSettings>,
+    pub vocabulary: Store<VocabularyData>,
+    pub recent_books: Store<RecentBooksData>,
+    pub cache: Store<CachedTranslations>,
+    pub pending_jobs: Store<PendingJobsData>,
+    pub usage: Store<UsageData>,
+    pub cloud_policy: Store<CloudPolicyData>,
+    pub private_books: Store<PrivateBooksData>,
+    pub response_cache: Store<ResponseCacheData>,
+    pub lookup_history: Store<LookupHistoryData>,
+    pub reading_activity: Store<ReadingActivity>,
+    pub transliteration_cache: Store<TransliterationCacheData>,
+    // Held by the flusher while it runs and while the stores switch files, so the flusher
+    // never loads or writes the files being switched away from.
+    switching: Mutex<()>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            settings: Store::new(read_settings_file, write_settings_file),
+            vocabulary: Store::new(read_vocabulary_file, write_vocabulary_file),
+            recent_books: Store::new(read_recent_books_file, write_recent_books_file).debounced(RECENT_BOOKS_DEBOUNCE),
+            cache: Store::new(read_cache_file, write_cache_file),
+            pending_jobs: Store::new(read_pending_jobs_file, write_pending_jobs_file),
+            usage: Store::new(read_usage_file, write_usage_file),
+            cloud_policy: Store::new(read_cloud_policy_file, write_cloud_policy_file),
+            private_books: Store::new(read_private_books_file, write_private_books_file),
+            response_cache: Store::new(read_response_cache_file, write_response_cache_file),
+            lookup_history: Store::new(read_lookup_history_file, write_lookup_history_file),
+            reading_activity: Store::new(read_reading_activity_file, write_reading_activity_file)
+                .debounced(RECENT_BOOKS_DEBOUNCE),
+            transliteration_cache: Store::new(read_transliteration_cache_file, write_transliteration_cache_file),
+            switching: Mutex::new(()),
+        }
+    }
+}
+
+impl AppState {
+    // Settings go first: the cache writer reads them to decide whether to encrypt.
+    fn write_back_all(&self, handle: &tauri::AppHandle, force: bool) -> Result<(), String> {
+        let results = [
+            self.settings.write_back(handle, force),
+            self.vocabulary.write_back(handle, force),
+            self.recent_books.write_back(handle, force),
+            self.cache.write_back(handle, force),
+            self.pending_jobs.write_back(handle, force),
+            self.usage.write_back(handle, force),
+            self.cloud_policy.write_back(handle, force),
+            self.private_books.write_back(handle, force),
+            self.response_cache.write_back(handle, force),
+            self.lookup_history.write_back(handle, force),
+            self.reading_activity.write_back(handle, force),
+            self.transliteration_cache.write_back(handle, force),
+        ];
+        results.into_iter().collect()
+    }
+
+    pub fn flush_all(&self, handle: &tauri::AppHandle) -> Result<(), String> {
+        self.write_back_all(handle, true)
+    }
+
+    // Saves and forgets every store, for when their files change underneath.
+    pub fn unload_all(&self, handle: &tauri::AppHandle) -> Result<(), String> {
+        self.switch_files(handle, || Ok(()))
+    }
+
+    // Saves every store, runs `switch` (which points them at other files, e.g. a profile switch
+    // or a move of the data directory) and forgets them, all with the flusher held off. When
+    // `switch` fails the stores stay as they were.
+    pub fn switch_files(
+        &self,
+        handle: &tauri::AppHandle,
+        switch: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        let _switching = self.switching.lock().unwrap_or_else(|e| e.into_inner());
+        self.flush_all(handle)?;
+        switch()?;
+        self.settings.unload();
+        self.vocabulary.unload();
+        self.recent_books.unload();
+        self.cache.unload();
+        self.pending_jobs.unload();
+        self.usage.unload();
+        self.cloud_policy.unload();
+        self.private_books.unload();
+        self.response_cache.unload();
+        self.lookup_history.unload();
+        self.reading_activity.unload();
+        self.transliteration_cache.unload();
+        Ok(())
+    }
+}
+
+// Loads every store up front and starts the background flusher.
+pub fn start(handle: &tauri::AppHandle) {
+    let state = handle.state::<AppState>();
+    let _ = state.settings.read(handle, |_| ());
+    let _ = state.vocabulary.read(handle, |_| ());
+    let _ = state.recent_books.read(handle, |_| ());
+    let _ = state.cache.read(handle, |_| ());
+    merge_sync_conflicts(handle);
+
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            let state = handle.state::<AppState>();
+            let _switching = state.switching.lock().unwrap_or_else(|e| e.into_inner());
+            merge_sync_conflicts(&handle);
+            if let Err(e) = state.write_back_all(&handle, false) {
+                log::error!("Failed to flush app state: {}", e);
+            }
+        }
+    });
+}
+
+// Conflict copies left by a sync client are merged before the next write could bury them.
+fn merge_sync_conflicts(handle: &tauri::AppHandle) {
+    if let Err(e) = merge_pending_conflicts(handle) {
+        log::error!("Failed to merge sync conflicts: {}", e);
+    }
+}
+
+pub fn flush(handle: &tauri::AppHandle) -> Result<(), String> {
+    handle.state::<AppState>().flush_all(handle)
+}
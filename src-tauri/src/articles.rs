@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::book_text::{store_book_pages, BookPage, PageParagraph};
+use crate::readability::Article;
+use crate::{add_recent_book, app_state, update_recent_books, BookStatus, RecentBook};
+
+// Web articles live in the library next to books, as single-page documents whose text is
+// stored like extracted book text, so translation, lookup and vocabulary work on them as is.
+pub const ARTICLE_FILE_TYPE: &str = "article";
+// Unread articles kept; older ones drop off like books do from the recent list.
+pub const MAX_UNREAD_ARTICLES: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticleSummary {
+    id: String,
+    url: String,
+    title: String,
+    author: Option<String>,
+    // `feed:<id>` for feed articles, `web` for saved pages.
+    source: Option<String>,
+    status: BookStatus,
+    last_opened_at: DateTime<Utc>,
+}
+
+impl From<&RecentBook> for ArticleSummary {
+    fn from(book: &RecentBook) -> Self {
+        ArticleSummary {
+            id: book.id.clone(),
+            url: book.file_path.clone(),
+            title: book.title.clone(),
+            author: book.author.clone(),
+            source: book.metadata.source.clone(),
+            status: book.status,
+            last_opened_at: book.last_opened_at,
+        }
+    }
+}
+
+pub fn article_id(url: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(format!("article:{}", url).as_bytes()));
+    digest[..12].to_string()
+}
+
+fn find_article(handle: &tauri::AppHandle, id: &str) -> Result<Option<RecentBook>, String> {
+    handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(handle, |data| data.books.iter().find(|b| b.id == id).cloned())
+}
+
+// Stores an extracted article as a library document. An article already stored is returned
+// as it is, keeping its reading state.
+pub fn store_article(
+    handle: &tauri::AppHandle,
+    url: &str,
+    article: &Article,
+    source: &str,
+) -> Result<ArticleSummary, String> {
+    let id = article_id(url);
+    if let Some(existing) = find_article(handle, &id)? {
+        return Ok(ArticleSummary::from(&existing));
+    }
+    if article.blocks.is_empty() {
+        return Err(format!("No readable text found at {}", url));
+    }
+    let title = article.title.clone().unwrap_or_else(|| url.to_string());
+    let paragraphs = article
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| PageParagraph {
+            sid: format!("{}:p1:{}", id, index),
+            text: block.text.clone(),
+            kind: block.kind,
+        })
+        .collect();
+    let page = BookPage { page: 1, title: Some(title.clone()), paragraphs, extracted: Vec::new() };
+    store_book_pages(handle.clone(), id.clone(), vec![page])?;
+    add_recent_book(
+        handle.clone(),
+        id.clone(),
+        url.to_string(),
+        title.clone(),
+        ARTICLE_FILE_TYPE.to_string(),
+        title,
+        article.byline.clone(),
+        None,
+        1,
+    )?;
+    update_recent_books(handle, |data| {
+        let book = data.books.iter_mut().find(|b| b.id == id)?;
+        book.metadata.source = Some(source.to_string());
+        book.metadata.enriched_at = Some(Utc::now());
+        Some(ArticleSummary::from(&*book))
+    })?
+    .ok_or_else(|| format!("Article was not stored: {}", url))
+}
+
+// Stored articles, most recently opened or added first. `source` keeps those of one feed
+// (`feed:<id>`) or of saved pages (`web`), `status` those unread, finished and so on.
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_articles(
+    handle: tauri::AppHandle,
+    source: Option<String>,
+    status: Option<BookStatus>,
+) -> Result<Vec<ArticleSummary>, String> {
+    let mut articles: Vec<ArticleSummary> = handle.state::<app_state::AppState>().recent_books.read(&handle, |data| {
+        data.books
+            .iter()
+            .filter(|b| b.file_type == ARTICLE_FILE_TYPE)
+            .filter(|b| source.is_none() || b.metadata.source == source)
+            .filter(|b| status.is_none_or(|status| b.status == status))
+            .map(ArticleSummary::from)
+            .collect()
+    })?;
+    articles.sort_by_key(|a| std::cmp::Reverse(a.last_opened_at));
+    Ok(articles)
+}
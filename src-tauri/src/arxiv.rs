@@ -0,0 +1,127 @@
+use chrono::Utc;
+use tauri::Manager;
+
+use crate::library_import::{element_text, ImportedBook};
+use crate::url_import::download_book;
+use crate::{app_state, update_recent_books};
+
+const ARXIV_API_URL: &str = "https://export.arxiv.org/api/query";
+
+struct ArxivPaper {
+    title: String,
+    authors: Vec<String>,
+    summary: String,
+    published_year: Option<i32>,
+    categories: Vec<String>,
+}
+
+// The paper id in an arXiv id or link: "2301.01234v2", "arXiv:2301.01234",
+// "https://arxiv.org/abs/2301.01234", ".../pdf/2301.01234v2.pdf" or an old-style
+// "hep-th/9901001".
+fn parse_arxiv_id(input: &str) -> Option<String> {
+    let input = input.trim();
+    let input = input.strip_prefix("arXiv:").or_else(|| input.strip_prefix("arxiv:")).unwrap_or(input);
+    let path = match input.find("arxiv.org/") {
+        Some(index) => {
+            let path = &input[index + "arxiv.org/".len()..];
+            let path = path.split(['?', '#']).next().unwrap_or(path);
+            path.split_once('/').map(|(_, id)| id)?
+        }
+        None => input,
+    };
+    let id = path.trim_end_matches('/').trim_end_matches(".pdf");
+    let version = |v: &str| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit());
+    let (number, _) = id.rsplit_once('v').filter(|(_, v)| version(v)).unwrap_or((id, ""));
+    let new_style = number.split_once('.').is_some_and(|(yymm, seq)| {
+        yymm.len() == 4 && (4..=5).contains(&seq.len()) && (yymm.to_string() + seq).chars().all(|c| c.is_ascii_digit())
+    });
+    let old_style = number.split_once('/').is_some_and(|(archive, seq)| {
+        !archive.is_empty() && seq.len() == 7 && seq.chars().all(|c| c.is_ascii_digit())
+    });
+    (new_style || old_style).then(|| id.to_string())
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+async fn fetch_paper(id: &str) -> Result<ArxivPaper, String> {
+    let response = reqwest::Client::new()
+        .get(ARXIV_API_URL)
+        .query(&[("id_list", id)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("arXiv API error: {}", response.status()));
+    }
+    let feed = response.text().await.map_err(|e| e.to_string())?;
+    let entry = feed.split("<entry>").nth(1).ok_or_else(|| format!("arXiv has no paper {}", id))?;
+    let title = element_text(entry, "title").map(|t| collapse(&t)).unwrap_or_default();
+    if title.is_empty() || title == "Error" {
+        return Err(format!("arXiv has no paper {}", id));
+    }
+    let authors = entry.split("<author>").skip(1).filter_map(|author| element_text(author, "name")).collect();
+    let categories = entry
+        .split("<category ")
+        .skip(1)
+        .filter_map(|tag| crate::epub::attribute(tag.split('>').next()?, "term"))
+        .collect();
+    Ok(ArxivPaper {
+        title,
+        authors,
+        summary: element_text(entry, "summary").map(|s| collapse(&s)).unwrap_or_default(),
+        published_year: element_text(entry, "published").and_then(|date| date.get(..4)?.parse().ok()),
+        categories,
+    })
+}
+
+// Downloads an arXiv paper's PDF into the library and fills in its title, authors, abstract
+// and categories from the arXiv API. The abstract is then given to chat about the paper.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_arxiv(handle: tauri::AppHandle, id_or_url: String) -> Result<ImportedBook, String> {
+    let id = parse_arxiv_id(&id_or_url).ok_or_else(|| format!("Not an arXiv id or link: {}", id_or_url))?;
+    let paper = fetch_paper(&id).await?;
+    let pdf_url = format!("https://arxiv.org/pdf/{}", id);
+    let file_name = format!("{}.pdf", id.replace('/', "_"));
+    let mut book = download_book(&handle, &pdf_url, Some(file_name)).await?;
+
+    let author = (!paper.authors.is_empty()).then(|| paper.authors.join(", "));
+    update_recent_books(&handle, |data| {
+        let Some(entry) = data.books.iter_mut().find(|b| b.id == book.id) else {
+            return;
+        };
+        let metadata = &mut entry.metadata;
+        let pinned = |field: &str| metadata.overridden.iter().any(|name| name == field);
+        if !pinned("title") {
+            entry.title = paper.title.clone();
+        }
+        if !pinned("author") && author.is_some() {
+            entry.author = author.clone();
+        }
+        if !pinned("description") {
+            metadata.description = Some(paper.summary.clone());
+        }
+        if !pinned("publication_year") {
+            metadata.publication_year = paper.published_year;
+        }
+        metadata.subjects = paper.categories.clone();
+        metadata.arxiv_id = Some(id.clone());
+        metadata.source = Some("arxiv".to_string());
+        metadata.enriched_at = Some(Utc::now());
+        book.title = entry.title.clone();
+        book.author = entry.author.clone();
+    })?;
+    Ok(book)
+}
+
+// Title, authors and abstract of an imported arXiv paper, for chat prompts about it.
+pub fn chat_preamble(handle: &tauri::AppHandle, book_id: &str) -> Result<Option<String>, String> {
+    handle.state::<app_state::AppState>().recent_books.read(handle, |data| {
+        let book = data.books.iter().find(|b| b.id == book_id)?;
+        book.metadata.arxiv_id.as_ref()?;
+        let summary = book.metadata.description.as_deref()?;
+        let authors = book.author.as_deref().map(|a| format!(" by {}", a)).unwrap_or_default();
+        Some(format!("Paper: {}{}\nAbstract: {}", book.title, authors, summary))
+    })
+}
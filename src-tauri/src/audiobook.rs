@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_config_dir;
+use crate::quarantine::parse_or_quarantine;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AudioSyncPoint {
+    page: u32,
+    // Seconds from the start of the audio file.
+    timestamp: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Audiobook {
+    audio_path: String,
+    sync_points: Vec<AudioSyncPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct AudiobooksData {
+    books: HashMap<String, Audiobook>,
+}
+
+fn audiobooks_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("audiobooks.json"))
+}
+
+fn load_audiobooks(handle: &tauri::AppHandle) -> Result<AudiobooksData, String> {
+    let path = audiobooks_file_path(handle)?;
+    if !path.exists() {
+        return Ok(AudiobooksData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_audiobooks(handle: &tauri::AppHandle, data: &AudiobooksData) -> Result<(), String> {
+    let path = audiobooks_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+fn find_audiobook<'a>(data: &'a AudiobooksData, book_id: &str) -> Result<&'a Audiobook, String> {
+    data.books
+        .get(book_id)
+        .ok_or_else(|| format!("No audiobook attached to book: {}", book_id))
+}
+
+// Piecewise-linear mapping through the sync points, which are kept sorted by page.
+// Outside the anchored range the rate of the nearest segment is extended.
+fn interpolate(points: &[(f64, f64)], x: f64) -> Option<f64> {
+    match points {
+        [] => None,
+        // A single anchor only tells us where that one spot is.
+        [(_, y0)] => Some(*y0),
+        _ => {
+            let segment = points
+                .windows(2)
+                .find(|w| x <= w[1].0)
+                .unwrap_or(&points[points.len() - 2..]);
+            let (x0, y0) = segment[0];
+            let (x1, y1) = segment[1];
+            if (x1 - x0).abs() < f64::EPSILON {
+                return Some(y0);
+            }
+            Some(y0 + (x - x0) * (y1 - y0) / (x1 - x0))
+        }
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn attach_audiobook(handle: tauri::AppHandle, book_id: String, audio_path: String) -> Result<(), String> {
+    if !std::path::Path::new(&audio_path).is_file() {
+        return Err(format!("Audio file not found: {}", audio_path));
+    }
+    let mut data = load_audiobooks(&handle)?;
+    let entry = data.books.entry(book_id).or_insert_with(|| Audiobook {
+        audio_path: audio_path.clone(),
+        sync_points: Vec::new(),
+    });
+    // A different recording invalidates the old anchors.
+    if entry.audio_path != audio_path {
+        entry.audio_path = audio_path;
+        entry.sync_points.clear();
+    }
+    save_audiobooks(&handle, &data)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn detach_audiobook(handle: tauri::AppHandle, book_id: String) -> Result<(), String> {
+    let mut data = load_audiobooks(&handle)?;
+    data.books.remove(&book_id);
+    save_audiobooks(&handle, &data)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_audiobook(handle: tauri::AppHandle, book_id: String) -> Result<Option<Audiobook>, String> {
+    Ok(load_audiobooks(&handle)?.books.remove(&book_id))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_audio_sync_point(
+    handle: tauri::AppHandle,
+    book_id: String,
+    page: u32,
+    timestamp: f64,
+) -> Result<(), String> {
+    if !timestamp.is_finite() || timestamp < 0.0 {
+        return Err("Timestamp must be a non-negative number of seconds.".to_string());
+    }
+    let mut data = load_audiobooks(&handle)?;
+    let audiobook = data
+        .books
+        .get_mut(&book_id)
+        .ok_or_else(|| format!("No audiobook attached to book: {}", book_id))?;
+
+    audiobook.sync_points.retain(|p| p.page != page);
+    audiobook.sync_points.push(AudioSyncPoint { page, timestamp });
+    audiobook.sync_points.sort_by_key(|p| p.page);
+    save_audiobooks(&handle, &data)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_audio_position(handle: tauri::AppHandle, book_id: String, page: u32) -> Result<f64, String> {
+    let data = load_audiobooks(&handle)?;
+    let audiobook = find_audiobook(&data, &book_id)?;
+    let points: Vec<(f64, f64)> = audiobook
+        .sync_points
+        .iter()
+        .map(|p| (p.page as f64, p.timestamp))
+        .collect();
+    interpolate(&points, page as f64)
+        .map(|t| t.max(0.0))
+        .ok_or_else(|| "Set at least one audio sync point first.".to_string())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_page_for_audio_position(
+    handle: tauri::AppHandle,
+    book_id: String,
+    timestamp: f64,
+) -> Result<u32, String> {
+    let data = load_audiobooks(&handle)?;
+    let audiobook = find_audiobook(&data, &book_id)?;
+    let mut points: Vec<(f64, f64)> = audiobook
+        .sync_points
+        .iter()
+        .map(|p| (p.timestamp, p.page as f64))
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    interpolate(&points, timestamp)
+        .map(|page| page.floor().max(1.0) as u32)
+        .ok_or_else(|| "Set at least one audio sync point first.".to_string())
+}
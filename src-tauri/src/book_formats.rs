@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::Manager;
+
+use crate::cloud_policy;
+use crate::position_map::{self, EpubPosition};
+use crate::private_books;
+use crate::{app_state, update_recent_books, RecentBook, RecentBooksData};
+
+// Another file of the same book, e.g. the EPUB next to a PDF. The library entry's own
+// `file_path` is the first format; progress is shared between all of them as a percentage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookFormat {
+    pub file_path: String,
+    pub file_type: String,
+    pub total_pages: u32,
+    pub last_page: u32,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FormatPosition {
+    file_path: String,
+    file_type: String,
+    // Page to open at, carried over from the shared progress when this format was not the
+    // one last read.
+    page: u32,
+    progress: f32,
+    // Chapter and offset to open an EPUB at, when switching from the PDF.
+    location: Option<EpubPosition>,
+}
+
+// Page in a `total_pages` long format at `progress` percent.
+pub fn page_at_progress(progress: f32, total_pages: u32) -> u32 {
+    if total_pages == 0 {
+        return 1;
+    }
+    ((progress.clamp(0.0, 100.0) / 100.0 * total_pages as f32).ceil() as u32).clamp(1, total_pages)
+}
+
+fn find_book<'a>(books: &'a mut [RecentBook], book_id: &str) -> Result<&'a mut RecentBook, String> {
+    books.iter_mut().find(|b| b.id == book_id).ok_or_else(|| format!("Book not found: {}", book_id))
+}
+
+// Folds `separate`, the library entry another format had of its own, into `book`. Cloud
+// opt-outs and private locks hold for the whole book; reading state is taken from whichever
+// of the two was opened last.
+fn fold_entry(book: &mut RecentBook, separate: RecentBook) {
+    book.cloud_allowed &= separate.cloud_allowed;
+    book.private_lock |= separate.private_lock;
+    if separate.last_opened_at > book.last_opened_at {
+        book.last_opened_at = separate.last_opened_at;
+        book.progress = separate.progress;
+        book.status = separate.status;
+        book.finished_at = separate.finished_at;
+    }
+    book.author = book.author.take().or(separate.author);
+    book.cover_image = book.cover_image.take().or(separate.cover_image);
+    if book.metadata.enriched_at.is_none() && book.metadata.overridden.is_empty() {
+        book.metadata = separate.metadata;
+    }
+    for format in separate.formats {
+        if format.file_path != book.file_path && !book.formats.iter().any(|f| f.file_path == format.file_path) {
+            book.formats.push(format);
+        }
+    }
+}
+
+// Attaches another file of the same book. If that file was already in the library on its
+// own, that entry is folded into this one.
+#[tauri::command(rename_all = "camelCase")]
+pub fn add_book_format(
+    handle: tauri::AppHandle,
+    book_id: String,
+    path: String,
+    file_type: String,
+    total_pages: Option<u32>,
+) -> Result<Vec<BookFormat>, String> {
+    let file_type = file_type.to_lowercase();
+    if !matches!(file_type.as_str(), "pdf" | "epub") {
+        return Err(format!("Unsupported format: {}", file_type));
+    }
+    if !Path::new(&path).exists() {
+        return Err(format!("File not found: {}", path));
+    }
+    // Checked before anything changes, since an update is saved even when it fails.
+    let check = |data: &RecentBooksData| {
+        let book = data.books.iter().find(|b| b.id == book_id).ok_or_else(|| format!("Book not found: {}", book_id))?;
+        if book.file_path == path || book.formats.iter().any(|f| f.file_path == path) {
+            return Err("This file is already a format of the book.".to_string());
+        }
+        Ok(())
+    };
+    handle.state::<app_state::AppState>().recent_books.read(&handle, check)??;
+
+    let (formats, cloud_allowed, private_lock) = update_recent_books(&handle, |data| {
+        let separate = data.books.iter().position(|b| b.file_path == path && b.id != book_id);
+        let separate = separate.map(|index| data.books.remove(index));
+        let book = find_book(&mut data.books, &book_id)?;
+        book.formats.push(BookFormat {
+            last_page: separate.as_ref().map_or(1, |b| b.last_page),
+            total_pages: total_pages.or(separate.as_ref().map(|b| b.total_pages)).unwrap_or(0),
+            file_path: path,
+            file_type,
+            added_at: Utc::now(),
+        });
+        if let Some(separate) = separate {
+            fold_entry(book, separate);
+        }
+        Ok::<_, String>((book.formats.clone(), book.cloud_allowed, book.private_lock))
+    })??;
+    if !cloud_allowed {
+        cloud_policy::set_cloud_allowed(&handle, &book_id, false)?;
+    }
+    if private_lock {
+        private_books::mark_private(&handle, &book_id)?;
+    }
+    Ok(formats)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn remove_book_format(handle: tauri::AppHandle, book_id: String, path: String) -> Result<(), String> {
+    update_recent_books(&handle, |data| {
+        let book = find_book(&mut data.books, &book_id)?;
+        if book.file_path == path {
+            return Err("The book's main file cannot be removed as a format.".to_string());
+        }
+        book.formats.retain(|f| f.file_path != path);
+        if book.preferred_format.as_deref() == Some(path.as_str()) {
+            book.preferred_format = None;
+        }
+        Ok(())
+    })?
+}
+
+// Picks the format to open, by default the one opened last, and where to open it. Choosing
+// a format remembers it for next time. Switching between a PDF and an EPUB goes through the
+// text alignment of the two when there is one, and through the shared percentage otherwise.
+#[tauri::command(rename_all = "camelCase")]
+pub fn open_book_format(
+    handle: tauri::AppHandle,
+    book_id: String,
+    path: Option<String>,
+) -> Result<FormatPosition, String> {
+    let (mut position, previous) = update_recent_books(&handle, |data| {
+        let book = find_book(&mut data.books, &book_id)?;
+        let path = path.or_else(|| book.preferred_format.clone()).unwrap_or_else(|| book.file_path.clone());
+        let files = std::iter::once((&book.file_path, &book.file_type, book.total_pages, book.last_page))
+            .chain(book.formats.iter().map(|f| (&f.file_path, &f.file_type, f.total_pages, f.last_page)));
+        let files: Vec<_> = files.map(|(p, t, total, last)| (p.clone(), t.clone(), total, last)).collect();
+        let current = book.preferred_format.clone().unwrap_or_else(|| book.file_path.clone());
+        let (_, file_type, total_pages, last_page) = files
+            .iter()
+            .find(|(p, ..)| *p == path)
+            .cloned()
+            .ok_or_else(|| format!("Not a format of this book: {}", path))?;
+        let previous = (current != path).then(|| files.into_iter().find(|(p, ..)| *p == current)).flatten();
+        let page = if previous.is_some() { page_at_progress(book.progress, total_pages) } else { last_page };
+        book.preferred_format = (path != book.file_path).then(|| path.clone());
+        let position = FormatPosition { file_path: path, file_type, page, progress: book.progress, location: None };
+        Ok::<_, String>((position, previous))
+    })??;
+
+    if let Some((_, previous_type, _, previous_page)) = previous {
+        match (previous_type.as_str(), position.file_type.as_str()) {
+            ("pdf", "epub") => match position_map::pdf_page_to_epub(&handle, &book_id, previous_page) {
+                Ok(location) => position.location = Some(location),
+                Err(e) => log::error!("Failed to map PDF page to EPUB: {}", e),
+            },
+            ("epub", "pdf") => {
+                let location = EpubPosition { percent: Some(position.progress), ..Default::default() };
+                match position_map::epub_to_pdf_page(&handle, &book_id, &location) {
+                    Ok(mapped) => position.page = mapped.page,
+                    Err(e) => log::error!("Failed to map EPUB position to PDF: {}", e),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(position)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_book_formats(handle: tauri::AppHandle, book_id: String) -> Result<Vec<BookFormat>, String> {
+    handle.state::<app_state::AppState>().recent_books.read(&handle, |data| {
+        data.books.iter().find(|b| b.id == book_id).map(|b| b.formats.clone()).unwrap_or_default()
+    })
+}
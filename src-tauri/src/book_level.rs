@@ -0,0 +1,159 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::book_text::{load_book_text, BookText};
+use crate::language::detect_language;
+use crate::load_vocabulary;
+use crate::page_words::tokenize_words;
+use crate::segmentation::segment;
+use crate::vocab_index::{lemma_candidates, normalize_word};
+use crate::word_frequency::word_level;
+use crate::word_lists::{load_word_set, IGNORED_WORDS_FILE, KNOWN_WORDS_FILE};
+
+// Pages read for an estimate, spread evenly through the book.
+const SAMPLE_PAGES: usize = 40;
+// Share of running words, names aside, that the words of a level and those below must cover
+// for a text to be at that level: about what a reader needs to follow without a dictionary.
+const LEVEL_COVERAGE: f64 = 0.95;
+// Average sentence lengths that move the estimate a level down or up.
+const SHORT_SENTENCE_WORDS: f64 = 8.0;
+const LONG_SENTENCE_WORDS: f64 = 25.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum CefrLevel {
+    A1,
+    A2,
+    B1,
+    B2,
+    C1,
+    C2,
+}
+
+const LEVELS: [CefrLevel; 6] =
+    [CefrLevel::A1, CefrLevel::A2, CefrLevel::B1, CefrLevel::B2, CefrLevel::C1, CefrLevel::C2];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BookLevel {
+    pub book_id: String,
+    pub language: Option<String>,
+    // These two are None for languages without a built-in graded word list; only English has
+    // one so far.
+    pub level: Option<CefrLevel>,
+    // Running words outside the graded list (C2 or rarer), names aside.
+    pub rare_word_percent: Option<f64>,
+    // Running words that are neither marked known nor saved to vocabulary; ignored words
+    // do not count either way.
+    pub unknown_word_percent: f64,
+    pub unknown_unique_words: usize,
+    pub sampled_words: usize,
+}
+
+// The words the user counts as known: those marked known plus saved vocabulary, which is
+// being learned and so is not new in a text. Stored as written; a word in a text is looked up
+// by its possible dictionary forms, so "running" is known to someone who knows "run".
+pub struct KnownWords {
+    known: HashSet<String>,
+    ignored: HashSet<String>,
+}
+
+impl KnownWords {
+    pub fn load(handle: &tauri::AppHandle) -> Result<Self, String> {
+        let marked = load_word_set(handle, KNOWN_WORDS_FILE)?;
+        let saved = load_vocabulary(handle)?.entries.into_iter().map(|entry| entry.word);
+        let known = marked.into_iter().chain(saved).map(|word| normalize_word(&word)).collect();
+        Ok(Self { known, ignored: load_word_set(handle, IGNORED_WORDS_FILE)? })
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        lemma_candidates(word).iter().any(|form| self.known.contains(form))
+    }
+}
+
+fn sample_paragraphs(text: &BookText) -> Vec<&str> {
+    let step = text.pages.len().div_ceil(SAMPLE_PAGES).max(1);
+    text.pages.iter().step_by(step).flat_map(|p| &p.paragraphs).map(|p| p.text.as_str()).collect()
+}
+
+// The lowest level whose words, with those below, cover enough of the text; `per_level`
+// counts running words by level, `counted` includes those outside the list.
+fn level_from(per_level: &[usize; 5], counted: usize, words_per_sentence: f64) -> CefrLevel {
+    let mut covered = 0;
+    let mut index = LEVELS.len() - 1;
+    for (level, &count) in per_level.iter().enumerate() {
+        covered += count;
+        if covered as f64 >= counted as f64 * LEVEL_COVERAGE {
+            index = level;
+            break;
+        }
+    }
+    if words_per_sentence > LONG_SENTENCE_WORDS {
+        index = (index + 1).min(LEVELS.len() - 1);
+    } else if words_per_sentence < SHORT_SENTENCE_WORDS {
+        index = index.saturating_sub(1);
+    }
+    LEVELS[index]
+}
+
+// Estimates the book's level from a sample of its extracted text, and how much of it is new
+// to the user. None when no text has been extracted.
+pub fn estimate(handle: &tauri::AppHandle, book_id: &str, known: &KnownWords) -> Result<Option<BookLevel>, String> {
+    let text = load_book_text(handle, book_id)?;
+    if text.pages.is_empty() {
+        return Ok(None);
+    }
+    let paragraphs = sample_paragraphs(&text);
+    let language = detect_language(&paragraphs.iter().take(50).copied().collect::<Vec<_>>().join(" "));
+    let lang = language.as_deref().unwrap_or("en");
+
+    let (mut words, mut sentences) = (0, 0);
+    // Words other than names, by level; of those, the ones not ignored.
+    let (mut counted, mut rare, mut considered, mut unknown) = (0, 0, 0, 0);
+    let mut per_level = [0; 5];
+    let mut unknown_unique = HashSet::new();
+    for paragraph in paragraphs {
+        sentences += segment(paragraph, lang, None).len();
+        for token in tokenize_words(paragraph) {
+            words += 1;
+            // Capitalized words outside the list are taken for names.
+            let level = word_level(token.text);
+            if token.text.starts_with(char::is_uppercase) && level.is_none() {
+                continue;
+            }
+            counted += 1;
+            match level {
+                Some(level) => per_level[level as usize] += 1,
+                None => rare += 1,
+            }
+            let lower = token.text.to_lowercase();
+            if known.ignored.contains(&lower) {
+                continue;
+            }
+            considered += 1;
+            if !known.contains(token.text) {
+                unknown += 1;
+                unknown_unique.insert(lower);
+            }
+        }
+    }
+
+    let percent = |part: usize, whole: usize| if whole == 0 { 0.0 } else { 100.0 * part as f64 / whole as f64 };
+    let words_per_sentence = words as f64 / sentences.max(1) as f64;
+    let has_list = lang == "en" && counted > 0;
+    Ok(Some(BookLevel {
+        book_id: book_id.to_string(),
+        level: has_list.then(|| level_from(&per_level, counted, words_per_sentence)),
+        language,
+        rare_word_percent: has_list.then(|| percent(rare, counted)),
+        unknown_word_percent: percent(unknown, considered),
+        unknown_unique_words: unknown_unique.len(),
+        sampled_words: words,
+    }))
+}
+
+// An approximate CEFR level for the book and the share of its words the user does not know
+// yet, for picking books that are challenging but still comprehensible.
+#[tauri::command(rename_all = "camelCase")]
+pub fn estimate_book_level(handle: tauri::AppHandle, book_id: String) -> Result<BookLevel, String> {
+    let known = KnownWords::load(&handle)?;
+    estimate(&handle, &book_id, &known)?.ok_or_else(|| format!("No extracted text stored for book: {}", book_id))
+}
@@ -0,0 +1,333 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::isbn::{detect_book_isbn, normalize_isbn};
+use crate::series::{fill_series, BookSeries};
+use crate::{app_state, ensure_cloud_allowed, update_recent_books, RecentBook};
+
+const GOOGLE_BOOKS_URL: &str = "https://www.googleapis.com/books/v1/volumes";
+const OPEN_LIBRARY_URL: &str = "https://openlibrary.org/search.json";
+
+// Bibliographic details beyond what the file itself tells us. Fields named in `overridden`
+// were set by hand and are never replaced by enrichment.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BookMetadata {
+    pub isbn: Option<String>,
+    pub arxiv_id: Option<String>,
+    pub description: Option<String>,
+    pub publication_year: Option<i32>,
+    pub publisher: Option<String>,
+    pub cover_url: Option<String>,
+    pub subjects: Vec<String>,
+    pub series: Option<BookSeries>,
+    pub source: Option<String>,
+    pub enriched_at: Option<DateTime<Utc>>,
+    pub overridden: Vec<String>,
+}
+
+// Values to pin by hand; omitted fields are left as they are.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct BookMetadataOverride {
+    author: Option<String>,
+    isbn: Option<String>,
+    description: Option<String>,
+    publication_year: Option<i32>,
+    publisher: Option<String>,
+    cover_url: Option<String>,
+}
+
+// What one catalog knows about a book.
+#[derive(Debug, Default)]
+struct CatalogRecord {
+    author: Option<String>,
+    isbn: Option<String>,
+    description: Option<String>,
+    publication_year: Option<i32>,
+    publisher: Option<String>,
+    cover_url: Option<String>,
+    subjects: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleBooksResponse {
+    #[serde(default)]
+    items: Vec<GoogleBooksVolume>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleBooksVolume {
+    #[serde(rename = "volumeInfo")]
+    volume_info: GoogleVolumeInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleVolumeInfo {
+    #[serde(default)]
+    authors: Vec<String>,
+    description: Option<String>,
+    published_date: Option<String>,
+    publisher: Option<String>,
+    #[serde(default)]
+    industry_identifiers: Vec<GoogleIdentifier>,
+    image_links: Option<GoogleImageLinks>,
+    #[serde(default)]
+    categories: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleIdentifier {
+    #[serde(rename = "type")]
+    kind: String,
+    identifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleImageLinks {
+    thumbnail: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenLibraryResponse {
+    #[serde(default)]
+    docs: Vec<OpenLibraryDoc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenLibraryDoc {
+    #[serde(default)]
+    author_name: Vec<String>,
+    first_publish_year: Option<i32>,
+    #[serde(default)]
+    isbn: Vec<String>,
+    cover_i: Option<u64>,
+    #[serde(default)]
+    publisher: Vec<String>,
+    #[serde(default)]
+    subject: Vec<String>,
+}
+
+fn publication_year(date: &str) -> Option<i32> {
+    date.get(..4)?.parse().ok()
+}
+
+async fn query_google_books(client: &reqwest::Client, query: &str) -> Result<Option<CatalogRecord>, String> {
+    let response = client
+        .get(GOOGLE_BOOKS_URL)
+        .query(&[("q", query), ("maxResults", "1")])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Google Books error: {}", response.status()));
+    }
+    let parsed: GoogleBooksResponse = response.json().await.map_err(|e| e.to_string())?;
+    Ok(parsed.items.into_iter().next().map(|volume| {
+        let info = volume.volume_info;
+        let isbn = info
+            .industry_identifiers
+            .iter()
+            .filter(|id| id.kind.starts_with("ISBN"))
+            .max_by_key(|id| id.kind == "ISBN_13")
+            .and_then(|id| normalize_isbn(&id.identifier));
+        CatalogRecord {
+            author: (!info.authors.is_empty()).then(|| info.authors.join(", ")),
+            isbn,
+            description: info.description,
+            publication_year: info.published_date.as_deref().and_then(publication_year),
+            publisher: info.publisher,
+            cover_url: info
+                .image_links
+                .and_then(|links| links.thumbnail)
+                .map(|url| url.replacen("http://", "https://", 1)),
+            subjects: info.categories,
+        }
+    }))
+}
+
+async fn query_open_library(
+    client: &reqwest::Client,
+    params: &[(&str, &str)],
+) -> Result<Option<CatalogRecord>, String> {
+    let response = client
+        .get(OPEN_LIBRARY_URL)
+        .query(params)
+        .query(&[("limit", "1")])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Open Library error: {}", response.status()));
+    }
+    let parsed: OpenLibraryResponse = response.json().await.map_err(|e| e.to_string())?;
+    Ok(parsed.docs.into_iter().next().map(|doc| CatalogRecord {
+        author: (!doc.author_name.is_empty()).then(|| doc.author_name.join(", ")),
+        isbn: doc.isbn.iter().find_map(|isbn| normalize_isbn(isbn)),
+        description: None,
+        publication_year: doc.first_publish_year,
+        publisher: doc.publisher.into_iter().next(),
+        cover_url: doc.cover_i.map(|id| format!("https://covers.openlibrary.org/b/id/{}-L.jpg", id)),
+        subjects: doc.subject.into_iter().take(10).collect(),
+    }))
+}
+
+fn fill<T>(slot: &mut Option<T>, value: Option<T>, field: &str, overridden: &[String]) {
+    if slot.is_none() && !overridden.iter().any(|name| name == field) {
+        *slot = value;
+    }
+}
+
+// Fills in what the record knows and the book does not; hand-set fields are kept.
+fn apply_record(book: &mut RecentBook, record: CatalogRecord, source: &str) {
+    let metadata = &mut book.metadata;
+    let overridden = metadata.overridden.clone();
+    if !overridden.iter().any(|name| name == "author") && book.author.is_none() {
+        book.author = record.author;
+    }
+    fill(&mut metadata.isbn, record.isbn, "isbn", &overridden);
+    fill(&mut metadata.description, record.description, "description", &overridden);
+    fill(&mut metadata.publication_year, record.publication_year, "publication_year", &overridden);
+    fill(&mut metadata.publisher, record.publisher, "publisher", &overridden);
+    fill(&mut metadata.cover_url, record.cover_url, "cover_url", &overridden);
+    if metadata.subjects.is_empty() {
+        metadata.subjects = record.subjects;
+    }
+    metadata.source.get_or_insert_with(|| source.to_string());
+}
+
+// The ISBN to look the book up by: a known one first, then one found in the file itself.
+fn lookup_isbn(handle: &tauri::AppHandle, book: &RecentBook) -> Option<String> {
+    book.metadata.isbn.clone().or_else(|| detect_book_isbn(handle, book))
+}
+
+// Looks the book up on Google Books and Open Library, by ISBN when one is known and by
+// title and author otherwise, and stores what they add. Values already present or set by
+// hand are kept; the first catalog to answer fills a field.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn enrich_book_metadata(handle: tauri::AppHandle, book_id: String) -> Result<BookMetadata, String> {
+    let book = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+        .ok_or_else(|| format!("Book not found: {}", book_id))?;
+    ensure_cloud_allowed(&handle, &book_id)?;
+
+    let isbn = lookup_isbn(&handle, &book);
+    let client = reqwest::Client::new();
+    let google_query = match &isbn {
+        Some(isbn) => format!("isbn:{}", isbn),
+        None => match &book.author {
+            Some(author) => format!("intitle:{} inauthor:{}", book.title, author),
+            None => format!("intitle:{}", book.title),
+        },
+    };
+    let mut library_params = vec![];
+    match &isbn {
+        Some(isbn) => library_params.push(("isbn", isbn.as_str())),
+        None => {
+            library_params.push(("title", book.title.as_str()));
+            if let Some(author) = &book.author {
+                library_params.push(("author", author.as_str()));
+            }
+        }
+    }
+
+    let mut records = Vec::new();
+    match query_google_books(&client, &google_query).await {
+        Ok(Some(record)) => records.push((record, "google_books")),
+        Ok(None) => {}
+        Err(e) => log::warn!("{}", e),
+    }
+    match query_open_library(&client, &library_params).await {
+        Ok(Some(record)) => records.push((record, "open_library")),
+        Ok(None) => {}
+        Err(e) => log::warn!("{}", e),
+    }
+    if records.is_empty() {
+        return Err(format!("No catalog entry found for \"{}\".", book.title));
+    }
+
+    update_recent_books(&handle, |data| {
+        let book = data
+            .books
+            .iter_mut()
+            .find(|b| b.id == book_id)
+            .ok_or_else(|| format!("Book not found: {}", book_id))?;
+        if book.metadata.isbn.is_none() && !book.metadata.overridden.iter().any(|name| name == "isbn") {
+            book.metadata.isbn = isbn.clone();
+        }
+        for (record, source) in records {
+            apply_record(book, record, source);
+        }
+        fill_series(book);
+        book.metadata.enriched_at = Some(Utc::now());
+        Ok(book.metadata.clone())
+    })?
+}
+
+// Sets metadata by hand. Each field given is stored and pinned against later enrichment.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_book_metadata(
+    handle: tauri::AppHandle,
+    book_id: String,
+    metadata: BookMetadataOverride,
+) -> Result<BookMetadata, String> {
+    if let Some(isbn) = &metadata.isbn {
+        if normalize_isbn(isbn).is_none() {
+            return Err(format!("Not a valid ISBN: {}", isbn));
+        }
+    }
+    update_recent_books(&handle, |data| {
+        let book = data
+            .books
+            .iter_mut()
+            .find(|b| b.id == book_id)
+            .ok_or_else(|| format!("Book not found: {}", book_id))?;
+        let mut pin = |field: &str| {
+            if !book.metadata.overridden.iter().any(|name| name == field) {
+                book.metadata.overridden.push(field.to_string());
+            }
+        };
+        if metadata.author.is_some() {
+            pin("author");
+        }
+        if metadata.isbn.is_some() {
+            pin("isbn");
+        }
+        if metadata.description.is_some() {
+            pin("description");
+        }
+        if metadata.publication_year.is_some() {
+            pin("publication_year");
+        }
+        if metadata.publisher.is_some() {
+            pin("publisher");
+        }
+        if metadata.cover_url.is_some() {
+            pin("cover_url");
+        }
+        if let Some(author) = metadata.author {
+            book.author = Some(author);
+        }
+        let stored = &mut book.metadata;
+        if let Some(isbn) = metadata.isbn.as_deref().and_then(normalize_isbn) {
+            stored.isbn = Some(isbn);
+        }
+        if metadata.description.is_some() {
+            stored.description = metadata.description;
+        }
+        if metadata.publication_year.is_some() {
+            stored.publication_year = metadata.publication_year;
+        }
+        if metadata.publisher.is_some() {
+            stored.publisher = metadata.publisher;
+        }
+        if metadata.cover_url.is_some() {
+            stored.cover_url = metadata.cover_url;
+        }
+        Ok(book.metadata.clone())
+    })?
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use tauri::Manager;
+
+use crate::entities::{load_book_entities, save_book_entities, BookEntities};
+use crate::{app_state, book_data_file_path, update_cache, update_recent_books, GenerationInfo};
+
+// Bumped when the pack layout changes incompatibly.
+const PACK_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReadingPosition {
+    last_page: u32,
+    progress: f32,
+}
+
+// Everything PDFRead knows about one book except the book itself. Book ids are content
+// hashes, so the recipient's own copy of the same file picks all of this up.
+#[derive(Debug, Serialize, Deserialize)]
+struct BookPack {
+    format_version: u32,
+    exported_at: DateTime<Utc>,
+    book_id: String,
+    title: Option<String>,
+    author: Option<String>,
+    reading_position: Option<ReadingPosition>,
+    translations: HashMap<String, String>,
+    #[serde(default)]
+    generations: HashMap<String, GenerationInfo>,
+    #[serde(default)]
+    glossary: BookEntities,
+    // Kept by the reader, so passed in on export and handed back on import as they were.
+    #[serde(default)]
+    highlights: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookPackImport {
+    book_id: String,
+    title: Option<String>,
+    translations_added: usize,
+    glossary_imported: bool,
+    reading_position: Option<ReadingPosition>,
+    // For the reader to merge into its own.
+    highlights: Vec<serde_json::Value>,
+}
+
+// Cache keys start with the book id, so this picks out one book's translations.
+fn belongs_to_book(key: &str, book_id: &str) -> bool {
+    key.split('|').next() == Some(book_id)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_book_pack(
+    handle: tauri::AppHandle,
+    book_id: String,
+    path: String,
+    highlights: Vec<serde_json::Value>,
+) -> Result<usize, String> {
+    // Validates the id the same way every per-book file does.
+    book_data_file_path(&handle, "entities", &book_id)?;
+
+    let state = handle.state::<app_state::AppState>();
+    let (translations, generations) = state.cache.read(&handle, |cache| {
+        let translations: HashMap<String, String> = cache
+            .entries
+            .iter()
+            .filter(|(key, _)| belongs_to_book(key, &book_id))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let generations: HashMap<String, GenerationInfo> = cache
+            .generations
+            .iter()
+            .filter(|(key, _)| translations.contains_key(*key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        (translations, generations)
+    })?;
+    let book = state
+        .recent_books
+        .read(&handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?;
+
+    let count = translations.len();
+    let pack = BookPack {
+        format_version: PACK_FORMAT_VERSION,
+        exported_at: Utc::now(),
+        title: book.as_ref().map(|b| b.title.clone()),
+        author: book.as_ref().and_then(|b| b.author.clone()),
+        reading_position: book.as_ref().map(|b| ReadingPosition {
+            last_page: b.last_page,
+            progress: b.progress,
+        }),
+        translations,
+        generations,
+        glossary: load_book_entities(&handle, &book_id)?,
+        highlights,
+        book_id,
+    };
+    let data = serde_json::to_string_pretty(&pack).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+// Merges a pack into the local data. Local translations and glossary win over the pack's;
+// the reading position is only applied to a book that has not been started yet. Highlights
+// are returned for the reader to merge.
+#[tauri::command(rename_all = "camelCase")]
+pub fn import_book_pack(handle: tauri::AppHandle, path: String) -> Result<BookPackImport, String> {
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let pack: BookPack = serde_json::from_str(&data).map_err(|e| format!("Not a valid book pack: {}", e))?;
+    if pack.format_version > PACK_FORMAT_VERSION {
+        return Err(format!(
+            "This book pack was exported by a newer version of PDFRead (format {}).",
+            pack.format_version
+        ));
+    }
+    let book_id = pack.book_id;
+    book_data_file_path(&handle, "entities", &book_id)?;
+
+    let translations_added = update_cache(&handle, |cache| {
+        let mut added = 0;
+        for (key, translation) in pack.translations {
+            if !belongs_to_book(&key, &book_id) || cache.entries.contains_key(&key) {
+                continue;
+            }
+            if let Some(generation) = pack.generations.get(&key) {
+                cache.generations.insert(key.clone(), generation.clone());
+            }
+            cache.entries.insert(key, translation);
+            added += 1;
+        }
+        added
+    })?;
+
+    let glossary_imported =
+        !pack.glossary.entities.is_empty() && load_book_entities(&handle, &book_id)?.entities.is_empty();
+    if glossary_imported {
+        save_book_entities(&handle, &book_id, &pack.glossary)?;
+    }
+
+    if let Some(position) = pack.reading_position {
+        update_recent_books(&handle, |data| {
+            if let Some(book) = data.books.iter_mut().find(|b| b.id == book_id && b.last_page <= 1) {
+                book.last_page = position.last_page.min(book.total_pages.max(1));
+                book.progress = position.progress.clamp(0.0, 100.0);
+            }
+        })?;
+    }
+
+    Ok(BookPackImport {
+        book_id,
+        title: pack.title,
+        translations_added,
+        glossary_imported,
+        reading_position: pack.reading_position,
+        highlights: pack.highlights,
+    })
+}
@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::book_text::{load_book_text, BookText};
+use crate::language::detect_language;
+use crate::page_words::tokenize_words;
+use crate::reading_activity::load_reading_activity;
+use crate::segmentation::segment;
+use crate::skip_ranges::load_skip_ranges;
+
+// Used until enough reading has been timed; a common figure for adult prose reading.
+const DEFAULT_WORDS_PER_MINUTE: f64 = 230.0;
+// Timed reading needed before the measured pace replaces the default.
+const MIN_MEASURED_MINUTES: i64 = 15;
+// Recent sessions the pace is measured over.
+const PACE_SESSIONS: usize = 100;
+// LIX counts words longer than this as long.
+const LIX_LONG_WORD_CHARS: usize = 6;
+// Languages written without spaces, where word and sentence length formulas do not apply.
+const UNSPACED_LANGUAGES: &[&str] = &["zh", "ja", "ko", "th", "lo", "km", "my"];
+
+#[derive(Debug, Serialize)]
+pub struct BookStatistics {
+    book_id: String,
+    language: Option<String>,
+    pages: usize,
+    word_count: usize,
+    unique_words: usize,
+    sentence_count: usize,
+    average_sentence_length: f64,
+    // Flesch reading ease, English only: higher is easier, 60-70 is plain English.
+    flesch_reading_ease: Option<f64>,
+    // LIX: below 30 is very easy, above 60 very hard. Not given for unspaced scripts.
+    lix: Option<f64>,
+    words_per_minute: f64,
+    // Whether `words_per_minute` comes from the user's reading sessions or is the default.
+    pace_measured: bool,
+    estimated_reading_minutes: u64,
+}
+
+fn word_count(text: &BookText) -> usize {
+    text.pages.iter().flat_map(|p| &p.paragraphs).map(|p| tokenize_words(&p.text).len()).sum()
+}
+
+// Vowel groups, less a silent final "e"; good enough for Flesch.
+fn english_syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = "aeiouy".contains(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    if count > 1 && word.ends_with('e') && !word.ends_with("le") {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+// Words per minute over recent reading sessions of books with extracted text, when enough
+// reading has been timed.
+fn measured_words_per_minute(handle: &tauri::AppHandle) -> Result<Option<f64>, String> {
+    let activity = load_reading_activity(handle)?;
+    let mut by_book: HashMap<String, (u32, i64)> = HashMap::new();
+    for session in activity.sessions.iter().rev().filter(|s| s.pages > 0 && s.minutes() > 0).take(PACE_SESSIONS) {
+        let (pages, minutes) = by_book.entry(session.book_id.clone()).or_default();
+        *pages += session.pages;
+        *minutes += session.minutes();
+    }
+
+    let (mut words, mut minutes) = (0.0, 0);
+    for (book_id, (pages, book_minutes)) in by_book {
+        let text = load_book_text(handle, &book_id)?;
+        if text.pages.is_empty() {
+            continue;
+        }
+        let words_per_page = word_count(&text) as f64 / text.pages.len() as f64;
+        words += words_per_page * pages as f64;
+        minutes += book_minutes;
+    }
+    Ok((minutes >= MIN_MEASURED_MINUTES).then(|| words / minutes as f64).filter(|wpm| *wpm > 0.0))
+}
+
+// Length and difficulty of a book's extracted text, and how long it would take to read at
+// the user's pace. Pages in the book's skip ranges are left out.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_book_statistics(handle: tauri::AppHandle, book_id: String) -> Result<BookStatistics, String> {
+    let mut text = load_book_text(&handle, &book_id)?;
+    if text.pages.is_empty() {
+        return Err(format!("No extracted text stored for book: {}", book_id));
+    }
+    let skipped = load_skip_ranges(&handle, &book_id)?;
+    text.pages.retain(|p| !skipped.skips_page(p.page));
+    let sample: Vec<&str> = text.pages.iter().flat_map(|p| &p.paragraphs).take(50).map(|p| p.text.as_str()).collect();
+    let language = detect_language(&sample.join(" "));
+    let lang = language.as_deref().unwrap_or("en");
+    let spaced = !UNSPACED_LANGUAGES.contains(&lang);
+
+    let (mut words, mut sentences, mut long_words, mut syllables) = (0, 0, 0, 0);
+    let mut unique = HashSet::new();
+    for paragraph in text.pages.iter().flat_map(|p| &p.paragraphs) {
+        sentences += segment(&paragraph.text, lang, None).len();
+        for token in tokenize_words(&paragraph.text) {
+            words += 1;
+            unique.insert(token.text.to_lowercase());
+            if token.text.chars().count() > LIX_LONG_WORD_CHARS {
+                long_words += 1;
+            }
+            if lang == "en" {
+                syllables += english_syllables(token.text);
+            }
+        }
+    }
+
+    let words_per_sentence = words as f64 / sentences.max(1) as f64;
+    let flesch_reading_ease = (lang == "en" && words > 0)
+        .then(|| 206.835 - 1.015 * words_per_sentence - 84.6 * (syllables as f64 / words as f64));
+    let lix = (spaced && words > 0).then(|| words_per_sentence + 100.0 * long_words as f64 / words as f64);
+    let measured = measured_words_per_minute(&handle)?;
+    let words_per_minute = measured.unwrap_or(DEFAULT_WORDS_PER_MINUTE);
+
+    Ok(BookStatistics {
+        book_id,
+        language,
+        pages: text.pages.len(),
+        word_count: words,
+        unique_words: unique.len(),
+        sentence_count: sentences,
+        average_sentence_length: words_per_sentence,
+        flesch_reading_ease,
+        lix,
+        words_per_minute,
+        pace_measured: measured.is_some(),
+        estimated_reading_minutes: (words as f64 / words_per_minute).ceil() as u64,
+    })
+}
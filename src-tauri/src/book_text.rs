@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::quarantine::parse_or_quarantine;
+use crate::{book_data_file_path, isbn};
+use crate::structure::{classify_block, BlockKind};
+use crate::text_cleanup::{clean_text, load_cleanup_options, strip_running_paragraphs, CleanupOptions};
+
+// Extracted text is produced by the frontend (pdf.js / epub.js) and mirrored here so
+// background work such as prefetching can run without the reader view being open.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageParagraph {
+    pub sid: String,
+    pub text: String,
+    #[serde(default)]
+    pub kind: BlockKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookPage {
+    pub page: u32,
+    // Chapter or section title, when the format provides one (EPUB).
+    #[serde(default)]
+    pub title: Option<String>,
+    pub paragraphs: Vec<PageParagraph>,
+    // The paragraphs as extracted, before cleanup, so the page can be cleaned again when the
+    // book's cleanup options change.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extracted: Vec<PageParagraph>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct BookText {
+    pub pages: Vec<BookPage>,
+}
+
+impl BookText {
+    pub fn page(&self, page: u32) -> Option<&BookPage> {
+        self.pages.iter().find(|p| p.page == page)
+    }
+}
+
+fn book_text_file_path(handle: &tauri::AppHandle, book_id: &str) -> Result<PathBuf, String> {
+    book_data_file_path(handle, "book_text", book_id)
+}
+
+pub fn load_book_text(handle: &tauri::AppHandle, book_id: &str) -> Result<BookText, String> {
+    let path = book_text_file_path(handle, book_id)?;
+    if !path.exists() {
+        return Ok(BookText::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn save_book_text(handle: &tauri::AppHandle, book_id: &str, text: &BookText) -> Result<(), String> {
+    let path = book_text_file_path(handle, book_id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string(text).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// Cleans the page's extracted paragraphs into its paragraphs. All but tables and code are
+// cleaned; ones left empty (bare page numbers) are dropped.
+fn clean_page(page: &mut BookPage, options: &CleanupOptions) {
+    page.paragraphs = page
+        .extracted
+        .iter()
+        .filter_map(|paragraph| {
+            let mut paragraph = paragraph.clone();
+            // Reflow would merge a table's rows or a listing's lines, so those stay as extracted.
+            if !paragraph.kind.is_verbatim() {
+                paragraph.text = clean_text(&paragraph.text, options);
+            }
+            (!paragraph.text.is_empty()).then_some(paragraph)
+        })
+        .collect();
+}
+
+// Cleans every stored page of a book again with `options`. Pages stored without their
+// extracted paragraphs are left as they are.
+pub fn clean_stored_pages(handle: &tauri::AppHandle, book_id: &str, options: &CleanupOptions) -> Result<(), String> {
+    let mut text = load_book_text(handle, book_id)?;
+    if text.pages.is_empty() {
+        return Ok(());
+    }
+    for page in text.pages.iter_mut().filter(|p| !p.extracted.is_empty()) {
+        clean_page(page, options);
+    }
+    strip_running_paragraphs(handle, book_id, &mut text, &[], options)?;
+    save_book_text(handle, book_id, &text)
+}
+
+// Upserts the given pages; pages not mentioned are kept as they are. Paragraphs are tagged
+// as body, caption, table or code and cleaned with the book's cleanup options; ones repeated
+// as running headers and footers are dropped. The extracted paragraphs are kept alongside.
+#[tauri::command(rename_all = "camelCase")]
+pub fn store_book_pages(handle: tauri::AppHandle, book_id: String, pages: Vec<BookPage>) -> Result<(), String> {
+    let options = load_cleanup_options(&handle, &book_id)?;
+    let mut text = load_book_text(&handle, &book_id)?;
+    let new_pages: Vec<u32> = pages.iter().map(|p| p.page).collect();
+    for mut page in pages {
+        for paragraph in &mut page.paragraphs {
+            if paragraph.kind == BlockKind::Body {
+                paragraph.kind = classify_block(&paragraph.text);
+            }
+        }
+        page.extracted = std::mem::take(&mut page.paragraphs);
+        clean_page(&mut page, &options);
+        text.pages.retain(|p| p.page != page.page);
+        text.pages.push(page);
+    }
+    text.pages.sort_by_key(|p| p.page);
+    strip_running_paragraphs(&handle, &book_id, &mut text, &new_pages, &options)?;
+    save_book_text(&handle, &book_id, &text)?;
+    isbn::note_stored_pages(&handle, &book_id, &new_pages);
+    Ok(())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_book_page(handle: tauri::AppHandle, book_id: String, page: u32) -> Result<Option<BookPage>, String> {
+    let text = load_book_text(&handle, &book_id)?;
+    Ok(text.page(page).cloned())
+}
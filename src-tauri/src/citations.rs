@@ -0,0 +1,327 @@
+use serde::{Deserialize, Serialize};
+
+use crate::book_text::load_book_text;
+use crate::encryption::{read_store_file, write_store_file};
+use crate::quarantine::parse_or_quarantine;
+use crate::{book_data_file_path, ensure_cloud_allowed};
+
+// Headings that open a reference list.
+const REFERENCE_HEADINGS: &[&str] = &[
+    "references",
+    "bibliography",
+    "works cited",
+    "literature cited",
+    "literatur",
+    "literaturverzeichnis",
+    "bibliographie",
+    "références",
+    "referencias",
+    "bibliografía",
+    "参考文献",
+];
+// Headings that end it.
+const SECTION_HEADINGS: &[&str] = &["appendix", "index", "notes", "acknowledgements", "acknowledgments"];
+// Crossref asks for a descriptive User-Agent with a contact URL.
+const CROSSREF_USER_AGENT: &str = "PDFRead (https://github.com/everettjf/PDFRead)";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Citation {
+    index: usize,
+    raw: String,
+    authors: Vec<String>,
+    title: Option<String>,
+    year: Option<u32>,
+    doi: Option<String>,
+    // Journal, proceedings or publisher, when Crossref knows it.
+    container: Option<String>,
+    url: Option<String>,
+    #[serde(default)]
+    resolved: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct CitationData {
+    citations: Vec<Citation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrossrefResponse {
+    message: CrossrefWork,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrossrefWork {
+    #[serde(default)]
+    title: Vec<String>,
+    #[serde(default)]
+    author: Vec<CrossrefAuthor>,
+    #[serde(default, rename = "container-title")]
+    container_title: Vec<String>,
+    #[serde(default)]
+    publisher: Option<String>,
+    #[serde(default)]
+    issued: Option<CrossrefDate>,
+    #[serde(default, rename = "URL")]
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrossrefAuthor {
+    #[serde(default)]
+    given: Option<String>,
+    #[serde(default)]
+    family: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrossrefDate {
+    #[serde(default, rename = "date-parts")]
+    date_parts: Vec<Vec<Option<u32>>>,
+}
+
+fn load_citations(handle: &tauri::AppHandle, book_id: &str) -> Result<CitationData, String> {
+    let path = book_data_file_path(handle, "citations", book_id)?;
+    if !path.exists() {
+        return Ok(CitationData::default());
+    }
+    let data = read_store_file(handle, &path)?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_citations(handle: &tauri::AppHandle, book_id: &str, citations: &CitationData) -> Result<(), String> {
+    let path = book_data_file_path(handle, "citations", book_id)?;
+    let data = serde_json::to_string_pretty(citations).map_err(|e| e.to_string())?;
+    write_store_file(handle, &path, &data)
+}
+
+fn heading_matches(text: &str, headings: &[&str]) -> bool {
+    let heading = text
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace())
+        .trim_end_matches(':')
+        .to_lowercase();
+    heading.len() <= 40 && headings.contains(&heading.as_str())
+}
+
+// Where entries start inside a run of reflowed text: "[12] " or "12. " at a word start.
+fn entry_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut previous = ' ';
+    for (index, c) in text.char_indices() {
+        if index > 0 && previous.is_whitespace() && (c == '[' || c.is_ascii_digit()) {
+            let rest = &text[index..];
+            let number = rest.trim_start_matches('[');
+            let digits = number.chars().take_while(char::is_ascii_digit).count();
+            let after = &number[digits..];
+            let bracketed = rest.starts_with('[') && after.starts_with(']');
+            let numbered = !rest.starts_with('[') && after.starts_with(". ");
+            if (1..=3).contains(&digits) && (bracketed || numbered) {
+                starts.push(index);
+            }
+        }
+        previous = c;
+    }
+    starts
+}
+
+// Splits the paragraphs of a reference section into single entries.
+fn split_entries(paragraphs: &[&str]) -> Vec<String> {
+    let mut entries = Vec::new();
+    for paragraph in paragraphs {
+        let starts = entry_starts(paragraph);
+        for (i, start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(paragraph.len());
+            let entry = paragraph[*start..end].trim();
+            if entry.chars().filter(|c| c.is_alphabetic()).count() >= 10 {
+                entries.push(entry.to_string());
+            }
+        }
+    }
+    entries
+}
+
+// "10.1000/xyz123" from "doi:10.1000/xyz123.", "https://doi.org/10.1000/xyz123" and the like.
+fn find_doi(text: &str) -> Option<String> {
+    let start = text.find("10.")?;
+    let candidate: String = text[start..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && !matches!(c, '"' | '<' | '>'))
+        .collect();
+    let doi = candidate.trim_end_matches(['.', ',', ';', ')', ']']);
+    let (prefix, suffix) = doi.split_once('/')?;
+    (prefix.len() > 3 && prefix[3..].chars().all(|c| c.is_ascii_digit() || c == '.') && !suffix.is_empty())
+        .then(|| doi.to_string())
+}
+
+// First plausible publication year, with its byte range.
+fn find_year(text: &str) -> Option<(u32, usize, usize)> {
+    let bytes = text.as_bytes();
+    (0..bytes.len().saturating_sub(3)).find_map(|i| {
+        let digits = &bytes[i..i + 4];
+        let bounded =
+            (i == 0 || !bytes[i - 1].is_ascii_digit()) && bytes.get(i + 4).is_none_or(|b| !b.is_ascii_digit());
+        if !bounded || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let year: u32 = std::str::from_utf8(digits).ok()?.parse().ok()?;
+        (1500..=2100).contains(&year).then_some((year, i, i + 4))
+    })
+}
+
+fn strip_entry_number(entry: &str) -> &str {
+    let rest = entry.trim_start_matches('[').trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() < entry.len() {
+        rest.trim_start_matches([']', '.']).trim_start()
+    } else {
+        entry
+    }
+}
+
+// Best-effort split of an entry into authors, year and title. Handles the common
+// "Authors (Year). Title. Venue." and "Authors. Title. Venue, Year." shapes.
+fn parse_entry(index: usize, raw: &str) -> Citation {
+    let body = strip_entry_number(raw);
+    let year = find_year(body);
+    let doi = find_doi(body);
+
+    // A year in the first half belongs to the author block ("Smith, J. (2019)."); a later one
+    // is part of the venue.
+    let (authors_part, rest) = match year {
+        Some((_, start, end)) if start > 0 && start < body.len() / 2 => {
+            let authors = body[..start].trim_end_matches(['(', ' ', ',']);
+            (authors, body[end..].trim_start_matches([')', '.', ',', ' ', ':']))
+        }
+        _ => body.split_once(". ").unwrap_or((body, "")),
+    };
+
+    let title = if let Some(open) = rest.find(['"', '“']) {
+        rest[open + rest[open..].chars().next().map_or(1, char::len_utf8)..]
+            .split(['"', '”'])
+            .next()
+            .map(str::to_string)
+    } else {
+        rest.split(". ").next().map(|t| t.trim_end_matches('.').to_string())
+    }
+    .filter(|t| !t.trim().is_empty());
+
+    let authors = authors_part
+        .split([';', '&'])
+        .flat_map(|part| part.split(" and "))
+        .map(|name| name.trim().trim_end_matches([',', '.']).to_string())
+        .filter(|name| !name.is_empty() && name.len() <= 80)
+        .collect();
+
+    Citation {
+        index,
+        raw: raw.to_string(),
+        authors,
+        title,
+        year: year.map(|(year, _, _)| year),
+        url: doi.as_ref().map(|doi| format!("https://doi.org/{}", doi)),
+        doi,
+        container: None,
+        resolved: false,
+    }
+}
+
+async fn resolve_doi(client: &reqwest::Client, citation: &mut Citation) -> Result<(), String> {
+    let Some(doi) = &citation.doi else {
+        return Ok(());
+    };
+    let response = client
+        .get(format!("https://api.crossref.org/works/{}", doi))
+        .header("User-Agent", CROSSREF_USER_AGENT)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Crossref error for {}: {}", doi, response.status()));
+    }
+    let work = response.json::<CrossrefResponse>().await.map_err(|e| e.to_string())?.message;
+
+    if let Some(title) = work.title.into_iter().next() {
+        citation.title = Some(title);
+    }
+    let authors: Vec<String> = work
+        .author
+        .into_iter()
+        .filter_map(|a| match (a.given, a.family, a.name) {
+            (Some(given), Some(family), _) => Some(format!("{} {}", given, family)),
+            (None, Some(family), _) => Some(family),
+            (_, None, name) => name,
+        })
+        .collect();
+    if !authors.is_empty() {
+        citation.authors = authors;
+    }
+    if let Some(year) = work
+        .issued
+        .and_then(|d| d.date_parts.into_iter().next())
+        .and_then(|parts| parts.into_iter().next().flatten())
+    {
+        citation.year = Some(year);
+    }
+    citation.container = work.container_title.into_iter().next().or(work.publisher);
+    citation.url = work.url.or(citation.url.take());
+    citation.resolved = true;
+    Ok(())
+}
+
+// Parses the book's reference section(s) into structured entries and stores them. With
+// `resolve_dois`, entries that carry a DOI are completed from Crossref; lookups that fail
+// leave the parsed entry as it is. Entries already resolved are not looked up again.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn extract_citations(
+    handle: tauri::AppHandle,
+    book_id: String,
+    resolve_dois: Option<bool>,
+) -> Result<Vec<Citation>, String> {
+    let text = load_book_text(&handle, &book_id)?;
+    let mut section: Vec<&str> = Vec::new();
+    let mut in_references = false;
+    for paragraph in text.pages.iter().flat_map(|p| p.paragraphs.iter()) {
+        if heading_matches(&paragraph.text, REFERENCE_HEADINGS) {
+            in_references = true;
+        } else if heading_matches(&paragraph.text, SECTION_HEADINGS) {
+            in_references = false;
+        } else if in_references {
+            section.push(&paragraph.text);
+        }
+    }
+
+    let previous = load_citations(&handle, &book_id)?;
+    let mut citations: Vec<Citation> = split_entries(&section)
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            previous
+                .citations
+                .iter()
+                .find(|c| c.resolved && c.raw == *raw)
+                .cloned()
+                .map(|c| Citation { index, ..c })
+                .unwrap_or_else(|| parse_entry(index, raw))
+        })
+        .collect();
+
+    if resolve_dois.unwrap_or(false) && citations.iter().any(|c| c.doi.is_some() && !c.resolved) {
+        ensure_cloud_allowed(&handle, &book_id)?;
+        let client = reqwest::Client::new();
+        for citation in citations.iter_mut().filter(|c| c.doi.is_some() && !c.resolved) {
+            if let Err(e) = resolve_doi(&client, citation).await {
+                log::warn!("{}", e);
+            }
+        }
+    }
+
+    save_citations(
+        &handle,
+        &book_id,
+        &CitationData {
+            citations: citations.clone(),
+        },
+    )?;
+    Ok(citations)
+}
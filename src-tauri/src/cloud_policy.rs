@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::quarantine::parse_or_quarantine;
+use crate::sync_conflicts::with_write_lock;
+use crate::{app_config_dir, read_recent_books_file};
+
+// Whether each book may be sent to cloud providers. Kept apart from the recent books, which are
+// trimmed, so an opt-out holds after the book drops off the list; entries are never removed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CloudPolicyData {
+    books: HashMap<String, BookCloudPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BookCloudPolicy {
+    pub cloud_allowed: bool,
+}
+
+impl Default for BookCloudPolicy {
+    fn default() -> Self {
+        Self { cloud_allowed: true }
+    }
+}
+
+fn cloud_policy_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("cloud_policy.json"))
+}
+
+// The first time, the policy is taken from the books still in the recent list.
+pub fn read_cloud_policy_file(handle: &tauri::AppHandle) -> Result<CloudPolicyData, String> {
+    let path = cloud_policy_file_path(handle)?;
+    if !path.exists() {
+        let books = read_recent_books_file(handle)?
+            .books
+            .iter()
+            .map(|b| (b.id.clone(), BookCloudPolicy { cloud_allowed: b.cloud_allowed }))
+            .collect();
+        return Ok(CloudPolicyData { books });
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn write_cloud_policy_file(handle: &tauri::AppHandle, data: &CloudPolicyData) -> Result<(), String> {
+    let path = cloud_policy_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    with_write_lock(&path, || fs::write(&path, data).map_err(|e| e.to_string()))
+}
+
+// None for a book the app has never opened.
+pub fn policy(handle: &tauri::AppHandle, book_id: &str) -> Result<Option<BookCloudPolicy>, String> {
+    handle.state::<AppState>().cloud_policy.read(handle, |data| data.books.get(book_id).copied())
+}
+
+// Makes a book known, allowed by default; an existing policy is kept.
+pub fn register(handle: &tauri::AppHandle, book_id: &str) -> Result<BookCloudPolicy, String> {
+    handle.state::<AppState>().cloud_policy.update(handle, |data| *data.books.entry(book_id.to_string()).or_default())
+}
+
+pub fn set_cloud_allowed(handle: &tauri::AppHandle, book_id: &str, allowed: bool) -> Result<(), String> {
+    handle.state::<AppState>().cloud_policy.update(handle, |data| {
+        data.books.entry(book_id.to_string()).or_default().cloud_allowed = allowed;
+    })
+}
@@ -0,0 +1,250 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::oneshot;
+
+use crate::{private_books, restricted_mode};
+use crate::{app_state, ensure_cloud_allowed, load_vocabulary};
+
+const DEFAULT_PORT: u16 = 47821;
+// How long a restart waits for the previous server to let go of its port.
+const STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompanionServerInfo {
+    address: String,
+    port: u16,
+    // Devices pass this as `Authorization: Bearer <token>` or `?token=`.
+    token: String,
+    lan: bool,
+}
+
+struct RunningServer {
+    info: CompanionServerInfo,
+    shutdown: oneshot::Sender<()>,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+// The opt-in read-only HTTP server for phones and tablets on the same network.
+#[derive(Default)]
+pub struct CompanionServer {
+    running: Mutex<Option<RunningServer>>,
+}
+
+#[derive(Clone)]
+struct ServerState {
+    handle: tauri::AppHandle,
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Flashcard {
+    front: String,
+    back: String,
+    phonetic: Option<String>,
+    source_lang: Option<String>,
+    target_lang: Option<String>,
+    added_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct BookProgress {
+    id: String,
+    title: String,
+    author: Option<String>,
+    progress: f32,
+    last_page: u32,
+    total_pages: u32,
+    last_opened_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+fn pairing_token() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn authorized(state: &ServerState, headers: &HeaderMap, query: &TokenQuery) -> bool {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or(query.token.as_deref()).is_some_and(|token| same_token(token, &state.token))
+}
+
+// Compares in time that does not depend on where the two differ, so the token cannot be
+// guessed byte by byte. Its length is fixed and not secret.
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(HashMap::from([("error", message)]))).into_response()
+}
+
+async fn flashcards(State(state): State<ServerState>, headers: HeaderMap, Query(query): Query<TokenQuery>) -> Response {
+    if !authorized(&state, &headers, &query) {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid pairing token.".to_string());
+    }
+    // Words saved from private books and books kept away from cloud providers stay here too.
+    let mut shared: HashMap<String, bool> = HashMap::new();
+    match load_vocabulary(&state.handle) {
+        Ok(vocabulary) => {
+            let cards: Vec<Flashcard> = vocabulary
+                .entries
+                .into_iter()
+                .filter(|entry| {
+                    entry.book_id.as_ref().is_none_or(|book_id| {
+                        *shared.entry(book_id.clone()).or_insert_with(|| book_shared(&state.handle, book_id))
+                    })
+                })
+                .map(|entry| Flashcard {
+                    back: entry
+                        .definitions
+                        .iter()
+                        .map(|d| format!("{} {}", d.pos, d.meanings))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    front: entry.word,
+                    phonetic: entry.phonetic,
+                    source_lang: entry.source_lang,
+                    target_lang: entry.target_lang,
+                    added_at: entry.added_at.to_rfc3339(),
+                })
+                .collect();
+            Json(cards).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+fn book_shared(handle: &tauri::AppHandle, book_id: &str) -> bool {
+    private_books::is_private(handle, book_id).is_ok_and(|private| !private)
+        && ensure_cloud_allowed(handle, book_id).is_ok()
+}
+
+async fn progress(State(state): State<ServerState>, headers: HeaderMap, Query(query): Query<TokenQuery>) -> Response {
+    if !authorized(&state, &headers, &query) {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid pairing token.".to_string());
+    }
+    // Private books and books kept away from cloud providers do not leave this machine either.
+    let books = state.handle.state::<app_state::AppState>().recent_books.read(&state.handle, |data| {
+        data.books
+            .iter()
+            .filter(|book| !book.private_lock)
+            .map(|book| BookProgress {
+                id: book.id.clone(),
+                title: book.title.clone(),
+                author: book.author.clone(),
+                progress: book.progress,
+                last_page: book.last_page,
+                total_pages: book.total_pages,
+                last_opened_at: book.last_opened_at.to_rfc3339(),
+            })
+            .collect::<Vec<_>>()
+    });
+    match books {
+        Ok(mut books) => {
+            books.retain(|book| ensure_cloud_allowed(&state.handle, &book.id).is_ok());
+            Json(books).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+// Starts the companion server, bound to localhost or, with `lan`, to every interface. A
+// fresh pairing token is issued on every start; restarting replaces a running server.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn start_companion_server(
+    handle: tauri::AppHandle,
+    lan: bool,
+    port: Option<u16>,
+) -> Result<CompanionServerInfo, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Starting the companion server")?;
+    if let Some(previous) = take_running(&handle) {
+        let _ = previous.shutdown.send(());
+        let _ = tokio::time::timeout(STOP_TIMEOUT, previous.task).await;
+    }
+
+    let ip = if lan { Ipv4Addr::UNSPECIFIED } else { Ipv4Addr::LOCALHOST };
+    let listener = bind(SocketAddr::from((ip, port.unwrap_or(DEFAULT_PORT))))
+        .map_err(|e| format!("Failed to start companion server: {}", e))?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+
+    let token = pairing_token();
+    let app = Router::new()
+        .route("/api/flashcards", get(flashcards))
+        .route("/api/progress", get(progress))
+        .with_state(ServerState {
+            handle: handle.clone(),
+            token: token.clone(),
+        });
+    let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+    let task = tauri::async_runtime::spawn(async move {
+        let server = axum::serve(listener, app).with_graceful_shutdown(async {
+            let _ = shutdown_signal.await;
+        });
+        if let Err(e) = server.await {
+            log::warn!("Companion server stopped: {}", e);
+        }
+    });
+
+    let info = CompanionServerInfo {
+        address: address.ip().to_string(),
+        port: address.port(),
+        token,
+        lan,
+    };
+    let server = handle.state::<CompanionServer>();
+    *server.running.lock().unwrap_or_else(|e| e.into_inner()) = Some(RunningServer {
+        info: info.clone(),
+        shutdown,
+        task,
+    });
+    Ok(info)
+}
+
+// SO_REUSEADDR lets a restart bind the port again while connections of the previous server
+// linger in TIME_WAIT. Only on Unix: on Windows it lets another process bind the same port and
+// take over its connections, and rebinding after TIME_WAIT works without it.
+fn bind(address: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    let socket = tokio::net::TcpSocket::new_v4()?;
+    #[cfg(unix)]
+    socket.set_reuseaddr(true)?;
+    socket.bind(address)?;
+    socket.listen(1024)
+}
+
+fn take_running(handle: &tauri::AppHandle) -> Option<RunningServer> {
+    handle.state::<CompanionServer>().running.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn stop_companion_server(handle: tauri::AppHandle) -> Result<(), String> {
+    if let Some(running) = take_running(&handle) {
+        let _ = running.shutdown.send(());
+    }
+    Ok(())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_companion_server(handle: tauri::AppHandle) -> Result<Option<CompanionServerInfo>, String> {
+    let server = handle.state::<CompanionServer>();
+    let running = server.running.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(running.as_ref().map(|running| running.info.clone()))
+}
@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use crate::book_text::load_book_text;
+use crate::{cached_unit_translation, extract_doc_id, read_cache, TranslateSentence, TranslationResult};
+
+// A sentence translated earlier, shown to the model so it keeps pronouns, tense and terms
+// consistent. It is never translated again.
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviousTranslation {
+    pub source: String,
+    pub translation: String,
+}
+
+// Sentence-mode sids carry a `#s<n>` suffix; the paragraph is what the book text knows.
+fn paragraph_sid(sid: &str) -> &str {
+    sid.split_once("#s").map_or(sid, |(paragraph, _)| paragraph)
+}
+
+// Up to `count` cached translations of the paragraphs just before `first` in the book's
+// text, stopping at a chapter boundary. Empty when the book text or cache has none.
+pub fn previous_translations(
+    handle: &tauri::AppHandle,
+    model: &str,
+    target_code: &str,
+    first: &TranslateSentence,
+    count: usize,
+) -> Vec<PreviousTranslation> {
+    let sid = paragraph_sid(&first.sid);
+    let Ok(text) = load_book_text(handle, extract_doc_id(sid)) else {
+        return Vec::new();
+    };
+    let mut preceding = Vec::new();
+    let mut chapter = None;
+    let mut found = false;
+    'pages: for page in &text.pages {
+        if page.title.is_some() && page.title != chapter {
+            chapter = page.title.clone();
+            preceding.clear();
+        }
+        for paragraph in &page.paragraphs {
+            if paragraph.sid == sid {
+                found = true;
+                break 'pages;
+            }
+            if !paragraph.kind.is_verbatim() {
+                preceding.push(paragraph);
+            }
+        }
+    }
+    if !found {
+        return Vec::new();
+    }
+
+    let start = preceding.len().saturating_sub(count);
+    read_cache(handle, |cache| {
+        preceding[start..]
+            .iter()
+            .filter_map(|paragraph| {
+                let unit = TranslateSentence {
+                    sid: paragraph.sid.clone(),
+                    text: paragraph.text.clone(),
+                    ..Default::default()
+                };
+                cached_unit_translation(cache, &unit, model, target_code)
+                    .map(|translation| PreviousTranslation { source: paragraph.text.clone(), translation })
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+// Moves the window on past a batch that was just translated, keeping the last `count`.
+pub fn advance(
+    previous: &mut Vec<PreviousTranslation>,
+    batch: &[TranslateSentence],
+    translations: &[TranslationResult],
+    count: usize,
+) {
+    for sentence in batch {
+        if let Some(item) = translations.iter().find(|item| item.sid == sentence.sid) {
+            previous.push(PreviousTranslation {
+                source: sentence.text.clone(),
+                translation: item.translation.clone(),
+            });
+        }
+    }
+    let excess = previous.len().saturating_sub(count);
+    previous.drain(..excess);
+}
+
+// Prompt section listing the established translations, or nothing when there are none.
+pub fn prompt_section(previous: &[PreviousTranslation]) -> String {
+    if previous.is_empty() {
+        return String::new();
+    }
+    format!(
+        "\nAlready translated (the text just before the input, for consistency of names, pronouns, tense and \
+         terminology; do not translate or return it again): {}",
+        serde_json::to_string(previous).unwrap_or_else(|_| "[]".to_string())
+    )
+}
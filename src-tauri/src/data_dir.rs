@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::Manager;
+use walkdir::WalkDir;
+
+use crate::app_state::AppState;
+use crate::jobs::JobRegistry;
+use crate::private_books;
+use crate::restricted_mode;
+
+const DATA_DIR_ARG: &str = "--data-dir";
+const DATA_DIR_ENV: &str = "PDFREAD_DATA_DIR";
+// Always kept in the default config directory, since it says where everything else lives.
+const LOCATION_FILE: &str = "data_location.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataDirSource {
+    Default,
+    Settings,
+    Env,
+    Cli,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct DataLocation {
+    data_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataDirInfo {
+    path: String,
+    default_path: String,
+    source: DataDirSource,
+}
+
+// The resolved storage root. Empty until `init` runs, in which case callers fall back to
+// the platform config directory.
+#[derive(Default)]
+pub struct DataDir {
+    resolved: RwLock<Option<(PathBuf, DataDirSource)>>,
+}
+
+impl DataDir {
+    pub fn current(&self) -> Option<PathBuf> {
+        self.resolved
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|(path, _)| path.clone())
+    }
+
+    fn set(&self, path: PathBuf, source: DataDirSource) {
+        *self.resolved.write().unwrap_or_else(|e| e.into_inner()) = Some((path, source));
+    }
+
+    fn source(&self) -> DataDirSource {
+        self.resolved
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|(_, source)| *source)
+            .unwrap_or(DataDirSource::Default)
+    }
+}
+
+pub fn default_config_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    handle
+        .path()
+        .app_config_dir()
+        .map_err(|_| "Failed to resolve app config directory.".to_string())
+}
+
+fn location_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(default_config_dir(handle)?.join(LOCATION_FILE))
+}
+
+fn load_location(handle: &tauri::AppHandle) -> Result<DataLocation, String> {
+    let path = location_file_path(handle)?;
+    if !path.exists() {
+        return Ok(DataLocation::default());
+    }
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map_err(|e| e.to_string())
+}
+
+fn save_location(handle: &tauri::AppHandle, location: &DataLocation) -> Result<(), String> {
+    let path = location_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(location).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn data_dir_from_args() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_ARG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg.strip_prefix(DATA_DIR_ARG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(value));
+        }
+    }
+    None
+}
+
+fn data_dir_from_env() -> Option<PathBuf> {
+    std::env::var_os(DATA_DIR_ENV)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+// Resolves the storage root: `--data-dir`, then PDFREAD_DATA_DIR, then the saved location,
+// then the platform config directory. Must run before anything touches the stores.
+pub fn init(handle: &tauri::AppHandle) -> Result<(), String> {
+    let (path, source) = if let Some(path) = data_dir_from_args() {
+        (path, DataDirSource::Cli)
+    } else if let Some(path) = data_dir_from_env() {
+        (path, DataDirSource::Env)
+    } else if let Some(path) = load_location(handle)?.data_dir {
+        (path, DataDirSource::Settings)
+    } else {
+        (default_config_dir(handle)?, DataDirSource::Default)
+    };
+    handle.state::<DataDir>().set(path, source);
+    Ok(())
+}
+
+fn data_dir_info(handle: &tauri::AppHandle) -> Result<DataDirInfo, String> {
+    let data_dir = handle.state::<DataDir>();
+    let default_path = default_config_dir(handle)?;
+    Ok(DataDirInfo {
+        path: data_dir
+            .current()
+            .unwrap_or_else(|| default_path.clone())
+            .to_string_lossy()
+            .to_string(),
+        default_path: default_path.to_string_lossy().to_string(),
+        source: data_dir.source(),
+    })
+}
+
+// Copies everything under `from` into `to`, skipping the location file. Returns the
+// relative paths copied, directories first.
+fn copy_tree(from: &Path, to: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut copied = Vec::new();
+    for entry in WalkDir::new(from) {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let relative = entry.path().strip_prefix(from).map_err(|e| e.to_string())?;
+        if relative.as_os_str().is_empty() || relative == Path::new(LOCATION_FILE) {
+            continue;
+        }
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+            copied.push(relative.to_path_buf());
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target).map_err(|e| format!("Failed to copy {}: {}", relative.display(), e))?;
+            let source_len = entry.metadata().map_err(|e| e.to_string())?.len();
+            let target_len = fs::metadata(&target).map_err(|e| e.to_string())?.len();
+            if source_len != target_len {
+                return Err(format!("Copy of {} is incomplete.", relative.display()));
+            }
+            copied.push(relative.to_path_buf());
+        }
+    }
+    Ok(copied)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_data_dir(handle: tauri::AppHandle) -> Result<DataDirInfo, String> {
+    data_dir_info(&handle)
+}
+
+// Moves the app's files to `new_path` and makes it the storage root. Files are copied and
+// checked first; the old copies are only removed once the new location is in use.
+#[tauri::command(rename_all = "camelCase")]
+pub fn migrate_data_dir(
+    handle: tauri::AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+    new_path: String,
+) -> Result<DataDirInfo, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Moving the data directory")?;
+    let data_dir = handle.state::<DataDir>();
+    if matches!(data_dir.source(), DataDirSource::Cli | DataDirSource::Env) {
+        return Err(format!(
+            "The data directory is set by {} or {}; remove it before moving the data.",
+            DATA_DIR_ARG, DATA_DIR_ENV
+        ));
+    }
+
+    let default_path = default_config_dir(&handle)?;
+    let current = data_dir.current().unwrap_or_else(|| default_path.clone());
+    let target = PathBuf::from(new_path.trim());
+    if !target.is_absolute() {
+        return Err("The data directory must be an absolute path.".to_string());
+    }
+    if target == current {
+        return data_dir_info(&handle);
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err("The new data directory cannot be inside the current one, or contain it.".to_string());
+    }
+    if target.exists()
+        && fs::read_dir(&target)
+            .map_err(|e| e.to_string())?
+            .any(|entry| entry.is_ok_and(|e| e.file_name() != LOCATION_FILE))
+    {
+        return Err(format!("The new data directory is not empty: {}", target.display()));
+    }
+
+    // Jobs stop and private books are sealed so none of them is copied in the clear. The stores
+    // go to disk and the copy is made with the flusher held off, so nothing is written to the old
+    // folder after it is copied.
+    jobs.cancel_all();
+    private_books::lock(&handle)?;
+    let mut copied = Vec::new();
+    handle.state::<AppState>().switch_files(&handle, || {
+        fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+        if current.exists() {
+            copied = copy_tree(&current, &target)?;
+        }
+        let location = DataLocation {
+            data_dir: (target != default_path).then(|| target.clone()),
+        };
+        save_location(&handle, &location)?;
+        let source = if location.data_dir.is_some() {
+            DataDirSource::Settings
+        } else {
+            DataDirSource::Default
+        };
+        data_dir.set(target.clone(), source);
+        Ok(())
+    })?;
+
+    // Best effort, and only what was copied: the old folder may hold unrelated files.
+    // Directories go last and are only removed once empty.
+    for relative in copied.iter().rev() {
+        let path = current.join(relative);
+        let _ = if path.is_dir() {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        };
+    }
+
+    data_dir_info(&handle)
+}
@@ -0,0 +1,241 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::profiles::ActiveProfile;
+use crate::response_cache::response_cache_file_path;
+use crate::restricted_mode;
+use crate::settings::{load_settings, save_settings};
+use crate::transliteration::transliteration_cache_path;
+use crate::{app_config_dir, cache_file_path};
+
+// Encrypted files start with this header, followed by salt, nonce and ciphertext.
+const MAGIC: &[u8] = b"PDFREAD-ENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+// Per-book annotation folders, one `<book_id>.json` each, kept encrypted along with the caches.
+// Extracted book text, pagination and other layout data are left as plaintext.
+const ENCRYPTED_BOOK_DIRS: &[&str] = &["alignments", "citations", "entities", "readings", "story"];
+
+const KEYCHAIN_SERVICE: &str = "com.xnu.pdfread";
+// Encryption is set per profile, so each profile keeps its passphrase under
+// `cache-passphrase:<profile>`. Passphrases from before that were shared under the bare name.
+const KEYCHAIN_ACCOUNT: &str = "cache-passphrase";
+
+// Secrets in the system keychain, one account each under the app's service name.
+fn keychain_entry(account: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, account).map_err(|e| e.to_string())
+}
+
+pub fn keychain_get(account: &str) -> Result<Option<String>, String> {
+    match keychain_entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read {} from keychain: {}", account, e)),
+    }
+}
+
+pub fn keychain_set(account: &str, secret: &str) -> Result<(), String> {
+    keychain_entry(account)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to store {} in keychain: {}", account, e))
+}
+
+pub fn keychain_delete(account: &str) -> Result<(), String> {
+    match keychain_entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn passphrase_account(profile: &str) -> String {
+    format!("{}:{}", KEYCHAIN_ACCOUNT, profile)
+}
+
+fn active_passphrase_account(handle: &tauri::AppHandle) -> Result<String, String> {
+    let profile = handle.state::<ActiveProfile>().current().ok_or_else(|| "No active profile.".to_string())?;
+    Ok(passphrase_account(&profile))
+}
+
+// Gives every profile its own copy of a passphrase shared from before profiles had one each,
+// then drops the shared entry. Runs once profiles are set up.
+pub fn split_shared_passphrase(profiles: &[String]) -> Result<(), String> {
+    let Some(shared) = keychain_get(KEYCHAIN_ACCOUNT)? else {
+        return Ok(());
+    };
+    for profile in profiles {
+        if keychain_get(&passphrase_account(profile))?.is_none() {
+            keychain_set(&passphrase_account(profile), &shared)?;
+        }
+    }
+    keychain_delete(KEYCHAIN_ACCOUNT)
+}
+
+fn load_passphrase(handle: &tauri::AppHandle) -> Result<Option<String>, String> {
+    keychain_get(&active_passphrase_account(handle)?)
+}
+
+fn require_passphrase(handle: &tauri::AppHandle) -> Result<String, String> {
+    load_passphrase(handle)?.ok_or_else(|| "Cache passphrase is missing from the keychain.".to_string())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key<Aes256Gcm> {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key.into()
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Failed to encrypt data.".to_string())?;
+
+    let mut output = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(MAGIC);
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+pub fn decrypt(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let body = &data[MAGIC.len()..];
+    if body.len() < SALT_LEN + NONCE_LEN {
+        return Err("Encrypted file is truncated.".to_string());
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, salt));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt data. The passphrase may be wrong.".to_string())
+}
+
+// Reads a store file, decrypting it when it carries the encryption header.
+// Plaintext files are still accepted so toggling encryption never loses data.
+pub fn read_store_file(handle: &tauri::AppHandle, path: &Path) -> Result<String, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let plaintext = if is_encrypted(&data) {
+        decrypt(&require_passphrase(handle)?, &data)?
+    } else {
+        data
+    };
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+// Writes a store file, encrypting it when cache encryption is enabled in settings.
+pub fn write_store_file(handle: &tauri::AppHandle, path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = if load_settings(handle)?.encrypt_cache {
+        encrypt(&require_passphrase(handle)?, contents.as_bytes())?
+    } else {
+        contents.as_bytes().to_vec()
+    };
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn encrypted_book_files(handle: &tauri::AppHandle) -> Result<Vec<PathBuf>, String> {
+    let config_dir = app_config_dir(handle)?;
+    let mut paths = Vec::new();
+    for dir in ENCRYPTED_BOOK_DIRS {
+        let Ok(entries) = fs::read_dir(config_dir.join(dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+#[derive(Debug, Serialize)]
+pub struct EncryptionStatus {
+    enabled: bool,
+    has_passphrase: bool,
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_cache_encryption_status(handle: tauri::AppHandle) -> Result<EncryptionStatus, String> {
+    Ok(EncryptionStatus {
+        enabled: load_settings(&handle)?.encrypt_cache,
+        has_passphrase: load_passphrase(&handle)?.is_some(),
+    })
+}
+
+// Toggles encryption for the active profile and rewrites its caches and annotation stores in the
+// new format. Other profiles keep their own passphrase and files.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_cache_encryption(
+    handle: tauri::AppHandle,
+    enabled: bool,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing cache encryption")?;
+    // Load with the current settings and passphrase before anything changes. The response and
+    // transliteration caches are only caches; one that cannot be read is dropped.
+    let cache = handle.state::<AppState>().cache.get(&handle)?;
+    let responses = handle.state::<AppState>().response_cache.get(&handle).unwrap_or_default();
+    let readings = handle.state::<AppState>().transliteration_cache.get(&handle).unwrap_or_default();
+    let book_files = encrypted_book_files(&handle)?
+        .into_iter()
+        .map(|path| Ok((read_store_file(&handle, &path)?, path)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let account = active_passphrase_account(&handle)?;
+    if enabled {
+        match passphrase.as_deref().map(str::trim) {
+            Some(value) if !value.is_empty() => keychain_set(&account, value)?,
+            _ => {
+                require_passphrase(&handle)?;
+            }
+        }
+    }
+
+    let mut settings = load_settings(&handle)?;
+    settings.encrypt_cache = enabled;
+    save_settings(&handle, &settings)?;
+    handle.state::<AppState>().cache.set(cache)?;
+    handle.state::<AppState>().response_cache.set(responses)?;
+    handle.state::<AppState>().transliteration_cache.set(readings)?;
+    // Rewrite right away rather than leaving plaintext on disk until the next flush. Held off
+    // from the flusher, so no write of theirs is still under way once this returns.
+    handle.state::<AppState>().unload_all(&handle)?;
+    for (data, path) in &book_files {
+        write_store_file(&handle, path, data)?;
+    }
+
+    // The passphrase goes only once nothing on disk needs it any more.
+    if !enabled {
+        let caches =
+            [cache_file_path(&handle)?, response_cache_file_path(&handle)?, transliteration_cache_path(&handle)?];
+        let paths = caches.into_iter().chain(book_files.into_iter().map(|(_, path)| path));
+        for path in paths {
+            if fs::read(&path).is_ok_and(|data| is_encrypted(&data)) {
+                return Err(format!("{} is still encrypted; the passphrase was kept.", path.display()));
+            }
+        }
+        keychain_delete(&account)?;
+    }
+    Ok(())
+}
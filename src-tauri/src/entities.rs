@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+
+use crate::book_text::{load_book_text, BookPage};
+use crate::encryption::{read_store_file, write_store_file};
+use crate::progress;
+use crate::quarantine::parse_or_quarantine;
+use crate::quota;
+use crate::restricted_mode;
+use crate::{
+    book_data_file_path, ensure_cloud_allowed, extract_json_object, load_openrouter_credentials, request_openrouter,
+    truncate_for_error,
+};
+
+// Roughly a few pages per request; keeps prompts well inside small context windows.
+const CHUNK_CHARS: usize = 8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Person,
+    Place,
+    Organization,
+    Term,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookEntity {
+    pub name: String,
+    pub kind: EntityKind,
+    pub description: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub first_page: u32,
+    pub mentions: u32,
+}
+
+impl BookEntity {
+    fn matches(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        self.name.to_lowercase() == name || self.aliases.iter().any(|a| a.to_lowercase() == name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct BookEntities {
+    pub entities: Vec<BookEntity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedEntity {
+    name: String,
+    kind: EntityKind,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntitiesResponse {
+    entities: Vec<ExtractedEntity>,
+}
+
+pub fn load_book_entities(handle: &tauri::AppHandle, book_id: &str) -> Result<BookEntities, String> {
+    let path = book_data_file_path(handle, "entities", book_id)?;
+    if !path.exists() {
+        return Ok(BookEntities::default());
+    }
+    let data = read_store_file(handle, &path)?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn save_book_entities(handle: &tauri::AppHandle, book_id: &str, entities: &BookEntities) -> Result<(), String> {
+    let path = book_data_file_path(handle, "entities", book_id)?;
+    let data = serde_json::to_string_pretty(entities).map_err(|e| e.to_string())?;
+    write_store_file(handle, &path, &data)
+}
+
+// Groups consecutive pages into chunks of roughly CHUNK_CHARS characters.
+fn chunk_pages(pages: &[BookPage]) -> Vec<(u32, String)> {
+    let mut chunks: Vec<(u32, String)> = Vec::new();
+    for page in pages {
+        let text: Vec<&str> = page.paragraphs.iter().map(|p| p.text.as_str()).collect();
+        let text = text.join("\n");
+        match chunks.last_mut() {
+            Some((_, chunk)) if chunk.len() + text.len() < CHUNK_CHARS => {
+                chunk.push_str("\n\n");
+                chunk.push_str(&text);
+            }
+            _ => chunks.push((page.page, text)),
+        }
+    }
+    chunks
+}
+
+fn build_entities_system_prompt() -> String {
+    [
+        "You are a named-entity extraction engine for books.",
+        "Find people, places, organizations and invented or technical terms.",
+        "Output STRICT JSON ONLY.",
+        "No markdown, no explanations, no extra text.",
+    ]
+    .join(" ")
+}
+
+fn build_entities_prompt(text: &str) -> String {
+    format!(
+        r#"Extract the named entities from this text.
+Return JSON in this exact format:
+{{"entities": [{{"name": "canonical name", "kind": "person|place|organization|term", "description": "one sentence based only on this text", "aliases": ["other names used"]}}]}}
+Text:
+{}"#,
+        text
+    )
+}
+
+fn merge_entity(entities: &mut Vec<BookEntity>, extracted: ExtractedEntity, page: u32) {
+    let name = extracted.name.trim().to_string();
+    if name.is_empty() {
+        return;
+    }
+    let existing = entities
+        .iter_mut()
+        .find(|e| e.matches(&name) || extracted.aliases.iter().any(|a| e.matches(a)));
+    match existing {
+        Some(entity) => {
+            entity.mentions += 1;
+            entity.first_page = entity.first_page.min(page);
+            if entity.description.is_empty() {
+                entity.description = extracted.description;
+            }
+            for alias in extracted.aliases.into_iter().chain(std::iter::once(name)) {
+                if !entity.matches(&alias) {
+                    entity.aliases.push(alias);
+                }
+            }
+        }
+        None => entities.push(BookEntity {
+            name,
+            kind: extracted.kind,
+            description: extracted.description,
+            aliases: extracted.aliases,
+            first_page: page,
+            mentions: 1,
+        }),
+    }
+}
+
+// Runs chunked NER over the book's extracted text and stores the merged entity list.
+// Reports progress after each chunk.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn extract_entities(handle: tauri::AppHandle, model: String, book_id: String) -> Result<Vec<BookEntity>, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Entity extraction")?;
+    ensure_cloud_allowed(&handle, &book_id)?;
+    let text = load_book_text(&handle, &book_id)?;
+    if text.pages.is_empty() {
+        return Err(format!("No extracted text stored for book: {}", book_id));
+    }
+
+    let credentials = load_openrouter_credentials(&handle)?.for_feature(quota::FEATURE_ENTITIES).pin_temperature();
+    let system_prompt = build_entities_system_prompt();
+    let chunks = chunk_pages(&text.pages);
+    let mut entities: Vec<BookEntity> = Vec::new();
+
+    for (index, (page, chunk)) in chunks.iter().enumerate() {
+        let user_prompt = build_entities_prompt(chunk);
+        let content = request_openrouter(&credentials, &model, 0.0, &system_prompt, &user_prompt).await?;
+        let json_content = extract_json_object(&content);
+        let parsed: EntitiesResponse = serde_json::from_str(&json_content).map_err(|e| {
+            format!("Failed to parse entities JSON: {} (content: {})", e, truncate_for_error(&json_content))
+        })?;
+        for extracted in parsed.entities {
+            merge_entity(&mut entities, extracted, *page);
+        }
+        let job_id = format!("entities:{}", book_id);
+        progress::emit(&handle, &job_id, "entity_extraction", index as u64 + 1, Some(chunks.len() as u64), None);
+    }
+
+    entities.sort_by(|a, b| b.mentions.cmp(&a.mentions).then(a.first_page.cmp(&b.first_page)));
+    save_book_entities(&handle, &book_id, &BookEntities { entities: entities.clone() })?;
+    Ok(entities)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_book_entities(handle: tauri::AppHandle, book_id: String) -> Result<Vec<BookEntity>, String> {
+    Ok(load_book_entities(&handle, &book_id)?.entities)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn lookup_entity(handle: tauri::AppHandle, book_id: String, name: String) -> Result<Option<BookEntity>, String> {
+    Ok(load_book_entities(&handle, &book_id)?
+        .entities
+        .into_iter()
+        .find(|e| e.matches(&name)))
+}
+
+// Entities whose name or alias appears in `text`, formatted as a short glossary for prompts.
+// With `max_page`, entities first seen after that page are left out.
+pub fn describe_mentioned_entities(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    text: &str,
+    max_page: Option<u32>,
+) -> Result<String, String> {
+    let lower = text.to_lowercase();
+    let lines: Vec<String> = load_book_entities(handle, book_id)?
+        .entities
+        .iter()
+        .filter(|e| max_page.is_none_or(|max| e.first_page <= max))
+        .filter(|e| {
+            std::iter::once(&e.name)
+                .chain(e.aliases.iter())
+                .any(|n| lower.contains(&n.to_lowercase()))
+        })
+        .map(|e| format!("- {}: {}", e.name, e.description))
+        .collect();
+    Ok(lines.join("\n"))
+}
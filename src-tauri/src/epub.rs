@@ -0,0 +1,332 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::hooks::{self, HookEvent};
+use crate::structure::{classify_block, BlockKind};
+use crate::{translate_sentences, TargetLanguage, TranslateSentence};
+
+// Tags that start or end a block of text.
+pub const BLOCK_TAGS: &[&str] = &[
+    "p", "div", "h1", "h2", "h3", "h4", "h5", "h6", "li", "ul", "ol", "blockquote", "section", "article", "aside",
+    "header", "footer", "figure", "figcaption", "table", "tr", "td", "th", "dt", "dd", "hr",
+];
+// Tags whose content is never reading text.
+pub const SKIPPED_TAGS: &[&str] = &["head", "script", "style", "svg"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChapterBlock {
+    pub sid: String,
+    pub kind: BlockKind,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranslatedBlock {
+    sid: String,
+    kind: BlockKind,
+    text: String,
+    // Code and tables carry their text unchanged; None for a block that could not be
+    // translated.
+    translation: Option<String>,
+}
+
+// Reads one file from an EPUB, either a zip archive or an unpacked bundle directory.
+fn read_epub_entry(path: &Path, name: &str) -> Result<String, String> {
+    if path.is_dir() {
+        return fs::read_to_string(path.join(name)).map_err(|e| format!("Failed to read {}: {}", name, e));
+    }
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut entry = archive.by_name(name).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents).map_err(|e| e.to_string())?;
+    Ok(contents)
+}
+
+// The package document (OPF) named in META-INF/container.xml.
+pub fn package_path(path: &Path) -> Result<String, String> {
+    let container = read_epub_entry(path, "META-INF/container.xml")?;
+    container
+        .split("full-path=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .map(str::to_string)
+        .ok_or_else(|| "EPUB container does not name a package document.".to_string())
+}
+
+pub fn read_package_document(path: &Path) -> Result<String, String> {
+    read_epub_entry(path, &package_path(path)?)
+}
+
+// Value of `name="..."` inside one tag's attribute text.
+pub fn attribute(tag: &str, name: &str) -> Option<String> {
+    let needle = format!("{}=\"", name);
+    let start = tag
+        .match_indices(&needle)
+        .map(|(index, _)| index)
+        .find(|&index| index == 0 || tag[..index].ends_with(char::is_whitespace))?
+        + needle.len();
+    tag[start..].split('"').next().map(str::to_string)
+}
+
+// Chapter hrefs in reading order, as listed by the package document's spine.
+pub fn spine_hrefs(path: &Path) -> Result<Vec<String>, String> {
+    let opf = read_package_document(path)?;
+    let tags = |prefix: &'static str| opf.split(prefix).skip(1).filter_map(|rest| rest.split('>').next());
+    let manifest: HashMap<String, String> = tags("<item ")
+        .filter_map(|tag| Some((attribute(tag, "id")?, attribute(tag, "href")?)))
+        .collect();
+    Ok(tags("<itemref ")
+        .filter_map(|tag| manifest.get(&attribute(tag, "idref")?).cloned())
+        .collect())
+}
+
+// Chapter hrefs are relative to the package document.
+pub fn chapter_entry_name(path: &Path, href: &str) -> Result<String, String> {
+    let opf_path = package_path(path)?;
+    let href = href.split('#').next().unwrap_or(href);
+    let mut parts: Vec<&str> = opf_path.split('/').collect();
+    parts.pop();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(segment),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Default)]
+struct BlockParser {
+    blocks: Vec<(BlockKind, String)>,
+    text: String,
+    pre_depth: usize,
+    code_depth: usize,
+    skip_depth: usize,
+    // Whether the current block has text outside and inside `<code>`, to spot paragraphs
+    // that are nothing but code.
+    plain_text: bool,
+    code_text: bool,
+}
+
+impl BlockParser {
+    fn push_text(&mut self, raw: &str) {
+        if self.skip_depth > 0 {
+            return;
+        }
+        if self.pre_depth == 0 && !raw.trim().is_empty() {
+            if self.code_depth > 0 {
+                self.code_text = true;
+            } else {
+                self.plain_text = true;
+            }
+        }
+        self.text.push_str(raw);
+    }
+
+    fn flush(&mut self) {
+        let decoded = html_escape::decode_html_entities(&self.text).to_string();
+        self.text.clear();
+        let code_only = self.code_text && !self.plain_text;
+        self.plain_text = false;
+        self.code_text = false;
+
+        if code_only {
+            let code = decoded.trim_matches('\n');
+            if !code.trim().is_empty() {
+                self.blocks.push((BlockKind::Code, code.to_string()));
+            }
+            return;
+        }
+        let text = collapse_whitespace(&decoded);
+        if !text.is_empty() {
+            self.blocks.push((classify_block(&text), text));
+        }
+    }
+
+    fn flush_pre(&mut self) {
+        let decoded = html_escape::decode_html_entities(&self.text).to_string();
+        self.text.clear();
+        self.plain_text = false;
+        self.code_text = false;
+        let code = decoded.trim_matches(['\n', '\r']);
+        if !code.trim().is_empty() {
+            self.blocks.push((BlockKind::Code, code.to_string()));
+        }
+    }
+
+    fn tag(&mut self, tag: &str) {
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        let name = name.rsplit(':').next().unwrap_or("");
+
+        if SKIPPED_TAGS.contains(&name) {
+            if closing {
+                self.skip_depth = self.skip_depth.saturating_sub(1);
+            } else if !self_closing {
+                self.skip_depth += 1;
+            }
+            return;
+        }
+        if self.skip_depth > 0 {
+            return;
+        }
+        match name {
+            "pre" if closing => {
+                self.pre_depth = self.pre_depth.saturating_sub(1);
+                if self.pre_depth == 0 {
+                    self.flush_pre();
+                }
+            }
+            "pre" if !self_closing => {
+                if self.pre_depth == 0 {
+                    self.flush();
+                }
+                self.pre_depth += 1;
+            }
+            "br" => self.text.push('\n'),
+            "code" if self.pre_depth == 0 && !self_closing => {
+                if closing {
+                    self.code_depth = self.code_depth.saturating_sub(1);
+                } else {
+                    self.code_depth += 1;
+                }
+            }
+            _ if self.pre_depth == 0 && BLOCK_TAGS.contains(&name) => self.flush(),
+            _ => {}
+        }
+    }
+
+    // A small tag scanner rather than a full HTML parser: chapters only need to be split into
+    // blocks, and listings kept with their line breaks and indentation.
+    fn parse(mut self, html: &str) -> Vec<(BlockKind, String)> {
+        let mut rest = html;
+        while let Some(open) = rest.find('<') {
+            self.push_text(&rest[..open]);
+            rest = &rest[open..];
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+            let Some(close) = rest.find('>') else {
+                break;
+            };
+            self.tag(&rest[1..close]);
+            rest = &rest[close + 1..];
+        }
+        self.push_text(rest);
+        if self.pre_depth > 0 {
+            self.flush_pre();
+        } else {
+            self.flush();
+        }
+        self.blocks
+    }
+}
+
+// Splits any HTML document into blocks the way chapters are split.
+pub fn html_blocks(html: &str) -> Vec<(BlockKind, String)> {
+    BlockParser::default().parse(html)
+}
+
+pub fn chapter_blocks(path: &str, book_id: &str, href: &str) -> Result<Vec<ChapterBlock>, String> {
+    let path = Path::new(path);
+    let html = read_epub_entry(path, &chapter_entry_name(path, href)?)?;
+    let chapter = href.split('#').next().unwrap_or(href);
+    Ok(BlockParser::default()
+        .parse(&html)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (kind, text))| ChapterBlock {
+            sid: format!("{}:{}#{}", book_id, chapter, index),
+            kind,
+            text,
+        })
+        .collect())
+}
+
+// A chapter's reading text, one block per line.
+pub fn chapter_text(path: &Path, href: &str) -> Result<String, String> {
+    let html = read_epub_entry(path, &chapter_entry_name(path, href)?)?;
+    let blocks: Vec<String> = BlockParser::default().parse(&html).into_iter().map(|(_, text)| text).collect();
+    Ok(blocks.join("\n"))
+}
+
+// Splits one EPUB chapter into blocks, tagging `<pre>` listings and paragraphs made only of
+// `<code>` as code so they are never translated.
+#[tauri::command(rename_all = "camelCase")]
+pub fn extract_epub_chapter(path: String, book_id: String, href: String) -> Result<Vec<ChapterBlock>, String> {
+    chapter_blocks(&path, &book_id, &href)
+}
+
+// Translates a chapter block by block. Code and tables are not sent; they come back verbatim,
+// in their original place between the translated blocks. Every block is returned, those left
+// untranslated without a translation.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn translate_epub_chapter(
+    handle: tauri::AppHandle,
+    path: String,
+    book_id: String,
+    href: String,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+) -> Result<Vec<TranslatedBlock>, String> {
+    let blocks = chapter_blocks(&path, &book_id, &href)?;
+    let sentences: Vec<TranslateSentence> = blocks
+        .iter()
+        .filter(|block| !block.kind.is_verbatim())
+        .map(|block| TranslateSentence {
+            sid: block.sid.clone(),
+            text: block.text.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut translations: HashMap<String, String> =
+        translate_sentences(&handle, &model, temperature, &target_language, sentences)
+            .await?
+            .into_iter()
+            .map(|item| (item.sid, item.translation))
+            .collect();
+
+    let data = serde_json::json!({
+        "kind": "epub_chapter",
+        "book_id": book_id,
+        "href": href,
+        "translated": translations.len(),
+    });
+    hooks::fire(&handle, HookEvent::TranslationJobCompleted, &book_id, data);
+
+    Ok(blocks
+        .into_iter()
+        .map(|block| {
+            let translation = if block.kind.is_verbatim() {
+                Some(block.text.clone())
+            } else {
+                translations.remove(&block.sid)
+            };
+            TranslatedBlock {
+                sid: block.sid,
+                kind: block.kind,
+                text: block.text,
+                translation,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,401 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use tauri::{Emitter, Manager};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+
+use crate::epub::{chapter_blocks, chapter_entry_name, collapse_whitespace, package_path, spine_hrefs, BLOCK_TAGS};
+use crate::epub::{attribute, SKIPPED_TAGS};
+use crate::hooks::{self, HookEvent};
+use crate::job_state::{self, PendingJob};
+use crate::jobs::JobRegistry;
+use crate::library_import::import_book_bytes;
+use crate::progress;
+use crate::quota;
+use crate::skip_ranges::load_skip_ranges;
+use crate::{app_state, ensure_cloud_allowed, translate_sentences, TargetLanguage, TranslateSentence};
+
+// Elements holding one block of reading text. Only those without blocks nested inside are
+// rewritten; the rest keep their original text.
+const TEXT_ELEMENTS: &[&str] =
+    &["p", "h1", "h2", "h3", "h4", "h5", "h6", "li", "dt", "dd", "figcaption", "td", "th", "blockquote"];
+// Elements whose translation goes inside them after a line break, so lists and tables keep
+// their shape in bilingual books.
+const INNER_TRANSLATION: &[&str] = &["li", "dt", "dd", "td", "th"];
+const TRANSLATION_CLASS: &str = "pdfread-translation";
+const TRANSLATION_STYLE: &str = "<style>.pdfread-translation { opacity: 0.8; }</style>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EpubLayout {
+    // Translated text replaces the original; inline markup stays.
+    Replace,
+    // Each paragraph is followed by its translation.
+    #[default]
+    Bilingual,
+}
+
+// What a translation job runs with, kept in its job state so it can be resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EpubJobParams {
+    path: String,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+    layout: EpubLayout,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EpubTranslationError {
+    book_id: String,
+    message: String,
+}
+
+fn job_id(book_id: &str) -> String {
+    format!("epub-translation:{}", book_id)
+}
+
+fn tag_name(tag: &str) -> String {
+    let name = tag
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    name.rsplit(':').next().unwrap_or("").to_string()
+}
+
+// An element's text as the chapter parser sees it: tags dropped, entities decoded and
+// whitespace collapsed, so it can be matched against the translated blocks.
+fn element_text(content: &str) -> String {
+    let mut text = String::new();
+    let mut rest = content;
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>').map(|c| open + c) else {
+            rest = "";
+            break;
+        };
+        if tag_name(&rest[open + 1..close]) == "br" {
+            text.push(' ');
+        }
+        rest = &rest[close + 1..];
+    }
+    text.push_str(rest);
+    collapse_whitespace(&html_escape::decode_html_entities(&text))
+}
+
+// An element's content with its text nodes replaced: the translation goes in the first one
+// with text and the others are emptied. The inline markup between them (anchors the TOC points
+// at, links, images) is kept.
+fn replace_text_nodes(content: &str, translation: &str) -> String {
+    let escaped = html_escape::encode_text(translation);
+    let mut output = String::with_capacity(content.len() + translation.len());
+    let mut placed = false;
+    let mut rest = content;
+    while !rest.is_empty() {
+        let (text, markup) = rest.split_at(rest.find('<').unwrap_or(rest.len()));
+        if text.trim().is_empty() {
+            output.push_str(text);
+        } else if !placed {
+            output.push_str(&escaped);
+            placed = true;
+        }
+        let tag_end = markup.find('>').map_or(markup.len(), |end| end + 1);
+        output.push_str(&markup[..tag_end]);
+        rest = &markup[tag_end..];
+    }
+    if !placed {
+        output.push_str(&escaped);
+    }
+    output
+}
+
+struct OpenElement {
+    name: String,
+    tag: String,
+    content_start: usize,
+    leaf: bool,
+}
+
+// Puts the translations into a chapter's markup. Markup around the text elements (images,
+// links to styles, ids the TOC points at) is left as it is.
+fn rewrite_chapter(html: &str, translations: &HashMap<String, String>, layout: EpubLayout) -> String {
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    let mut stack: Vec<OpenElement> = Vec::new();
+    let mut skip_depth = 0usize;
+    let mut position = 0;
+    while let Some(offset) = html[position..].find('<') {
+        let start = position + offset;
+        if html[start..].starts_with("<!--") {
+            position = html[start..].find("-->").map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+        let Some(close) = html[start..].find('>') else {
+            break;
+        };
+        let end = start + close + 1;
+        position = end;
+        let tag = &html[start + 1..end - 1];
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag_name(tag);
+
+        if SKIPPED_TAGS.contains(&name.as_str()) || name == "pre" {
+            if closing {
+                skip_depth = skip_depth.saturating_sub(1);
+            } else if !self_closing {
+                skip_depth += 1;
+                stack.iter_mut().for_each(|open| open.leaf = false);
+            }
+            continue;
+        }
+        if skip_depth > 0 || tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if closing {
+            let Some(index) = stack.iter().rposition(|open| open.name == name) else {
+                continue;
+            };
+            let open = stack.remove(index);
+            stack.truncate(index);
+            if !open.leaf {
+                continue;
+            }
+            let content = &html[open.content_start..start];
+            let Some(translation) = translations.get(&element_text(content)) else {
+                continue;
+            };
+            let escaped = html_escape::encode_text(translation);
+            let edit = match layout {
+                EpubLayout::Replace => (open.content_start, start, replace_text_nodes(content, translation)),
+                EpubLayout::Bilingual if INNER_TRANSLATION.contains(&open.name.as_str()) => {
+                    (start, start, format!("<br/><span class=\"{}\">{}</span>", TRANSLATION_CLASS, escaped))
+                }
+                EpubLayout::Bilingual => {
+                    let class = match attribute(&open.tag, "class") {
+                        Some(class) => format!("{} {}", class, TRANSLATION_CLASS),
+                        None => TRANSLATION_CLASS.to_string(),
+                    };
+                    (end, end, format!("<{name} class=\"{}\">{}</{name}>", class, escaped, name = open.name))
+                }
+            };
+            edits.push(edit);
+        } else if TEXT_ELEMENTS.contains(&name.as_str()) && !self_closing {
+            stack.iter_mut().for_each(|open| open.leaf = false);
+            stack.push(OpenElement { name, tag: tag.to_string(), content_start: end, leaf: true });
+        } else if BLOCK_TAGS.contains(&name.as_str()) {
+            stack.iter_mut().for_each(|open| open.leaf = false);
+        }
+    }
+
+    let mut output = html.to_string();
+    for (start, end, replacement) in edits.into_iter().rev() {
+        output.replace_range(start..end, &replacement);
+    }
+    if layout == EpubLayout::Bilingual {
+        if let Some(head_end) = output.find("</head>") {
+            output.insert_str(head_end, TRANSLATION_STYLE);
+        }
+    }
+    output
+}
+
+// Marks the package as the translation: the title names the target language and, when the
+// original text is replaced, the book's language changes with it.
+fn rewrite_package(opf: &str, target_language: &TargetLanguage, layout: EpubLayout) -> String {
+    let mut opf = opf.to_string();
+    if let Some(title_end) = opf.find("</dc:title>") {
+        opf.insert_str(title_end, &format!(" ({})", html_escape::encode_text(&target_language.label)));
+    }
+    if layout == EpubLayout::Replace {
+        if let Some(start) = opf.find("<dc:language").and_then(|s| opf[s..].find('>').map(|e| s + e + 1)) {
+            if let Some(end) = opf[start..].find("</dc:language>").map(|e| start + e) {
+                opf.replace_range(start..end, &target_language.tag());
+            }
+        }
+    }
+    opf
+}
+
+// Every file in the EPUB, from a zip archive or an unpacked bundle directory.
+fn read_entries(path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut entries = Vec::new();
+    if path.is_dir() {
+        for entry in WalkDir::new(path).into_iter().filter_map(Result::ok).filter(|e| e.file_type().is_file()) {
+            let name = entry.path().strip_prefix(path).map_err(|e| e.to_string())?;
+            let name = name.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            entries.push((name, fs::read(entry.path()).map_err(|e| e.to_string())?));
+        }
+        return Ok(entries);
+    }
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        entries.push((entry.name().to_string(), bytes));
+    }
+    Ok(entries)
+}
+
+// Writes the same bytes for the same entries: they go in name order with a fixed timestamp,
+// so a book exported twice keeps its id.
+pub fn write_epub(mut entries: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>, String> {
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut buffer = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buffer);
+        let options = SimpleFileOptions::default().last_modified_time(zip::DateTime::default());
+        // The mimetype entry comes first and uncompressed, as readers sniff for it
+        let stored = options.compression_method(zip::CompressionMethod::Stored);
+        let deflated = options.compression_method(zip::CompressionMethod::Deflated);
+        zip.start_file("mimetype", stored).map_err(|e| e.to_string())?;
+        zip.write_all(b"application/epub+zip").map_err(|e| e.to_string())?;
+        for (name, bytes) in entries.into_iter().filter(|(name, _)| name != "mimetype") {
+            zip.start_file(name, deflated).map_err(|e| e.to_string())?;
+            zip.write_all(&bytes).map_err(|e| e.to_string())?;
+        }
+        zip.finish().map_err(|e| e.to_string())?;
+    }
+    Ok(buffer.into_inner())
+}
+
+// Every chapter waits for a provider slot, even one an earlier run finished: its translations
+// come from the cache only while they are still there, and a miss goes to the provider.
+async fn translate_book(
+    handle: &tauri::AppHandle,
+    job_id: &str,
+    book_id: &str,
+    params: &EpubJobParams,
+) -> Result<(), String> {
+    let EpubJobParams { path, model, temperature, target_language, layout } = params;
+    let (temperature, layout) = (*temperature, *layout);
+    let epub = Path::new(path);
+    let mut entries = read_entries(epub)?;
+    let hrefs = spine_hrefs(epub)?;
+    job_state::set_pending(handle, job_id, hrefs.clone());
+    let skipped = load_skip_ranges(handle, book_id)?;
+    let mut translated = 0;
+    for (index, href) in hrefs.iter().enumerate() {
+        let blocks = chapter_blocks(path, book_id, href)?;
+        let sentences: Vec<TranslateSentence> = blocks
+            .iter()
+            .filter(|block| !block.kind.is_verbatim())
+            .map(|block| TranslateSentence { sid: block.sid.clone(), text: block.text.clone(), ..Default::default() })
+            .collect();
+        // Skipped chapters are carried over untranslated.
+        let results = if skipped.skips_chapter(href) {
+            Vec::new()
+        } else {
+            let jobs = handle.state::<JobRegistry>();
+            let _slot = jobs.acquire_provider_slot().await?;
+            translate_sentences(handle, model, temperature, target_language, sentences).await?
+        };
+        translated += results.len();
+        let by_sid: HashMap<String, String> = results.into_iter().map(|item| (item.sid, item.translation)).collect();
+        let translations: HashMap<String, String> = blocks
+            .into_iter()
+            .filter_map(|block| Some((block.text, by_sid.get(&block.sid)?.clone())))
+            .collect();
+
+        let entry_name = chapter_entry_name(epub, href)?;
+        if let Some((_, bytes)) = entries.iter_mut().find(|(name, _)| *name == entry_name) {
+            let html = String::from_utf8_lossy(bytes).into_owned();
+            *bytes = rewrite_chapter(&html, &translations, layout).into_bytes();
+        }
+        let total = Some(hrefs.len() as u64);
+        progress::emit(handle, job_id, "epub_translation", index as u64 + 1, total, Some(href.clone()));
+        job_state::complete_unit(handle, job_id, href);
+    }
+
+    let opf_name = package_path(epub)?;
+    if let Some((_, bytes)) = entries.iter_mut().find(|(name, _)| *name == opf_name) {
+        let opf = String::from_utf8_lossy(bytes).into_owned();
+        *bytes = rewrite_package(&opf, target_language, layout).into_bytes();
+    }
+
+    let stem = epub.file_stem().and_then(|s| s.to_str()).unwrap_or("book");
+    let file_name = format!("{} ({}).epub", stem, target_language.tag());
+    let book = import_book_bytes(handle, &write_epub(entries)?, Some(file_name), None)?;
+    let data = serde_json::json!({
+        "kind": "epub_book",
+        "book_id": book_id,
+        "output_book_id": book.id,
+        "translated": translated,
+    });
+    hooks::fire(handle, HookEvent::TranslationJobCompleted, book_id, data);
+    let _ = handle.emit("book-imported", book);
+    Ok(())
+}
+
+fn spawn_job(handle: &tauri::AppHandle, jobs: &JobRegistry, book_id: String, params: EpubJobParams) -> String {
+    let id = job_id(&book_id);
+    let job_handle = handle.clone();
+    let job_key = id.clone();
+    let job = async move {
+        match translate_book(&job_handle, &job_key, &book_id, &params).await {
+            Ok(()) => job_state::remove(&job_handle, &job_key),
+            Err(message) => {
+                job_state::record_error(&job_handle, &job_key, &message);
+                if quota::is_quota_error(&message) {
+                    quota::announce_paused(&job_handle, &job_key, &message);
+                } else {
+                    let _ = job_handle.emit("epub-translation-error", EpubTranslationError { book_id, message });
+                }
+            }
+        }
+    };
+    jobs.spawn(handle, id.clone(), "epub_translation", job);
+    id
+}
+
+// Translates a whole EPUB in the background and adds the result to the library as a new
+// book, either with the text replaced or with each paragraph followed by its translation.
+// Images, styles and the table of contents are carried over; code and tables, and chapters in
+// the book's skip ranges, stay as they are. Progress is reported per chapter. Returns the job id.
+#[tauri::command(rename_all = "camelCase")]
+pub fn translate_epub_book(
+    handle: tauri::AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+    book_id: String,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+    layout: EpubLayout,
+) -> Result<String, String> {
+    let book = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+        .ok_or_else(|| format!("Book not found: {}", book_id))?;
+    let path = std::iter::once((book.file_type.as_str(), book.file_path.as_str()))
+        .chain(book.formats.iter().map(|f| (f.file_type.as_str(), f.file_path.as_str())))
+        .find(|(kind, _)| *kind == "epub")
+        .map(|(_, path)| path.to_string())
+        .ok_or_else(|| "The book has no EPUB format to translate.".to_string())?;
+    ensure_cloud_allowed(&handle, &book_id)?;
+
+    let params = EpubJobParams { path, model, temperature, target_language, layout };
+    let value = serde_json::to_value(&params).map_err(|e| e.to_string())?;
+    job_state::record(&handle, PendingJob::new(job_id(&book_id), "epub_translation", book_id.clone(), value))?;
+    Ok(spawn_job(&handle, &jobs, book_id, params))
+}
+
+// Picks up a translation job left unfinished, skipping the chapters it already translated.
+pub fn resume(handle: &tauri::AppHandle, jobs: &JobRegistry, job: &PendingJob) -> Result<(), String> {
+    let params: EpubJobParams = serde_json::from_value(job.params.clone()).map_err(|e| e.to_string())?;
+    if !Path::new(&params.path).exists() {
+        return Err(format!("File not found: {}", params.path));
+    }
+    ensure_cloud_allowed(handle, &job.book_id)?;
+    spawn_job(handle, jobs, job.book_id.clone(), params);
+    Ok(())
+}
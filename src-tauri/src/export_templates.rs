@@ -0,0 +1,124 @@
+use chrono::Utc;
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::app_config_dir;
+use crate::app_state::AppState;
+use crate::book_statistics::get_book_statistics;
+use crate::reading_report::generate_reading_report;
+use crate::vocab_export::filtered_entries;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateHighlight {
+    text: String,
+    page: Option<u32>,
+    note: Option<String>,
+}
+
+// What a template is rendered with.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExportScope {
+    // `entries`: saved words, only those in `language` when given.
+    Vocabulary { language: Option<String> },
+    // `book` and `highlights`; highlights are kept by the reader and passed in.
+    Highlights { book_id: String, highlights: Vec<TemplateHighlight> },
+    // `report`: the year-in-review, this year unless `year` is given.
+    ReadingStats { year: Option<i32> },
+    // `book` and `statistics`: length, difficulty and reading time of one book.
+    BookStatistics { book_id: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportTemplate {
+    name: String,
+    path: String,
+}
+
+fn templates_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("export_templates"))
+}
+
+fn template_path(handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let bare = Path::new(name).file_name().is_some_and(|file_name| file_name == name);
+    if !bare || name.starts_with('.') {
+        return Err(format!("Invalid template name: {}", name));
+    }
+    let path = templates_dir(handle)?.join(name);
+    if !path.is_file() {
+        return Err(format!("Template not found: {}", name));
+    }
+    Ok(path)
+}
+
+fn book_value(handle: &tauri::AppHandle, book_id: &str) -> Result<serde_json::Value, String> {
+    let book = handle
+        .state::<AppState>()
+        .recent_books
+        .read(handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+        .ok_or_else(|| format!("Book not found: {}", book_id))?;
+    serde_json::to_value(book).map_err(|e| e.to_string())
+}
+
+fn scope_data(handle: &tauri::AppHandle, scope: ExportScope) -> Result<serde_json::Value, String> {
+    let json = |value: serde_json::Result<serde_json::Value>| value.map_err(|e| e.to_string());
+    Ok(match scope {
+        ExportScope::Vocabulary { language } => {
+            let entries = filtered_entries(handle, language.as_deref())?;
+            serde_json::json!({ "entries": json(serde_json::to_value(entries))? })
+        }
+        ExportScope::Highlights { book_id, highlights } => serde_json::json!({
+            "book": book_value(handle, &book_id)?,
+            "highlights": json(serde_json::to_value(highlights))?,
+        }),
+        ExportScope::ReadingStats { year } => {
+            let report = generate_reading_report(handle.clone(), year)?;
+            serde_json::json!({ "report": json(serde_json::to_value(report))? })
+        }
+        ExportScope::BookStatistics { book_id } => {
+            let statistics = get_book_statistics(handle.clone(), book_id.clone())?;
+            serde_json::json!({
+                "book": book_value(handle, &book_id)?,
+                "statistics": json(serde_json::to_value(statistics))?,
+            })
+        }
+    })
+}
+
+// Templates the user has put in the `export_templates` folder, which is made on first call so
+// they know where that is.
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_export_templates(handle: tauri::AppHandle) -> Result<Vec<ExportTemplate>, String> {
+    let dir = templates_dir(&handle)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut templates: Vec<ExportTemplate> = fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            (!name.starts_with('.')).then(|| ExportTemplate { name, path: path.to_string_lossy().to_string() })
+        })
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+// Renders the Handlebars template `template` (a file name in the `export_templates` folder)
+// with the data `scope` selects, plus `generated_at`, and returns the text for the frontend to
+// save. Output is not HTML-escaped, so templates can produce any text format.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_with_template(handle: tauri::AppHandle, template: String, scope: ExportScope) -> Result<String, String> {
+    let source = fs::read_to_string(template_path(&handle, &template)?).map_err(|e| e.to_string())?;
+    let mut data = scope_data(&handle, scope)?;
+    if let Some(fields) = data.as_object_mut() {
+        fields.insert("generated_at".to_string(), serde_json::Value::String(Utc::now().to_rfc3339()));
+    }
+    let mut registry = Handlebars::new();
+    registry.register_escape_fn(handlebars::no_escape);
+    registry.render_template(&source, &data).map_err(|e| format!("Template {} failed: {}", template, e))
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+use tauri::Manager;
+use tauri_plugin_opener::OpenerExt;
+
+use crate::app_state;
+use crate::restricted_mode;
+use crate::settings::{load_settings, save_settings};
+
+fn book_file(handle: &tauri::AppHandle, book_id: &str) -> Result<String, String> {
+    let path = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(handle, |data| {
+            let book = data.books.iter().find(|b| b.id == book_id)?;
+            Some(book.preferred_format.clone().unwrap_or_else(|| book.file_path.clone()))
+        })?
+        .ok_or_else(|| format!("Book not found: {}", book_id))?;
+    if !Path::new(&path).exists() {
+        return Err(format!("The book's file is missing: {}", path));
+    }
+    Ok(path)
+}
+
+// An installed application: an `.app` bundle on macOS, an executable file elsewhere, given by
+// absolute path. Returned canonicalized, so what is stored is what gets launched.
+fn validated_app(app: &str) -> Result<String, String> {
+    let path = Path::new(app);
+    let bundle = cfg!(target_os = "macos") && path.extension().is_some_and(|ext| ext == "app") && path.is_dir();
+    if !path.is_absolute() || !(bundle || path.is_file()) {
+        return Err(format!("Not an installed application: {}", app));
+    }
+    let path = path.canonicalize().map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// Shows the book's file selected in Finder, Explorer or the desktop's file manager.
+#[tauri::command(rename_all = "camelCase")]
+pub fn reveal_book_in_file_manager(handle: tauri::AppHandle, book_id: String) -> Result<(), String> {
+    let path = book_file(&handle, &book_id)?;
+    handle.opener().reveal_item_in_dir(&path).map_err(|e| format!("Failed to reveal {}: {}", path, e))
+}
+
+// Opens the book's file in another application, e.g. Preview or Acrobat for printing: the
+// one picked with `set_external_app` when `use_external_app` is set, otherwise the system's
+// default application for the file type.
+#[tauri::command(rename_all = "camelCase")]
+pub fn open_book_externally(handle: tauri::AppHandle, book_id: String, use_external_app: bool) -> Result<(), String> {
+    let path = book_file(&handle, &book_id)?;
+    let app = if use_external_app {
+        let app = load_settings(&handle)?.external_app.ok_or("No external application has been chosen.")?;
+        Some(validated_app(&app)?)
+    } else {
+        None
+    };
+    handle.opener().open_path(path.as_str(), app).map_err(|e| format!("Failed to open {}: {}", path, e))
+}
+
+// Sets the application `open_book_externally` can use, as picked in a file dialog; None
+// clears it.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_external_app(handle: tauri::AppHandle, app: Option<String>) -> Result<Option<String>, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing the external application")?;
+    let app = app.as_deref().map(validated_app).transpose()?;
+    let mut settings = load_settings(&handle)?;
+    settings.external_app = app.clone();
+    save_settings(&handle, &settings)?;
+    Ok(app)
+}
@@ -0,0 +1,241 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_config_dir;
+use crate::articles::{article_id, store_article, ArticleSummary};
+use crate::epub::attribute;
+use crate::quarantine::parse_or_quarantine;
+use crate::readability::extract_article;
+
+// Items remembered per feed so they are not fetched again after being read or removed.
+const MAX_SEEN_ITEMS: usize = 500;
+// New items fetched per feed and refresh; a newly subscribed feed does not flood the library.
+const MAX_NEW_ITEMS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feed {
+    id: String,
+    url: String,
+    title: String,
+    added_at: DateTime<Utc>,
+    last_checked_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    last_error: Option<String>,
+    // Item ids (guid, Atom id or link) already turned into articles, newest last.
+    #[serde(default)]
+    seen: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct FeedData {
+    feeds: Vec<Feed>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedRefresh {
+    feed_id: String,
+    added: Vec<ArticleSummary>,
+    error: Option<String>,
+}
+
+struct FeedItem {
+    key: String,
+    link: Option<String>,
+    // Full content or summary as the feed carries it.
+    html: Option<String>,
+}
+
+fn feeds_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("feeds.json"))
+}
+
+fn load_feeds(handle: &tauri::AppHandle) -> Result<FeedData, String> {
+    let path = feeds_file_path(handle)?;
+    if !path.exists() {
+        return Ok(FeedData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_feeds(handle: &tauri::AppHandle, data: &FeedData) -> Result<(), String> {
+    let path = feeds_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+// Text of the first `<tag>` element, with CDATA sections unwrapped and entities decoded.
+fn element(xml: &str, tag: &str) -> Option<String> {
+    let mut rest = xml;
+    loop {
+        let open = rest.find(&format!("<{}", tag))?;
+        let after = &rest[open + tag.len() + 1..];
+        // `<link` must not match `<linkage`
+        if after.starts_with(['>', ' ', '/', '\t', '\n', '\r']) {
+            let (head, body) = after.split_once('>')?;
+            if head.ends_with('/') {
+                return None;
+            }
+            let body = &body[..body.find(&format!("</{}>", tag))?];
+            let body = body.trim();
+            let text = match body.strip_prefix("<![CDATA[").and_then(|b| b.strip_suffix("]]>")) {
+                Some(cdata) => cdata.to_string(),
+                None => html_escape::decode_html_entities(body).to_string(),
+            };
+            return (!text.trim().is_empty()).then(|| text.trim().to_string());
+        }
+        rest = after;
+    }
+}
+
+// Items of an RSS 2.0 or Atom feed, newest first as feeds list them, and the feed's title.
+fn parse_feed(xml: &str) -> Result<(Option<String>, Vec<FeedItem>), String> {
+    let (atom, marker) = if xml.contains("<item") { (false, "<item") } else { (true, "<entry") };
+    let head = xml.split(marker).next().unwrap_or("");
+    if !xml.contains("<rss") && !xml.contains("<feed") && !xml.contains("<rdf:RDF") {
+        return Err("Not an RSS or Atom feed.".to_string());
+    }
+    let title = element(head, "title");
+    let items = xml
+        .split(marker)
+        .skip(1)
+        .filter_map(|chunk| {
+            let link = if atom {
+                let links: Vec<&str> = chunk.split("<link").skip(1).filter_map(|l| l.split('>').next()).collect();
+                links
+                    .iter()
+                    .find(|tag| attribute(tag, "rel").is_none_or(|rel| rel == "alternate"))
+                    .and_then(|tag| attribute(tag, "href"))
+            } else {
+                element(chunk, "link")
+            };
+            let key = element(chunk, if atom { "id" } else { "guid" }).or_else(|| link.clone())?;
+            let html = element(chunk, "content:encoded")
+                .or_else(|| element(chunk, "content"))
+                .or_else(|| element(chunk, "description"))
+                .or_else(|| element(chunk, "summary"))
+                .map(|html| match element(chunk, "title") {
+                    Some(title) => format!("<title>{}</title><article>{}</article>", title, html),
+                    None => html,
+                });
+            Some(FeedItem { key, link, html })
+        })
+        .collect();
+    Ok((title, items))
+}
+
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    response.text().await.map_err(|e| e.to_string())
+}
+
+// The item's article: the linked page through readability, or the feed's own content when
+// the page cannot be fetched or has nothing readable.
+async fn fetch_item(
+    handle: &tauri::AppHandle,
+    client: &reqwest::Client,
+    feed_id: &str,
+    item: &FeedItem,
+) -> Result<ArticleSummary, String> {
+    let url = item.link.clone().unwrap_or_else(|| item.key.clone());
+    let source = format!("feed:{}", feed_id);
+    let base = reqwest::Url::parse(&url).ok();
+    if let Ok(page) = fetch_text(client, &url).await {
+        let article = extract_article(&page, base.as_ref());
+        if !article.blocks.is_empty() {
+            return store_article(handle, &url, &article, &source);
+        }
+    }
+    let html = item.html.as_deref().ok_or_else(|| format!("No readable text for {}", url))?;
+    store_article(handle, &url, &extract_article(html, base.as_ref()), &source)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn subscribe_feed(handle: tauri::AppHandle, url: String) -> Result<Feed, String> {
+    let url = url.trim().to_string();
+    reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let xml = fetch_text(&reqwest::Client::new(), &url).await?;
+    let (title, _) = parse_feed(&xml)?;
+    let mut data = load_feeds(&handle)?;
+    if let Some(existing) = data.feeds.iter().find(|f| f.url == url) {
+        return Ok(existing.clone());
+    }
+    let feed = Feed {
+        id: article_id(&url),
+        title: title.unwrap_or_else(|| url.clone()),
+        url,
+        added_at: Utc::now(),
+        last_checked_at: None,
+        last_error: None,
+        seen: Vec::new(),
+    };
+    data.feeds.push(feed.clone());
+    save_feeds(&handle, &data)?;
+    Ok(feed)
+}
+
+// Stops following a feed. Articles already fetched stay in the library.
+#[tauri::command(rename_all = "camelCase")]
+pub fn unsubscribe_feed(handle: tauri::AppHandle, feed_id: String) -> Result<(), String> {
+    let mut data = load_feeds(&handle)?;
+    data.feeds.retain(|f| f.id != feed_id);
+    save_feeds(&handle, &data)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_feeds(handle: tauri::AppHandle) -> Result<Vec<Feed>, String> {
+    Ok(load_feeds(&handle)?.feeds)
+}
+
+// Checks the feeds (or one of them) and stores items not seen before as articles. A feed
+// that fails is reported and left for the next refresh; the others carry on.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn refresh_feeds(handle: tauri::AppHandle, feed_id: Option<String>) -> Result<Vec<FeedRefresh>, String> {
+    let feeds: Vec<Feed> = load_feeds(&handle)?
+        .feeds
+        .into_iter()
+        .filter(|f| feed_id.as_ref().is_none_or(|id| *id == f.id))
+        .collect();
+    let client = reqwest::Client::new();
+    let mut results = Vec::new();
+    for feed in feeds {
+        let mut refresh = FeedRefresh { feed_id: feed.id.clone(), added: Vec::new(), error: None };
+        let mut seen = Vec::new();
+        match fetch_text(&client, &feed.url).await.and_then(|xml| parse_feed(&xml)) {
+            Ok((_, items)) => {
+                let new_items = items.iter().filter(|item| !feed.seen.contains(&item.key)).take(MAX_NEW_ITEMS);
+                for item in new_items {
+                    match fetch_item(&handle, &client, &feed.id, item).await {
+                        Ok(article) => {
+                            seen.push(item.key.clone());
+                            refresh.added.push(article);
+                        }
+                        Err(e) => log::error!("Failed to fetch feed item {}: {}", item.key, e),
+                    }
+                }
+            }
+            Err(e) => refresh.error = Some(e),
+        }
+
+        // Re-read so subscriptions changed meanwhile are kept
+        let mut data = load_feeds(&handle)?;
+        if let Some(stored) = data.feeds.iter_mut().find(|f| f.id == feed.id) {
+            stored.last_checked_at = Some(Utc::now());
+            stored.last_error = refresh.error.clone();
+            stored.seen.extend(seen);
+            let excess = stored.seen.len().saturating_sub(MAX_SEEN_ITEMS);
+            stored.seen.drain(..excess);
+        }
+        save_feeds(&handle, &data)?;
+        results.push(refresh);
+    }
+    Ok(results)
+}
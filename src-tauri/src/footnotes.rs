@@ -0,0 +1,209 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::book_text::{load_book_text, BookPage, BookText};
+
+const SUPERSCRIPT_DIGITS: [(char, char); 10] = [
+    ('⁰', '0'),
+    ('¹', '1'),
+    ('²', '2'),
+    ('³', '3'),
+    ('⁴', '4'),
+    ('⁵', '5'),
+    ('⁶', '6'),
+    ('⁷', '7'),
+    ('⁸', '8'),
+    ('⁹', '9'),
+];
+const NOTE_SYMBOLS: &[char] = &['*', '†', '‡', '§', '¶'];
+// Punctuation a note number may follow without a space, as in "the end.3".
+const GLUED_AFTER: &[char] = &['.', ',', ';', ':', '!', '?', '"', '”', '’', ')'];
+// Note numbers beyond this are more likely years or quantities.
+const MAX_NOTE_DIGITS: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FootnoteReference {
+    // The paragraph the marker appears in, with the marker's UTF-16 range inside it.
+    sid: String,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Footnote {
+    // In the book's `fn` namespace, e.g. "<book>:fn:12:3", so note translations are cached
+    // apart from the paragraph the note body was extracted as.
+    pub sid: String,
+    pub marker: String,
+    pub text: String,
+    pub page: u32,
+    // The stored paragraph holding the note body.
+    pub source_sid: String,
+    references: Vec<FootnoteReference>,
+}
+
+fn superscript_digit(c: char) -> Option<char> {
+    SUPERSCRIPT_DIGITS.iter().find(|(sup, _)| *sup == c).map(|(_, digit)| *digit)
+}
+
+// "3 Text", "3. Text", "³ Text", "* Text", "†† Text" -> (marker, body).
+fn note_body(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start();
+    let superscript: String = text.chars().map_while(superscript_digit).collect();
+    let (marker, rest) = if !superscript.is_empty() {
+        let len: usize = text.chars().take(superscript.len()).map(char::len_utf8).sum();
+        (superscript, &text[len..])
+    } else if text.starts_with(NOTE_SYMBOLS) {
+        let first = text.chars().next()?;
+        let len = text.chars().take_while(|c| *c == first).count();
+        (first.to_string().repeat(len), &text[first.len_utf8() * len..])
+    } else {
+        let digits: String = text.chars().take_while(char::is_ascii_digit).collect();
+        if digits.is_empty() || digits.len() > MAX_NOTE_DIGITS {
+            return None;
+        }
+        let rest = &text[digits.len()..];
+        let rest = rest.strip_prefix(['.', ')']).unwrap_or(rest);
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        (digits, rest)
+    };
+    let body = rest.trim();
+    body.starts_with(|c: char| c.is_alphabetic() || matches!(c, '"' | '“' | '‘' | '('))
+        .then_some((marker, body))
+}
+
+// The block of note bodies at the end of a page, bottom-most last.
+fn page_note_block(page: &BookPage) -> Vec<(usize, String, &str)> {
+    let mut notes: Vec<(usize, String, &str)> = page
+        .paragraphs
+        .iter()
+        .enumerate()
+        .rev()
+        .map_while(|(index, p)| note_body(&p.text).map(|(marker, body)| (index, marker, body)))
+        .collect();
+    notes.reverse();
+    notes
+}
+
+// Marker occurrences in body text: superscripts, "[3]", symbols right after a word, and
+// digits glued to the end of a word or punctuation ("end.3"). Only `markers` are reported.
+fn find_markers(text: &str, markers: &[String]) -> Vec<(String, usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut utf16_starts = Vec::with_capacity(chars.len() + 1);
+    let mut offset = 0;
+    for c in &chars {
+        utf16_starts.push(offset);
+        offset += c.len_utf16();
+    }
+    utf16_starts.push(offset);
+
+    let mut found = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        let previous = index.checked_sub(1).map(|i| chars[i]);
+        let after_word = previous.is_some_and(|p| !p.is_whitespace());
+        let run_end = |from: usize, f: &dyn Fn(char) -> bool| {
+            (from..chars.len()).find(|i| !f(chars[*i])).unwrap_or(chars.len())
+        };
+
+        let candidate = if superscript_digit(c).is_some() {
+            let end = run_end(index, &|c| superscript_digit(c).is_some());
+            let marker: String = chars[index..end].iter().filter_map(|c| superscript_digit(*c)).collect();
+            Some((marker, index, end))
+        } else if c == '[' {
+            let end = run_end(index + 1, &|c: char| c.is_ascii_digit());
+            (end > index + 1 && chars.get(end) == Some(&']'))
+                .then(|| (chars[index + 1..end].iter().collect(), index, end + 1))
+        } else if NOTE_SYMBOLS.contains(&c) && after_word {
+            let end = run_end(index, &|other| other == c);
+            Some((chars[index..end].iter().collect(), index, end))
+        } else if c.is_ascii_digit() && previous.is_some_and(|p| p.is_alphabetic() || GLUED_AFTER.contains(&p)) {
+            let end = run_end(index, &|c: char| c.is_ascii_digit());
+            let followed_by_break = chars.get(end).is_none_or(|c| c.is_whitespace());
+            followed_by_break.then(|| (chars[index..end].iter().collect(), index, end))
+        } else {
+            None
+        };
+
+        match candidate {
+            Some((marker, start, end)) => {
+                if markers.contains(&marker) {
+                    found.push((marker, utf16_starts[start], utf16_starts[end]));
+                }
+                index = end;
+            }
+            None => index += 1,
+        }
+    }
+    found
+}
+
+// Footnotes whose markers appear on `page`, each linked to every reference to it there.
+// Bodies are looked for at the foot of the page, then at the end of the last page of the
+// same chapter, which is where EPUB chapters keep their endnotes.
+pub fn page_footnotes(text: &BookText, book_id: &str, page: u32) -> Vec<Footnote> {
+    let Some(book_page) = text.page(page) else {
+        return Vec::new();
+    };
+    let chapter_end = book_page.title.as_ref().and_then(|title| {
+        text.pages
+            .iter()
+            .filter(|p| p.title.as_ref() == Some(title))
+            .max_by_key(|p| p.page)
+            .filter(|p| p.page > page)
+    });
+
+    let mut notes: Vec<Footnote> = Vec::new();
+    let mut note_indices: Vec<usize> = Vec::new();
+    for source in std::iter::once(book_page).chain(chapter_end) {
+        for (index, marker, body) in page_note_block(source) {
+            if source.page == page {
+                note_indices.push(index);
+            }
+            if notes.iter().any(|n| n.marker == marker) {
+                continue;
+            }
+            notes.push(Footnote {
+                sid: format!("{}:fn:{}:{}", book_id, source.page, marker),
+                marker,
+                text: body.to_string(),
+                page: source.page,
+                source_sid: source.paragraphs[index].sid.clone(),
+                references: Vec::new(),
+            });
+        }
+    }
+
+    let markers: Vec<String> = notes.iter().map(|n| n.marker.clone()).collect();
+    let mut references: HashMap<String, Vec<FootnoteReference>> = HashMap::new();
+    for (index, paragraph) in book_page.paragraphs.iter().enumerate() {
+        if note_indices.contains(&index) {
+            continue;
+        }
+        for (marker, start, end) in find_markers(&paragraph.text, &markers) {
+            references.entry(marker).or_default().push(FootnoteReference {
+                sid: paragraph.sid.clone(),
+                start,
+                end,
+            });
+        }
+    }
+
+    // Bodies on this page count even without a detected marker; endnotes only when referenced.
+    notes
+        .into_iter()
+        .filter_map(|mut note| {
+            note.references = references.remove(&note.marker).unwrap_or_default();
+            (note.page == page || !note.references.is_empty()).then_some(note)
+        })
+        .collect()
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn extract_footnotes(handle: tauri::AppHandle, book_id: String, page: u32) -> Result<Vec<Footnote>, String> {
+    let text = load_book_text(&handle, &book_id)?;
+    Ok(page_footnotes(&text, &book_id, page))
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::book_text::load_book_text;
+use crate::page_words::tokenize_words;
+use crate::quarantine::parse_or_quarantine;
+use crate::quota;
+use crate::vocab_index::lemma_candidates;
+use crate::word_frequency::word_rank;
+use crate::word_lists::{load_word_set, IGNORED_WORDS_FILE, KNOWN_WORDS_FILE};
+use crate::{
+    app_config_dir, ensure_cloud_allowed, extract_json_object, load_openrouter_credentials, load_vocabulary,
+    request_openrouter, truncate_for_error, TargetLanguage,
+};
+
+// Enough of the page for the model to pick the right sense of each word.
+const CONTEXT_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlossSource {
+    Cache,
+    Vocabulary,
+    Model,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PageGloss {
+    word: String,
+    gloss: String,
+    // Frequency rank, or None when the word is rarer than the built-in list covers.
+    rank: Option<u32>,
+    source: GlossSource,
+    // Paragraphs on the page where the word occurs.
+    sids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct GlossCache {
+    entries: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GlossItem {
+    word: String,
+    gloss: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GlossResponse {
+    glosses: Vec<GlossItem>,
+}
+
+fn gloss_cache_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("gloss_cache.json"))
+}
+
+fn load_gloss_cache(handle: &tauri::AppHandle) -> Result<GlossCache, String> {
+    let path = gloss_cache_path(handle)?;
+    if !path.exists() {
+        return Ok(GlossCache::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_gloss_cache(handle: &tauri::AppHandle, cache: &GlossCache) -> Result<(), String> {
+    let path = gloss_cache_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(cache).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn gloss_cache_key(model: &str, target_code: &str, word: &str) -> String {
+    format!("{}|{}|{}", model, target_code, word)
+}
+
+fn build_gloss_system_prompt() -> String {
+    [
+        "You are a glossary engine for language learners.",
+        "Give each word a very short gloss that fits how it is used in the text.",
+        "Output STRICT JSON ONLY.",
+        "No markdown, no explanations, no extra text.",
+    ]
+    .join(" ")
+}
+
+fn build_gloss_prompt(words: &[String], context: &str, target_language: &TargetLanguage) -> String {
+    format!(
+        r#"Gloss these words in {} ({}): {}
+Text they appear in:
+{}
+Return JSON in this exact format:
+{{"glosses": [{{"word": "word as given", "gloss": "1-4 word meaning"}}]}}"#,
+        target_language.label,
+        target_language.prompt_tag(),
+        serde_json::to_string(words).unwrap_or_else(|_| "[]".to_string()),
+        context
+    )
+}
+
+// Glosses for the words on a page that are rarer than `difficulty_threshold` (a frequency
+// rank), skipping known and ignored words. Cached glosses and saved vocabulary are used
+// first; the rest is looked up in one model request.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_page_gloss(
+    handle: tauri::AppHandle,
+    model: String,
+    target_language: TargetLanguage,
+    book_id: String,
+    page: u32,
+    difficulty_threshold: u32,
+) -> Result<Vec<PageGloss>, String> {
+    let text = load_book_text(&handle, &book_id)?;
+    let book_page = text
+        .page(page)
+        .ok_or_else(|| format!("No extracted text for page {} of book {}", page, book_id))?;
+
+    let known = load_word_set(&handle, KNOWN_WORDS_FILE)?;
+    let ignored = load_word_set(&handle, IGNORED_WORDS_FILE)?;
+    let is_familiar = |word: &str| {
+        ignored.contains(word) || lemma_candidates(word).iter().any(|form| known.contains(form))
+    };
+
+    // Unique hard words in page order, with the paragraphs they appear in.
+    let mut order: Vec<String> = Vec::new();
+    let mut occurrences: HashMap<String, Vec<String>> = HashMap::new();
+    for paragraph in &book_page.paragraphs {
+        for token in tokenize_words(&paragraph.text) {
+            let word = token.text.to_lowercase();
+            if word.chars().count() < 2 || is_familiar(&word) {
+                continue;
+            }
+            if word_rank(&word).is_some_and(|rank| rank <= difficulty_threshold) {
+                continue;
+            }
+            let sids = occurrences.entry(word.clone()).or_insert_with(|| {
+                order.push(word.clone());
+                Vec::new()
+            });
+            if !sids.contains(&paragraph.sid) {
+                sids.push(paragraph.sid.clone());
+            }
+        }
+    }
+    if order.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let cache = load_gloss_cache(&handle)?;
+    let vocabulary: HashMap<String, String> = load_vocabulary(&handle)?
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            let meaning = entry.definitions.first()?.meanings.clone();
+            Some((entry.word.to_lowercase(), meaning))
+        })
+        .collect();
+
+    let mut glosses: HashMap<String, (String, GlossSource)> = HashMap::new();
+    let mut missing: Vec<String> = Vec::new();
+    for word in &order {
+        if let Some(gloss) = cache.entries.get(&gloss_cache_key(&model, &target_language.tag(), word)) {
+            glosses.insert(word.clone(), (gloss.clone(), GlossSource::Cache));
+        } else if let Some(gloss) = lemma_candidates(word).iter().find_map(|form| vocabulary.get(form)) {
+            glosses.insert(word.clone(), (gloss.clone(), GlossSource::Vocabulary));
+        } else {
+            missing.push(word.clone());
+        }
+    }
+
+    if !missing.is_empty() {
+        ensure_cloud_allowed(&handle, &book_id)?;
+        let credentials = load_openrouter_credentials(&handle)?.for_feature(quota::FEATURE_GLOSS).pin_temperature();
+        let page_text: Vec<&str> = book_page.paragraphs.iter().map(|p| p.text.as_str()).collect();
+        let context: String = page_text.join("\n").chars().take(CONTEXT_CHARS).collect();
+        let system_prompt = build_gloss_system_prompt();
+        let user_prompt = build_gloss_prompt(&missing, &context, &target_language);
+        let content = request_openrouter(&credentials, &model, 0.0, &system_prompt, &user_prompt).await?;
+        let json_content = extract_json_object(&content);
+        let parsed: GlossResponse = serde_json::from_str(&json_content).map_err(|e| {
+            format!("Failed to parse gloss JSON: {} (content: {})", e, truncate_for_error(&json_content))
+        })?;
+
+        let requested: HashSet<&String> = missing.iter().collect();
+        let mut cache = load_gloss_cache(&handle)?;
+        for item in parsed.glosses {
+            let word = item.word.trim().to_lowercase();
+            if !requested.contains(&word) || item.gloss.trim().is_empty() {
+                continue;
+            }
+            cache.entries.insert(
+                gloss_cache_key(&model, &target_language.tag(), &word),
+                item.gloss.trim().to_string(),
+            );
+            glosses.insert(word, (item.gloss.trim().to_string(), GlossSource::Model));
+        }
+        save_gloss_cache(&handle, &cache)?;
+    }
+
+    Ok(order
+        .into_iter()
+        .filter_map(|word| {
+            let (gloss, source) = glosses.remove(&word)?;
+            Some(PageGloss {
+                rank: word_rank(&word),
+                sids: occurrences.remove(&word).unwrap_or_default(),
+                word,
+                gloss,
+                source,
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,102 @@
+use crate::language::{detect_language, primary_language};
+use crate::segmentation::segment;
+use crate::{TranslateSentence, TranslationResult};
+
+// Units on each side of a unit sent along with it in window mode.
+const WINDOW_BEFORE: usize = 2;
+const WINDOW_AFTER: usize = 1;
+
+// Target languages written without spaces between sentences.
+const UNSPACED_LANGUAGES: &[&str] = &["zh", "ja", "th", "lo", "my"];
+
+// Attaches the neighbouring units of the request as context. Tables and code are neither
+// given nor used as context. Context the caller already set is kept.
+pub fn with_window_context(mut sentences: Vec<TranslateSentence>) -> Vec<TranslateSentence> {
+    let texts: Vec<Option<String>> = sentences
+        .iter()
+        .map(|s| (!s.kind.is_verbatim()).then(|| s.text.clone()))
+        .collect();
+    let join = |range: &[Option<String>]| {
+        let joined: Vec<&str> = range.iter().flatten().map(String::as_str).collect();
+        (!joined.is_empty()).then(|| joined.join("\n"))
+    };
+    for (index, sentence) in sentences.iter_mut().enumerate() {
+        if texts[index].is_none() {
+            continue;
+        }
+        if sentence.before.is_none() {
+            sentence.before = join(&texts[index.saturating_sub(WINDOW_BEFORE)..index]);
+        }
+        if sentence.after.is_none() {
+            sentence.after = join(&texts[index + 1..(index + 1 + WINDOW_AFTER).min(texts.len())]);
+        }
+    }
+    sentences
+}
+
+// Splits each unit into sentences with sids `<sid>#s<n>`. Returns them with, per unit, the
+// number of sentences it became. Tables and code stay whole.
+pub fn split_sentences(units: &[TranslateSentence]) -> (Vec<TranslateSentence>, Vec<usize>) {
+    let mut sentences = Vec::new();
+    let mut counts = Vec::with_capacity(units.len());
+    for unit in units {
+        let lang = detect_language(&unit.text).unwrap_or_else(|| "en".to_string());
+        let parts = if unit.kind.is_verbatim() {
+            Vec::new()
+        } else {
+            segment(&unit.text, &lang, None)
+        };
+        if parts.len() < 2 {
+            sentences.push(unit.clone());
+            counts.push(1);
+            continue;
+        }
+        counts.push(parts.len());
+        sentences.extend(parts.into_iter().enumerate().map(|(index, part)| TranslateSentence {
+            sid: format!("{}#s{}", unit.sid, index),
+            text: part.text,
+            ..Default::default()
+        }));
+    }
+    (sentences, counts)
+}
+
+// What goes between sentences joined in `language`: nothing for scripts without spaces.
+pub fn sentence_separator(language: &str) -> &'static str {
+    if UNSPACED_LANGUAGES.contains(&primary_language(language).as_str()) {
+        ""
+    } else {
+        " "
+    }
+}
+
+// Puts sentence translations back together into one result per unit. A unit is left out
+// when any of its sentences is missing, as untranslated units are elsewhere.
+pub fn join_sentences(
+    units: &[TranslateSentence],
+    counts: &[usize],
+    translations: Vec<TranslationResult>,
+    target_code: &str,
+) -> Vec<TranslationResult> {
+    let separator = sentence_separator(target_code);
+    let mut translations = translations.into_iter().peekable();
+    let mut results = Vec::with_capacity(units.len());
+    for (unit, &count) in units.iter().zip(counts) {
+        let prefix = format!("{}#s", unit.sid);
+        let mut parts = Vec::with_capacity(count);
+        while let Some(item) = translations.next_if(|item| item.sid == unit.sid || item.sid.starts_with(&prefix)) {
+            parts.push(item);
+        }
+        if parts.len() != count {
+            continue;
+        }
+        let generation = parts.iter().find_map(|part| part.generation.clone());
+        let text: Vec<String> = parts.into_iter().map(|part| part.translation.trim().to_string()).collect();
+        results.push(TranslationResult {
+            sid: unit.sid.clone(),
+            translation: text.join(separator),
+            generation,
+        });
+    }
+    results
+}
@@ -0,0 +1,309 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::quarantine::parse_or_quarantine;
+use crate::restricted_mode;
+use crate::sync_conflicts::with_write_lock;
+use crate::{app_config_dir, ensure_cloud_allowed, hash_source_text};
+
+// Words collected before a `vocabulary_added` hook fires, unless it sets its own threshold.
+const DEFAULT_WORD_THRESHOLD: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    BookFinished,
+    TranslationJobCompleted,
+    VocabularyAdded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookTarget {
+    // Receives the event as a JSON POST body.
+    Url { url: String },
+    // Run with the event JSON on stdin. Only set through `choose_hook_script`'s file dialog,
+    // and never run in restricted mode.
+    Script { path: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    #[serde(default)]
+    id: String,
+    event: HookEvent,
+    target: HookTarget,
+    #[serde(default = "crate::default_true")]
+    enabled: bool,
+    // For `vocabulary_added`: how many new words to collect before firing.
+    #[serde(default)]
+    word_threshold: Option<u32>,
+    // Words added since this hook last fired.
+    #[serde(default)]
+    pending_words: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct HookData {
+    hooks: Vec<Hook>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HookPayload {
+    event: HookEvent,
+    fired_at: String,
+    data: serde_json::Value,
+}
+
+// Serializes read-modify-write of the hooks file between commands and event sources.
+#[derive(Default)]
+pub struct HookStore {
+    lock: Mutex<()>,
+}
+
+fn hooks_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("hooks.json"))
+}
+
+fn load_hooks(handle: &tauri::AppHandle) -> Result<HookData, String> {
+    let path = hooks_file_path(handle)?;
+    if !path.exists() {
+        return Ok(HookData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_hooks(handle: &tauri::AppHandle, hooks: &HookData) -> Result<(), String> {
+    let path = hooks_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(hooks).map_err(|e| e.to_string())?;
+    with_write_lock(&path, || fs::write(&path, data).map_err(|e| e.to_string()))
+}
+
+fn update_hooks<T>(handle: &tauri::AppHandle, f: impl FnOnce(&mut HookData) -> T) -> Result<T, String> {
+    let store = handle.state::<HookStore>();
+    let _lock = store.lock.lock().unwrap_or_else(|e| e.into_inner());
+    let mut hooks = load_hooks(handle)?;
+    let result = f(&mut hooks);
+    save_hooks(handle, &hooks)?;
+    Ok(result)
+}
+
+async fn deliver(target: &HookTarget, payload: &HookPayload) -> Result<(), String> {
+    match target {
+        HookTarget::Url { url } => {
+            let response = reqwest::Client::new()
+                .post(url)
+                .json(payload)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("Hook {} answered {}", url, response.status()));
+            }
+            Ok(())
+        }
+        HookTarget::Script { path } => {
+            let path = path.clone();
+            let input = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+            tauri::async_runtime::spawn_blocking(move || {
+                let mut child = Command::new(&path)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .map_err(|e| format!("Failed to run hook script {}: {}", path, e))?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(&input).map_err(|e| e.to_string())?;
+                }
+                let status = child.wait().map_err(|e| e.to_string())?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("Hook script {} exited with {}", path, status))
+                }
+            })
+            .await
+            .map_err(|e| e.to_string())?
+        }
+    }
+}
+
+// Whether `target` may receive an event now: scripts are not run in restricted mode.
+fn may_deliver(handle: &tauri::AppHandle, target: &HookTarget) -> bool {
+    !matches!(target, HookTarget::Script { .. }) || !restricted_mode::is_restricted(handle)
+}
+
+fn deliver_in_background(targets: Vec<HookTarget>, payload: HookPayload) {
+    if targets.is_empty() {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        for target in &targets {
+            if let Err(e) = deliver(target, &payload).await {
+                log::warn!("{}", e);
+            }
+        }
+    });
+}
+
+fn payload(event: HookEvent, data: serde_json::Value) -> HookPayload {
+    HookPayload {
+        event,
+        fired_at: Utc::now().to_rfc3339(),
+        data,
+    }
+}
+
+// Sends `event` about `book_id` to every enabled hook registered for it. Webhooks are skipped
+// for books kept off the cloud. Delivery runs in the background and failures are only logged,
+// so a broken endpoint never gets in the way of reading.
+pub fn fire(handle: &tauri::AppHandle, event: HookEvent, book_id: &str, data: serde_json::Value) {
+    let cloud_allowed = ensure_cloud_allowed(handle, book_id).is_ok();
+    let targets = match load_hooks(handle) {
+        Ok(hooks) => hooks
+            .hooks
+            .into_iter()
+            .filter(|hook| hook.enabled && hook.event == event)
+            .map(|hook| hook.target)
+            .filter(|target| may_deliver(handle, target))
+            .filter(|target| cloud_allowed || !matches!(target, HookTarget::Url { .. }))
+            .collect(),
+        Err(e) => {
+            log::error!("Failed to load hooks: {}", e);
+            return;
+        }
+    };
+    deliver_in_background(targets, payload(event, data));
+}
+
+// Counts newly added vocabulary words saved from `book_id`; each `vocabulary_added` hook fires
+// once its own threshold is reached, with the total it collected. Webhooks don't count words
+// from books kept off the cloud, just as `fire` skips them for those books.
+pub fn record_new_words(handle: &tauri::AppHandle, book_id: Option<&str>, count: u32) {
+    let counting = load_hooks(handle)
+        .is_ok_and(|data| data.hooks.iter().any(|hook| hook.enabled && hook.event == HookEvent::VocabularyAdded));
+    if !counting {
+        return;
+    }
+    let cloud_allowed = book_id.is_none_or(|book_id| ensure_cloud_allowed(handle, book_id).is_ok());
+    let result = update_hooks(handle, |data| {
+        let mut due = Vec::new();
+        for hook in data.hooks.iter_mut() {
+            if !hook.enabled || hook.event != HookEvent::VocabularyAdded {
+                continue;
+            }
+            if !cloud_allowed && matches!(hook.target, HookTarget::Url { .. }) {
+                continue;
+            }
+            hook.pending_words += count;
+            if hook.pending_words >= hook.word_threshold.unwrap_or(DEFAULT_WORD_THRESHOLD) {
+                due.push((hook.target.clone(), hook.pending_words));
+                hook.pending_words = 0;
+            }
+        }
+        due
+    });
+    match result {
+        Ok(due) => {
+            for (target, words) in due.into_iter().filter(|(target, _)| may_deliver(handle, target)) {
+                let data = serde_json::json!({ "new_words": words });
+                deliver_in_background(vec![target], payload(HookEvent::VocabularyAdded, data));
+            }
+        }
+        Err(e) => log::error!("Failed to update hooks: {}", e),
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_hooks(handle: tauri::AppHandle) -> Result<Vec<Hook>, String> {
+    Ok(load_hooks(&handle)?.hooks)
+}
+
+fn store_hook(handle: &tauri::AppHandle, mut hook: Hook) -> Result<Hook, String> {
+    if hook.id.is_empty() {
+        let seed = format!("{:?}|{}", hook.target, Utc::now().timestamp_nanos_opt().unwrap_or_default());
+        hook.id = hash_source_text(&seed)[..12].to_string();
+    }
+    update_hooks(handle, |data| {
+        match data.hooks.iter_mut().find(|existing| existing.id == hook.id) {
+            Some(existing) => *existing = hook.clone(),
+            None => data.hooks.push(hook.clone()),
+        }
+        hook
+    })
+}
+
+// Adds a hook, or replaces the one with the same id. New hooks get an id assigned. A script
+// hook keeps the script it has; scripts are only picked with `choose_hook_script`.
+#[tauri::command(rename_all = "camelCase")]
+pub fn save_hook(handle: tauri::AppHandle, hook: Hook) -> Result<Hook, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Editing hooks")?;
+    match &hook.target {
+        HookTarget::Url { url } if !(url.starts_with("http://") || url.starts_with("https://")) => {
+            return Err(format!("Hook URL must start with http:// or https://: {}", url));
+        }
+        HookTarget::Script { path } => {
+            let unchanged = load_hooks(&handle)?.hooks.iter().any(|existing| {
+                existing.id == hook.id && matches!(&existing.target, HookTarget::Script { path: p } if p == path)
+            });
+            if !unchanged {
+                return Err("Hook scripts can only be chosen with the file dialog.".to_string());
+            }
+        }
+        _ => {}
+    }
+    store_hook(&handle, hook)
+}
+
+// Asks for a script in a file dialog and makes it the target of `hook`, which is added or
+// replaced as with `save_hook`. None when the dialog is cancelled.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn choose_hook_script(handle: tauri::AppHandle, mut hook: Hook) -> Result<Option<Hook>, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Editing hooks")?;
+    let dialog = handle.clone();
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        dialog.dialog().file().set_title("Choose a hook script").blocking_pick_file()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked.into_path().map_err(|e| e.to_string())?;
+    if !path.is_file() {
+        return Err(format!("Hook script not found: {}", path.display()));
+    }
+    hook.target = HookTarget::Script { path: path.to_string_lossy().to_string() };
+    store_hook(&handle, hook).map(Some)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn remove_hook(handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Editing hooks")?;
+    update_hooks(&handle, |data| data.hooks.retain(|hook| hook.id != id))
+}
+
+// Delivers a sample event right away and reports the outcome, for checking a setup.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn test_hook(handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Testing hooks")?;
+    let hook = load_hooks(&handle)?
+        .hooks
+        .into_iter()
+        .find(|hook| hook.id == id)
+        .ok_or_else(|| format!("Hook not found: {}", id))?;
+    if !may_deliver(&handle, &hook.target) {
+        return Err("Hook scripts do not run in restricted mode.".to_string());
+    }
+    deliver(&hook.target, &payload(hook.event, serde_json::json!({ "test": true }))).await
+}
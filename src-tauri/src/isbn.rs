@@ -0,0 +1,201 @@
+use std::path::Path;
+use tauri::Manager;
+
+use crate::book_metadata::enrich_book_metadata;
+use crate::book_text::{load_book_text, BookText};
+use crate::epub::{chapter_text, read_package_document, spine_hrefs};
+use crate::{app_state, ensure_cloud_allowed, update_recent_books, RecentBook};
+
+// Copyright pages sit among the first pages of a book, or occasionally the last ones.
+const FRONT_PAGES: u32 = 6;
+const BACK_PAGES: u32 = 4;
+const FRONT_CHAPTERS: usize = 3;
+const BACK_CHAPTERS: usize = 2;
+
+// Digits of a valid ISBN-10 or ISBN-13 (hyphens and spaces dropped), or None.
+pub fn normalize_isbn(text: &str) -> Option<String> {
+    let isbn: String = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, 'X' | 'x'))
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let digit = |c: char| c.to_digit(10);
+    let valid = match isbn.len() {
+        10 => {
+            let sum: Option<u32> = isbn.chars().enumerate().try_fold(0, |sum, (i, c)| {
+                let value = if i == 9 && c == 'X' { 10 } else { digit(c)? };
+                Some(sum + value * (10 - i as u32))
+            });
+            sum.is_some_and(|sum| sum % 11 == 0)
+        }
+        13 => {
+            let sum: Option<u32> = isbn.chars().enumerate().try_fold(0, |sum, (i, c)| {
+                Some(sum + digit(c)? * if i % 2 == 0 { 1 } else { 3 })
+            });
+            sum.is_some_and(|sum| sum % 10 == 0)
+        }
+        _ => false,
+    };
+    valid.then_some(isbn)
+}
+
+// Valid ISBNs found in running text, in order of trust: ones labelled "ISBN" first, then
+// unlabelled 978/979 numbers. An unlabelled ten-digit number is too often something else.
+pub fn find_isbns(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut labelled = Vec::new();
+    let mut bare = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() || (i > 0 && chars[i - 1].is_ascii_digit()) {
+            i += 1;
+            continue;
+        }
+        // Digits with single hyphens or spaces between them, up to thirteen of them
+        let mut digits = String::new();
+        let mut ten = None;
+        let mut j = i;
+        while j < chars.len() && digits.len() < 13 {
+            let c = chars[j];
+            let next_is_digit = chars.get(j + 1).is_some_and(|n| n.is_ascii_digit() || matches!(n, 'X' | 'x'));
+            if c.is_ascii_digit() || (matches!(c, 'X' | 'x') && digits.len() == 9) {
+                digits.push(c);
+            } else if !(matches!(c, '-' | ' ' | '\u{2010}' | '\u{2013}') && next_is_digit) {
+                break;
+            }
+            j += 1;
+            if digits.len() == 10 && ten.is_none() {
+                ten = Some(digits.clone());
+            }
+            if digits.ends_with(['X', 'x']) {
+                break;
+            }
+        }
+        let overlong = digits.len() == 13 && chars.get(j).is_some_and(|c| c.is_ascii_digit());
+        let found = (!overlong)
+            .then(|| normalize_isbn(&digits))
+            .flatten()
+            .or_else(|| ten.as_deref().and_then(normalize_isbn));
+
+        if let Some(isbn) = found {
+            let before: String = chars[i.saturating_sub(16)..i].iter().collect();
+            if before.to_lowercase().contains("isbn") {
+                labelled.push(isbn);
+            } else if isbn.len() == 13 && (isbn.starts_with("978") || isbn.starts_with("979")) {
+                bare.push(isbn);
+            }
+        }
+        i = j.max(i + 1);
+    }
+    labelled.extend(bare);
+    let mut seen = Vec::new();
+    labelled.retain(|isbn| {
+        let first = !seen.contains(isbn);
+        seen.push(isbn.clone());
+        first
+    });
+    labelled
+}
+
+// The ISBN an EPUB's package document declares among its identifiers.
+fn package_isbn(path: &Path) -> Option<String> {
+    let opf = read_package_document(path).ok()?;
+    opf.split("<dc:identifier").skip(1).find_map(|rest| {
+        let value = rest.split_once('>')?.1.split('<').next()?;
+        let value = value.trim().trim_start_matches("urn:isbn:").trim_start_matches("isbn:");
+        normalize_isbn(value)
+    })
+}
+
+fn epub_isbn(path: &Path) -> Option<String> {
+    if let Some(isbn) = package_isbn(path) {
+        return Some(isbn);
+    }
+    let spine = spine_hrefs(path).ok()?;
+    let back = spine.len().saturating_sub(BACK_CHAPTERS).max(FRONT_CHAPTERS.min(spine.len()));
+    let text: Vec<String> = spine[..FRONT_CHAPTERS.min(spine.len())]
+        .iter()
+        .chain(&spine[back..])
+        .filter_map(|href| chapter_text(path, href).ok())
+        .collect();
+    find_isbns(&text.join("\n")).into_iter().next()
+}
+
+fn is_scanned_page(page: u32, total_pages: u32) -> bool {
+    page <= FRONT_PAGES || page + BACK_PAGES > total_pages
+}
+
+// Scans the mirrored text of the front and back pages of a PDF.
+fn text_isbn(text: &BookText, total_pages: u32) -> Option<String> {
+    let scanned: Vec<&str> = text
+        .pages
+        .iter()
+        .filter(|page| is_scanned_page(page.page, total_pages))
+        .flat_map(|page| page.paragraphs.iter().map(|p| p.text.as_str()))
+        .collect();
+    find_isbns(&scanned.join("\n")).into_iter().next()
+}
+
+pub fn detect_book_isbn(handle: &tauri::AppHandle, book: &RecentBook) -> Option<String> {
+    if book.file_type == "epub" {
+        return epub_isbn(Path::new(&book.file_path));
+    }
+    let text = load_book_text(handle, &book.id).ok()?;
+    text_isbn(&text, book.total_pages)
+}
+
+// Looks for an ISBN in the book's own content: the EPUB package identifiers and opening and
+// closing chapters, or the first and last pages of a PDF. PDF text comes from what the
+// reader has extracted, so a PDF must be in the library and opened once.
+#[tauri::command(rename_all = "camelCase")]
+pub fn detect_isbn(handle: tauri::AppHandle, path: String) -> Result<Option<String>, String> {
+    let book = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.iter().find(|b| b.file_path == path).cloned())?;
+    match book {
+        Some(book) => Ok(detect_book_isbn(&handle, &book)),
+        None if path.to_lowercase().ends_with(".epub") || Path::new(&path).is_dir() => {
+            Ok(epub_isbn(Path::new(&path)))
+        }
+        None => Err(format!("Book not found in the library: {}", path)),
+    }
+}
+
+// Called as page text arrives. When front or back pages of a book without an ISBN come in,
+// an ISBN found on them is stored and the book is enriched from it, so scanned paper books
+// pick up their metadata without being asked.
+pub fn note_stored_pages(handle: &tauri::AppHandle, book_id: &str, pages: &[u32]) {
+    let book = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(handle, |data| data.books.iter().find(|b| b.id == book_id).cloned());
+    let Ok(Some(book)) = book else {
+        return;
+    };
+    let pinned = book.metadata.overridden.iter().any(|name| name == "isbn");
+    if book.metadata.isbn.is_some() || pinned || !pages.iter().any(|&p| is_scanned_page(p, book.total_pages)) {
+        return;
+    }
+    let Some(isbn) = load_book_text(handle, book_id).ok().and_then(|text| text_isbn(&text, book.total_pages)) else {
+        return;
+    };
+    let stored = update_recent_books(handle, |data| {
+        if let Some(book) = data.books.iter_mut().find(|b| b.id == book_id) {
+            book.metadata.isbn.get_or_insert(isbn);
+        }
+    });
+    if let Err(e) = stored {
+        log::error!("Failed to store detected ISBN: {}", e);
+        return;
+    }
+    if book.metadata.enriched_at.is_none() && ensure_cloud_allowed(handle, book_id).is_ok() {
+        let handle = handle.clone();
+        let book_id = book_id.to_string();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = enrich_book_metadata(handle, book_id).await {
+                log::error!("Metadata enrichment failed: {}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_config_dir;
+use crate::app_state::AppState;
+use crate::epub_translation;
+use crate::jobs::JobRegistry;
+use crate::quarantine::parse_or_quarantine;
+use crate::sync_conflicts::with_write_lock;
+
+// Whole-book jobs that have not finished. A job stays here until it completes or is
+// cancelled, so one cut short by a crash or by quitting can be picked up again.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PendingJobsData {
+    jobs: Vec<PendingJob>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingJob {
+    pub job_id: String,
+    pub kind: String,
+    pub book_id: String,
+    // What the job was started with, enough to start it again.
+    pub params: serde_json::Value,
+    // Units of work (chapters for EPUB translation) still to do, and those done.
+    #[serde(default)]
+    pub pending: Vec<String>,
+    #[serde(default)]
+    pub completed: Vec<String>,
+    // Failures so far; the last one is why the job stopped, if it did.
+    #[serde(default)]
+    pub errors: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PendingJob {
+    pub fn new(job_id: String, kind: &str, book_id: String, params: serde_json::Value) -> Self {
+        Self {
+            job_id,
+            kind: kind.to_string(),
+            book_id,
+            params,
+            pending: Vec::new(),
+            completed: Vec::new(),
+            errors: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+fn pending_jobs_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("pending_jobs.json"))
+}
+
+pub fn read_pending_jobs_file(handle: &tauri::AppHandle) -> Result<PendingJobsData, String> {
+    let path = pending_jobs_file_path(handle)?;
+    if !path.exists() {
+        return Ok(PendingJobsData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn write_pending_jobs_file(handle: &tauri::AppHandle, data: &PendingJobsData) -> Result<(), String> {
+    let path = pending_jobs_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    with_write_lock(&path, || fs::write(&path, data).map_err(|e| e.to_string()))
+}
+
+// Records a job as started, replacing any earlier record under the same id.
+pub fn record(handle: &tauri::AppHandle, job: PendingJob) -> Result<(), String> {
+    handle.state::<AppState>().pending_jobs.update(handle, |data| {
+        data.jobs.retain(|j| j.job_id != job.job_id);
+        data.jobs.push(job);
+    })
+}
+
+// Changes a job's record; does nothing once the job is no longer recorded.
+fn update(handle: &tauri::AppHandle, job_id: &str, f: impl FnOnce(&mut PendingJob)) {
+    let result = handle.state::<AppState>().pending_jobs.update(handle, |data| {
+        if let Some(job) = data.jobs.iter_mut().find(|j| j.job_id == job_id) {
+            f(job);
+            job.updated_at = Utc::now();
+        }
+    });
+    if let Err(e) = result {
+        log::error!("Failed to update job state for {}: {}", job_id, e);
+    }
+}
+
+// Sets the work still to do, leaving out units already done.
+pub fn set_pending(handle: &tauri::AppHandle, job_id: &str, units: Vec<String>) {
+    update(handle, job_id, |job| {
+        job.pending = units.into_iter().filter(|unit| !job.completed.contains(unit)).collect();
+    });
+}
+
+pub fn complete_unit(handle: &tauri::AppHandle, job_id: &str, unit: &str) {
+    update(handle, job_id, |job| {
+        job.pending.retain(|u| u != unit);
+        if !job.completed.iter().any(|u| u == unit) {
+            job.completed.push(unit.to_string());
+        }
+    });
+}
+
+pub fn record_error(handle: &tauri::AppHandle, job_id: &str, message: &str) {
+    update(handle, job_id, |job| job.errors.push(message.to_string()));
+}
+
+pub fn remove(handle: &tauri::AppHandle, job_id: &str) {
+    let result = handle
+        .state::<AppState>()
+        .pending_jobs
+        .update(handle, |data| data.jobs.retain(|j| j.job_id != job_id));
+    if let Err(e) = result {
+        log::error!("Failed to remove job state for {}: {}", job_id, e);
+    }
+}
+
+// Jobs left unfinished by an earlier run (or stopped by an error), for the app to offer
+// resuming at startup.
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_pending_jobs(
+    handle: tauri::AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+) -> Result<Vec<PendingJob>, String> {
+    pending_jobs(&handle, &jobs)
+}
+
+fn pending_jobs(handle: &tauri::AppHandle, jobs: &JobRegistry) -> Result<Vec<PendingJob>, String> {
+    let pending = handle.state::<AppState>().pending_jobs.read(handle, |data| data.jobs.clone())?;
+    Ok(pending.into_iter().filter(|job| !jobs.is_running(&job.job_id)).collect())
+}
+
+// Starts every pending job again from where it stopped. Returns the ids of the jobs started.
+#[tauri::command(rename_all = "camelCase")]
+pub fn resume_pending_jobs(
+    handle: tauri::AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+) -> Result<Vec<String>, String> {
+    let mut resumed = Vec::new();
+    for job in pending_jobs(&handle, &jobs)? {
+        let result = match job.kind.as_str() {
+            "epub_translation" => epub_translation::resume(&handle, &jobs, &job),
+            kind => Err(format!("Jobs of kind {} cannot be resumed.", kind)),
+        };
+        match result {
+            Ok(()) => resumed.push(job.job_id),
+            Err(e) => {
+                log::error!("Failed to resume job {}: {}", job.job_id, e);
+                record_error(&handle, &job.job_id, &e);
+            }
+        }
+    }
+    Ok(resumed)
+}
+
+// Forgets a pending job without running it.
+#[tauri::command(rename_all = "camelCase")]
+pub fn discard_pending_job(handle: tauri::AppHandle, job_id: String) -> Result<(), String> {
+    remove(&handle, &job_id);
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::Manager;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::job_state;
+
+// Background provider calls share this many slots so prefetching never floods OpenRouter
+// or starves the requests the reader is actively waiting on.
+const BACKGROUND_PROVIDER_SLOTS: usize = 1;
+
+struct JobEntry {
+    kind: String,
+    token: u64,
+    task: JoinHandle<()>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobInfo {
+    job_id: String,
+    kind: String,
+}
+
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, JobEntry>>,
+    next_token: AtomicU64,
+    provider_slots: Semaphore,
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            next_token: AtomicU64::new(1),
+            provider_slots: Semaphore::new(BACKGROUND_PROVIDER_SLOTS),
+        }
+    }
+}
+
+impl JobRegistry {
+    // Starts a job under `job_id`, cancelling any job already running under the same id.
+    pub fn spawn<F>(&self, handle: &tauri::AppHandle, job_id: String, kind: &str, job: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        let finished_handle = handle.clone();
+        let finished_id = job_id.clone();
+
+        // Hold the lock while spawning so a job that finishes instantly cannot
+        // run `finish` before its entry exists.
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        // The registry is closed once shutdown starts; late jobs are dropped.
+        if self.provider_slots.is_closed() {
+            return;
+        }
+        let task = tauri::async_runtime::spawn(async move {
+            job.await;
+            finished_handle.state::<JobRegistry>().finish(&finished_id, token);
+        });
+        if let Some(previous) = jobs.insert(
+            job_id,
+            JobEntry {
+                kind: kind.to_string(),
+                token,
+                task,
+            },
+        ) {
+            previous.task.abort();
+        }
+    }
+
+    pub fn cancel(&self, job_id: &str) -> bool {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        match jobs.remove(job_id) {
+            Some(entry) => {
+                entry.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Aborts every running job but keeps accepting new ones.
+    pub fn cancel_all(&self) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        for (_, entry) in jobs.drain() {
+            entry.task.abort();
+        }
+    }
+
+    // Stops accepting jobs, wakes anything waiting for a provider slot with an error and
+    // aborts every running job, dropping its in-flight requests.
+    pub fn shutdown(&self) {
+        self.provider_slots.close();
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        for (_, entry) in jobs.drain() {
+            entry.task.abort();
+        }
+    }
+
+    fn finish(&self, job_id: &str, token: u64) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if jobs.get(job_id).is_some_and(|entry| entry.token == token) {
+            jobs.remove(job_id);
+        }
+    }
+
+    pub fn is_running(&self, job_id: &str) -> bool {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.contains_key(job_id)
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.iter()
+            .map(|(job_id, entry)| JobInfo {
+                job_id: job_id.clone(),
+                kind: entry.kind.clone(),
+            })
+            .collect()
+    }
+
+    // Waits for a background provider slot; the slot is released when the permit drops.
+    pub async fn acquire_provider_slot(&self) -> Result<SemaphorePermit<'_>, String> {
+        self.provider_slots.acquire().await.map_err(|e| e.to_string())
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_jobs(jobs: tauri::State<'_, JobRegistry>) -> Result<Vec<JobInfo>, String> {
+    Ok(jobs.list())
+}
+
+// Cancelled jobs are forgotten rather than offered for resuming later.
+#[tauri::command(rename_all = "camelCase")]
+pub fn cancel_job(
+    handle: tauri::AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+    job_id: String,
+) -> Result<bool, String> {
+    job_state::remove(&handle, &job_id);
+    Ok(jobs.cancel(&job_id))
+}
@@ -0,0 +1,346 @@
+use chrono::{DateTime, Utc};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::book_formats::page_at_progress;
+use crate::epub::{chapter_text, spine_hrefs};
+use crate::restricted_mode;
+use crate::{app_config_dir, app_state, ensure_cloud_allowed, hash_source_text, update_recent_books, RecentBook};
+
+const DEFAULT_SERVER: &str = "https://sync.koreader.rocks";
+const ACCEPT: &str = "application/vnd.koreader.v1+json";
+const DEVICE_NAME: &str = "PDFRead";
+// Progress differences below this (in percent) are not worth moving the reader for.
+const PROGRESS_TOLERANCE: f32 = 0.5;
+
+// Credentials as KOReader keeps them: the password only as its MD5 "userkey".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KoreaderAccount {
+    server: String,
+    username: String,
+    userkey: String,
+}
+
+// Which KOReader document a book syncs with. `document` is KOReader's hash of the file,
+// or whatever hash the device uses when it reads a different copy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KoreaderBookLink {
+    document: String,
+    enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct KoreaderSyncData {
+    account: Option<KoreaderAccount>,
+    device_id: String,
+    books: HashMap<String, KoreaderBookLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KoreaderSyncStatus {
+    server: Option<String>,
+    username: Option<String>,
+    device_id: String,
+    books: HashMap<String, KoreaderBookLink>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    Pushed,
+    Pulled,
+    Unchanged,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KoreaderSyncResult {
+    direction: SyncDirection,
+    page: u32,
+    progress: f32,
+    // The device the position came from, when it was pulled.
+    device: Option<String>,
+}
+
+// What the server stores per document. Everything is optional: an unknown document is `{}`.
+#[derive(Debug, Deserialize)]
+struct RemoteProgress {
+    progress: Option<String>,
+    percentage: Option<f32>,
+    device: Option<String>,
+    device_id: Option<String>,
+    timestamp: Option<i64>,
+}
+
+fn sync_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("koreader_sync.json"))
+}
+
+fn load_sync_data(handle: &tauri::AppHandle) -> Result<KoreaderSyncData, String> {
+    let path = sync_file_path(handle)?;
+    let mut data: KoreaderSyncData = if path.exists() {
+        let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&contents).map_err(|e| e.to_string())?
+    } else {
+        KoreaderSyncData::default()
+    };
+    if data.device_id.is_empty() {
+        // KOReader device ids are 32 hex digits.
+        let seed = format!("{}|{}", Utc::now().to_rfc3339(), std::process::id());
+        data.device_id = hash_source_text(&seed)[..32].to_uppercase();
+        save_sync_data(handle, &data)?;
+    }
+    Ok(data)
+}
+
+fn save_sync_data(handle: &tauri::AppHandle, data: &KoreaderSyncData) -> Result<(), String> {
+    let path = sync_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn md5_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Md5::digest(bytes))
+}
+
+// KOReader's "binary" document hash: MD5 over 1 KiB samples at offsets 0, 1 KiB, 4 KiB,
+// 16 KiB, ... up to 1 GiB, stopping at the end of the file.
+pub fn document_hash(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; 1024];
+    for i in -1i32..=10 {
+        let offset = if i < 0 { 0 } else { 1024u64 << (2 * i) };
+        file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn request(
+    client: &reqwest::Client,
+    method: reqwest::Method,
+    account: &KoreaderAccount,
+    path: &str,
+) -> reqwest::RequestBuilder {
+    client
+        .request(method, format!("{}{}", account.server.trim_end_matches('/'), path))
+        .header("Accept", ACCEPT)
+        .header("x-auth-user", &account.username)
+        .header("x-auth-key", &account.userkey)
+}
+
+fn find_book(handle: &tauri::AppHandle, book_id: &str) -> Result<RecentBook, String> {
+    handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+        .ok_or_else(|| format!("Book not found: {}", book_id))
+}
+
+// KOReader locates EPUB positions by XPointer; the spine item at the book's progress is as
+// close as we get without the rendered document.
+fn epub_xpointer(path: &Path, progress: f32) -> Option<String> {
+    let hrefs = spine_hrefs(path).ok()?;
+    let lengths: Vec<usize> = hrefs.iter().map(|href| chapter_text(path, href).map_or(0, |t| t.len())).collect();
+    let total: usize = lengths.iter().sum();
+    let target = (progress.clamp(0.0, 100.0) / 100.0 * total as f32) as usize;
+    let mut start = 0;
+    for (index, length) in lengths.iter().enumerate() {
+        if target < start + length || index + 1 == lengths.len() {
+            return Some(format!("/body/DocFragment[{}]/body", index + 1));
+        }
+        start += length;
+    }
+    None
+}
+
+// The position string KOReader expects: the page number for paged formats, an XPointer for EPUB.
+fn progress_string(book: &RecentBook) -> String {
+    if book.file_type == "epub" {
+        if let Some(xpointer) = epub_xpointer(Path::new(&book.file_path), book.progress) {
+            return xpointer;
+        }
+    }
+    book.last_page.max(1).to_string()
+}
+
+// Signs in to a KOReader sync server (KOReader's own by default), creating the account
+// first when `register` is set. Only the password's MD5 is stored, as KOReader does.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn koreader_login(
+    handle: tauri::AppHandle,
+    server: Option<String>,
+    username: String,
+    password: String,
+    register: bool,
+) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Signing in to KOReader sync")?;
+    let account = KoreaderAccount {
+        server: server.filter(|s| !s.trim().is_empty()).unwrap_or_else(|| DEFAULT_SERVER.to_string()),
+        username: username.trim().to_string(),
+        userkey: md5_hex(password.as_bytes()),
+    };
+    if account.username.is_empty() {
+        return Err("Username is empty.".to_string());
+    }
+    let client = reqwest::Client::new();
+    if register {
+        let response = request(&client, reqwest::Method::POST, &account, "/users/create")
+            .json(&serde_json::json!({ "username": account.username, "password": account.userkey }))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Could not create the account: {}", response.status()));
+        }
+    }
+    let response = request(&client, reqwest::Method::GET, &account, "/users/auth")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("KOReader sync login failed: {}", response.status()));
+    }
+
+    let mut data = load_sync_data(&handle)?;
+    data.account = Some(account);
+    save_sync_data(&handle, &data)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn koreader_logout(handle: tauri::AppHandle) -> Result<(), String> {
+    let mut data = load_sync_data(&handle)?;
+    data.account = None;
+    save_sync_data(&handle, &data)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_koreader_sync_status(handle: tauri::AppHandle) -> Result<KoreaderSyncStatus, String> {
+    let data = load_sync_data(&handle)?;
+    Ok(KoreaderSyncStatus {
+        server: data.account.as_ref().map(|a| a.server.clone()),
+        username: data.account.as_ref().map(|a| a.username.clone()),
+        device_id: data.device_id,
+        books: data.books,
+    })
+}
+
+// Turns syncing on or off for a book. The document hash defaults to KOReader's hash of the
+// book's file; pass the device's own when it reads a different copy.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_koreader_book_sync(
+    handle: tauri::AppHandle,
+    book_id: String,
+    enabled: bool,
+    document: Option<String>,
+) -> Result<KoreaderBookLink, String> {
+    let book = find_book(&handle, &book_id)?;
+    let document = match document.map(|d| d.trim().to_lowercase()).filter(|d| !d.is_empty()) {
+        Some(document) => document,
+        None => document_hash(Path::new(&book.file_path))?,
+    };
+    let link = KoreaderBookLink { document, enabled };
+    let mut data = load_sync_data(&handle)?;
+    data.books.insert(book_id, link.clone());
+    save_sync_data(&handle, &data)?;
+    Ok(link)
+}
+
+// Compares the book's position with the server's. A newer position from another device is
+// applied to the book; otherwise ours is uploaded.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn sync_koreader_progress(
+    handle: tauri::AppHandle,
+    book_id: String,
+) -> Result<KoreaderSyncResult, String> {
+    let data = load_sync_data(&handle)?;
+    let account = data.account.clone().ok_or_else(|| "Not signed in to a KOReader sync server.".to_string())?;
+    let link = data
+        .books
+        .get(&book_id)
+        .filter(|link| link.enabled)
+        .cloned()
+        .ok_or_else(|| "KOReader sync is not enabled for this book.".to_string())?;
+    ensure_cloud_allowed(&handle, &book_id)?;
+    let book = find_book(&handle, &book_id)?;
+
+    let client = reqwest::Client::new();
+    let progress_path = format!("/syncs/progress/{}", link.document);
+    let response = request(&client, reqwest::Method::GET, &account, &progress_path)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("KOReader sync error: {}", response.status()));
+    }
+    let remote: RemoteProgress = response.json().await.map_err(|e| e.to_string())?;
+
+    let remote_time = remote.timestamp.and_then(|t| DateTime::<Utc>::from_timestamp(t, 0));
+    let from_other_device = remote.device_id.as_deref().is_some_and(|id| id != data.device_id);
+    if let (Some(percentage), Some(remote_time)) = (remote.percentage, remote_time) {
+        let progress = (percentage * 100.0).clamp(0.0, 100.0);
+        if from_other_device && remote_time > book.last_opened_at {
+            if (progress - book.progress).abs() < PROGRESS_TOLERANCE {
+                return Ok(KoreaderSyncResult {
+                    direction: SyncDirection::Unchanged,
+                    page: book.last_page,
+                    progress: book.progress,
+                    device: remote.device,
+                });
+            }
+            // Paged formats report the page itself; anything else goes by percentage.
+            let page = match remote.progress.as_deref().and_then(|p| p.parse::<u32>().ok()) {
+                Some(page) if book.file_type != "epub" => page.clamp(1, book.total_pages.max(1)),
+                _ => page_at_progress(progress, book.total_pages),
+            };
+            update_recent_books(&handle, |data| {
+                if let Some(book) = data.books.iter_mut().find(|b| b.id == book_id) {
+                    book.last_page = page;
+                    book.progress = progress;
+                    book.last_opened_at = remote_time;
+                }
+            })?;
+            return Ok(KoreaderSyncResult {
+                direction: SyncDirection::Pulled,
+                page,
+                progress,
+                device: remote.device,
+            });
+        }
+    }
+
+    let body = serde_json::json!({
+        "document": link.document,
+        "progress": progress_string(&book),
+        "percentage": book.progress / 100.0,
+        "device": DEVICE_NAME,
+        "device_id": data.device_id,
+    });
+    let response = request(&client, reqwest::Method::PUT, &account, "/syncs/progress")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("KOReader sync error: {}", response.status()));
+    }
+    Ok(KoreaderSyncResult {
+        direction: SyncDirection::Pushed,
+        page: book.last_page,
+        progress: book.progress,
+        device: None,
+    })
+}
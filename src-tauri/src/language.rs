@@ -0,0 +1,31 @@
+use whatlang::Lang;
+
+// ISO 639-3 (what the detector reports) to the ISO 639-1 codes used across the app.
+const ISO_639_1: &[(Lang, &str)] = &[
+    (Lang::Eng, "en"), (Lang::Cmn, "zh"), (Lang::Jpn, "ja"), (Lang::Kor, "ko"), (Lang::Spa, "es"),
+    (Lang::Fra, "fr"), (Lang::Deu, "de"), (Lang::Ita, "it"), (Lang::Por, "pt"), (Lang::Rus, "ru"),
+    (Lang::Ukr, "uk"), (Lang::Nld, "nl"), (Lang::Pol, "pl"), (Lang::Tur, "tr"), (Lang::Ara, "ar"),
+    (Lang::Heb, "he"), (Lang::Hin, "hi"), (Lang::Vie, "vi"), (Lang::Tha, "th"), (Lang::Ind, "id"),
+    (Lang::Swe, "sv"), (Lang::Dan, "da"), (Lang::Fin, "fi"), (Lang::Ell, "el"), (Lang::Ces, "cs"),
+    (Lang::Hun, "hu"), (Lang::Ron, "ro"), (Lang::Bul, "bg"), (Lang::Pes, "fa"),
+];
+
+fn iso_639_1(lang: Lang) -> String {
+    ISO_639_1
+        .iter()
+        .find(|(candidate, _)| *candidate == lang)
+        .map(|(_, code)| code.to_string())
+        .unwrap_or_else(|| lang.code().to_string())
+}
+
+// Detects the language of `text`. Short Latin-script text (a single word, say) is too
+// ambiguous to call, so only reliable guesses are returned.
+pub fn detect_language(text: &str) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    info.is_reliable().then(|| iso_639_1(info.lang()))
+}
+
+// Primary subtag, lowercased: "zh-CN" and "zh" name the same language for filtering.
+pub fn primary_language(code: &str) -> String {
+    code.split(['-', '_']).next().unwrap_or(code).to_lowercase()
+}
@@ -0,0 +1,136 @@
+use serde::Serialize;
+
+use crate::language::primary_language;
+
+// A target language offered in the picker. `code` is what the reader has always sent;
+// `script` and `region` name the variant explicitly.
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedLanguage {
+    label: &'static str,
+    native_label: &'static str,
+    code: &'static str,
+    script: Option<&'static str>,
+    region: Option<&'static str>,
+    // How prompts spell the variant out for the model.
+    variant_note: Option<&'static str>,
+}
+
+const fn plain(label: &'static str, native_label: &'static str, code: &'static str) -> SupportedLanguage {
+    SupportedLanguage { label, native_label, code, script: None, region: None, variant_note: None }
+}
+
+const fn variant(
+    label: &'static str,
+    native_label: &'static str,
+    code: &'static str,
+    script: Option<&'static str>,
+    region: Option<&'static str>,
+    variant_note: &'static str,
+) -> SupportedLanguage {
+    SupportedLanguage { label, native_label, code, script, region, variant_note: Some(variant_note) }
+}
+
+const SUPPORTED_LANGUAGES: &[SupportedLanguage] = &[
+    variant(
+        "Chinese (Simplified)",
+        "简体中文",
+        "zh-CN",
+        Some("Hans"),
+        Some("CN"),
+        "Simplified Chinese characters, Mainland China usage",
+    ),
+    variant(
+        "Chinese (Traditional, Taiwan)",
+        "繁體中文（台灣）",
+        "zh-TW",
+        Some("Hant"),
+        Some("TW"),
+        "Traditional Chinese characters, Taiwan usage and vocabulary",
+    ),
+    variant(
+        "Chinese (Traditional, Hong Kong)",
+        "繁體中文（香港）",
+        "zh-HK",
+        Some("Hant"),
+        Some("HK"),
+        "Traditional Chinese characters, Hong Kong usage and vocabulary",
+    ),
+    plain("Japanese", "日本語", "ja"),
+    plain("Korean", "한국어", "ko"),
+    variant("English (US)", "English (US)", "en-US", None, Some("US"), "American spelling and usage"),
+    variant("English (UK)", "English (UK)", "en-GB", None, Some("GB"), "British spelling and usage"),
+    plain("Spanish", "Español", "es"),
+    variant(
+        "Spanish (Latin America)",
+        "Español (Latinoamérica)",
+        "es-419",
+        None,
+        Some("419"),
+        "Latin American Spanish; ustedes rather than vosotros",
+    ),
+    plain("French", "Français", "fr"),
+    variant("French (Canada)", "Français (Canada)", "fr-CA", None, Some("CA"), "Canadian French usage"),
+    plain("German", "Deutsch", "de"),
+    plain("Italian", "Italiano", "it"),
+    variant("Portuguese (Brazil)", "Português (Brasil)", "pt-BR", None, Some("BR"), "Brazilian Portuguese"),
+    variant("Portuguese (Portugal)", "Português (Portugal)", "pt-PT", None, Some("PT"), "European Portuguese"),
+    plain("Russian", "Русский", "ru"),
+    plain("Ukrainian", "Українська", "uk"),
+    variant("Serbian (Cyrillic)", "Српски", "sr", Some("Cyrl"), None, "Serbian in Cyrillic script"),
+    variant("Serbian (Latin)", "Srpski", "sr", Some("Latn"), None, "Serbian in Latin script"),
+    plain("Dutch", "Nederlands", "nl"),
+    plain("Polish", "Polski", "pl"),
+    plain("Turkish", "Türkçe", "tr"),
+    plain("Arabic", "العربية", "ar"),
+    plain("Hebrew", "עברית", "he"),
+    plain("Hindi", "हिन्दी", "hi"),
+    plain("Vietnamese", "Tiếng Việt", "vi"),
+    plain("Thai", "ไทย", "th"),
+    plain("Indonesian", "Bahasa Indonesia", "id"),
+    plain("Swedish", "Svenska", "sv"),
+];
+
+// Canonical BCP 47 casing: language lowercase, script title case, region uppercase.
+pub fn variant_tag(code: &str, script: Option<&str>, region: Option<&str>) -> String {
+    let mut parts = vec![primary_language(code)];
+    if let Some(script) = script.map(str::trim).filter(|s| !s.is_empty()) {
+        let mut chars = script.chars();
+        let first = chars.next().map(|c| c.to_ascii_uppercase()).into_iter();
+        parts.push(first.chain(chars.map(|c| c.to_ascii_lowercase())).collect());
+    }
+    if let Some(region) = region.map(str::trim).filter(|r| !r.is_empty()) {
+        parts.push(region.to_ascii_uppercase());
+    }
+    parts.join("-")
+}
+
+// The catalog code for a tag when the code names the variant by itself ("zh-Hans-CN" and
+// "zh-cn" become "zh-CN"), otherwise the tag as given. Cache keys and per-language rules use
+// these codes, as the reader always sent them.
+pub fn normalize_tag(tag: &str) -> String {
+    SUPPORTED_LANGUAGES
+        .iter()
+        .filter(|language| language.code.contains('-'))
+        .find(|language| {
+            let full = variant_tag(language.code, language.script, language.region);
+            language.code.eq_ignore_ascii_case(tag) || full.eq_ignore_ascii_case(tag)
+        })
+        .map_or_else(|| tag.to_string(), |language| language.code.to_string())
+}
+
+// What the variant means, for prompts. Matches the full variant tag ("zh-Hant-TW") or a
+// catalog code that names the variant by itself ("zh-TW", but not "sr").
+pub fn variant_note(tag: &str) -> Option<&'static str> {
+    SUPPORTED_LANGUAGES
+        .iter()
+        .find(|language| {
+            let full = variant_tag(language.code, language.script, language.region);
+            (language.code.contains('-') && language.code.eq_ignore_ascii_case(tag)) || full.eq_ignore_ascii_case(tag)
+        })
+        .and_then(|language| language.variant_note)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_supported_languages() -> Vec<SupportedLanguage> {
+    SUPPORTED_LANGUAGES.to_vec()
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{app_config_dir, hash_source_text};
+use crate::language::primary_language;
+use crate::language_catalog::normalize_tag;
+use crate::quarantine::parse_or_quarantine;
+use crate::restricted_mode;
+use crate::undo;
+
+// Standing instructions per target language ("use 繁體中文", "prefer tu over vous"), keyed
+// by language code as given, e.g. "zh-TW" or "fr".
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct LanguageRulesData {
+    rules: BTreeMap<String, Vec<String>>,
+}
+
+fn rules_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("language_rules.json"))
+}
+
+fn load_rules(handle: &tauri::AppHandle) -> Result<LanguageRulesData, String> {
+    let path = rules_file_path(handle)?;
+    if !path.exists() {
+        return Ok(LanguageRulesData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_rules(handle: &tauri::AppHandle, data: &LanguageRulesData) -> Result<(), String> {
+    let path = rules_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// Rules for a target language: those for the exact code ("pt-BR", or "zh-TW" when saved as
+// "zh-Hant-TW") first, then those for the language as a whole ("pt").
+pub fn rules_for(handle: &tauri::AppHandle, code: &str) -> Vec<String> {
+    let data = match load_rules(handle) {
+        Ok(data) => data,
+        Err(e) => {
+            log::error!("Failed to load language rules: {}", e);
+            return Vec::new();
+        }
+    };
+    let primary = primary_language(code);
+    let code = normalize_tag(code);
+    let is_exact = |key: &str| normalize_tag(key).eq_ignore_ascii_case(&code);
+    let mut rules: Vec<String> = Vec::new();
+    let exact = data.rules.iter().filter(|(key, _)| is_exact(key));
+    let general = data.rules.iter().filter(|(key, _)| !is_exact(key) && key.to_lowercase() == primary);
+    for (_, list) in exact.chain(general) {
+        for rule in list {
+            if !rules.contains(rule) {
+                rules.push(rule.clone());
+            }
+        }
+    }
+    rules
+}
+
+// Identifies the rules in force for a target language, or None when it has none.
+pub fn rules_hash(handle: &tauri::AppHandle, code: &str) -> Option<String> {
+    let rules = rules_for(handle, code);
+    if rules.is_empty() {
+        return None;
+    }
+    Some(hash_source_text(&rules.join("\n"))[..12].to_string())
+}
+
+// Prompt lines for the target language's rules, or nothing when it has none.
+pub fn prompt_section(handle: &tauri::AppHandle, code: &str) -> String {
+    let rules = rules_for(handle, code);
+    if rules.is_empty() {
+        return String::new();
+    }
+    let lines: Vec<String> = rules.iter().map(|rule| format!("- {}", rule)).collect();
+    format!("\nRules for this target language (always follow):\n{}", lines.join("\n"))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_language_rules(handle: tauri::AppHandle) -> Result<BTreeMap<String, Vec<String>>, String> {
+    Ok(load_rules(&handle)?.rules)
+}
+
+// Replaces the rules for one language code; an empty list removes them.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_language_rules(
+    handle: tauri::AppHandle,
+    language: String,
+    rules: Vec<String>,
+) -> Result<Vec<String>, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Editing language rules")?;
+    let language = language.trim().to_string();
+    if language.is_empty() {
+        return Err("Language code is empty.".to_string());
+    }
+    let mut cleaned: Vec<String> = Vec::new();
+    for rule in rules.iter().map(|rule| rule.trim()).filter(|rule| !rule.is_empty()) {
+        if !cleaned.iter().any(|r| r == rule) {
+            cleaned.push(rule.to_string());
+        }
+    }
+
+    let before = stored_rules(&load_rules(&handle)?, &language);
+    let after = if cleaned.is_empty() { Vec::new() } else { vec![(language.clone(), cleaned.clone())] };
+    replace_rules(&handle, &language, after.clone())?;
+    undo::record(&handle, undo::Operation::SetLanguageRules { language, before, after });
+    Ok(cleaned)
+}
+
+// Rules stored under any spelling of `language`, e.g. both "pt-br" and "pt-BR".
+fn stored_rules(data: &LanguageRulesData, language: &str) -> Vec<(String, Vec<String>)> {
+    data.rules
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case(language))
+        .map(|(key, rules)| (key.clone(), rules.clone()))
+        .collect()
+}
+
+// Drops the rules stored under any spelling of `language` and stores `rules` instead. Undo
+// and redo go through here too, so restricted mode covers them.
+pub fn replace_rules(
+    handle: &tauri::AppHandle,
+    language: &str,
+    rules: Vec<(String, Vec<String>)>,
+) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(handle, "Editing language rules")?;
+    let mut data = load_rules(handle)?;
+    data.rules.retain(|key, _| !key.eq_ignore_ascii_case(language));
+    data.rules.extend(rules);
+    save_rules(handle, &data)
+}
@@ -1,26 +1,200 @@
 use serde::{Deserialize, Serialize};
-use tauri::Manager;
-use std::collections::HashMap;
+use tauri::{Emitter, Manager};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
-use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Datelike, Utc};
+
+mod activity_log;
+mod alignment;
+mod alternatives;
+mod app_state;
+mod articles;
+mod arxiv;
+mod audiobook;
+mod book_formats;
+mod book_level;
+mod book_metadata;
+mod book_pack;
+mod book_statistics;
+mod book_text;
+mod citations;
+mod cloud_policy;
+mod companion_server;
+mod continuation;
+mod data_dir;
+mod encryption;
+mod entities;
+mod epub;
+mod epub_translation;
+mod export_templates;
+mod external_open;
+mod feeds;
+mod footnotes;
+mod gloss;
+mod granularity;
+mod hooks;
+mod isbn;
+mod job_state;
+mod jobs;
+mod koreader_sync;
+mod language;
+mod language_catalog;
+mod language_rules;
+mod library_cleanup;
+mod library_import;
+mod lookup_history;
+mod maintenance;
+mod math;
+mod model_catalog;
+mod model_params;
+mod new_edition;
+mod obsidian;
+mod offline;
+mod onboarding;
+mod openrouter_oauth;
+mod page_words;
+mod pagination;
+mod plugins;
+mod position_map;
+mod prefetch;
+mod private_books;
+mod profiles;
+mod progress;
+mod pronunciation;
+mod quarantine;
+mod quota;
+mod quote_card;
+mod read_aloud;
+mod readability;
+mod reading_activity;
+mod reading_report;
+mod readings;
+mod recommendations;
+mod response_cache;
+mod restricted_mode;
+mod review_reminders;
+mod segmentation;
+mod send_to_device;
+mod series;
+mod settings;
+mod skip_ranges;
+mod srs;
+mod story;
+mod structure;
+mod sync_conflicts;
+mod text_cleanup;
+mod translation_feedback;
+mod transliteration;
+mod undo;
+#[cfg(feature = "updater")]
+mod updater;
+mod url_import;
+mod vocab_export;
+mod vocab_index;
+mod vocab_merge;
+mod vocab_sheet;
+mod web_import;
+mod word_frequency;
+mod word_lists;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TargetLanguage {
     label: String,
     code: String,
+    // Script (e.g. "Hant") and region (e.g. "BR") of the variant wanted, when the code alone
+    // does not say; see `language_catalog`.
+    #[serde(default)]
+    script: Option<String>,
+    #[serde(default)]
+    region: Option<String>,
+}
+
+impl TargetLanguage {
+    // BCP 47 tag of the wanted variant, e.g. "sr-Latn" or "pt-BR". A variant the catalog has
+    // a short code for gets that code ("zh-Hans-CN" is "zh-CN"), so existing cache entries and
+    // language rules keep matching.
+    fn tag(&self) -> String {
+        if self.script.is_none() && self.region.is_none() {
+            return language_catalog::normalize_tag(&self.code);
+        }
+        let tag = language_catalog::variant_tag(&self.code, self.script.as_deref(), self.region.as_deref());
+        language_catalog::normalize_tag(&tag)
+    }
+
+    // What prompts put in brackets after the label: the tag, and what the variant means.
+    fn prompt_tag(&self) -> String {
+        let tag = self.tag();
+        match language_catalog::variant_note(&tag) {
+            Some(note) => format!("{}; {}", tag, note),
+            None => tag,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 struct TranslateSentence {
     sid: String,
     text: String,
+    // Neighbouring source text sent along as context only; see `granularity`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    before: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    after: Option<String>,
+    // As tagged at extraction; tables and code pass through untranslated.
+    #[serde(default, skip_serializing_if = "structure::BlockKind::is_body")]
+    kind: structure::BlockKind,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TranslationResult {
     sid: String,
     translation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generation: Option<GenerationInfo>,
+}
+
+// Which request produced a translation, as reported by OpenRouter, for auditing which
+// provider actually served it.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct GenerationInfo {
+    generation_id: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    // What the request this came in cost, in US dollars, and how long it took. A batch's
+    // translations all carry the batch's figures.
+    #[serde(default)]
+    cost: Option<f64>,
+    #[serde(default)]
+    latency_ms: Option<u64>,
+    // Missing on translations cached before this was recorded.
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+}
+
+// Sent as `generation-completed` after every model request, for features that return only
+// the text.
+#[derive(Debug, Clone, Serialize)]
+struct GenerationCompleted {
+    feature: String,
+    #[serde(flatten)]
+    generation: GenerationInfo,
+}
+
+// The part of OpenRouter's generation stats used here.
+#[derive(Debug, Deserialize)]
+struct GenerationStats {
+    #[serde(default)]
+    total_cost: Option<f64>,
+    #[serde(default)]
+    provider_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerationStatsResponse {
+    data: GenerationStats,
 }
 
 // Flexible struct to handle various LLM response formats
@@ -39,23 +213,96 @@ struct OpenRouterMessage {
 #[derive(Debug, Deserialize)]
 struct OpenRouterChoice {
     message: OpenRouterMessage,
+    // "length" when the answer was cut off at `max_tokens`.
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenRouterResponse {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    provider: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
     choices: Vec<OpenRouterChoice>,
+    #[serde(default)]
+    usage: Option<quota::ReportedUsage>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+struct OpenRouterCompletion {
+    content: String,
+    generation: GenerationInfo,
+    // The answer hit the output token limit and is incomplete.
+    truncated: bool,
+}
+
+// Everything needed to call OpenRouter on the user's behalf.
+#[derive(Clone)]
+struct OpenRouterCredentials {
+    api_key: String,
+    user: Option<String>,
+    metadata: BTreeMap<String, String>,
+    sampling: model_params::SamplingSettings,
+    // For checking usage caps and recording usage under `feature`.
+    handle: tauri::AppHandle,
+    feature: String,
+    // Sent as `max_tokens`; the configured limit unless a request needs less.
+    max_tokens: Option<u32>,
+    // The caller's temperature is kept even when a sampling preset is set.
+    pinned_temperature: bool,
+}
+
+impl OpenRouterCredentials {
+    fn for_feature(&self, feature: &str) -> Self {
+        Self { feature: feature.to_string(), ..self.clone() }
+    }
+
+    // For requests that depend on their temperature: deterministic lookups and extraction at
+    // 0, and translation at the temperature the user chose.
+    fn pin_temperature(&self) -> Self {
+        Self { pinned_temperature: true, ..self.clone() }
+    }
+
+    // Requests at most `tokens` of answer, or the configured limit when that is lower.
+    fn limit_output(&self, tokens: u32) -> Self {
+        let max_tokens = Some(self.max_tokens.map_or(tokens, |limit| limit.min(tokens)));
+        Self { max_tokens, ..self.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
 struct CachedTranslations {
     entries: HashMap<String, String>,
+    // Keyed like `entries`; only translations made since this was added have one.
+    #[serde(default)]
+    generations: HashMap<String, GenerationInfo>,
 }
 
+// Root for all stored data; see `data_dir` for how it can be moved.
+fn data_root(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    match handle.state::<data_dir::DataDir>().current() {
+        Some(path) => Ok(path),
+        None => data_dir::default_config_dir(handle),
+    }
+}
+
+// Folder of the active profile, where nearly everything is stored; see `profiles`.
 fn app_config_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-    handle
-        .path()
-        .app_config_dir()
-        .map_err(|_| "Failed to resolve app config directory.".to_string())
+    let root = data_root(handle)?;
+    Ok(match handle.state::<profiles::ActiveProfile>().current() {
+        Some(name) => profiles::profile_dir(&root, &name),
+        None => root,
+    })
+}
+
+// Per-book data lives in `<config>/<dir_name>/<book_id>.json`.
+fn book_data_file_path(handle: &tauri::AppHandle, dir_name: &str, book_id: &str) -> Result<PathBuf, String> {
+    if book_id.is_empty() || book_id.contains(['/', '\\', '.']) {
+        return Err(format!("Invalid book id: {}", book_id));
+    }
+    Ok(app_config_dir(handle)?.join(dir_name).join(format!("{}.json", book_id)))
 }
 
 fn cache_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
@@ -63,7 +310,8 @@ fn cache_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
 }
 
 fn openrouter_key_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-    Ok(app_config_dir(handle)?.join("openrouter_key.txt"))
+    // Shared by all profiles.
+    Ok(data_root(handle)?.join("openrouter_key.txt"))
 }
 
 fn vocabulary_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
@@ -76,49 +324,78 @@ struct VocabularyEntry {
     phonetic: Option<String>,
     definitions: Vec<WordDefinitionResult>,
     added_at: DateTime<Utc>,
+    // Language of the word and of its definitions; missing on entries saved before these
+    // were recorded.
+    #[serde(default)]
+    source_lang: Option<String>,
+    #[serde(default)]
+    target_lang: Option<String>,
+    // The book the word was saved from; missing on entries saved before it was recorded.
+    #[serde(default)]
+    book_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct VocabularyData {
     entries: Vec<VocabularyEntry>,
 }
 
-fn load_vocabulary(handle: &tauri::AppHandle) -> Result<VocabularyData, String> {
+fn read_vocabulary_file(handle: &tauri::AppHandle) -> Result<VocabularyData, String> {
     let path = vocabulary_file_path(handle)?;
     if !path.exists() {
         return Ok(VocabularyData { entries: Vec::new() });
     }
-    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&data).map_err(|e| e.to_string())
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    quarantine::parse_or_quarantine(handle, &path, &data)
 }
 
-fn save_vocabulary(handle: &tauri::AppHandle, vocab: &VocabularyData) -> Result<(), String> {
+fn write_vocabulary_file(handle: &tauri::AppHandle, vocab: &VocabularyData) -> Result<(), String> {
     let path = vocabulary_file_path(handle)?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
     let data = serde_json::to_string_pretty(vocab).map_err(|e| e.to_string())?;
-    fs::write(path, data).map_err(|e| e.to_string())
+    sync_conflicts::with_write_lock(&path, || fs::write(&path, data).map_err(|e| e.to_string()))
 }
 
-fn load_cache(handle: &tauri::AppHandle) -> Result<CachedTranslations, String> {
+fn read_cache_file(handle: &tauri::AppHandle) -> Result<CachedTranslations, String> {
     let path = cache_file_path(handle)?;
     if !path.exists() {
         return Ok(CachedTranslations {
             entries: HashMap::new(),
+            generations: HashMap::new(),
         });
     }
-    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&data).map_err(|e| e.to_string())
+    let data = encryption::read_store_file(handle, &path)?;
+    quarantine::parse_or_quarantine(handle, &path, &data)
 }
 
-fn save_cache(handle: &tauri::AppHandle, cache: &CachedTranslations) -> Result<(), String> {
+fn write_cache_file(handle: &tauri::AppHandle, cache: &CachedTranslations) -> Result<(), String> {
     let path = cache_file_path(handle)?;
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-    }
     let data = serde_json::to_string_pretty(cache).map_err(|e| e.to_string())?;
-    fs::write(path, data).map_err(|e| e.to_string())
+    encryption::write_store_file(handle, &path, &data)
+}
+
+fn read_cache<R>(handle: &tauri::AppHandle, f: impl FnOnce(&CachedTranslations) -> R) -> Result<R, String> {
+    handle.state::<app_state::AppState>().cache.read(handle, f)
+}
+
+fn update_cache<R>(
+    handle: &tauri::AppHandle,
+    f: impl FnOnce(&mut CachedTranslations) -> R,
+) -> Result<R, String> {
+    handle.state::<app_state::AppState>().cache.update(handle, f)
+}
+
+fn load_vocabulary(handle: &tauri::AppHandle) -> Result<VocabularyData, String> {
+    handle.state::<app_state::AppState>().vocabulary.get(handle)
+}
+
+fn update_recent_books<R>(
+    handle: &tauri::AppHandle,
+    f: impl FnOnce(&mut RecentBooksData) -> R,
+) -> Result<R, String> {
+    handle.state::<app_state::AppState>().recent_books.update(handle, f)
 }
 
 fn load_openrouter_key(handle: &tauri::AppHandle) -> Result<String, String> {
@@ -145,6 +422,7 @@ fn get_openrouter_key_info(handle: tauri::AppHandle) -> Result<KeyInfo, String>
 
 #[tauri::command]
 fn save_openrouter_key(handle: tauri::AppHandle, key: String) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing the API key")?;
     let trimmed = key.trim();
     if trimmed.is_empty() {
         return Err("OpenRouter API key is empty.".to_string());
@@ -158,6 +436,7 @@ fn save_openrouter_key(handle: tauri::AppHandle, key: String) -> Result<(), Stri
 
 #[tauri::command]
 async fn test_openrouter_key(handle: tauri::AppHandle) -> Result<(), String> {
+    offline::ensure_online(&handle)?;
     let api_key = load_openrouter_key(&handle)?;
     let client = reqwest::Client::new();
     let response = client
@@ -205,10 +484,42 @@ Return JSON in this exact format:
 - definitions: array of objects with pos (part of speech like n., v., adj., adv., etc.) and meanings (translations separated by semicolons)
 - Only include parts of speech that apply to this word
 - Meanings should be in {}"#,
-        word, target_language.label, target_language.code, target_language.label
+        word, target_language.label, target_language.prompt_tag(), target_language.label
+    )
+}
+
+fn build_phrase_lookup_system_prompt() -> String {
+    [
+        "You are a dictionary of idioms, phrasal verbs and set expressions.",
+        "Explain the expression as a whole, not word by word.",
+        "Output STRICT JSON ONLY.",
+        "No markdown, no explanations, no extra text.",
+    ]
+    .join(" ")
+}
+
+fn build_phrase_lookup_prompt(phrase: &str, context: &str, target_language: &TargetLanguage) -> String {
+    format!(
+        r#"Explain the expression "{}" as used in this context: "{}"
+Answer in {} ({}).
+Return JSON in this exact format:
+{{"meaning": "what the expression means here", "literal_gloss": "word-by-word literal rendering", "register": "formal|neutral|informal|slang|literary|technical", "examples": ["example sentence 1", "example sentence 2"]}}
+- meaning: the idiomatic meaning in {}
+- literal_gloss: the literal meaning of the individual words in {}
+- examples: one or two short sentences using the expression, in its original language"#,
+        phrase, context, target_language.label, target_language.prompt_tag(), target_language.label, target_language.label
     )
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct PhraseLookupResult {
+    meaning: String,
+    literal_gloss: Option<String>,
+    register: Option<String>,
+    #[serde(default)]
+    examples: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct WordLookupResult {
     phonetic: Option<String>,
@@ -225,7 +536,7 @@ fn build_user_prompt(target_language: &TargetLanguage, sentences: &[TranslateSen
     let payload = serde_json::to_string(sentences).unwrap_or_else(|_| "[]".to_string());
     format!(
         "Target language: {} ({})\nTranslation style: faithful, clear, readable\nInput JSON: {}",
-        target_language.label, target_language.code, payload
+        target_language.label, target_language.prompt_tag(), payload
     )
 }
 
@@ -234,6 +545,9 @@ fn read_pdf_file(path: String) -> Result<Vec<u8>, String> {
     let path_ref = std::path::Path::new(&path);
 
     // Check if the path is a directory (macOS treats some epub files as bundles)
+    if library_import::is_epub_dir(path_ref) {
+        return library_import::pack_epub_dir(path_ref);
+    }
     if path_ref.is_dir() {
         // If it's a directory (epub bundle), zip it into memory
         return zip_directory_to_bytes(path_ref);
@@ -287,31 +601,83 @@ fn zip_directory_to_bytes(dir_path: &std::path::Path) -> Result<Vec<u8>, String>
     Ok(buffer.into_inner())
 }
 
-async fn request_openrouter(
-    api_key: &str,
+const OPENROUTER_REFERER: &str = "https://github.com/everettjf/PDFRead";
+const OPENROUTER_TITLE: &str = "PDFRead";
+const GENERATION_STATS_RETRIES: u32 = 3;
+const GENERATION_STATS_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+fn load_openrouter_credentials(handle: &tauri::AppHandle) -> Result<OpenRouterCredentials, String> {
+    let settings = settings::load_settings(handle)?;
+    Ok(OpenRouterCredentials {
+        api_key: load_openrouter_key(handle)?,
+        user: settings.openrouter_user.filter(|user| !user.trim().is_empty()),
+        metadata: settings.openrouter_metadata,
+        sampling: model_params::SamplingSettings {
+            preset: settings.parameter_preset,
+            top_p: settings.top_p,
+        },
+        handle: handle.clone(),
+        feature: quota::FEATURE_OTHER.to_string(),
+        max_tokens: settings.max_output_tokens,
+        pinned_temperature: false,
+    })
+}
+
+async fn request_openrouter_completion(
+    credentials: &OpenRouterCredentials,
     model: &str,
     temperature: f32,
     system_prompt: &str,
     user_prompt: &str,
-) -> Result<String, String> {
+) -> Result<OpenRouterCompletion, String> {
+    let (temperature, top_p) = credentials.sampling.resolve(temperature, credentials.pinned_temperature);
+    model_params::validate_params(model, temperature, top_p)?;
+    offline::ensure_online(&credentials.handle)?;
+    let prompt_tokens = model_catalog::estimate_tokens(system_prompt) + model_catalog::estimate_tokens(user_prompt);
+    let estimated_tokens = prompt_tokens.saturating_add(credentials.max_tokens.unwrap_or(prompt_tokens));
+    let _reservation = quota::check(&credentials.handle, &credentials.feature, estimated_tokens.into())?;
+
     let client = reqwest::Client::new();
-    let body = serde_json::json!({
+    let mut body = serde_json::json!({
         "model": model,
-        "temperature": temperature,
         "messages": [
             { "role": "system", "content": system_prompt },
             { "role": "user", "content": user_prompt }
-        ]
+        ],
+        // Asks OpenRouter to report the cost, for usage caps.
+        "usage": { "include": true }
     });
+    if !model_params::fixed_sampling(model) {
+        body["temperature"] = serde_json::json!(temperature);
+        if let Some(top_p) = top_p {
+            body["top_p"] = serde_json::json!(top_p);
+        }
+    }
+    if let Some(max_tokens) = credentials.max_tokens {
+        body["max_tokens"] = serde_json::json!(max_tokens);
+    }
+    if let Some(user) = &credentials.user {
+        body["user"] = serde_json::json!(user);
+    }
+    if !credentials.metadata.is_empty() {
+        body["metadata"] = serde_json::json!(credentials.metadata);
+    }
 
+    let started = Instant::now();
     let response = client
         .post("https://openrouter.ai/api/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Authorization", format!("Bearer {}", credentials.api_key))
         .header("Content-Type", "application/json")
+        // App attribution shown on openrouter.ai.
+        .header("HTTP-Referer", OPENROUTER_REFERER)
+        .header("X-Title", OPENROUTER_TITLE)
         .json(&body)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| {
+            offline::note_request_error(&credentials.handle, &e);
+            e.to_string()
+        })?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -320,14 +686,72 @@ async fn request_openrouter(
     }
 
     let parsed: OpenRouterResponse = response.json().await.map_err(|e| e.to_string())?;
-    let content = parsed
-        .choices
-        .first()
-        .ok_or_else(|| "OpenRouter returned no choices.".to_string())?
-        .message
-        .content
-        .clone();
-    Ok(content)
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let mut usage = parsed.usage.unwrap_or_default();
+    let mut provider = parsed.provider;
+    // Providers that leave the cost out of `usage` still report it in the generation stats.
+    if let (None, Some(id)) = (usage.cost(), &parsed.id) {
+        match fetch_generation_stats(credentials, id).await {
+            Ok(stats) => {
+                usage.set_cost(stats.total_cost);
+                provider = provider.or(stats.provider_name);
+            }
+            Err(e) => log::error!("Failed to fetch generation stats: {}", e),
+        }
+    }
+    quota::record(&credentials.handle, &credentials.feature, &usage, latency_ms);
+    let choice = parsed.choices.first().ok_or_else(|| "OpenRouter returned no choices.".to_string())?;
+    let content = choice.message.content.clone();
+    let truncated = choice.finish_reason.as_deref() == Some("length");
+    let generation = GenerationInfo {
+        generation_id: parsed.id,
+        provider,
+        model: parsed.model,
+        cost: usage.cost(),
+        latency_ms: Some(latency_ms),
+        created_at: Some(Utc::now()),
+    };
+    let completed = GenerationCompleted { feature: credentials.feature.clone(), generation: generation.clone() };
+    let _ = credentials.handle.emit("generation-completed", completed);
+    Ok(OpenRouterCompletion { content, generation, truncated })
+}
+
+// Cost and serving provider of a finished request. The stats can take a moment to appear,
+// so a missing one is asked for again a few times.
+async fn fetch_generation_stats(credentials: &OpenRouterCredentials, id: &str) -> Result<GenerationStats, String> {
+    let client = reqwest::Client::new();
+    let mut attempt = 0;
+    loop {
+        let response = client
+            .get("https://openrouter.ai/api/v1/generation")
+            .query(&[("id", id)])
+            .header("Authorization", format!("Bearer {}", credentials.api_key))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND && attempt < GENERATION_STATS_RETRIES {
+            attempt += 1;
+            tokio::time::sleep(GENERATION_STATS_RETRY_DELAY).await;
+            continue;
+        }
+        if !response.status().is_success() {
+            return Err(format!("OpenRouter error: {}", response.status()));
+        }
+        let parsed: GenerationStatsResponse = response.json().await.map_err(|e| e.to_string())?;
+        return Ok(parsed.data);
+    }
+}
+
+async fn request_openrouter(
+    credentials: &OpenRouterCredentials,
+    model: &str,
+    temperature: f32,
+    system_prompt: &str,
+    user_prompt: &str,
+) -> Result<String, String> {
+    Ok(request_openrouter_completion(credentials, model, temperature, system_prompt, user_prompt)
+        .await?
+        .content)
 }
 
 fn parse_translation_json(content: &str) -> Result<Vec<TranslationResult>, String> {
@@ -345,6 +769,7 @@ fn parse_translation_json(content: &str) -> Result<Vec<TranslationResult>, Strin
             item.translation.map(|t| TranslationResult {
                 sid: item.sid,
                 translation: t,
+                generation: None,
             })
         })
         .collect();
@@ -415,87 +840,673 @@ fn extract_doc_id(sid: &str) -> &str {
     sid.split(':').next().unwrap_or(sid)
 }
 
-#[tauri::command(rename_all = "camelCase")]
-async fn openrouter_translate(
-    handle: tauri::AppHandle,
-    model: String,
+// Errors the frontend needs to branch on carry a stable code prefix, e.g. "CLOUD_NOT_ALLOWED: ...".
+const ERR_CLOUD_NOT_ALLOWED: &str = "CLOUD_NOT_ALLOWED";
+const ERR_PROMPT_TOO_LONG: &str = "PROMPT_TOO_LONG";
+const ERR_INVALID_PARAMS: &str = "INVALID_PARAMS";
+const ERR_QUOTA_EXCEEDED: &str = "QUOTA_EXCEEDED";
+const ERR_RESTRICTED: &str = "RESTRICTED";
+const ERR_OFFLINE: &str = "OFFLINE";
+const ERR_PRIVATE_LOCKED: &str = "PRIVATE_LOCKED";
+
+fn coded_error(code: &str, message: &str) -> String {
+    format!("{}: {}", code, message)
+}
+
+// Refuses to send content from a book that has opted out of cloud providers, or that is
+// private and locked (its cache is sealed, so results could not be kept). A book the app
+// has never opened is refused too, so an opt-out cannot be sidestepped with an unknown id.
+fn ensure_cloud_allowed(handle: &tauri::AppHandle, book_id: &str) -> Result<(), String> {
+    if private_books::is_private(handle, book_id)? && !private_books::is_unlocked(handle) {
+        return Err(coded_error(ERR_PRIVATE_LOCKED, "Unlock private books to use this book."));
+    }
+    let title = handle.state::<app_state::AppState>().recent_books.read(handle, |data| {
+        data.books.iter().find(|b| b.id == book_id).map(|b| b.title.clone())
+    })?;
+    match cloud_policy::policy(handle, book_id)? {
+        Some(policy) if policy.cloud_allowed => Ok(()),
+        Some(_) => Err(coded_error(
+            ERR_CLOUD_NOT_ALLOWED,
+            &format!("\"{}\": cloud processing is disabled for this book.", title.as_deref().unwrap_or(book_id)),
+        )),
+        None => Err(coded_error(
+            ERR_CLOUD_NOT_ALLOWED,
+            &format!("Unknown book {}; open it before sending its content to cloud providers.", book_id),
+        )),
+    }
+}
+
+// The target part of cache keys: the language tag, followed by a hash of the language's rules
+// when it has any, so translations made under other rules are not served.
+fn cache_target(handle: &tauri::AppHandle, code: &str) -> String {
+    match language_rules::rules_hash(handle, code) {
+        Some(hash) => format!("{}~{}", code, hash),
+        None => code.to_string(),
+    }
+}
+
+// `target_code` comes from `cache_target`.
+fn translation_cache_key(sid: &str, text: &str, model: &str, target_code: &str) -> String {
+    let doc_id = extract_doc_id(sid);
+    let source_hash = hash_source_text(text);
+    format!("{}|{}|{}|{}|{}", doc_id, sid, source_hash, model, target_code)
+}
+
+// EPUB paragraphs were numbered `epub:<n>` before their ids carried the book id. Translations
+// cached under those ids are copied to the `<book>:<n>` keys on first use; the source hash in the
+// key keeps a paragraph from picking up another book's translation.
+fn adopt_legacy_epub_translations(
+    handle: &tauri::AppHandle,
+    sentences: &[TranslateSentence],
+    model: &str,
+    target_code: &str,
+) -> Result<(), String> {
+    let legacy_sid = |sid: &str| {
+        let (_, index) = sid.split_once(':')?;
+        (!index.is_empty() && index.chars().all(|c| c.is_ascii_digit())).then(|| format!("epub:{}", index))
+    };
+    let adoptable: Vec<(String, String)> = read_cache(handle, |cache| {
+        sentences
+            .iter()
+            .filter_map(|sentence| {
+                let legacy = translation_cache_key(&legacy_sid(&sentence.sid)?, &sentence.text, model, target_code);
+                let key = unit_cache_key(sentence, model, target_code);
+                (cache.entries.contains_key(&legacy) && !cache.entries.contains_key(&key)).then_some((legacy, key))
+            })
+            .collect()
+    })?;
+    if adoptable.is_empty() {
+        return Ok(());
+    }
+    update_cache(handle, |cache| {
+        for (legacy, key) in adoptable {
+            if let Some(translation) = cache.entries.get(&legacy).cloned() {
+                cache.entries.insert(key.clone(), translation);
+            }
+            if let Some(generation) = cache.generations.get(&legacy).cloned() {
+                cache.generations.insert(key, generation);
+            }
+        }
+    })
+}
+
+// Cache key of a unit as `translate_units` sends it. Context attached in window mode only
+// goes into the prompt, so a unit shares its entry with the other granularities.
+fn unit_cache_key(sentence: &TranslateSentence, model: &str, target_code: &str) -> String {
+    translation_cache_key(&sentence.sid, &sentence.text, model, target_code)
+}
+
+// A unit's cached translation: its own entry or, when it was translated in sentence mode, its
+// sentences' entries joined.
+fn cached_unit_translation(
+    cache: &CachedTranslations,
+    unit: &TranslateSentence,
+    model: &str,
+    target_code: &str,
+) -> Option<String> {
+    if let Some(translation) = cache.entries.get(&unit_cache_key(unit, model, target_code)) {
+        return Some(translation.clone());
+    }
+    let (sentences, _) = granularity::split_sentences(std::slice::from_ref(unit));
+    if sentences.len() < 2 {
+        return None;
+    }
+    let parts = sentences
+        .iter()
+        .map(|s| cache.entries.get(&unit_cache_key(s, model, target_code)).map(|t| t.trim()))
+        .collect::<Option<Vec<&str>>>()?;
+    Some(parts.join(granularity::sentence_separator(target_code)))
+}
+
+// What a request for uncached sentences brought back: the batches that came through, and
+// the error that stopped the rest, if any.
+#[derive(Debug, Clone, Default)]
+struct RequestedTranslations {
+    translations: Vec<TranslationResult>,
+    error: Option<String>,
+}
+
+type SharedBatch = Arc<tokio::sync::OnceCell<RequestedTranslations>>;
+
+// Requests currently being sent to OpenRouter: individual sentence cache keys (so
+// background work can skip them) and whole batches (so identical requests share one call).
+#[derive(Default)]
+struct InFlightTranslations {
+    keys: Mutex<HashSet<String>>,
+    batches: Mutex<HashMap<String, SharedBatch>>,
+}
+
+impl InFlightTranslations {
+    fn contains(&self, key: &str) -> bool {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner()).contains(key)
+    }
+
+    fn batch(&self, batch_key: &str) -> SharedBatch {
+        let mut batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        batches.entry(batch_key.to_string()).or_default().clone()
+    }
+
+    fn finish_batch(&self, batch_key: &str, batch: &SharedBatch) {
+        let mut batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        if batches.get(batch_key).is_some_and(|current| Arc::ptr_eq(current, batch)) {
+            batches.remove(batch_key);
+        }
+    }
+}
+
+fn translation_batch_key(keys: &[String], temperature: f32) -> String {
+    let mut sorted: Vec<&str> = keys.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    hash_source_text(&format!("{}|{}", temperature, sorted.join("\n")))
+}
+
+// Removes its keys from the in-flight set when the request finishes or is cancelled.
+struct InFlightGuard<'a> {
+    in_flight: &'a InFlightTranslations,
+    keys: Vec<String>,
+}
+
+impl<'a> InFlightGuard<'a> {
+    fn register(in_flight: &'a InFlightTranslations, keys: Vec<String>) -> Self {
+        in_flight
+            .keys
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(keys.iter().cloned());
+        Self { in_flight, keys }
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        let mut keys = self.in_flight.keys.lock().unwrap_or_else(|e| e.into_inner());
+        for key in &self.keys {
+            keys.remove(key);
+        }
+    }
+}
+
+// Requests and parses one batch, retrying once with a stricter prompt if the JSON is unusable.
+async fn fetch_translation_batch(
+    credentials: &OpenRouterCredentials,
+    model: &str,
     temperature: f32,
-    target_language: TargetLanguage,
+    target_language: &TargetLanguage,
+    missing: &[TranslateSentence],
+    instructions: &str,
+) -> Result<Vec<TranslationResult>, String> {
+    // Formulas go out as placeholders and are put back into the results.
+    let (protected, math_spans) = math::protect_sentences(missing);
+    let system_prompt = build_system_prompt();
+    let mut user_prompt = build_user_prompt(target_language, &protected);
+    if !math_spans.is_empty() {
+        user_prompt.push_str("\nKeep placeholders such as ⟦M0⟧ exactly as they are; they stand for formulas.");
+    }
+    if missing.iter().any(|s| s.before.is_some() || s.after.is_some()) {
+        user_prompt.push_str(
+            "\n\"before\" and \"after\" hold the surrounding text for context only. \
+             Translate only \"text\" and return one item per sid with its translation.",
+        );
+    }
+    user_prompt.push_str(instructions);
+
+    let credentials = match model_catalog::output_tokens(model, missing) {
+        Some(tokens) => credentials.limit_output(tokens),
+        None => credentials.clone(),
+    };
+    let mut completion =
+        request_openrouter_completion(&credentials, model, temperature, &system_prompt, &user_prompt).await?;
+    if completion.truncated {
+        return split_truncated_batch(&credentials, model, temperature, target_language, missing, instructions).await;
+    }
+    let mut parsed = parse_translation_json(&completion.content);
+
+    if parsed.is_err() {
+        let strict_user_prompt = format!(
+            "Return ONLY this JSON array format with no extra text. Target language: {} ({})\nInput JSON: {}",
+            target_language.label,
+            target_language.prompt_tag(),
+            serde_json::to_string(&protected).unwrap_or_else(|_| "[]".to_string())
+        );
+        completion =
+            request_openrouter_completion(&credentials, model, temperature, &system_prompt, &strict_user_prompt)
+                .await?;
+        if completion.truncated {
+            return split_truncated_batch(&credentials, model, temperature, target_language, missing, instructions)
+                .await;
+        }
+        parsed = parse_translation_json(&completion.content);
+    }
+
+    let mut translations = parsed.map_err(|e| format!("Failed to parse OpenRouter JSON: {}", e))?;
+    for item in &mut translations {
+        if let Some(spans) = math_spans.get(&item.sid) {
+            item.translation = math::restore_math(&item.translation, spans);
+        }
+        item.generation = Some(completion.generation.clone());
+    }
+    Ok(translations)
+}
+
+// An answer cut off at the output limit is incomplete JSON; the batch is translated again in
+// two halves, each with its own limit. Splitting stops at one sentence: a single sentence that
+// is cut off fails right away instead of being retried.
+async fn split_truncated_batch(
+    credentials: &OpenRouterCredentials,
+    model: &str,
+    temperature: f32,
+    target_language: &TargetLanguage,
+    missing: &[TranslateSentence],
+    instructions: &str,
+) -> Result<Vec<TranslationResult>, String> {
+    if missing.len() < 2 {
+        let sid = missing.first().map_or("", |s| s.sid.as_str());
+        return Err(format!(
+            "The translation of {} was cut off at {} tokens; raise the output token limit.",
+            sid,
+            credentials.max_tokens.unwrap_or_default()
+        ));
+    }
+    let (first, second) = missing.split_at(missing.len() / 2);
+    let mut translations =
+        Box::pin(fetch_translation_batch(credentials, model, temperature, target_language, first, instructions))
+            .await?;
+    translations.extend(
+        Box::pin(fetch_translation_batch(credentials, model, temperature, target_language, second, instructions))
+            .await?,
+    );
+    Ok(translations)
+}
+
+// Sends the batch to two models at once and keeps the first usable answer, with the model
+// that gave it; the slower request is dropped (and thereby cancelled).
+async fn race_translation_batch(
+    credentials: &OpenRouterCredentials,
+    model: &str,
+    race_model: &str,
+    temperature: f32,
+    target_language: &TargetLanguage,
+    missing: &[TranslateSentence],
+    instructions: &str,
+) -> Result<(String, Vec<TranslationResult>), String> {
+    let primary = fetch_translation_batch(credentials, model, temperature, target_language, missing, instructions);
+    let secondary =
+        fetch_translation_batch(credentials, race_model, temperature, target_language, missing, instructions);
+    tokio::pin!(primary, secondary);
+
+    let (model, race_model) = (model.to_string(), race_model.to_string());
+    tokio::select! {
+        result = &mut primary => match result {
+            Ok(translations) if !translations.is_empty() => Ok((model, translations)),
+            _ => secondary.await.map(|translations| (race_model, translations)),
+        },
+        result = &mut secondary => match result {
+            Ok(translations) if !translations.is_empty() => Ok((race_model, translations)),
+            _ => primary.await.map(|translations| (model, translations)),
+        },
+    }
+}
+
+// Sends uncached sentences to OpenRouter, in as many batches as the model's context needs,
+// and stores each batch in the cache under the model that produced it as soon as it arrives.
+// A failed batch stops the rest; what came through before it is kept and returned.
+async fn request_translations(
+    handle: &tauri::AppHandle,
+    model: &str,
+    temperature: f32,
+    target_language: &TargetLanguage,
+    missing: &[TranslateSentence],
+    keys: Vec<String>,
+) -> RequestedTranslations {
+    let in_flight = handle.state::<InFlightTranslations>();
+    let _guard = InFlightGuard::register(&in_flight, keys);
+    let mut requested = RequestedTranslations::default();
+    if let Err(e) = request_batches(handle, model, temperature, target_language, missing, &mut requested).await {
+        requested.error = Some(e);
+    }
+    if let Err(e) = reading_activity::record_translations(handle, requested.translations.len()) {
+        log::error!("Failed to record reading activity: {}", e);
+    }
+    activity_log::record_translated_pages(handle, requested.translations.iter().map(|t| t.sid.as_str()));
+    requested
+}
+
+async fn request_batches(
+    handle: &tauri::AppHandle,
+    model: &str,
+    temperature: f32,
+    target_language: &TargetLanguage,
+    missing: &[TranslateSentence],
+    requested: &mut RequestedTranslations,
+) -> Result<(), String> {
+    let credentials = load_openrouter_credentials(handle)?.for_feature(quota::FEATURE_TRANSLATION).pin_temperature();
+    let settings = settings::load_settings(handle)?;
+    let race_model = settings.race_model.filter(|race_model| !race_model.is_empty() && race_model != model);
+    let target_code = cache_target(handle, &target_language.tag());
+
+    // Translations just before each batch, carried from one batch to the next.
+    let continuation = settings.continuation_context;
+    let mut previous = match missing.first() {
+        Some(first) if continuation > 0 => {
+            continuation::previous_translations(handle, model, &target_code, first, continuation)
+        }
+        _ => Vec::new(),
+    };
+
+    let rules = language_rules::prompt_section(handle, &target_language.tag());
+
+    // Sized to the model's context window so long chapters are not silently truncated.
+    let context_tokens = model_catalog::context_length(handle, model).await;
+    for batch in model_catalog::plan_batches(missing, context_tokens, settings.max_output_tokens)? {
+        let instructions = format!("{}{}", rules, continuation::prompt_section(&previous));
+        let (batch_model, batch_translations) = match &race_model {
+            Some(race_model) => {
+                let race = race_translation_batch(
+                    &credentials,
+                    model,
+                    race_model,
+                    temperature,
+                    target_language,
+                    batch,
+                    &instructions,
+                );
+                race.await?
+            }
+            None => {
+                let fetch =
+                    fetch_translation_batch(&credentials, model, temperature, target_language, batch, &instructions);
+                (model.to_string(), fetch.await?)
+            }
+        };
+        if continuation > 0 {
+            continuation::advance(&mut previous, batch, &batch_translations, continuation);
+        }
+        update_cache(handle, |cache| {
+            for item in &batch_translations {
+                let source_text = batch
+                    .iter()
+                    .find(|sentence| sentence.sid == item.sid)
+                    .map(|sentence| sentence.text.as_str())
+                    .unwrap_or_default();
+                let key = translation_cache_key(&item.sid, source_text, &batch_model, &target_code);
+                if let Some(generation) = &item.generation {
+                    cache.generations.insert(key.clone(), generation.clone());
+                }
+                cache.entries.insert(key, item.translation.clone());
+            }
+        })?;
+        requested.translations.extend(batch_translations);
+    }
+    Ok(())
+}
+
+// Translates sentences at the granularity chosen in settings: as given, split into
+// sentences, or each with its neighbours as context.
+async fn translate_sentences(
+    handle: &tauri::AppHandle,
+    model: &str,
+    temperature: f32,
+    target_language: &TargetLanguage,
+    sentences: Vec<TranslateSentence>,
+) -> Result<Vec<TranslationResult>, String> {
+    match settings::load_settings(handle)?.translation_granularity {
+        settings::TranslationGranularity::Paragraph => {
+            translate_units(handle, model, temperature, target_language, sentences).await
+        }
+        settings::TranslationGranularity::Window => {
+            let sentences = granularity::with_window_context(sentences);
+            translate_units(handle, model, temperature, target_language, sentences).await
+        }
+        settings::TranslationGranularity::Sentence => {
+            let (split, counts) = granularity::split_sentences(&sentences);
+            let translations = translate_units(handle, model, temperature, target_language, split).await?;
+            Ok(granularity::join_sentences(&sentences, &counts, translations, &target_language.tag()))
+        }
+    }
+}
+
+// Translates units through the cache; only cache misses are sent to OpenRouter.
+async fn translate_units(
+    handle: &tauri::AppHandle,
+    model: &str,
+    temperature: f32,
+    target_language: &TargetLanguage,
     sentences: Vec<TranslateSentence>,
 ) -> Result<Vec<TranslationResult>, String> {
     if sentences.is_empty() {
         return Ok(Vec::new());
     }
 
-    let mut cache = load_cache(&handle)?;
-    let cache_key = |sid: &str, text: &str| {
-        let doc_id = extract_doc_id(sid);
-        let source_hash = hash_source_text(text);
-        format!(
-            "{}|{}|{}|{}|{}",
-            doc_id, sid, source_hash, model, target_language.code
-        )
-    };
+    let target_code = cache_target(handle, &target_language.tag());
+    adopt_legacy_epub_translations(handle, &sentences, model, &target_code)?;
+    let cache_key = |sentence: &TranslateSentence| unit_cache_key(sentence, model, &target_code);
 
-    let mut results: HashMap<String, String> = HashMap::new();
+    let mut results: HashMap<String, TranslationResult> = HashMap::new();
     let mut missing: Vec<TranslateSentence> = Vec::new();
 
-    for sentence in sentences.iter() {
-        if let Some(value) = cache.entries.get(&cache_key(&sentence.sid, &sentence.text)) {
-            results.insert(sentence.sid.clone(), value.clone());
-        } else {
-            missing.push(TranslateSentence {
-                sid: sentence.sid.clone(),
-                text: sentence.text.clone(),
-            });
+    read_cache(handle, |cache| {
+        for sentence in sentences.iter() {
+            // Tables and code pass through untranslated, in their place among the results.
+            if sentence.kind.is_verbatim() {
+                results.insert(
+                    sentence.sid.clone(),
+                    TranslationResult {
+                        sid: sentence.sid.clone(),
+                        translation: sentence.text.clone(),
+                        generation: None,
+                    },
+                );
+                continue;
+            }
+            let key = cache_key(sentence);
+            if let Some(value) = cache.entries.get(&key) {
+                results.insert(
+                    sentence.sid.clone(),
+                    TranslationResult {
+                        sid: sentence.sid.clone(),
+                        translation: value.clone(),
+                        generation: cache.generations.get(&key).cloned(),
+                    },
+                );
+            } else {
+                missing.push(sentence.clone());
+            }
         }
+    })?;
+
+    // Offline, what is cached is all there is; the rest is left out as untranslated.
+    if !missing.is_empty() && offline::is_offline(handle) {
+        if results.is_empty() {
+            offline::ensure_online(handle)?;
+        }
+        missing.clear();
     }
 
     if !missing.is_empty() {
-        let api_key = load_openrouter_key(&handle)?;
-        let system_prompt = build_system_prompt();
-        let user_prompt = build_user_prompt(&target_language, &missing);
+        let mut doc_ids: Vec<&str> = missing.iter().map(|s| extract_doc_id(&s.sid)).collect();
+        doc_ids.dedup();
+        for doc_id in doc_ids {
+            ensure_cloud_allowed(handle, doc_id)?;
+        }
+
+        let keys: Vec<String> = missing.iter().map(cache_key).collect();
+        let batch_key = translation_batch_key(&keys, temperature);
+        let in_flight = handle.state::<InFlightTranslations>();
+        let batch = in_flight.batch(&batch_key);
+        // Only the first caller runs the request; duplicates await its result.
+        let requested = batch
+            .get_or_init(|| request_translations(handle, model, temperature, target_language, &missing, keys))
+            .await
+            .clone();
+        in_flight.finish_batch(&batch_key, &batch);
+
+        for item in requested.translations {
+            results.insert(item.sid.clone(), item);
+        }
+        // Whatever did come back is returned, the rest left out as untranslated; the error
+        // only fails the call when there is nothing to show.
+        if let Some(message) = requested.error {
+            if results.is_empty() {
+                return Err(message);
+            }
+            let sids = missing.iter().filter(|s| !results.contains_key(&s.sid)).map(|s| s.sid.clone()).collect();
+            let _ = handle.emit("translations-incomplete", TranslationFailure { sids, message });
+        }
+    }
+
+    Ok(sentences
+        .into_iter()
+        .filter_map(|sentence| results.remove(&sentence.sid))
+        .collect())
+}
 
-        let mut content = request_openrouter(&api_key, &model, temperature, &system_prompt, &user_prompt).await?;
-        let mut parsed = parse_translation_json(&content);
+// Cached translations of these sentences made by some model other than `model`, by sid.
+// Sentences already cached for `model` itself are left out.
+fn find_stale_translations(
+    handle: &tauri::AppHandle,
+    model: &str,
+    target_language: &TargetLanguage,
+    sentences: &[TranslateSentence],
+) -> Result<HashMap<String, TranslationResult>, String> {
+    // Cache keys end in `|model|target`; what comes before identifies the source sentence.
+    fn split_key(key: &str) -> Option<(&str, &str, &str)> {
+        let (rest, target) = key.rsplit_once('|')?;
+        let (source, key_model) = rest.rsplit_once('|')?;
+        Some((source, key_model, target))
+    }
 
-        if parsed.is_err() {
-            let strict_user_prompt = format!(
-                "Return ONLY this JSON array format with no extra text. Target language: {} ({})\nInput JSON: {}",
-                target_language.label,
-                target_language.code,
-                serde_json::to_string(&missing).unwrap_or_else(|_| "[]".to_string())
-            );
-            content = request_openrouter(&api_key, &model, temperature, &system_prompt, &strict_user_prompt).await?;
-            parsed = parse_translation_json(&content);
+    let target_code = cache_target(handle, &target_language.tag());
+    read_cache(handle, |cache| {
+        let mut wanted: HashMap<String, &TranslateSentence> = HashMap::new();
+        for sentence in sentences {
+            let key = unit_cache_key(sentence, model, &target_code);
+            if !cache.entries.contains_key(&key) {
+                if let Some((source, _, _)) = split_key(&key) {
+                    wanted.insert(source.to_string(), sentence);
+                }
+            }
         }
 
-        let translations = parsed.map_err(|e| format!("Failed to parse OpenRouter JSON: {}", e))?;
-        for item in translations {
-            let source_text = missing
-                .iter()
-                .find(|sentence| sentence.sid == item.sid)
-                .map(|sentence| sentence.text.as_str())
-                .unwrap_or("");
-            cache
-                .entries
-                .insert(cache_key(&item.sid, source_text), item.translation.clone());
-            results.insert(item.sid.clone(), item.translation);
+        if wanted.is_empty() {
+            return HashMap::new();
         }
-        save_cache(&handle, &cache)?;
+        // When several other models translated a sentence, the most recent translation is used;
+        // ones cached before their time was recorded count as oldest.
+        let mut newest: HashMap<&str, (&String, Option<DateTime<Utc>>)> = HashMap::new();
+        for key in cache.entries.keys() {
+            let Some((source, key_model, target)) = split_key(key) else {
+                continue;
+            };
+            if target != target_code || key_model == model {
+                continue;
+            }
+            let Some(sentence) = wanted.get(source) else {
+                continue;
+            };
+            let created_at = cache.generations.get(key).and_then(|g| g.created_at);
+            let slot = newest.entry(sentence.sid.as_str()).or_insert((key, created_at));
+            if slot.1 < created_at {
+                *slot = (key, created_at);
+            }
+        }
+        newest
+            .into_iter()
+            .map(|(sid, (key, _))| {
+                let result = TranslationResult {
+                    sid: sid.to_string(),
+                    translation: cache.entries[key].clone(),
+                    generation: cache.generations.get(key).cloned(),
+                };
+                (sid.to_string(), result)
+            })
+            .collect()
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TranslationFailure {
+    sids: Vec<String>,
+    message: String,
+}
+
+// Re-translates sentences that were served from another model's cache entries and emits
+// `translations-refreshed` with the new results so the reader can swap them in.
+async fn refresh_stale_translations(
+    handle: tauri::AppHandle,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+    sentences: Vec<TranslateSentence>,
+) {
+    let in_flight = handle.state::<InFlightTranslations>();
+    let target_code = cache_target(&handle, &target_language.tag());
+    let sentences: Vec<TranslateSentence> =
+        sentences.into_iter().filter(|s| !in_flight.contains(&unit_cache_key(s, &model, &target_code))).collect();
+    if sentences.is_empty() {
+        return;
+    }
+    let sids: Vec<String> = sentences.iter().map(|s| s.sid.clone()).collect();
+
+    let result = async {
+        let jobs = handle.state::<jobs::JobRegistry>();
+        let _slot = jobs.acquire_provider_slot().await?;
+        translate_sentences(&handle, &model, temperature, &target_language, sentences).await
     }
+    .await;
 
-    let mut output: Vec<TranslationResult> = Vec::new();
-    for sentence in sentences {
-        if let Some(translation) = results.get(&sentence.sid) {
-            output.push(TranslationResult {
-                sid: sentence.sid,
-                translation: translation.clone(),
-            });
+    match result {
+        Ok(translations) => {
+            let _ = handle.emit("translations-refreshed", translations);
+        }
+        Err(message) => {
+            let _ = handle.emit("translations-refresh-error", TranslationFailure { sids, message });
         }
     }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+async fn openrouter_translate(
+    handle: tauri::AppHandle,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+    sentences: Vec<TranslateSentence>,
+) -> Result<Vec<TranslationResult>, String> {
+    let policy = settings::load_settings(&handle)?.translation_cache_policy;
+    if policy != settings::TranslationCachePolicy::StaleWhileRevalidate {
+        return translate_sentences(&handle, &model, temperature, &target_language, sentences).await;
+    }
+
+    let stale = find_stale_translations(&handle, &model, &target_language, &sentences)?;
+    if stale.is_empty() {
+        return translate_sentences(&handle, &model, temperature, &target_language, sentences).await;
+    }
+
+    let order: Vec<String> = sentences.iter().map(|s| s.sid.clone()).collect();
+    let (stale_sentences, fresh_sentences): (Vec<TranslateSentence>, Vec<TranslateSentence>) =
+        sentences.into_iter().partition(|s| stale.contains_key(&s.sid));
+
+    // Offline, the other model's translations stand in for the missing ones.
+    let fresh = match translate_sentences(&handle, &model, temperature, &target_language, fresh_sentences).await {
+        Err(e) if offline::is_offline_error(&e) => Vec::new(),
+        fresh => fresh?,
+    };
+    let mut results: HashMap<String, TranslationResult> =
+        fresh.into_iter().map(|item| (item.sid.clone(), item)).collect();
+    results.extend(stale);
+    if offline::is_offline(&handle) {
+        return Ok(order.into_iter().filter_map(|sid| results.remove(&sid)).collect());
+    }
 
-    Ok(output)
+    tauri::async_runtime::spawn(refresh_stale_translations(
+        handle.clone(),
+        model,
+        temperature,
+        target_language,
+        stale_sentences,
+    ));
+
+    Ok(order.into_iter().filter_map(|sid| results.remove(&sid)).collect())
 }
 
 #[tauri::command(rename_all = "camelCase")]
@@ -504,12 +1515,24 @@ async fn openrouter_word_lookup(
     model: String,
     target_language: TargetLanguage,
     word: String,
+    book_id: String,
 ) -> Result<WordLookupResult, String> {
-    let api_key = load_openrouter_key(&handle)?;
+    ensure_cloud_allowed(&handle, &book_id)?;
+    let credentials = load_openrouter_credentials(&handle)?.pin_temperature();
     let system_prompt = build_word_lookup_system_prompt();
-    let user_prompt = build_word_lookup_prompt(&word, &target_language);
-
-    let content = request_openrouter(&api_key, &model, 0.0, &system_prompt, &user_prompt).await?;
+    let mut user_prompt = build_word_lookup_prompt(&word, &target_language);
+    user_prompt.push_str(&language_rules::prompt_section(&handle, &target_language.tag()));
+
+    let content = response_cache::request_openrouter_cached(
+        &handle,
+        response_cache::FEATURE_EXPLANATION,
+        &credentials,
+        &model,
+        0.0,
+        &system_prompt,
+        &user_prompt,
+    )
+    .await?;
 
     // Try to extract JSON from the response
     let json_content = extract_json_object(&content);
@@ -517,40 +1540,146 @@ async fn openrouter_word_lookup(
     let result: WordLookupResult = serde_json::from_str(&json_content)
         .map_err(|e| format!("Failed to parse word lookup JSON: {} (content: {})", e, truncate_for_error(&json_content)))?;
 
+    let recorded = lookup_history::record_lookup(
+        &handle,
+        lookup_history::LookupRecord {
+            text: word,
+            book_id: Some(book_id),
+            looked_up_at: Utc::now(),
+            kind: lookup_history::LookupKind::Word,
+            target_lang: Some(target_language.tag()),
+            phonetic: result.phonetic.clone(),
+            definitions: result.definitions.clone(),
+            context: None,
+        },
+    );
+    if let Err(e) = recorded {
+        log::error!("Failed to record lookup: {}", e);
+    }
+    Ok(result)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+async fn lookup_phrase(
+    handle: tauri::AppHandle,
+    model: String,
+    target_language: TargetLanguage,
+    phrase: String,
+    context: String,
+    book_id: String,
+) -> Result<PhraseLookupResult, String> {
+    ensure_cloud_allowed(&handle, &book_id)?;
+    let credentials = load_openrouter_credentials(&handle)?.pin_temperature();
+    let system_prompt = build_phrase_lookup_system_prompt();
+    let mut user_prompt = build_phrase_lookup_prompt(&phrase, &context, &target_language);
+    user_prompt.push_str(&language_rules::prompt_section(&handle, &target_language.tag()));
+
+    let content = response_cache::request_openrouter_cached(
+        &handle,
+        response_cache::FEATURE_EXPLANATION,
+        &credentials,
+        &model,
+        0.0,
+        &system_prompt,
+        &user_prompt,
+    )
+    .await?;
+    let json_content = extract_json_object(&content);
+
+    let result: PhraseLookupResult = serde_json::from_str(&json_content)
+        .map_err(|e| format!("Failed to parse phrase lookup JSON: {} (content: {})", e, truncate_for_error(&json_content)))?;
+
+    let recorded = lookup_history::record_lookup(
+        &handle,
+        lookup_history::LookupRecord {
+            text: phrase,
+            book_id: Some(book_id),
+            looked_up_at: Utc::now(),
+            kind: lookup_history::LookupKind::Phrase,
+            target_lang: Some(target_language.tag()),
+            phonetic: None,
+            definitions: vec![WordDefinitionResult { pos: "phrase".to_string(), meanings: result.meaning.clone() }],
+            context: Some(context),
+        },
+    );
+    if let Err(e) = recorded {
+        log::error!("Failed to record lookup: {}", e);
+    }
     Ok(result)
 }
 
 #[tauri::command(rename_all = "camelCase")]
 fn add_vocabulary_word(
     handle: tauri::AppHandle,
+    index: tauri::State<'_, vocab_index::VocabularyIndex>,
     word: String,
     phonetic: Option<String>,
     definitions: Vec<WordDefinitionResult>,
+    source_lang: Option<String>,
+    target_lang: Option<String>,
+    context: Option<String>,
+    // The book the word was saved from, for its activity timeline.
+    book_id: Option<String>,
 ) -> Result<(), String> {
-    let mut vocab = load_vocabulary(&handle)?;
+    // A lone word is rarely enough to detect its language; the sentence it came from helps.
+    let source_lang = source_lang
+        .or_else(|| language::detect_language(context.as_deref().unwrap_or(&word)));
+    let target_lang = target_lang.or_else(|| {
+        let meanings: Vec<&str> = definitions.iter().map(|d| d.meanings.as_str()).collect();
+        language::detect_language(&meanings.join(" "))
+    });
+    let saved_word = word.clone();
 
-    // Check if word already exists (case-insensitive)
-    let word_lower = word.to_lowercase();
-    if vocab.entries.iter().any(|e| e.word.to_lowercase() == word_lower) {
-        return Ok(()); // Already exists, don't add duplicate
-    }
+    let added = handle.state::<app_state::AppState>().vocabulary.update(&handle, |vocab| {
+        // Check if word already exists (case-insensitive)
+        let word_lower = word.to_lowercase();
+        if vocab.entries.iter().any(|e| e.word.to_lowercase() == word_lower) {
+            return false; // Already exists, don't add duplicate
+        }
 
-    vocab.entries.push(VocabularyEntry {
-        word,
-        phonetic,
-        definitions,
-        added_at: Utc::now(),
-    });
+        vocab.entries.push(VocabularyEntry {
+            word,
+            phonetic,
+            definitions,
+            added_at: Utc::now(),
+            source_lang,
+            target_lang,
+            book_id: book_id.clone(),
+        });
+        true
+    })?;
 
-    save_vocabulary(&handle, &vocab)
+    if added {
+        index.invalidate();
+        hooks::record_new_words(&handle, book_id.as_deref(), 1);
+        activity_log::record(&handle, activity_log::ActivityKind::WordSaved, book_id.as_deref(), Some(&saved_word));
+    }
+    Ok(())
 }
 
 #[tauri::command(rename_all = "camelCase")]
-fn remove_vocabulary_word(handle: tauri::AppHandle, word: String) -> Result<(), String> {
-    let mut vocab = load_vocabulary(&handle)?;
+fn remove_vocabulary_word(
+    handle: tauri::AppHandle,
+    index: tauri::State<'_, vocab_index::VocabularyIndex>,
+    word: String,
+) -> Result<(), String> {
     let word_lower = word.to_lowercase();
-    vocab.entries.retain(|e| e.word.to_lowercase() != word_lower);
-    save_vocabulary(&handle, &vocab)
+    let removed = handle.state::<app_state::AppState>().vocabulary.update(&handle, |vocab| {
+        let removed: Vec<(usize, VocabularyEntry)> = vocab
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.word.to_lowercase() == word_lower)
+            .map(|(position, e)| (position, e.clone()))
+            .collect();
+        vocab.entries.retain(|e| e.word.to_lowercase() != word_lower);
+        removed
+    })?;
+    index.invalidate();
+    if !removed.is_empty() {
+        undo::record(&handle, undo::Operation::RemoveVocabularyWord { word, removed });
+    }
+    Ok(())
 }
 
 #[tauri::command(rename_all = "camelCase")]
@@ -559,22 +1688,37 @@ fn get_vocabulary(handle: tauri::AppHandle) -> Result<Vec<VocabularyEntry>, Stri
     Ok(vocab.entries)
 }
 
+// Entries whose word is in `lang`; "zh" also matches entries saved as "zh-CN".
 #[tauri::command(rename_all = "camelCase")]
-fn is_word_in_vocabulary(handle: tauri::AppHandle, word: String) -> Result<bool, String> {
-    let vocab = load_vocabulary(&handle)?;
-    let word_lower = word.to_lowercase();
-    Ok(vocab.entries.iter().any(|e| e.word.to_lowercase() == word_lower))
+fn get_vocabulary_by_language(handle: tauri::AppHandle, lang: String) -> Result<Vec<VocabularyEntry>, String> {
+    let lang = language::primary_language(&lang);
+    Ok(load_vocabulary(&handle)?
+        .entries
+        .into_iter()
+        .filter(|e| e.source_lang.as_deref().is_some_and(|code| language::primary_language(code) == lang))
+        .collect())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+fn is_word_in_vocabulary(
+    handle: tauri::AppHandle,
+    index: tauri::State<'_, vocab_index::VocabularyIndex>,
+    word: String,
+) -> Result<bool, String> {
+    Ok(index.find(&handle, &word)?.is_some())
 }
 
 #[tauri::command(rename_all = "camelCase")]
 fn export_vocabulary_markdown(handle: tauri::AppHandle) -> Result<String, String> {
-    let vocab = load_vocabulary(&handle)?;
+    Ok(vocabulary_markdown(&load_vocabulary(&handle)?.entries))
+}
 
+fn vocabulary_markdown(entries: &[VocabularyEntry]) -> String {
     let mut markdown = String::from("# My Vocabulary\n\n");
-    markdown.push_str(&format!("Total words: {}\n\n", vocab.entries.len()));
+    markdown.push_str(&format!("Total words: {}\n\n", entries.len()));
     markdown.push_str("---\n\n");
 
-    for entry in vocab.entries {
+    for entry in entries {
         markdown.push_str(&format!("## {}\n\n", entry.word));
 
         if let Some(phonetic) = &entry.phonetic {
@@ -593,7 +1737,7 @@ fn export_vocabulary_markdown(handle: tauri::AppHandle) -> Result<String, String
         markdown.push_str("---\n\n");
     }
 
-    Ok(markdown)
+    markdown
 }
 
 // Recent books management
@@ -610,9 +1754,41 @@ struct RecentBook {
     last_page: u32,
     progress: f32,
     last_opened_at: DateTime<Utc>,
+    #[serde(default = "default_true")]
+    cloud_allowed: bool,
+    #[serde(default)]
+    status: BookStatus,
+    #[serde(default)]
+    finished_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    metadata: book_metadata::BookMetadata,
+    // Other files of the same book, and which of them was opened last (None: `file_path`).
+    #[serde(default)]
+    formats: Vec<book_formats::BookFormat>,
+    #[serde(default)]
+    preferred_format: Option<String>,
+    // Behind the private books passphrase; see `private_books`.
+    #[serde(default)]
+    private_lock: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Only books being read appear in the recent list; the others are reached through
+// `list_books_by_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum BookStatus {
+    #[default]
+    Reading,
+    Finished,
+    Abandoned,
+    Archived,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct RecentBooksData {
     books: Vec<RecentBook>,
 }
@@ -621,28 +1797,35 @@ fn recent_books_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String>
     Ok(app_config_dir(handle)?.join("recent_books.json"))
 }
 
-fn load_recent_books(handle: &tauri::AppHandle) -> Result<RecentBooksData, String> {
+fn read_recent_books_file(handle: &tauri::AppHandle) -> Result<RecentBooksData, String> {
     let path = recent_books_file_path(handle)?;
     if !path.exists() {
         return Ok(RecentBooksData { books: Vec::new() });
     }
-    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&data).map_err(|e| e.to_string())
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    quarantine::parse_or_quarantine(handle, &path, &data)
 }
 
-fn save_recent_books(handle: &tauri::AppHandle, data: &RecentBooksData) -> Result<(), String> {
+fn write_recent_books_file(handle: &tauri::AppHandle, data: &RecentBooksData) -> Result<(), String> {
     let path = recent_books_file_path(handle)?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
     let json = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
-    fs::write(path, json).map_err(|e| e.to_string())
+    sync_conflicts::with_write_lock(&path, || fs::write(&path, json).map_err(|e| e.to_string()))
 }
 
 #[tauri::command(rename_all = "camelCase")]
 fn get_recent_books(handle: tauri::AppHandle) -> Result<Vec<RecentBook>, String> {
-    let data = load_recent_books(&handle)?;
-    let mut books = data.books;
+    let mut books = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.clone())?;
+    books.retain(|b| b.status == BookStatus::Reading && b.file_type != articles::ARTICLE_FILE_TYPE);
+    // Private books stay out of sight until unlocked for the session.
+    if !private_books::is_unlocked(&handle) {
+        books.retain(|b| !b.private_lock);
+    }
     books.sort_by(|a, b| b.last_opened_at.cmp(&a.last_opened_at));
     Ok(books.into_iter().take(50).collect())
 }
@@ -659,31 +1842,63 @@ fn add_recent_book(
     cover_image: Option<String>,
     total_pages: u32,
 ) -> Result<(), String> {
-    let mut data = load_recent_books(&handle)?;
-
-    // Remove existing entry with same id OR same file_path (to prevent duplicates)
-    data.books.retain(|b| b.id != id && b.file_path != file_path);
-
-    // Add new entry
-    data.books.push(RecentBook {
-        id,
-        file_path,
-        file_name,
-        file_type,
-        title,
-        author,
-        cover_image,
-        total_pages,
-        last_page: 1,
-        progress: 0.0,
-        last_opened_at: Utc::now(),
-    });
-
-    // Keep only last 50 books
-    data.books.sort_by(|a, b| b.last_opened_at.cmp(&a.last_opened_at));
-    data.books.truncate(50);
-
-    save_recent_books(&handle, &data)
+    activity_log::record(&handle, activity_log::ActivityKind::BookOpened, Some(&id), None);
+    // The cloud policy and private flag outlive the book's place in the list, so they come
+    // from their own stores.
+    let cloud_allowed = cloud_policy::register(&handle, &id)?.cloud_allowed;
+    let private_lock = private_books::is_private(&handle, &id)?;
+    update_recent_books(&handle, |data| {
+        // Per-book preferences and status survive re-adding the same book
+        let previous = data.books.iter().find(|b| b.id == id);
+        let status = previous.map(|b| b.status).unwrap_or_default();
+        let finished_at = previous.and_then(|b| b.finished_at);
+        let metadata = previous.map(|b| b.metadata.clone()).unwrap_or_default();
+        let formats = previous.map(|b| b.formats.clone()).unwrap_or_default();
+        let preferred_format = previous.and_then(|b| b.preferred_format.clone());
+
+        // Remove existing entry with same id OR same file_path (to prevent duplicates)
+        data.books.retain(|b| b.id != id && b.file_path != file_path);
+
+        // Add new entry
+        let mut book = RecentBook {
+            id,
+            file_path,
+            file_name,
+            file_type,
+            title,
+            author,
+            cover_image,
+            total_pages,
+            last_page: 1,
+            progress: 0.0,
+            last_opened_at: Utc::now(),
+            cloud_allowed,
+            status,
+            finished_at,
+            metadata,
+            formats,
+            preferred_format,
+            private_lock,
+        };
+        series::fill_series(&mut book);
+        data.books.push(book);
+
+        // Keep only last 50 books being read, and articles apart from them; finished and
+        // shelved books are kept for good
+        data.books.sort_by(|a, b| b.last_opened_at.cmp(&a.last_opened_at));
+        let (mut reading, mut articles) = (0, 0);
+        data.books.retain(|b| {
+            if b.status != BookStatus::Reading {
+                return true;
+            }
+            if b.file_type == articles::ARTICLE_FILE_TYPE {
+                articles += 1;
+                return articles <= articles::MAX_UNREAD_ARTICLES;
+            }
+            reading += 1;
+            reading <= 50
+        });
+    })
 }
 
 #[tauri::command(rename_all = "camelCase")]
@@ -692,23 +1907,105 @@ fn update_book_progress(
     id: String,
     last_page: u32,
     progress: f32,
+    file_path: Option<String>,
 ) -> Result<(), String> {
-    let mut data = load_recent_books(&handle)?;
-
-    if let Some(book) = data.books.iter_mut().find(|b| b.id == id) {
-        book.last_page = last_page;
+    if let Err(e) = reading_activity::record_page_turn(&handle, &id) {
+        log::error!("Failed to record reading activity: {}", e);
+    }
+    let finished = update_recent_books(&handle, |data| {
+        let book = data.books.iter_mut().find(|b| b.id == id)?;
+        let was_finished = book.progress >= 100.0 || book.status == BookStatus::Finished;
+        // Reading another format of the book moves that format's page; progress is shared.
+        match file_path.and_then(|path| book.formats.iter_mut().find(|f| f.file_path == path)) {
+            Some(format) => format.last_page = last_page,
+            None => book.last_page = last_page,
+        }
         book.progress = progress;
         book.last_opened_at = Utc::now();
+        (!was_finished && progress >= 100.0).then(|| (book.title.clone(), book.author.clone()))
+    })?;
+    if let Some((title, author)) = finished {
+        let data = serde_json::json!({ "book_id": id, "title": title, "author": author });
+        hooks::fire(&handle, hooks::HookEvent::BookFinished, &id, data);
     }
+    Ok(())
+}
 
-    save_recent_books(&handle, &data)
+#[tauri::command(rename_all = "camelCase")]
+fn set_book_cloud_allowed(handle: tauri::AppHandle, id: String, allowed: bool) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing cloud access")?;
+    update_recent_books(&handle, |data| {
+        let book = data
+            .books
+            .iter_mut()
+            .find(|b| b.id == id)
+            .ok_or_else(|| format!("Book not found: {}", id))?;
+        book.cloud_allowed = allowed;
+        Ok::<(), String>(())
+    })??;
+    cloud_policy::set_cloud_allowed(&handle, &id, allowed)
+}
+
+// Marking a book finished stamps `finished_at`; moving it back to reading or abandoned clears
+// it, while archiving keeps it so the book still counts as read.
+#[tauri::command(rename_all = "camelCase")]
+fn set_book_status(handle: tauri::AppHandle, id: String, status: BookStatus) -> Result<(), String> {
+    let finished = update_recent_books(&handle, |data| {
+        let book = data
+            .books
+            .iter_mut()
+            .find(|b| b.id == id)
+            .ok_or_else(|| format!("Book not found: {}", id))?;
+        let newly_finished = status == BookStatus::Finished && book.status != BookStatus::Finished;
+        match status {
+            BookStatus::Finished if newly_finished => book.finished_at = Some(Utc::now()),
+            BookStatus::Reading | BookStatus::Abandoned => book.finished_at = None,
+            _ => {}
+        }
+        book.status = status;
+        // Reaching the last page already announced the book as finished.
+        Ok::<_, String>((newly_finished && book.progress < 100.0).then(|| (book.title.clone(), book.author.clone())))
+    })??;
+    if let Some((title, author)) = finished {
+        let data = serde_json::json!({ "book_id": id, "title": title, "author": author });
+        hooks::fire(&handle, hooks::HookEvent::BookFinished, &id, data);
+    }
+    Ok(())
+}
+
+// Books with `status`, most recently finished or opened first. `year` keeps those finished
+// (or, for other statuses, last opened) in that year.
+#[tauri::command(rename_all = "camelCase")]
+fn list_books_by_status(
+    handle: tauri::AppHandle,
+    status: BookStatus,
+    year: Option<i32>,
+) -> Result<Vec<RecentBook>, String> {
+    let unlocked = private_books::is_unlocked(&handle);
+    let mut books: Vec<RecentBook> = handle.state::<app_state::AppState>().recent_books.read(&handle, |data| {
+        data.books
+            .iter()
+            .filter(|b| b.status == status)
+            // Like the recent list, private books stay out of sight until unlocked.
+            .filter(|b| !b.private_lock || unlocked)
+            .filter(|b| year.is_none_or(|year| b.finished_at.unwrap_or(b.last_opened_at).year() == year))
+            .cloned()
+            .collect()
+    })?;
+    books.sort_by_key(|b| std::cmp::Reverse(b.finished_at.unwrap_or(b.last_opened_at)));
+    Ok(books)
 }
 
 #[tauri::command(rename_all = "camelCase")]
 fn remove_recent_book(handle: tauri::AppHandle, id: String) -> Result<(), String> {
-    let mut data = load_recent_books(&handle)?;
-    data.books.retain(|b| b.id != id);
-    save_recent_books(&handle, &data)
+    let removed = update_recent_books(&handle, |data| {
+        let position = data.books.iter().position(|b| b.id == id)?;
+        Some((position, data.books.remove(position)))
+    })?;
+    if let Some((position, book)) = removed {
+        undo::record(&handle, undo::Operation::RemoveRecentBook { position, book: Box::new(book) });
+    }
+    Ok(())
 }
 
 // Chat with context command
@@ -718,18 +2015,75 @@ async fn chat_with_context(
     model: String,
     context: String,
     question: String,
+    book_id: String,
+    max_page: Option<u32>,
 ) -> Result<String, String> {
-    let api_key = load_openrouter_key(&handle)?;
-
-    let system_prompt = "You are a helpful reading assistant. Answer questions about the provided text context clearly and concisely. If the answer cannot be found in the context, say so.";
+    restricted_mode::ensure_unrestricted(&handle, "Chat")?;
+    ensure_cloud_allowed(&handle, &book_id)?;
+    let credentials = load_openrouter_credentials(&handle)?;
+
+    let mut system_prompt = "You are a helpful reading assistant. Answer questions about the provided text context clearly and concisely. If the answer cannot be found in the context, say so.".to_string();
+    let mut context = context;
+
+    if let Some(max_page) = max_page {
+        system_prompt.push_str(&format!(
+            " The reader has only read up to page {}. Do not reveal, hint at or speculate about anything that happens later; if answering would require later material, refuse and say it has not come up yet in their reading.",
+            max_page
+        ));
+        context = strip_later_paragraphs(&handle, &book_id, &context, max_page)?;
+    }
 
-    let user_prompt = format!(
+    let mut user_prompt = format!(
         "Context from the document:\n\n{}\n\n---\n\nQuestion: {}",
         context, question
     );
+    let glossary = entities::describe_mentioned_entities(&handle, &book_id, &question, max_page)?;
+    if !glossary.is_empty() {
+        user_prompt = format!("Known people, places and terms in this book:\n{}\n\n{}", glossary, user_prompt);
+    }
+    if let Some(max_page) = max_page {
+        if let Some(summary) = story::stored_story_summary(&handle, &book_id, max_page)? {
+            user_prompt = format!("Story so far:\n{}\n\n{}", summary, user_prompt);
+        }
+    }
+    if let Some(preamble) = arxiv::chat_preamble(&handle, &book_id)? {
+        user_prompt = format!("{}\n\n{}", preamble, user_prompt);
+    }
 
-    let content = request_openrouter(&api_key, &model, 0.3, system_prompt, &user_prompt).await?;
-    Ok(content)
+    response_cache::request_openrouter_cached(
+        &handle,
+        response_cache::FEATURE_CHAT,
+        &credentials,
+        &model,
+        0.3,
+        &system_prompt,
+        &user_prompt,
+    )
+    .await
+}
+
+// Drops context lines that only occur on pages after `max_page` of the stored book text.
+fn strip_later_paragraphs(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    context: &str,
+    max_page: u32,
+) -> Result<String, String> {
+    let text = book_text::load_book_text(handle, book_id)?;
+    let mut earlier: HashSet<&str> = HashSet::new();
+    let mut later: HashSet<&str> = HashSet::new();
+    for page in &text.pages {
+        let target = if page.page <= max_page { &mut earlier } else { &mut later };
+        target.extend(page.paragraphs.iter().map(|p| p.text.trim()));
+    }
+    Ok(context
+        .lines()
+        .filter(|line| {
+            let line = line.trim();
+            !later.contains(line) || earlier.contains(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
 }
 
 fn extract_json_object(content: &str) -> String {
@@ -763,29 +2117,275 @@ fn extract_json_object(content: &str) -> String {
     trimmed.to_string()
 }
 
+// Stops background jobs before the final flush so nothing writes to the stores afterwards.
+fn shutdown(handle: &tauri::AppHandle) {
+    handle.state::<jobs::JobRegistry>().shutdown();
+    if let Err(e) = private_books::lock(handle) {
+        log::error!("Failed to lock private books on shutdown: {}", e);
+    }
+    if let Err(e) = app_state::flush(handle) {
+        log::error!("Failed to save app state on shutdown: {}", e);
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // Background failures are logged to stdout and the app's log folder.
+        .plugin(tauri_plugin_log::Builder::new().level(log::LevelFilter::Info).build())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(data_dir::DataDir::default())
+        .manage(profiles::ActiveProfile::default())
+        .manage(app_state::AppState::default())
+        .manage(vocab_index::VocabularyIndex::default())
+        .manage(jobs::JobRegistry::default())
+        .manage(InFlightTranslations::default())
+        .manage(hooks::HookStore::default())
+        .manage(companion_server::CompanionServer::default())
+        .manage(openrouter_oauth::OpenRouterOAuth::default())
+        .manage(activity_log::EventLog::default())
+        .manage(private_books::PrivateBooks::default())
+        .manage(undo::UndoJournal::default())
+        .manage(maintenance::Maintenance::default())
+        .manage(quarantine::Quarantine::default())
+        .manage(srs::ReviewLock::default())
+        .manage(read_aloud::ReadAloud::default())
+        .manage(offline::Connectivity::default())
+        .manage(restricted_mode::PinAttempts::default())
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                library_import::import_dropped(window.app_handle(), paths.clone());
+            }
+        })
+        .setup(|app| {
+            data_dir::init(app.handle())?;
+            profiles::init(app.handle())?;
+            app_state::start(app.handle());
+            if let Err(e) = private_books::recover(app.handle()) {
+                log::error!("Failed to seal private books left open: {}", e);
+            }
+            maintenance::start(app.handle());
+            review_reminders::start(app.handle());
+            offline::start(app.handle());
+            #[cfg(feature = "updater")]
+            app.handle().plugin(updater::plugin())?;
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             read_pdf_file,
             openrouter_translate,
             openrouter_word_lookup,
+            lookup_phrase,
             save_openrouter_key,
             get_openrouter_key_info,
             test_openrouter_key,
             add_vocabulary_word,
             remove_vocabulary_word,
             get_vocabulary,
+            get_vocabulary_by_language,
             is_word_in_vocabulary,
+            vocab_index::check_words_in_vocabulary,
+            word_lists::mark_word_known,
+            word_lists::unmark_word_known,
+            word_lists::get_known_words,
+            word_lists::mark_word_ignored,
+            word_lists::unmark_word_ignored,
+            word_lists::get_ignored_words,
+            word_lists::import_wordlist,
+            word_lists::get_wordlists,
+            send_to_device::send_book_to_device,
+            send_to_device::set_smtp_password,
+            koreader_sync::koreader_login,
+            koreader_sync::koreader_logout,
+            koreader_sync::get_koreader_sync_status,
+            koreader_sync::set_koreader_book_sync,
+            koreader_sync::sync_koreader_progress,
+            language_catalog::list_supported_languages,
+            language_rules::get_language_rules,
+            language_rules::set_language_rules,
+            word_lists::remove_wordlist,
+            page_words::classify_page_words,
+            gloss::get_page_gloss,
+            audiobook::attach_audiobook,
+            audiobook::detach_audiobook,
+            audiobook::get_audiobook,
+            audiobook::set_audio_sync_point,
+            audiobook::get_audio_position,
+            audiobook::get_page_for_audio_position,
+            book_pack::export_book_pack,
+            book_pack::import_book_pack,
+            book_text::store_book_pages,
+            book_text::get_book_page,
+            epub::extract_epub_chapter,
+            epub::translate_epub_chapter,
+            epub_translation::translate_epub_book,
+            footnotes::extract_footnotes,
+            citations::extract_citations,
+            sync_conflicts::merge_sync_conflicts,
+            vocab_merge::merge_vocabulary,
+            companion_server::start_companion_server,
+            companion_server::stop_companion_server,
+            companion_server::get_companion_server,
+            hooks::list_hooks,
+            hooks::save_hook,
+            hooks::choose_hook_script,
+            hooks::remove_hook,
+            hooks::test_hook,
+            jobs::list_jobs,
+            model_catalog::get_model_catalog,
+            model_params::list_parameter_presets,
+            model_params::validate_model_params,
+            obsidian::export_to_obsidian,
+            jobs::cancel_job,
+            job_state::list_pending_jobs,
+            job_state::resume_pending_jobs,
+            job_state::discard_pending_job,
+            onboarding::get_onboarding_state,
+            onboarding::complete_onboarding_step,
+            onboarding::reset_onboarding,
+            onboarding::validate_setup,
+            quota::get_usage_summary,
+            maintenance::run_maintenance_now,
+            maintenance::get_maintenance_report,
+            quarantine::get_data_recovery_report,
+            quarantine::recover_quarantined_file,
+            quarantine::discard_quarantined_file,
+            undo::get_undo_state,
+            undo::undo_last_operation,
+            undo::redo_last_operation,
+            lookup_history::get_lookup_history,
+            lookup_history::save_lookup_to_vocabulary,
+            profiles::list_profiles,
+            profiles::create_profile,
+            profiles::switch_profile,
+            restricted_mode::get_restricted_mode,
+            restricted_mode::enable_restricted_mode,
+            restricted_mode::disable_restricted_mode,
+            openrouter_oauth::begin_openrouter_oauth,
+            openrouter_oauth::cancel_openrouter_oauth,
+            prefetch::prefetch_translations,
+            prefetch::cancel_prefetch,
+            prefetch::get_translation_coverage,
+            offline::get_offline_state,
+            offline::set_offline_mode,
+            new_edition::rebind_cache_to_new_edition,
+            translation_feedback::rate_translation,
+            translation_feedback::retranslate_sentence,
+            alternatives::get_alternative_translations,
+            transliteration::transliterate,
+            readings::annotate_page_readings,
+            readings::get_page_readings,
+            readings::get_page_readings_html,
+            response_cache::get_response_cache_stats,
+            response_cache::clear_response_cache,
+            segmentation::segment_text,
+            segmentation::rekey_translation_cache,
+            structure::classify_blocks,
+            text_cleanup::get_cleanup_options,
+            text_cleanup::set_cleanup_options,
+            text_cleanup::get_running_lines,
+            text_cleanup::prepare_page_text,
+            entities::extract_entities,
+            entities::get_book_entities,
+            entities::lookup_entity,
+            story::get_story_context,
+            story::chat_with_book,
             export_vocabulary_markdown,
+            vocab_export::export_vocabulary,
+            export_templates::list_export_templates,
+            export_templates::export_with_template,
+            book_statistics::get_book_statistics,
+            book_level::estimate_book_level,
+            recommendations::recommend_next_books,
+            srs::get_due_reviews,
+            srs::record_review,
+            review_reminders::snooze_review_reminder,
+            read_aloud::start_read_aloud,
+            read_aloud::start_bilingual_read_aloud,
+            read_aloud::stop_read_aloud,
+            read_aloud::get_read_aloud_state,
+            read_aloud::set_read_aloud_speed,
+            read_aloud::skip_read_aloud_sentence,
+            pronunciation::score_pronunciation,
+            quote_card::export_quote_card,
+            vocab_sheet::export_vocabulary_pdf,
+            library_cleanup::scan_library_issues,
+            library_cleanup::apply_cleanup,
+            alignment::align_and_seed_cache,
+            alignment::get_alignment,
+            pagination::get_pagination_settings,
+            pagination::repaginate_book,
+            plugins::list_plugins,
+            plugins::run_plugin,
             get_recent_books,
             add_recent_book,
             update_book_progress,
             remove_recent_book,
-            chat_with_context
+            set_book_cloud_allowed,
+            set_book_status,
+            list_books_by_status,
+            book_metadata::enrich_book_metadata,
+            book_metadata::set_book_metadata,
+            book_formats::add_book_format,
+            book_formats::remove_book_format,
+            book_formats::open_book_format,
+            book_formats::get_book_formats,
+            position_map::map_pdf_to_epub,
+            position_map::map_epub_to_pdf,
+            isbn::detect_isbn,
+            external_open::reveal_book_in_file_manager,
+            external_open::open_book_externally,
+            external_open::set_external_app,
+            url_import::import_book_from_url,
+            arxiv::import_arxiv,
+            articles::list_articles,
+            feeds::subscribe_feed,
+            feeds::unsubscribe_feed,
+            feeds::list_feeds,
+            feeds::refresh_feeds,
+            web_import::import_web_page,
+            series::set_book_series,
+            series::get_series,
+            reading_report::generate_reading_report,
+            activity_log::log_activity,
+            activity_log::get_activity_timeline,
+            chat_with_context,
+            settings::get_app_settings,
+            settings::save_app_settings,
+            settings::export_settings,
+            settings::import_settings,
+            skip_ranges::get_skip_ranges,
+            skip_ranges::set_skip_ranges,
+            skip_ranges::suggest_skip_ranges,
+            #[cfg(feature = "updater")]
+            updater::get_update_channel,
+            #[cfg(feature = "updater")]
+            updater::set_update_channel,
+            #[cfg(feature = "updater")]
+            updater::check_for_updates,
+            #[cfg(feature = "updater")]
+            updater::install_update,
+            encryption::get_cache_encryption_status,
+            encryption::set_cache_encryption,
+            private_books::get_private_books_state,
+            private_books::unlock_private_books,
+            private_books::lock_private_books,
+            private_books::set_book_private_lock,
+            data_dir::get_data_dir,
+            data_dir::migrate_data_dir
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|handle, event| match event {
+            tauri::RunEvent::ExitRequested { .. } => shutdown(handle),
+            // Debounced writes (reading progress in particular) must not be lost on quit.
+            tauri::RunEvent::Exit => {
+                if let Err(e) = app_state::flush(handle) {
+                    log::error!("Failed to save app state on exit: {}", e);
+                }
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::maintenance::{find_missing_books, MissingBook};
+use crate::{
+    book_data_file_path, read_cache, remove_recent_book, set_book_status, update_cache, update_recent_books, BookStatus,
+    RecentBook,
+};
+
+// Books still marked as being read, never read past the start and not opened for this long.
+const STALE_AFTER_DAYS: i64 = 90;
+// Books whose cached data takes more than this are worth a look.
+const OVERSIZED_CACHE_BYTES: u64 = 10 * 1024 * 1024;
+// Per-book data that is derived from the book and made again when needed.
+const DERIVED_DATA_DIRS: &[&str] = &["book_text", "entities", "story", "position_maps"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BookSummary {
+    id: String,
+    title: String,
+    file_path: String,
+    progress: f32,
+    last_opened_at: DateTime<Utc>,
+}
+
+impl From<&RecentBook> for BookSummary {
+    fn from(book: &RecentBook) -> Self {
+        Self {
+            id: book.id.clone(),
+            title: book.title.clone(),
+            file_path: book.file_path.clone(),
+            progress: book.progress,
+            last_opened_at: book.last_opened_at,
+        }
+    }
+}
+
+// Library entries whose files have the same content. The first is the one to keep: the most
+// read, then the most recently opened.
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup {
+    content_hash: String,
+    books: Vec<BookSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheOwner {
+    #[serde(flatten)]
+    book: BookSummary,
+    translation_cache_bytes: u64,
+    derived_data_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryIssues {
+    duplicates: Vec<DuplicateGroup>,
+    missing: Vec<MissingBook>,
+    stale: Vec<BookSummary>,
+    // Largest first.
+    oversized_caches: Vec<CacheOwner>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum CleanupAction {
+    RemoveBook { book_id: String },
+    Archive { book_id: String },
+    // Points a book whose file moved at its new location.
+    Relink { book_id: String, file_path: String },
+    // Drops the book's cached translations and derived data; nothing the user wrote.
+    ClearCache { book_id: String },
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct CleanupReport {
+    applied: usize,
+    bytes_freed: u64,
+    // Actions that failed; the others were still applied.
+    errors: Vec<String>,
+}
+
+fn content_hash(path: &str) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+fn find_duplicates(books: &[RecentBook]) -> Vec<DuplicateGroup> {
+    let mut by_hash: HashMap<String, Vec<&RecentBook>> = HashMap::new();
+    for book in books {
+        if let Some(hash) = content_hash(&book.file_path) {
+            by_hash.entry(hash).or_default().push(book);
+        }
+    }
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_, books)| books.len() > 1)
+        .map(|(content_hash, mut books)| {
+            books.sort_by(|a, b| b.progress.total_cmp(&a.progress).then(b.last_opened_at.cmp(&a.last_opened_at)));
+            DuplicateGroup { content_hash, books: books.into_iter().map(BookSummary::from).collect() }
+        })
+        .collect();
+    groups.sort_by(|a, b| a.books[0].title.cmp(&b.books[0].title));
+    groups
+}
+
+fn find_stale(books: &[RecentBook]) -> Vec<BookSummary> {
+    let now = Utc::now();
+    books
+        .iter()
+        .filter(|b| b.status == BookStatus::Reading && b.progress <= 0.0)
+        .filter(|b| (now - b.last_opened_at).num_days() >= STALE_AFTER_DAYS)
+        .map(BookSummary::from)
+        .collect()
+}
+
+fn derived_data_bytes(handle: &tauri::AppHandle, book_id: &str) -> u64 {
+    DERIVED_DATA_DIRS
+        .iter()
+        .filter_map(|dir| book_data_file_path(handle, dir, book_id).ok())
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+// Bytes of cached translations per book, keys included.
+fn translation_cache_bytes(handle: &tauri::AppHandle) -> Result<HashMap<String, u64>, String> {
+    read_cache(handle, |cache| {
+        let mut sizes: HashMap<String, u64> = HashMap::new();
+        for (key, translation) in &cache.entries {
+            let doc_id = key.split('|').next().unwrap_or(key);
+            *sizes.entry(doc_id.to_string()).or_default() += (key.len() + translation.len()) as u64;
+        }
+        sizes
+    })
+}
+
+fn find_oversized_caches(handle: &tauri::AppHandle, books: &[RecentBook]) -> Result<Vec<CacheOwner>, String> {
+    let cache_sizes = translation_cache_bytes(handle)?;
+    let mut owners: Vec<CacheOwner> = books
+        .iter()
+        .map(|book| CacheOwner {
+            book: BookSummary::from(book),
+            translation_cache_bytes: cache_sizes.get(&book.id).copied().unwrap_or(0),
+            derived_data_bytes: derived_data_bytes(handle, &book.id),
+        })
+        .filter(|owner| owner.translation_cache_bytes + owner.derived_data_bytes > OVERSIZED_CACHE_BYTES)
+        .collect();
+    owners.sort_by_key(|owner| std::cmp::Reverse(owner.translation_cache_bytes + owner.derived_data_bytes));
+    Ok(owners)
+}
+
+fn clear_book_cache(handle: &tauri::AppHandle, book_id: &str) -> Result<u64, String> {
+    let prefix = format!("{}|", book_id);
+    let mut freed = update_cache(handle, |cache| {
+        let mut freed = 0;
+        cache.entries.retain(|key, translation| {
+            let keep = !key.starts_with(&prefix);
+            if !keep {
+                freed += (key.len() + translation.len()) as u64;
+            }
+            keep
+        });
+        cache.generations.retain(|key, _| !key.starts_with(&prefix));
+        freed
+    })?;
+    for dir in DERIVED_DATA_DIRS {
+        let path = book_data_file_path(handle, dir, book_id)?;
+        if let Ok(metadata) = fs::metadata(&path) {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+            freed += metadata.len();
+        }
+    }
+    Ok(freed)
+}
+
+fn relink(handle: &tauri::AppHandle, book_id: &str, file_path: &str) -> Result<(), String> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        return Err(format!("No file at {}", file_path));
+    }
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    update_recent_books(handle, |data| {
+        let book = data
+            .books
+            .iter_mut()
+            .find(|b| b.id == book_id)
+            .ok_or_else(|| format!("Book not found: {}", book_id))?;
+        book.file_path = file_path.to_string();
+        book.file_name = file_name;
+        Ok(())
+    })?
+}
+
+fn apply(handle: &tauri::AppHandle, action: CleanupAction) -> Result<u64, String> {
+    match action {
+        CleanupAction::RemoveBook { book_id } => remove_recent_book(handle.clone(), book_id).map(|()| 0),
+        CleanupAction::Archive { book_id } => {
+            set_book_status(handle.clone(), book_id, BookStatus::Archived).map(|()| 0)
+        }
+        CleanupAction::Relink { book_id, file_path } => relink(handle, &book_id, &file_path).map(|()| 0),
+        CleanupAction::ClearCache { book_id } => clear_book_cache(handle, &book_id),
+    }
+}
+
+// Problems worth cleaning up in a large library: entries for the same file content, books
+// whose file is gone, books added long ago and never started, and books with a lot of cached
+// data. Hashes every book file, so it takes a while on big libraries.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn scan_library_issues(handle: tauri::AppHandle) -> Result<LibraryIssues, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let books = handle.state::<AppState>().recent_books.read(&handle, |data| data.books.clone())?;
+        Ok(LibraryIssues {
+            duplicates: find_duplicates(&books),
+            missing: find_missing_books(&handle)?,
+            stale: find_stale(&books),
+            oversized_caches: find_oversized_caches(&handle, &books)?,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Resolves issues from `scan_library_issues` in bulk. Removed books can be brought back one
+// at a time with undo.
+#[tauri::command(rename_all = "camelCase")]
+pub fn apply_cleanup(handle: tauri::AppHandle, actions: Vec<CleanupAction>) -> Result<CleanupReport, String> {
+    let mut report = CleanupReport::default();
+    for action in actions {
+        let description = format!("{:?}", action);
+        match apply(&handle, action) {
+            Ok(freed) => {
+                report.applied += 1;
+                report.bytes_freed += freed;
+            }
+            Err(e) => report.errors.push(format!("{}: {}", description, e)),
+        }
+    }
+    Ok(report)
+}
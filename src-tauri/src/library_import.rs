@@ -0,0 +1,253 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+use walkdir::WalkDir;
+
+use crate::epub::read_package_document;
+use crate::epub_translation::write_epub;
+use crate::progress;
+use crate::settings::{load_settings, ImportMode};
+use crate::{add_recent_book, app_state, data_root, read_pdf_file};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedBook {
+    pub id: String,
+    pub file_path: String,
+    pub file_type: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub total_pages: u32,
+    // Whether the file was copied into the library folder.
+    pub copied: bool,
+    pub already_in_library: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ImportFailed {
+    path: String,
+    message: String,
+}
+
+// The book's format from its first bytes: `%PDF-`, or a zip whose leading `mimetype` entry
+// says EPUB.
+pub fn sniff_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"%PDF-") {
+        return Some("pdf");
+    }
+    let head = &bytes[..bytes.len().min(128)];
+    let epub = bytes.starts_with(b"PK\x03\x04") && head.windows(20).any(|w| w == b"application/epub+zip");
+    epub.then_some("epub")
+}
+
+// Library ids are the start of the file's SHA-256, as the reader computes them on open.
+pub fn book_id(bytes: &[u8]) -> String {
+    let digest = format!("{:x}", Sha256::digest(bytes));
+    digest[..12].to_string()
+}
+
+pub fn library_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    match load_settings(handle)?.library_folder.filter(|folder| !folder.trim().is_empty()) {
+        Some(folder) => Ok(PathBuf::from(folder)),
+        // Shared by all profiles, so a book is stored once however many read it.
+        None => Ok(data_root(handle)?.join("library")),
+    }
+}
+
+// A free name for `file_name` in `dir`; an existing file with the same content is reused.
+fn library_destination(dir: &Path, file_name: &str, id: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    let same_content = |path: &Path| fs::read(path).is_ok_and(|existing| book_id(&existing) == id);
+    if !candidate.exists() || same_content(&candidate) {
+        return candidate;
+    }
+    let path = Path::new(file_name);
+    let stem = path.file_stem().map_or_else(|| "book".into(), |s| s.to_string_lossy());
+    let extension = path.extension().map_or_else(|| "".into(), |e| e.to_string_lossy());
+    let renamed = dir.join(format!("{} ({}).{}", stem, id, extension));
+    if !renamed.exists() || same_content(&renamed) {
+        return renamed;
+    }
+    dir.join(format!("{} ({}-{}).{}", stem, id, chrono::Utc::now().timestamp(), extension))
+}
+
+pub fn element_text(xml: &str, tag: &str) -> Option<String> {
+    let rest = xml.split(&format!("<{}", tag)).nth(1)?;
+    let text = rest.split_once('>')?.1.split('<').next()?;
+    let text = html_escape::decode_html_entities(text.trim()).to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+// Title and author as the file records them: the EPUB package's Dublin Core fields, or the
+// document information of a PDF when it is stored uncompressed.
+fn file_metadata(path: &Path, file_type: &str, bytes: &[u8]) -> (Option<String>, Option<String>) {
+    if file_type == "epub" {
+        return match read_package_document(path) {
+            Ok(opf) => (element_text(&opf, "dc:title"), element_text(&opf, "dc:creator")),
+            Err(_) => (None, None),
+        };
+    }
+    let info = |key: &str| {
+        let needle = format!("/{} (", key);
+        let start = bytes.windows(needle.len()).position(|w| w == needle.as_bytes())? + needle.len();
+        let end = bytes[start..].iter().position(|&b| b == b')')?;
+        let value = String::from_utf8(bytes[start..start + end].to_vec()).ok()?;
+        let value = value.trim().to_string();
+        (!value.is_empty()).then_some(value)
+    };
+    (info("Title"), info("Author"))
+}
+
+// Page objects in a PDF. Zero when they sit in compressed object streams; the reader fills in
+// the count when the book is first opened.
+fn pdf_page_count(bytes: &[u8]) -> u32 {
+    let needle = b"/Type";
+    let mut count = 0;
+    for (index, _) in bytes.windows(needle.len()).enumerate().filter(|(_, w)| *w == needle) {
+        let rest = &bytes[index + needle.len()..bytes.len().min(index + needle.len() + 8)];
+        let rest: Vec<u8> = rest.iter().copied().skip_while(|b| b.is_ascii_whitespace()).collect();
+        if rest.starts_with(b"/Page") && !rest.starts_with(b"/Pages") {
+            count += 1;
+        }
+    }
+    count
+}
+
+// An unpacked EPUB, as macOS shows some of them: a folder with META-INF/container.xml.
+pub fn is_epub_dir(path: &Path) -> bool {
+    path.join("META-INF").join("container.xml").is_file()
+}
+
+// Packs an unpacked EPUB into a file the way readers expect it, the mimetype entry first.
+// Entries go in name order, so the same folder always gives the same bytes and book id.
+pub fn pack_epub_dir(dir: &Path) -> Result<Vec<u8>, String> {
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir).map_err(|e| e.to_string())?;
+        let name: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+        entries.push((name.join("/"), fs::read(entry.path()).map_err(|e| e.to_string())?));
+    }
+    write_epub(entries)
+}
+
+// Validates a book file and adds it to the library. Folders are only taken as unpacked EPUBs.
+pub fn import_book_file(handle: &tauri::AppHandle, source: &Path) -> Result<ImportedBook, String> {
+    if source.is_dir() && !is_epub_dir(source) {
+        return Err(format!("Not an EPUB folder: {}", source.display()));
+    }
+    let bytes = read_pdf_file(source.to_string_lossy().to_string())?;
+    let file_name = source.file_name().map(|name| name.to_string_lossy().to_string());
+    import_book_bytes(handle, &bytes, file_name, Some(source))
+}
+
+// Adds a book to the library, copying it into the library folder first when the import mode
+// says so or when there is no `source` file to link to (downloads). A book already in the
+// library is left as it is.
+pub fn import_book_bytes(
+    handle: &tauri::AppHandle,
+    bytes: &[u8],
+    file_name: Option<String>,
+    source: Option<&Path>,
+) -> Result<ImportedBook, String> {
+    let file_type = sniff_format(bytes).ok_or_else(|| {
+        let name = file_name.clone().unwrap_or_default();
+        format!("Not a PDF or EPUB file: {}", name)
+    })?;
+    let id = book_id(bytes);
+
+    let existing = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(handle, |data| data.books.iter().find(|b| b.id == id).cloned())?;
+    if let Some(book) = existing {
+        return Ok(ImportedBook {
+            id,
+            copied: false,
+            already_in_library: true,
+            file_path: book.file_path,
+            file_type: book.file_type,
+            title: book.title,
+            author: book.author,
+            total_pages: book.total_pages,
+        });
+    }
+
+    let mut file_name = file_name.unwrap_or_else(|| format!("{}.{}", id, file_type));
+    let link = load_settings(handle)?.import_mode == ImportMode::Link;
+    let source = source.filter(|_| link);
+    let copied = source.is_none();
+    let path = match source {
+        Some(source) => source.to_path_buf(),
+        None => {
+            // An unpacked EPUB bundle or a download without an extension gets one
+            if !file_name.to_lowercase().ends_with(&format!(".{}", file_type)) {
+                file_name = format!("{}.{}", file_name, file_type);
+            }
+            let dir = library_dir(handle)?;
+            fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let destination = library_destination(&dir, &file_name, &id);
+            if !destination.exists() {
+                fs::write(&destination, bytes).map_err(|e| e.to_string())?;
+            }
+            destination
+        }
+    };
+
+    let (title, author) = file_metadata(&path, file_type, bytes);
+    let title = title.unwrap_or_else(|| {
+        let name = Path::new(&file_name);
+        name.file_stem().map_or_else(|| file_name.clone(), |s| s.to_string_lossy().to_string())
+    });
+    let total_pages = if file_type == "pdf" { pdf_page_count(bytes) } else { 0 };
+    let file_path = path.to_string_lossy().to_string();
+    add_recent_book(
+        handle.clone(),
+        id.clone(),
+        file_path.clone(),
+        file_name,
+        file_type.to_string(),
+        title.clone(),
+        author.clone(),
+        None,
+        total_pages,
+    )?;
+    Ok(ImportedBook {
+        id,
+        file_path,
+        file_type: file_type.to_string(),
+        title,
+        author,
+        total_pages,
+        copied,
+        already_in_library: false,
+    })
+}
+
+// Imports files dropped on a window, off the event loop. Each result is announced as
+// `book-imported` or `book-import-failed`.
+pub fn import_dropped(handle: &tauri::AppHandle, paths: Vec<PathBuf>) {
+    let handle = handle.clone();
+    let job_id = format!("import:{}", chrono::Utc::now().timestamp_millis());
+    tauri::async_runtime::spawn_blocking(move || {
+        let total = paths.len() as u64;
+        for (index, path) in paths.into_iter().enumerate() {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+            progress::emit(&handle, &job_id, "import", index as u64, Some(total), name);
+            match import_book_file(&handle, &path) {
+                Ok(book) => {
+                    let _ = handle.emit("book-imported", book);
+                }
+                Err(message) => {
+                    let failed = ImportFailed { path: path.to_string_lossy().to_string(), message };
+                    let _ = handle.emit("book-import-failed", failed);
+                }
+            }
+        }
+        progress::emit(&handle, &job_id, "import", total, Some(total), None);
+    });
+}
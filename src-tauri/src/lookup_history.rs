@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::quarantine::parse_or_quarantine;
+use crate::vocab_index::VocabularyIndex;
+use crate::{add_vocabulary_word, app_config_dir, load_vocabulary, WordDefinitionResult};
+
+// Past this, the oldest lookups are dropped as new ones come in.
+const MAX_RECORDS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupKind {
+    #[default]
+    Word,
+    Phrase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookupRecord {
+    pub text: String,
+    pub book_id: Option<String>,
+    pub looked_up_at: DateTime<Utc>,
+    #[serde(default)]
+    pub kind: LookupKind,
+    // What the lookup returned, kept so it can be saved to vocabulary later without asking the
+    // model again. Missing on records from before these were stored.
+    #[serde(default)]
+    pub target_lang: Option<String>,
+    #[serde(default)]
+    pub phonetic: Option<String>,
+    #[serde(default)]
+    pub definitions: Vec<WordDefinitionResult>,
+    // The sentence a phrase was looked up in.
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LookupHistoryData {
+    pub records: Vec<LookupRecord>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct LookupHistoryFilter {
+    // Case-insensitive substring of the looked-up text.
+    query: Option<String>,
+    book_id: Option<String>,
+    kind: Option<LookupKind>,
+    since: Option<DateTime<Utc>>,
+    // Leave out lookups whose text is in the vocabulary already.
+    unsaved_only: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LookupHistoryItem {
+    #[serde(flatten)]
+    record: LookupRecord,
+    in_vocabulary: bool,
+}
+
+fn lookup_history_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("lookup_history.json"))
+}
+
+pub fn read_lookup_history_file(handle: &tauri::AppHandle) -> Result<LookupHistoryData, String> {
+    let path = lookup_history_file_path(handle)?;
+    if !path.exists() {
+        return Ok(LookupHistoryData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn write_lookup_history_file(handle: &tauri::AppHandle, history: &LookupHistoryData) -> Result<(), String> {
+    let path = lookup_history_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(history).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+pub fn load_lookup_history(handle: &tauri::AppHandle) -> Result<LookupHistoryData, String> {
+    handle.state::<AppState>().lookup_history.get(handle)
+}
+
+fn update_lookup_history<R>(
+    handle: &tauri::AppHandle,
+    f: impl FnOnce(&mut LookupHistoryData) -> R,
+) -> Result<R, String> {
+    handle.state::<AppState>().lookup_history.update(handle, f)
+}
+
+pub fn record_lookup(handle: &tauri::AppHandle, mut record: LookupRecord) -> Result<(), String> {
+    record.text = record.text.trim().to_string();
+    update_lookup_history(handle, |history| {
+        history.records.push(record);
+        let excess = history.records.len().saturating_sub(MAX_RECORDS);
+        history.records.drain(..excess);
+    })
+}
+
+// A book's lookups, so they can be sealed with its other private data.
+pub fn book_records(handle: &tauri::AppHandle, book_id: &str) -> Result<Vec<LookupRecord>, String> {
+    handle.state::<AppState>().lookup_history.read(handle, |history| {
+        history.records.iter().filter(|r| r.book_id.as_deref() == Some(book_id)).cloned().collect()
+    })
+}
+
+// Drops a book's lookups up to `until`, once they are sealed.
+pub fn remove_book_records(handle: &tauri::AppHandle, book_id: &str, until: DateTime<Utc>) -> Result<(), String> {
+    update_lookup_history(handle, |history| {
+        history.records.retain(|r| r.book_id.as_deref() != Some(book_id) || r.looked_up_at > until);
+    })
+}
+
+// Puts sealed lookups back in time order.
+pub fn restore_records(handle: &tauri::AppHandle, records: Vec<LookupRecord>) -> Result<(), String> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    update_lookup_history(handle, |history| {
+        history.records.extend(records);
+        history.records.sort_by_key(|r| r.looked_up_at);
+    })
+}
+
+fn vocabulary_words(handle: &tauri::AppHandle) -> Result<HashSet<String>, String> {
+    Ok(load_vocabulary(handle)?.entries.iter().map(|e| e.word.to_lowercase()).collect())
+}
+
+// Lookups newest first, at most `limit` of them, narrowed by `filter`.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_lookup_history(
+    handle: tauri::AppHandle,
+    limit: Option<usize>,
+    filter: Option<LookupHistoryFilter>,
+) -> Result<Vec<LookupHistoryItem>, String> {
+    let filter = filter.unwrap_or_default();
+    let query = filter.query.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_lowercase);
+    let saved = vocabulary_words(&handle)?;
+    let items = load_lookup_history(&handle)?
+        .records
+        .into_iter()
+        .rev()
+        .filter(|r| query.as_ref().is_none_or(|q| r.text.to_lowercase().contains(q)))
+        .filter(|r| filter.book_id.as_ref().is_none_or(|id| r.book_id.as_ref() == Some(id)))
+        .filter(|r| filter.kind.is_none_or(|kind| r.kind == kind))
+        .filter(|r| filter.since.is_none_or(|since| r.looked_up_at >= since))
+        .map(|record| {
+            let in_vocabulary = saved.contains(&record.text.to_lowercase());
+            LookupHistoryItem { record, in_vocabulary }
+        })
+        .filter(|item| !(filter.unsaved_only && item.in_vocabulary))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(items)
+}
+
+// Saves a looked-up word or phrase to vocabulary with the definitions from its most recent
+// lookup that returned any.
+#[tauri::command(rename_all = "camelCase")]
+pub fn save_lookup_to_vocabulary(
+    handle: tauri::AppHandle,
+    index: tauri::State<'_, VocabularyIndex>,
+    text: String,
+) -> Result<(), String> {
+    let wanted = text.trim().to_lowercase();
+    let record = load_lookup_history(&handle)?
+        .records
+        .into_iter()
+        .rev()
+        .filter(|r| r.text.to_lowercase() == wanted)
+        .find(|r| !r.definitions.is_empty())
+        .ok_or_else(|| format!("No saved lookup result for: {}", text.trim()))?;
+    add_vocabulary_word(
+        handle,
+        index,
+        record.text,
+        record.phonetic,
+        record.definitions,
+        None,
+        record.target_lang,
+        record.context,
+        record.book_id,
+    )
+}
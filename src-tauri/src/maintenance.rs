@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::app_state::AppState;
+use crate::response_cache::clear_response_cache;
+use crate::{app_config_dir, update_recent_books};
+
+// The first run waits until startup has settled; later runs follow every day.
+const STARTUP_DELAY: Duration = Duration::from_secs(2 * 60);
+const RUN_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTrigger {
+    Startup,
+    Scheduled,
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingBook {
+    id: String,
+    title: String,
+    file_path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    trigger: MaintenanceTrigger,
+    started_at: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+    expired_responses_removed: usize,
+    // Generation details left behind by translations that are no longer cached.
+    orphaned_generations_removed: usize,
+    // Books whose file is gone; they stay in the library so the user can relink or remove them.
+    missing_books: Vec<MissingBook>,
+    // Session-only `blob:` covers replaced by the metadata cover, or cleared so the reader
+    // makes a new one next time the book opens.
+    stale_covers_rebuilt: usize,
+    // Steps that failed; the others still ran.
+    errors: Vec<String>,
+}
+
+// Only one run at a time; a manual run during a scheduled one is refused.
+#[derive(Default)]
+pub struct Maintenance {
+    running: Mutex<()>,
+}
+
+fn report_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("maintenance.json"))
+}
+
+fn load_report(handle: &tauri::AppHandle) -> Result<Option<MaintenanceReport>, String> {
+    let path = report_file_path(handle)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map(Some).map_err(|e| e.to_string())
+}
+
+fn save_report(handle: &tauri::AppHandle, report: &MaintenanceReport) -> Result<(), String> {
+    let path = report_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn prune_orphaned_generations(handle: &tauri::AppHandle) -> Result<usize, String> {
+    handle.state::<AppState>().cache.update(handle, |cache| {
+        let before = cache.generations.len();
+        let entries = &cache.entries;
+        cache.generations.retain(|key, _| entries.contains_key(key));
+        before - cache.generations.len()
+    })
+}
+
+pub fn find_missing_books(handle: &tauri::AppHandle) -> Result<Vec<MissingBook>, String> {
+    let books = handle.state::<AppState>().recent_books.read(handle, |data| data.books.clone())?;
+    Ok(books
+        .into_iter()
+        .filter(|b| !Path::new(&b.file_path).exists() && !b.formats.iter().any(|f| Path::new(&f.file_path).exists()))
+        .map(|b| MissingBook { id: b.id, title: b.title, file_path: b.file_path })
+        .collect())
+}
+
+fn rebuild_stale_covers(handle: &tauri::AppHandle) -> Result<usize, String> {
+    update_recent_books(handle, |data| {
+        let mut rebuilt = 0;
+        for book in &mut data.books {
+            if book.cover_image.as_deref().is_some_and(|cover| cover.starts_with("blob:")) {
+                book.cover_image = book.metadata.cover_url.clone();
+                rebuilt += 1;
+            }
+        }
+        rebuilt
+    })
+}
+
+fn collect<T: Default>(errors: &mut Vec<String>, step: &str, result: Result<T, String>) -> T {
+    result.unwrap_or_else(|e| {
+        errors.push(format!("{}: {}", step, e));
+        T::default()
+    })
+}
+
+// Runs every maintenance step, keeps the report for `get_maintenance_report` and announces it
+// with `maintenance-finished`.
+pub fn run(handle: &tauri::AppHandle, trigger: MaintenanceTrigger) -> Result<MaintenanceReport, String> {
+    let maintenance = handle.state::<Maintenance>();
+    let Ok(_running) = maintenance.running.try_lock() else {
+        return Err("Maintenance is already running.".to_string());
+    };
+
+    let started_at = Utc::now();
+    let mut errors = Vec::new();
+    let expired_responses_removed =
+        collect(&mut errors, "response cache", clear_response_cache(handle.clone(), None, None, Some(true)));
+    let orphaned_generations_removed = collect(&mut errors, "translation cache", prune_orphaned_generations(handle));
+    let missing_books = collect(&mut errors, "book files", find_missing_books(handle));
+    let stale_covers_rebuilt = collect(&mut errors, "covers", rebuild_stale_covers(handle));
+    // Rewrites every store from memory, which also drops whatever the pruning removed.
+    collect(&mut errors, "stores", handle.state::<AppState>().flush_all(handle));
+
+    let report = MaintenanceReport {
+        trigger,
+        started_at,
+        finished_at: Utc::now(),
+        expired_responses_removed,
+        orphaned_generations_removed,
+        missing_books,
+        stale_covers_rebuilt,
+        errors,
+    };
+    save_report(handle, &report)?;
+    let _ = handle.emit("maintenance-finished", report.clone());
+    Ok(report)
+}
+
+// Schedules a run shortly after startup and then every 24 hours.
+pub fn start(handle: &tauri::AppHandle) {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        let mut trigger = MaintenanceTrigger::Startup;
+        loop {
+            if let Err(e) = run(&handle, trigger) {
+                log::error!("Maintenance failed: {}", e);
+            }
+            tokio::time::sleep(RUN_INTERVAL).await;
+            trigger = MaintenanceTrigger::Scheduled;
+        }
+    });
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn run_maintenance_now(handle: tauri::AppHandle) -> Result<MaintenanceReport, String> {
+    run(&handle, MaintenanceTrigger::Manual)
+}
+
+// The report of the last run, None before the first one.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_maintenance_report(handle: tauri::AppHandle) -> Result<Option<MaintenanceReport>, String> {
+    load_report(&handle)
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use crate::TranslateSentence;
+
+// Delimited math, outermost forms first so "$$" is not read as two empty "$" spans.
+const DELIMITERS: &[(&str, &str)] = &[
+    ("$$", "$$"),
+    ("\\[", "\\]"),
+    ("\\(", "\\)"),
+    ("\\begin{", "\\end{"),
+    ("<math", "</math>"),
+    ("$", "$"),
+];
+// Characters that only show up in formulas.
+const MATH_SYMBOLS: &str = "=<>≤≥≠≈≡±∓×÷·∑∏∫∮√∞∂∇∈∉∋⊂⊃⊆⊇∪∩∧∨¬→←↔⇒⇐⇔∀∃∅′″^_";
+// A run of formula-looking words needs this many tokens before it is protected.
+const MIN_FORMULA_TOKENS: usize = 3;
+
+fn placeholder(index: usize) -> String {
+    format!("⟦M{}⟧", index)
+}
+
+// End of the delimited span opening at `start` with `open`, if it closes.
+fn delimited_end(text: &str, start: usize, open: &str, close: &str) -> Option<usize> {
+    let body_start = start + open.len();
+    match open {
+        "\\begin{" => {
+            let name_end = body_start + text[body_start..].find('}')?;
+            let end_tag = format!("\\end{{{}}}", &text[body_start..name_end]);
+            Some(name_end + text[name_end..].find(&end_tag)? + end_tag.len())
+        }
+        // Inline "$...$" must hug its content, so prices like "$5 and $10" are left alone.
+        "$" => {
+            let body = &text[body_start..];
+            let close_at = body.find(close)?;
+            let inner = &body[..close_at];
+            let hugs =
+                !inner.is_empty() && !inner.starts_with(char::is_whitespace) && !inner.ends_with(char::is_whitespace);
+            let digit_after = body[close_at + 1..].starts_with(|c: char| c.is_ascii_digit());
+            (hugs && !digit_after && !inner.contains('\n')).then_some(body_start + close_at + close.len())
+        }
+        _ => Some(body_start + text[body_start..].find(close)? + close.len()),
+    }
+}
+
+fn delimited_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while from < text.len() {
+        let next = DELIMITERS
+            .iter()
+            .filter_map(|(open, close)| text[from..].find(open).map(|at| (from + at, *open, *close)))
+            .min_by_key(|(at, open, _)| (*at, std::cmp::Reverse(open.len())));
+        let Some((start, open, close)) = next else {
+            break;
+        };
+        match delimited_end(text, start, open, close) {
+            Some(end) => {
+                spans.push((start, end));
+                from = end;
+            }
+            None => from = start + open.len(),
+        }
+    }
+    spans
+}
+
+// Byte ranges of the whitespace-separated tokens of `text`.
+fn token_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (index, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if let Some(start) = start.take() {
+                ranges.push((start, index));
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if let Some(start) = start {
+        ranges.push((start, text.len()));
+    }
+    ranges
+}
+
+// Numbers, operators, single-letter variables and terms like "f(x)", "x_i" or "2πr". Short
+// words ("is", "to", "a") do not count, so prose next to a stray "=" is left alone.
+fn is_operand(token: &str) -> bool {
+    let core = token.trim_matches(|c: char| matches!(c, '(' | ')' | '[' | ']' | ','));
+    let mut chars = core.chars();
+    let variable = match (chars.next(), chars.next()) {
+        (Some(c), None) => c.is_alphabetic() && !matches!(c, 'a' | 'A' | 'I'),
+        _ => false,
+    };
+    let operator = !core.is_empty() && core.chars().all(|c| matches!(c, '+' | '-' | '*' | '/'));
+    let greek = |c: char| ('\u{0391}'..='\u{03C9}').contains(&c);
+    variable || operator || core.chars().any(|c| c.is_ascii_digit() || matches!(c, '(' | '^' | '_') || greek(c))
+}
+
+fn is_strong_math(token: &str) -> bool {
+    token.chars().any(|c| MATH_SYMBOLS.contains(c) || ('\u{2200}'..='\u{22FF}').contains(&c))
+}
+
+// Undelimited formulas such as "E = mc^2" or "∑ x_i ≤ n": runs of short operands and math
+// symbols with at least one symbol that prose does not use.
+fn formula_spans(text: &str, taken: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut run: Vec<(usize, usize, bool)> = Vec::new();
+    let mut flush = |run: &mut Vec<(usize, usize, bool)>| {
+        if run.len() >= MIN_FORMULA_TOKENS && run.iter().any(|(_, _, strong)| *strong) {
+            let start = run[0].0;
+            let end = run[run.len() - 1].1;
+            let trimmed = text[start..end].trim_end_matches(['.', ',', ';', ':']);
+            spans.push((start, start + trimmed.len()));
+        }
+        run.clear();
+    };
+
+    for (start, end) in token_ranges(text) {
+        let token = &text[start..end];
+        let overlaps = taken.iter().any(|(s, e)| start < *e && end > *s);
+        let strong = is_strong_math(token);
+        if !overlaps && (strong || is_operand(token)) {
+            run.push((start, end, strong));
+        } else {
+            flush(&mut run);
+        }
+    }
+    flush(&mut run);
+    spans
+}
+
+// Replaces math in `text` with numbered placeholders; returns the text and the spans in
+// placeholder order.
+pub fn protect_math(text: &str) -> (String, Vec<String>) {
+    let mut spans = delimited_spans(text);
+    spans.extend(formula_spans(text, &spans));
+    spans.sort_unstable();
+
+    let mut output = String::new();
+    let mut math = Vec::new();
+    let mut last = 0;
+    for (start, end) in spans {
+        output.push_str(&text[last..start]);
+        output.push_str(&placeholder(math.len()));
+        math.push(text[start..end].to_string());
+        last = end;
+    }
+    output.push_str(&text[last..]);
+    (output, math)
+}
+
+// Puts the original math back. Spans whose placeholder the model dropped are appended,
+// so a formula is never silently lost.
+pub fn restore_math(translation: &str, math: &[String]) -> String {
+    let mut output = translation.to_string();
+    for (index, span) in math.iter().enumerate() {
+        let marker = placeholder(index);
+        if output.contains(&marker) {
+            output = output.replace(&marker, span);
+        } else {
+            output.push(' ');
+            output.push_str(span);
+        }
+    }
+    output
+}
+
+// `protect_math` over a batch; only sentences that contained math appear in the map.
+pub fn protect_sentences(sentences: &[TranslateSentence]) -> (Vec<TranslateSentence>, HashMap<String, Vec<String>>) {
+    let mut protected = Vec::with_capacity(sentences.len());
+    let mut spans = HashMap::new();
+    for sentence in sentences {
+        let (text, math) = protect_math(&sentence.text);
+        if !math.is_empty() {
+            spans.insert(sentence.sid.clone(), math);
+        }
+        protected.push(TranslateSentence {
+            sid: sentence.sid.clone(),
+            text,
+            before: sentence.before.clone(),
+            after: sentence.after.clone(),
+            kind: sentence.kind,
+        });
+    }
+    (protected, spans)
+}
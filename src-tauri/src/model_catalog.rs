@@ -0,0 +1,214 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::model_params;
+use crate::offline;
+use crate::quarantine::parse_or_quarantine;
+use crate::{app_config_dir, coded_error, TranslateSentence, ERR_PROMPT_TOO_LONG};
+
+// Used when a model is missing from the catalog or the catalog cannot be fetched; small
+// enough for any chat model still served.
+const DEFAULT_CONTEXT_TOKENS: u32 = 8192;
+// System prompt, instructions and JSON framing around the sentences.
+const PROMPT_OVERHEAD_TOKENS: u32 = 400;
+// Room left for the answer, per input token: translations run longer than their source in
+// many language pairs, and the JSON echoes every sid.
+const OUTPUT_TOKENS_PER_INPUT: f32 = 1.5;
+// Tokens the sid, quotes and separators add to each sentence.
+const SENTENCE_FRAMING_TOKENS: u32 = 8;
+// Answers are never limited to less than this, so short batches have room for a preamble.
+const MIN_OUTPUT_TOKENS: u32 = 512;
+// The answer limit is this many times the estimate: a cut-off answer costs a retry, room to
+// spare costs nothing.
+const OUTPUT_HEADROOM: f32 = 2.0;
+// Reasoning models spend an unpredictable share of their answer tokens thinking, so their
+// answers are not limited below the configured maximum. Besides the fixed-sampling models.
+const REASONING_MODEL_PREFIXES: &[&str] = &["deepseek/deepseek-r1", "qwen/qwq", "x-ai/grok-3-mini"];
+const REASONING_MODEL_SUFFIX: &str = ":thinking";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    id: String,
+    name: String,
+    context_length: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct ModelCatalog {
+    fetched_at: Option<DateTime<Utc>>,
+    models: Vec<ModelInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterModels {
+    data: Vec<ModelInfo>,
+}
+
+fn model_catalog_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("model_catalog.json"))
+}
+
+fn load_model_catalog(handle: &tauri::AppHandle) -> Result<ModelCatalog, String> {
+    let path = model_catalog_path(handle)?;
+    if !path.exists() {
+        return Ok(ModelCatalog::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_model_catalog(handle: &tauri::AppHandle, catalog: &ModelCatalog) -> Result<(), String> {
+    let path = model_catalog_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(catalog).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+async fn fetch_model_catalog(handle: &tauri::AppHandle) -> Result<ModelCatalog, String> {
+    let response = reqwest::Client::new()
+        .get("https://openrouter.ai/api/v1/models")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("OpenRouter error: {}", response.status()));
+    }
+    let models = response.json::<OpenRouterModels>().await.map_err(|e| e.to_string())?;
+    let catalog = ModelCatalog {
+        fetched_at: Some(Utc::now()),
+        models: models.data,
+    };
+    save_model_catalog(handle, &catalog)?;
+    Ok(catalog)
+}
+
+// The stored catalog, refetched once a day. A failed refetch falls back to the stored copy.
+async fn cached_model_catalog(handle: &tauri::AppHandle, refresh: bool) -> Result<ModelCatalog, String> {
+    let catalog = load_model_catalog(handle)?;
+    let fresh = catalog.fetched_at.is_some_and(|at| Utc::now() - at < Duration::days(1));
+    if (fresh && !refresh) || offline::is_offline(handle) {
+        return Ok(catalog);
+    }
+    match fetch_model_catalog(handle).await {
+        Ok(fetched) => Ok(fetched),
+        Err(e) if !catalog.models.is_empty() => {
+            log::error!("Failed to refresh model catalog: {}", e);
+            Ok(catalog)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn context_length(handle: &tauri::AppHandle, model: &str) -> u32 {
+    match cached_model_catalog(handle, false).await {
+        Ok(catalog) => catalog
+            .models
+            .iter()
+            .find(|info| info.id == model)
+            .and_then(|info| info.context_length)
+            .unwrap_or(DEFAULT_CONTEXT_TOKENS),
+        Err(e) => {
+            log::warn!("Model catalog unavailable: {}", e);
+            DEFAULT_CONTEXT_TOKENS
+        }
+    }
+}
+
+// The model's catalog entry, or None when OpenRouter does not list it.
+pub async fn find_model(handle: &tauri::AppHandle, model: &str) -> Result<Option<ModelInfo>, String> {
+    let catalog = cached_model_catalog(handle, false).await?;
+    Ok(catalog.models.into_iter().find(|info| info.id == model))
+}
+
+// Rough token count without the model's tokenizer: CJK characters tend to cost a token each,
+// other scripts about four characters per token.
+pub fn estimate_tokens(text: &str) -> u32 {
+    let (wide, other) = text.chars().fold((0u32, 0u32), |(wide, other), c| {
+        let cjk = matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF);
+        if cjk {
+            (wide + 1, other)
+        } else {
+            (wide, other + 1)
+        }
+    });
+    wide + other.div_ceil(4)
+}
+
+fn sentence_tokens(sentence: &TranslateSentence) -> u32 {
+    let context: u32 = [&sentence.before, &sentence.after].into_iter().flatten().map(|t| estimate_tokens(t)).sum();
+    estimate_tokens(&sentence.sid) + estimate_tokens(&sentence.text) + context + SENTENCE_FRAMING_TOKENS
+}
+
+fn is_reasoning_model(model: &str) -> bool {
+    model_params::fixed_sampling(model)
+        || model.ends_with(REASONING_MODEL_SUFFIX)
+        || REASONING_MODEL_PREFIXES.iter().any(|prefix| model.starts_with(prefix))
+}
+
+// Answer tokens to allow for a batch: each translation, at the same generous ratio batches
+// are planned with, plus its sid and JSON framing, with headroom on top. Context is not echoed
+// back. None for reasoning models, which get no limit beyond the configured one.
+pub fn output_tokens(model: &str, sentences: &[TranslateSentence]) -> Option<u32> {
+    if is_reasoning_model(model) {
+        return None;
+    }
+    let answer: f32 = sentences
+        .iter()
+        .map(|s| {
+            estimate_tokens(&s.text) as f32 * OUTPUT_TOKENS_PER_INPUT
+                + (estimate_tokens(&s.sid) + SENTENCE_FRAMING_TOKENS) as f32
+        })
+        .sum();
+    Some(((answer * OUTPUT_HEADROOM).ceil() as u32 + PROMPT_OVERHEAD_TOKENS).max(MIN_OUTPUT_TOKENS))
+}
+
+// Splits sentences into consecutive batches whose prompt and expected answer fit in the
+// model's context window and, when set, the output token limit. A sentence that cannot fit
+// even alone is refused.
+pub fn plan_batches(
+    sentences: &[TranslateSentence],
+    context_tokens: u32,
+    max_output_tokens: Option<u32>,
+) -> Result<Vec<&[TranslateSentence]>, String> {
+    let available = context_tokens.saturating_sub(PROMPT_OVERHEAD_TOKENS) as f32;
+    let budget = (available / (1.0 + OUTPUT_TOKENS_PER_INPUT)) as u32;
+    // The output limit only makes batches smaller; a long sentence still goes alone.
+    let batch_budget = max_output_tokens.map_or(budget, |limit| {
+        let answer = limit.saturating_sub(PROMPT_OVERHEAD_TOKENS) as f32 / OUTPUT_TOKENS_PER_INPUT;
+        budget.min(answer as u32)
+    });
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (index, sentence) in sentences.iter().enumerate() {
+        let tokens = sentence_tokens(sentence);
+        if tokens > budget {
+            return Err(coded_error(
+                ERR_PROMPT_TOO_LONG,
+                &format!(
+                    "Sentence {} is about {} tokens, more than the model's {}-token context window allows.",
+                    sentence.sid, tokens, context_tokens
+                ),
+            ));
+        }
+        if used + tokens > batch_budget && index > start {
+            batches.push(&sentences[start..index]);
+            start = index;
+            used = 0;
+        }
+        used += tokens;
+    }
+    if start < sentences.len() {
+        batches.push(&sentences[start..]);
+    }
+    Ok(batches)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_model_catalog(handle: tauri::AppHandle, refresh: Option<bool>) -> Result<Vec<ModelInfo>, String> {
+    Ok(cached_model_catalog(&handle, refresh.unwrap_or(false)).await?.models)
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{coded_error, ERR_INVALID_PARAMS};
+
+// Model-id prefixes of reasoning models that only run at their default sampling; requests to
+// them leave temperature and top_p out.
+const FIXED_SAMPLING_MODELS: &[&str] = &["openai/o1", "openai/o3", "openai/o4", "openai/gpt-5"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterPreset {
+    Precise,
+    Balanced,
+    Creative,
+}
+
+impl ParameterPreset {
+    // (temperature, top_p)
+    fn values(self) -> (f32, Option<f32>) {
+        match self {
+            ParameterPreset::Precise => (0.1, Some(0.9)),
+            ParameterPreset::Balanced => (0.5, None),
+            ParameterPreset::Creative => (0.9, Some(0.95)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PresetInfo {
+    preset: ParameterPreset,
+    temperature: f32,
+    top_p: Option<f32>,
+}
+
+// Sampling choices from settings, applied on top of the temperature each feature asks for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SamplingSettings {
+    pub preset: Option<ParameterPreset>,
+    pub top_p: Option<f32>,
+}
+
+impl SamplingSettings {
+    // A preset replaces both values, unless the caller pinned its temperature; otherwise the
+    // caller's temperature and the configured top_p apply.
+    pub fn resolve(self, temperature: f32, pinned: bool) -> (f32, Option<f32>) {
+        match self.preset {
+            Some(preset) if !pinned => preset.values(),
+            _ => (temperature, self.top_p),
+        }
+    }
+}
+
+fn invalid(message: String) -> String {
+    coded_error(ERR_INVALID_PARAMS, &message)
+}
+
+pub fn check_top_p(top_p: f32) -> Result<(), String> {
+    if top_p > 0.0 && top_p <= 1.0 {
+        Ok(())
+    } else {
+        Err(invalid(format!("top_p must be greater than 0 and at most 1, got {}.", top_p)))
+    }
+}
+
+pub fn fixed_sampling(model: &str) -> bool {
+    FIXED_SAMPLING_MODELS.iter().any(|prefix| model.starts_with(prefix))
+}
+
+// Rejects values the model's provider would answer with a 400, before anything is sent.
+// Models with fixed sampling accept anything, since the values are not sent to them.
+pub fn validate_params(model: &str, temperature: f32, top_p: Option<f32>) -> Result<(), String> {
+    if let Some(top_p) = top_p {
+        check_top_p(top_p)?;
+    }
+    if fixed_sampling(model) {
+        return Ok(());
+    }
+    let max_temperature = if model.starts_with("anthropic/") { 1.0 } else { 2.0 };
+    if !(0.0..=max_temperature).contains(&temperature) {
+        return Err(invalid(format!(
+            "Temperature for {} must be between 0 and {}, got {}.",
+            model, max_temperature, temperature
+        )));
+    }
+    Ok(())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_parameter_presets() -> Result<Vec<PresetInfo>, String> {
+    Ok([ParameterPreset::Precise, ParameterPreset::Balanced, ParameterPreset::Creative]
+        .into_iter()
+        .map(|preset| {
+            let (temperature, top_p) = preset.values();
+            PresetInfo {
+                preset,
+                temperature,
+                top_p,
+            }
+        })
+        .collect())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn validate_model_params(model: String, temperature: f32, top_p: Option<f32>) -> Result<(), String> {
+    validate_params(&model, temperature, top_p)
+}
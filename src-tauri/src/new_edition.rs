@@ -0,0 +1,244 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::book_text::{load_book_text, BookText};
+use crate::granularity::split_sentences;
+use crate::library_import::book_id;
+use crate::{hash_source_text, read_cache, translation_cache_key, update_cache, TranslateSentence};
+
+// Paragraphs at least this alike (Dice over character pairs) count as the same paragraph,
+// lightly edited.
+const EDITED_MIN_SIMILARITY: f64 = 0.85;
+// How far before and after its expected place an edited paragraph is looked for.
+const SEARCH_BEFORE: usize = 5;
+const SEARCH_AFTER: usize = 30;
+// Cut-off for paragraph text quoted in the report.
+const REPORT_TEXT_CHARS: usize = 120;
+
+#[derive(Debug, Serialize)]
+pub struct UncarriedParagraph {
+    sid: String,
+    text: String,
+    // Cache entries left behind with it.
+    entries: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RebindReport {
+    new_book_id: String,
+    unchanged_paragraphs: usize,
+    edited_paragraphs: usize,
+    carried_entries: usize,
+    // Translated paragraphs with no counterpart in the new edition, and entries made with
+    // surrounding context (window mode), whose keys cannot be rebuilt.
+    not_carried: Vec<UncarriedParagraph>,
+}
+
+struct Paragraph {
+    sid: String,
+    text: String,
+}
+
+// A cache entry's key split into its parts: `doc_id|sid|hash|model|target`.
+struct EntryKey<'a> {
+    sid: &'a str,
+    hash: &'a str,
+    model: &'a str,
+    target: &'a str,
+}
+
+fn parse_key(key: &str) -> Option<EntryKey<'_>> {
+    let mut parts = key.splitn(5, '|');
+    let _doc_id = parts.next()?;
+    Some(EntryKey { sid: parts.next()?, hash: parts.next()?, model: parts.next()?, target: parts.next()? })
+}
+
+fn paragraphs(text: &BookText) -> Vec<Paragraph> {
+    text.pages
+        .iter()
+        .flat_map(|page| &page.paragraphs)
+        .map(|p| Paragraph { sid: p.sid.clone(), text: p.text.clone() })
+        .collect()
+}
+
+fn bigrams(text: &str) -> HashMap<(char, char), usize> {
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+    let mut counts = HashMap::new();
+    for pair in chars.windows(2) {
+        *counts.entry((pair[0], pair[1])).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn similarity(a: &HashMap<(char, char), usize>, b: &HashMap<(char, char), usize>) -> f64 {
+    let total: usize = a.values().sum::<usize>() + b.values().sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    let shared: usize = a.iter().map(|(pair, &count)| count.min(b.get(pair).copied().unwrap_or(0))).sum();
+    2.0 * shared as f64 / total as f64
+}
+
+// Pairs each old paragraph with a new one, keeping their order: identical text first, then,
+// near where the neighbours landed, the most alike unclaimed paragraph. Returns the new index
+// per old paragraph and whether it was an exact match.
+fn match_paragraphs(old: &[Paragraph], new: &[Paragraph]) -> Vec<Option<(usize, bool)>> {
+    let mut by_text: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, paragraph) in new.iter().enumerate().rev() {
+        by_text.entry(paragraph.text.as_str()).or_default().push(index);
+    }
+    let mut claimed = vec![false; new.len()];
+    let mut matches: Vec<Option<(usize, bool)>> = old
+        .iter()
+        .map(|paragraph| {
+            let index = by_text.get_mut(paragraph.text.as_str())?.pop()?;
+            claimed[index] = true;
+            Some((index, true))
+        })
+        .collect();
+
+    let new_bigrams: Vec<_> = new.iter().map(|p| bigrams(&p.text)).collect();
+    let mut expected = 0;
+    for (index, paragraph) in old.iter().enumerate() {
+        if let Some((new_index, _)) = matches[index] {
+            expected = new_index + 1;
+            continue;
+        }
+        let wanted = bigrams(&paragraph.text);
+        let window = expected.saturating_sub(SEARCH_BEFORE)..(expected + SEARCH_AFTER).min(new.len());
+        let best = window
+            .filter(|&candidate| !claimed[candidate])
+            .map(|candidate| (candidate, similarity(&wanted, &new_bigrams[candidate])))
+            .filter(|&(_, score)| score >= EDITED_MIN_SIMILARITY)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((candidate, _)) = best {
+            claimed[candidate] = true;
+            matches[index] = Some((candidate, false));
+            expected = candidate + 1;
+        }
+    }
+    matches
+}
+
+// Sentences of a paragraph as sentence-mode translation splits it, by sid.
+fn sentences(paragraph: &Paragraph) -> Vec<TranslateSentence> {
+    let unit = TranslateSentence { sid: paragraph.sid.clone(), text: paragraph.text.clone(), ..Default::default() };
+    split_sentences(std::slice::from_ref(&unit)).0
+}
+
+fn excerpt(text: &str) -> String {
+    match text.char_indices().nth(REPORT_TEXT_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn rebind(handle: &tauri::AppHandle, old_id: &str, new_path: &str) -> Result<RebindReport, String> {
+    let bytes = fs::read(new_path).map_err(|e| format!("Failed to read {}: {}", new_path, e))?;
+    let new_id = book_id(&bytes);
+    if new_id == old_id {
+        return Err("The new file is the same as the current one.".to_string());
+    }
+    let old = paragraphs(&load_book_text(handle, old_id)?);
+    let new = paragraphs(&load_book_text(handle, &new_id)?);
+    if new.is_empty() {
+        return Err("Open the new edition once so its text is extracted, then try again.".to_string());
+    }
+    let matches = match_paragraphs(&old, &new);
+    let owner: HashMap<&str, usize> = old.iter().enumerate().map(|(index, p)| (p.sid.as_str(), index)).collect();
+
+    // Where each of the old edition's entries goes, and per old paragraph how many stay behind.
+    let prefix = format!("{}|", old_id);
+    let (moves, left_behind) = read_cache(handle, |cache| {
+        let mut moves: Vec<(String, String)> = Vec::new();
+        let mut left_behind: HashMap<usize, usize> = HashMap::new();
+        let mut split_old: HashMap<usize, Vec<TranslateSentence>> = HashMap::new();
+        let mut split_new: HashMap<usize, Vec<TranslateSentence>> = HashMap::new();
+        for key in cache.entries.keys().filter(|key| key.starts_with(&prefix)) {
+            let Some(entry) = parse_key(key) else {
+                continue;
+            };
+            let (paragraph_sid, is_sentence) = match entry.sid.split_once("#s") {
+                Some((paragraph_sid, _)) => (paragraph_sid, true),
+                None => (entry.sid, false),
+            };
+            let Some(&index) = owner.get(paragraph_sid) else {
+                continue;
+            };
+            let new_key = matches[index].and_then(|(new_index, _)| {
+                let target = &new[new_index];
+                if !is_sentence {
+                    (entry.hash == hash_source_text(&old[index].text))
+                        .then(|| translation_cache_key(&target.sid, &target.text, entry.model, entry.target))
+                } else {
+                    // Sentences carry over only when the same sentence is in the new paragraph;
+                    // edited paragraphs keep the ones that did not change.
+                    let old_sentences = split_old.entry(index).or_insert_with(|| sentences(&old[index]));
+                    let old_sentence = old_sentences.iter().find(|s| s.sid == entry.sid)?;
+                    if entry.hash != hash_source_text(&old_sentence.text) {
+                        return None;
+                    }
+                    let candidates = split_new.entry(new_index).or_insert_with(|| sentences(target));
+                    let same = candidates.iter().find(|s| s.text == old_sentence.text)?;
+                    Some(translation_cache_key(&same.sid, &same.text, entry.model, entry.target))
+                }
+            });
+            match new_key {
+                Some(new_key) => moves.push((key.clone(), new_key)),
+                None => *left_behind.entry(index).or_default() += 1,
+            }
+        }
+        (moves, left_behind)
+    })?;
+
+    let carried_entries = update_cache(handle, |cache| {
+        let mut carried = 0;
+        for (old_key, new_key) in &moves {
+            let Some(translation) = cache.entries.get(old_key).cloned() else {
+                continue;
+            };
+            if let Some(generation) = cache.generations.get(old_key).cloned() {
+                cache.generations.entry(new_key.clone()).or_insert(generation);
+            }
+            // The new edition may have been translated already; what it has is kept.
+            if !cache.entries.contains_key(new_key) {
+                cache.entries.insert(new_key.clone(), translation);
+                carried += 1;
+            }
+        }
+        carried
+    })?;
+
+    let mut not_carried: Vec<UncarriedParagraph> = left_behind
+        .into_iter()
+        .map(|(index, entries)| UncarriedParagraph {
+            sid: old[index].sid.clone(),
+            text: excerpt(&old[index].text),
+            entries,
+        })
+        .collect();
+    not_carried.sort_by_key(|p| owner.get(p.sid.as_str()).copied());
+    Ok(RebindReport {
+        new_book_id: new_id,
+        unchanged_paragraphs: matches.iter().flatten().filter(|(_, exact)| *exact).count(),
+        edited_paragraphs: matches.iter().flatten().filter(|(_, exact)| !*exact).count(),
+        carried_entries,
+        not_carried,
+    })
+}
+
+// Carries the book's cached translations over to a new edition of it at `new_path`, whose
+// text must have been extracted (it is once the file is opened). Paragraphs are matched in
+// order, identical or lightly edited; translations of edited ones are carried as they are.
+// The old edition's entries are kept.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn rebind_cache_to_new_edition(
+    handle: tauri::AppHandle,
+    book_id: String,
+    new_path: String,
+) -> Result<RebindReport, String> {
+    tauri::async_runtime::spawn_blocking(move || rebind(&handle, &book_id, &new_path))
+        .await
+        .map_err(|e| e.to_string())?
+}
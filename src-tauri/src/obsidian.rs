@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::lookup_history::load_lookup_history;
+use crate::{app_state, load_vocabulary, RecentBook};
+
+// Characters Obsidian or the file system do not allow in note names.
+const FILE_NAME_RESERVED: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']'];
+// Frontmatter keys written by the export; any other key belongs to the user.
+const MANAGED_KEYS: &[&str] = &["pdfread_id", "title", "author", "progress", "last_page", "total_pages", "last_read"];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObsidianHighlight {
+    text: String,
+    page: Option<u32>,
+    note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ObsidianExportOptions {
+    // Folder inside the vault for the book notes.
+    folder: String,
+    // Books to export; all recent books when empty.
+    book_ids: Vec<String>,
+    // Highlights and notes by book id, as kept by the reader.
+    highlights: HashMap<String, Vec<ObsidianHighlight>>,
+    include_vocabulary: bool,
+    include_stats: bool,
+}
+
+impl Default for ObsidianExportOptions {
+    fn default() -> Self {
+        Self {
+            folder: "PDFRead".to_string(),
+            book_ids: Vec::new(),
+            highlights: HashMap::new(),
+            include_vocabulary: true,
+            include_stats: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ObsidianExportResult {
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+}
+
+fn section_start(name: &str) -> String {
+    format!("<!-- pdfread:{} -->", name)
+}
+
+fn section_end(name: &str) -> String {
+    format!("<!-- /pdfread:{} -->", name)
+}
+
+fn sanitize_file_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| if FILE_NAME_RESERVED.contains(&c) { ' ' } else { c })
+        .collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name.chars().take(100).collect()
+    }
+}
+
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+// (frontmatter lines, body), for notes that open with a `---` block.
+fn split_frontmatter(note: &str) -> (Vec<String>, &str) {
+    let Some(rest) = note.strip_prefix("---\n") else {
+        return (Vec::new(), note);
+    };
+    let end = match rest.find("\n---\n") {
+        Some(end) => end,
+        None if rest.ends_with("\n---") => rest.len() - 4,
+        None => return (Vec::new(), note),
+    };
+    let body = rest.get(end + 5..).unwrap_or("");
+    (rest[..end].lines().map(str::to_string).collect(), body)
+}
+
+fn frontmatter_id(note: &str) -> Option<String> {
+    split_frontmatter(note).0.iter().find_map(|line| {
+        let value = line.strip_prefix("pdfread_id:")?.trim();
+        Some(serde_json::from_str::<String>(value).unwrap_or_else(|_| value.to_string()))
+    })
+}
+
+// Existing notes in the export folder by book id, so renamed books and renamed notes keep
+// being updated in place.
+fn existing_notes(folder: &Path) -> HashMap<String, PathBuf> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return HashMap::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let note = fs::read_to_string(&path).ok()?;
+            Some((frontmatter_id(&note)?, path))
+        })
+        .collect()
+}
+
+fn render_frontmatter(book: &RecentBook) -> BTreeMap<&'static str, String> {
+    let mut values = BTreeMap::new();
+    values.insert("pdfread_id", yaml_string(&book.id));
+    values.insert("title", yaml_string(&book.title));
+    if let Some(author) = &book.author {
+        values.insert("author", yaml_string(author));
+    }
+    values.insert("progress", format!("{:.0}", book.progress));
+    values.insert("last_page", book.last_page.to_string());
+    values.insert("total_pages", book.total_pages.to_string());
+    values.insert("last_read", book.last_opened_at.format("%Y-%m-%d").to_string());
+    values
+}
+
+fn render_highlights(highlights: &[ObsidianHighlight]) -> String {
+    if highlights.is_empty() {
+        return "## Highlights\n\n_No highlights yet._\n".to_string();
+    }
+    let mut out = String::from("## Highlights\n\n");
+    for highlight in highlights {
+        let quote = highlight.text.trim().replace('\n', "\n> ");
+        out.push_str(&format!("> {}\n", quote));
+        if let Some(page) = highlight.page {
+            out.push_str(&format!(">\n> — p. {}\n", page));
+        }
+        if let Some(note) = highlight.note.as_deref().filter(|note| !note.trim().is_empty()) {
+            out.push_str(&format!("\n{}\n", note.trim()));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn render_vocabulary(words: &[(String, Option<String>)]) -> String {
+    if words.is_empty() {
+        return "## Vocabulary\n\n_No words looked up in this book yet._\n".to_string();
+    }
+    let mut out = String::from("## Vocabulary\n\n");
+    for (word, meaning) in words {
+        match meaning {
+            Some(meaning) => out.push_str(&format!("- **{}**: {}\n", word, meaning)),
+            None => out.push_str(&format!("- **{}**\n", word)),
+        }
+    }
+    out
+}
+
+fn render_stats(book: &RecentBook, lookups: usize, highlights: usize) -> String {
+    format!(
+        "## Reading stats\n\n- Progress: {:.0}%\n- Page: {} of {}\n- Last read: {}\n- Lookups: {}\n- Highlights: {}\n",
+        book.progress,
+        book.last_page,
+        book.total_pages,
+        book.last_opened_at.format("%Y-%m-%d %H:%M"),
+        lookups,
+        highlights
+    )
+}
+
+// Replaces the managed frontmatter keys and sections of `note`, appending any that are
+// missing. Everything outside them is left as the user wrote it.
+fn merge_note(note: &str, frontmatter: &BTreeMap<&str, String>, sections: &[(&str, String)]) -> String {
+    let (lines, body) = split_frontmatter(note);
+    let mut merged: Vec<String> = lines
+        .into_iter()
+        .filter(|line| {
+            let key = line.split(':').next().unwrap_or("").trim();
+            !MANAGED_KEYS.contains(&key)
+        })
+        .collect();
+    for key in MANAGED_KEYS {
+        if let Some(value) = frontmatter.get(key) {
+            merged.push(format!("{}: {}", key, value));
+        }
+    }
+
+    let mut body = body.to_string();
+    for (name, content) in sections {
+        let start = section_start(name);
+        let end = section_end(name);
+        let block = format!("{}\n{}{}", start, content, end);
+        match (body.find(&start), body.find(&end)) {
+            (Some(from), Some(to)) if from < to => body.replace_range(from..to + end.len(), &block),
+            _ => {
+                if !body.is_empty() && !body.ends_with("\n\n") {
+                    body.push_str(if body.ends_with('\n') { "\n" } else { "\n\n" });
+                }
+                body.push_str(&block);
+                body.push('\n');
+            }
+        }
+    }
+    format!("---\n{}\n---\n{}", merged.join("\n"), body)
+}
+
+// Writes one Markdown note per book into `<vault>/<folder>`. Notes are found again by the
+// `pdfread_id` in their frontmatter, and only the managed keys and sections are rewritten,
+// so the user's own notes around them survive re-exports.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_to_obsidian(
+    handle: tauri::AppHandle,
+    vault_path: String,
+    options: Option<ObsidianExportOptions>,
+) -> Result<ObsidianExportResult, String> {
+    let options = options.unwrap_or_default();
+    let vault = PathBuf::from(&vault_path);
+    if !vault.is_dir() {
+        return Err(format!("Vault folder not found: {}", vault_path));
+    }
+    let folder = vault.join(options.folder.trim_matches(['/', '\\']));
+    fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+
+    let books: Vec<RecentBook> = handle.state::<app_state::AppState>().recent_books.read(&handle, |data| {
+        data.books
+            .iter()
+            .filter(|b| options.book_ids.is_empty() || options.book_ids.contains(&b.id))
+            .cloned()
+            .collect()
+    })?;
+    let history = load_lookup_history(&handle)?;
+    let vocabulary = load_vocabulary(&handle)?;
+    let meanings: HashMap<String, String> = vocabulary
+        .entries
+        .iter()
+        .map(|entry| {
+            let meaning: Vec<String> =
+                entry.definitions.iter().map(|d| format!("{} {}", d.pos, d.meanings)).collect();
+            (entry.word.to_lowercase(), meaning.join("; "))
+        })
+        .collect();
+
+    let mut notes = existing_notes(&folder);
+    let mut result = ObsidianExportResult::default();
+    for book in &books {
+        let highlights = options.highlights.get(&book.id).map(Vec::as_slice).unwrap_or(&[]);
+        let lookups: Vec<&str> = history
+            .records
+            .iter()
+            .filter(|record| record.book_id.as_deref() == Some(&book.id))
+            .map(|record| record.text.as_str())
+            .collect();
+
+        let mut sections = vec![("highlights", render_highlights(highlights))];
+        if options.include_vocabulary {
+            let mut words: Vec<(String, Option<String>)> = Vec::new();
+            for text in &lookups {
+                let key = text.to_lowercase();
+                if !words.iter().any(|(word, _)| word.to_lowercase() == key) {
+                    words.push((text.to_string(), meanings.get(&key).cloned()));
+                }
+            }
+            sections.push(("vocabulary", render_vocabulary(&words)));
+        }
+        if options.include_stats {
+            sections.push(("stats", render_stats(book, lookups.len(), highlights.len())));
+        }
+
+        let path = match notes.remove(&book.id) {
+            Some(path) => path,
+            None => {
+                let name = sanitize_file_name(&book.title);
+                let mut path = folder.join(format!("{}.md", name));
+                if path.exists() {
+                    path = folder.join(format!("{} ({}).md", name, book.id));
+                }
+                path
+            }
+        };
+        let previous = fs::read_to_string(&path).ok();
+        let note = merge_note(previous.as_deref().unwrap_or(""), &render_frontmatter(book), &sections);
+        match previous {
+            Some(previous) if previous == note => result.unchanged += 1,
+            Some(_) => {
+                fs::write(&path, note).map_err(|e| e.to_string())?;
+                result.updated += 1;
+            }
+            None => {
+                fs::write(&path, note).map_err(|e| e.to_string())?;
+                result.created += 1;
+            }
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::restricted_mode;
+use crate::settings::{load_settings, save_settings};
+use crate::{coded_error, ERR_OFFLINE};
+
+const PROBE_URL: &str = "https://openrouter.ai";
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Whether the provider looked unreachable at the last probe or request.
+#[derive(Default)]
+pub struct Connectivity {
+    detected_offline: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OfflineState {
+    offline: bool,
+    // Turned on by hand in settings.
+    manual: bool,
+    // The provider could not be reached.
+    detected: bool,
+}
+
+fn manual_offline(handle: &tauri::AppHandle) -> bool {
+    match load_settings(handle) {
+        Ok(settings) => settings.offline_mode,
+        Err(e) => {
+            log::error!("Failed to read offline mode setting: {}", e);
+            false
+        }
+    }
+}
+
+fn offline_state(handle: &tauri::AppHandle) -> OfflineState {
+    let manual = manual_offline(handle);
+    let detected = handle.state::<Connectivity>().detected_offline.load(Ordering::Relaxed);
+    OfflineState { offline: manual || detected, manual, detected }
+}
+
+pub fn is_offline(handle: &tauri::AppHandle) -> bool {
+    offline_state(handle).offline
+}
+
+pub fn is_offline_error(message: &str) -> bool {
+    message.starts_with(ERR_OFFLINE)
+}
+
+// Refuses network requests while offline, switched on or detected. Every model request goes
+// through here, so commands fall back to what is cached or fail with OFFLINE.
+pub fn ensure_online(handle: &tauri::AppHandle) -> Result<(), String> {
+    let state = offline_state(handle);
+    if state.manual {
+        return Err(coded_error(ERR_OFFLINE, "Offline mode is on; only cached results are available."));
+    }
+    if state.detected {
+        return Err(coded_error(ERR_OFFLINE, "No connection; only cached results are available."));
+    }
+    Ok(())
+}
+
+fn set_detected(handle: &tauri::AppHandle, offline: bool) {
+    let previous = handle.state::<Connectivity>().detected_offline.swap(offline, Ordering::Relaxed);
+    if previous != offline {
+        let _ = handle.emit("offline-changed", offline_state(handle));
+    }
+}
+
+// Requests that could not connect mark the provider unreachable right away, rather than at
+// the next probe.
+pub fn note_request_error(handle: &tauri::AppHandle, error: &reqwest::Error) {
+    if error.is_connect() || error.is_timeout() {
+        set_detected(handle, true);
+    }
+}
+
+async fn probe() -> bool {
+    let Ok(client) = reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() else {
+        return false;
+    };
+    client.head(PROBE_URL).send().await.is_ok()
+}
+
+// Checks now and then whether the provider can be reached, and emits `offline-changed` when
+// that changes. Nothing is sent while offline mode is on by hand, not even the probe.
+pub fn start(handle: &tauri::AppHandle) {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if !manual_offline(&handle) {
+                let reachable = probe().await;
+                set_detected(&handle, !reachable);
+            }
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_offline_state(handle: tauri::AppHandle) -> Result<OfflineState, String> {
+    Ok(offline_state(&handle))
+}
+
+// Switches offline mode by hand, e.g. before a flight. While on, nothing is sent to the
+// provider even when a connection is available.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_offline_mode(handle: tauri::AppHandle, enabled: bool) -> Result<OfflineState, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing settings")?;
+    let mut settings = load_settings(&handle)?;
+    settings.offline_mode = enabled;
+    save_settings(&handle, &settings)?;
+    let state = offline_state(&handle);
+    let _ = handle.emit("offline-changed", state.clone());
+    Ok(state)
+}
@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::model_catalog::find_model;
+use crate::quarantine::parse_or_quarantine;
+use crate::quota;
+use crate::{
+    app_config_dir, load_openrouter_credentials, load_openrouter_key, request_openrouter, test_openrouter_key,
+    TargetLanguage,
+};
+
+// Text translated by the test translation in `validate_setup`.
+const TEST_SENTENCE: &str = "Reading is to the mind what exercise is to the body.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    Welcome,
+    ApiKey,
+    Model,
+    TargetLanguage,
+    TestTranslation,
+}
+
+// In the order the guided setup walks through them.
+const STEPS: &[OnboardingStep] = &[
+    OnboardingStep::Welcome,
+    OnboardingStep::ApiKey,
+    OnboardingStep::Model,
+    OnboardingStep::TargetLanguage,
+    OnboardingStep::TestTranslation,
+];
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct OnboardingData {
+    completed: Vec<OnboardingStep>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepState {
+    step: OnboardingStep,
+    done: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OnboardingState {
+    steps: Vec<StepState>,
+    // The first step not done yet; None once onboarding is finished.
+    current: Option<OnboardingStep>,
+    finished: bool,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetupCheck {
+    name: &'static str,
+    ok: bool,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetupReport {
+    ok: bool,
+    checks: Vec<SetupCheck>,
+    // What the test translation came back as, to show the user.
+    sample: Option<String>,
+}
+
+fn onboarding_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("onboarding.json"))
+}
+
+fn load_onboarding(handle: &tauri::AppHandle) -> Result<OnboardingData, String> {
+    let path = onboarding_file_path(handle)?;
+    if !path.exists() {
+        return Ok(OnboardingData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_onboarding(handle: &tauri::AppHandle, data: &OnboardingData) -> Result<(), String> {
+    let path = onboarding_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// A saved key counts as the key step done, so users who set one up before onboarding
+// existed are not asked again.
+fn onboarding_state(handle: &tauri::AppHandle, data: &OnboardingData) -> OnboardingState {
+    let has_key = load_openrouter_key(handle).is_ok();
+    let steps: Vec<StepState> = STEPS
+        .iter()
+        .map(|&step| StepState {
+            step,
+            done: data.completed.contains(&step) || (step == OnboardingStep::ApiKey && has_key),
+        })
+        .collect();
+    let current = steps.iter().find(|s| !s.done).map(|s| s.step);
+    OnboardingState {
+        steps,
+        current: current.filter(|_| data.finished_at.is_none()),
+        finished: data.finished_at.is_some(),
+        finished_at: data.finished_at,
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_onboarding_state(handle: tauri::AppHandle) -> Result<OnboardingState, String> {
+    let data = load_onboarding(&handle)?;
+    Ok(onboarding_state(&handle, &data))
+}
+
+// Marks a step done. Onboarding finishes once every step is.
+#[tauri::command(rename_all = "camelCase")]
+pub fn complete_onboarding_step(handle: tauri::AppHandle, step: OnboardingStep) -> Result<OnboardingState, String> {
+    let mut data = load_onboarding(&handle)?;
+    if !data.completed.contains(&step) {
+        data.completed.push(step);
+    }
+    let state = onboarding_state(&handle, &data);
+    if data.finished_at.is_none() && state.steps.iter().all(|s| s.done) {
+        data.finished_at = Some(Utc::now());
+    }
+    save_onboarding(&handle, &data)?;
+    Ok(onboarding_state(&handle, &data))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn reset_onboarding(handle: tauri::AppHandle) -> Result<OnboardingState, String> {
+    let data = OnboardingData::default();
+    save_onboarding(&handle, &data)?;
+    Ok(onboarding_state(&handle, &data))
+}
+
+fn check(name: &'static str, result: Result<String, String>) -> SetupCheck {
+    match result {
+        Ok(message) => SetupCheck { name, ok: true, message },
+        Err(message) => SetupCheck { name, ok: false, message },
+    }
+}
+
+// Checks the whole setup in order: a key is saved, OpenRouter accepts it, the model is
+// served, and a short test translation comes back. Later checks are skipped once one fails.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn validate_setup(
+    handle: tauri::AppHandle,
+    model: String,
+    target_language: TargetLanguage,
+) -> Result<SetupReport, String> {
+    let mut checks = Vec::new();
+    let mut sample = None;
+
+    checks.push(check("api_key", load_openrouter_key(&handle).map(|_| "An API key is saved.".to_string())));
+    if checks.iter().all(|c| c.ok) {
+        let result = test_openrouter_key(handle.clone()).await;
+        checks.push(check("key_accepted", result.map(|_| "OpenRouter accepted the key.".to_string())));
+    }
+    if checks.iter().all(|c| c.ok) {
+        let result = match find_model(&handle, &model).await {
+            Ok(Some(_)) => Ok(format!("{} is available.", model)),
+            Ok(None) => Err(format!("OpenRouter does not list the model {}.", model)),
+            Err(e) => Err(format!("Could not load the model list: {}", e)),
+        };
+        checks.push(check("model", result));
+    }
+    if checks.iter().all(|c| c.ok) {
+        let credentials = load_openrouter_credentials(&handle)?.for_feature(quota::FEATURE_SETUP).pin_temperature();
+        let system_prompt = "You are a translation engine. Reply with the translation only.";
+        let user_prompt = format!("Translate into {}:\n{}", target_language.prompt_tag(), TEST_SENTENCE);
+        let result = match request_openrouter(&credentials, &model, 0.0, system_prompt, &user_prompt).await {
+            Ok(text) if !text.trim().is_empty() => {
+                sample = Some(text.trim().to_string());
+                Ok("The test translation came back.".to_string())
+            }
+            Ok(_) => Err("The model returned an empty translation.".to_string()),
+            Err(e) => Err(e),
+        };
+        checks.push(check("test_translation", result));
+    }
+
+    Ok(SetupReport { ok: checks.len() == 4 && checks.iter().all(|c| c.ok), checks, sample })
+}
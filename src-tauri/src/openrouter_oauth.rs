@@ -0,0 +1,177 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use axum::extract::{Query, State};
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tokio::sync::oneshot;
+
+use crate::restricted_mode;
+use crate::save_openrouter_key;
+
+const AUTH_URL: &str = "https://openrouter.ai/auth";
+const KEY_EXCHANGE_URL: &str = "https://openrouter.ai/api/v1/auth/keys";
+// How long the loopback listener waits for the browser to come back.
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const CALLBACK_PAGE: &str = "<!doctype html><meta charset=\"utf-8\"><title>PDFRead</title>\
+    <p>PDFRead is connected to OpenRouter. You can close this window.</p>";
+const CALLBACK_ERROR_PAGE: &str = "<!doctype html><meta charset=\"utf-8\"><title>PDFRead</title>\
+    <p>OpenRouter did not authorize PDFRead. Return to the app to try again.</p>";
+
+// The sign-in in progress, if any; starting another or cancelling stops its listener.
+#[derive(Default)]
+pub struct OpenRouterOAuth {
+    cancel: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OAuthStart {
+    // Opened in the browser already; shown in case that did not work.
+    auth_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct OAuthFailed {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackQuery {
+    code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyExchangeResponse {
+    key: String,
+}
+
+type CodeSender = Arc<Mutex<Option<oneshot::Sender<Option<String>>>>>;
+
+fn base64_url(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+        for i in 0..chunk.len() + 1 {
+            out.push(ALPHABET[((n >> (18 - 6 * i)) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+// A PKCE verifier and its S256 challenge.
+fn pkce_pair() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let verifier = base64_url(&bytes);
+    let challenge = base64_url(&Sha256::digest(verifier.as_bytes()));
+    (verifier, challenge)
+}
+
+async fn callback(State(sender): State<CodeSender>, Query(query): Query<CallbackQuery>) -> Html<&'static str> {
+    let page = if query.code.is_some() { CALLBACK_PAGE } else { CALLBACK_ERROR_PAGE };
+    if let Some(sender) = sender.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        let _ = sender.send(query.code);
+    }
+    Html(page)
+}
+
+async fn exchange_code(code: &str, verifier: &str) -> Result<String, String> {
+    let body = serde_json::json!({ "code": code, "code_verifier": verifier, "code_challenge_method": "S256" });
+    let response = reqwest::Client::new()
+        .post(KEY_EXCHANGE_URL)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("OpenRouter error: {} {}", status, text));
+    }
+    Ok(response.json::<KeyExchangeResponse>().await.map_err(|e| e.to_string())?.key)
+}
+
+// Starts OpenRouter's PKCE sign-in: opens the authorization page in the browser and waits on
+// a loopback listener for it to redirect back with a code, which is exchanged for an API key
+// and saved like a pasted one. Ends with `openrouter-oauth-finished` or
+// `openrouter-oauth-failed`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn begin_openrouter_oauth(handle: tauri::AppHandle) -> Result<OAuthStart, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing the API key")?;
+    cancel_openrouter_oauth(handle.clone())?;
+
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+        .await
+        .map_err(|e| format!("Failed to start the sign-in listener: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let (verifier, challenge) = pkce_pair();
+    let callback_url = format!("http://localhost:{}/callback", port);
+    let auth_url = reqwest::Url::parse_with_params(
+        AUTH_URL,
+        [("callback_url", callback_url.as_str()), ("code_challenge", &challenge), ("code_challenge_method", "S256")],
+    )
+    .map_err(|e| e.to_string())?
+    .to_string();
+
+    let (code_sender, code_receiver) = oneshot::channel::<Option<String>>();
+    let (cancel, cancelled) = oneshot::channel::<()>();
+    let app = Router::new()
+        .route("/callback", get(callback))
+        .with_state(Arc::new(Mutex::new(Some(code_sender))));
+    let (stop_server, server_stopped) = oneshot::channel::<()>();
+    tauri::async_runtime::spawn(async move {
+        let server = axum::serve(listener, app).with_graceful_shutdown(async {
+            let _ = server_stopped.await;
+        });
+        if let Err(e) = server.await {
+            log::warn!("OpenRouter sign-in listener stopped: {}", e);
+        }
+    });
+
+    let flow_handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = tokio::select! {
+            code = tokio::time::timeout(CALLBACK_TIMEOUT, code_receiver) => match code {
+                Ok(Ok(Some(code))) => match exchange_code(&code, &verifier).await {
+                    Ok(key) => save_openrouter_key(flow_handle.clone(), key),
+                    Err(e) => Err(e),
+                },
+                Ok(Ok(None)) => Err("OpenRouter sign-in was declined.".to_string()),
+                Ok(Err(_)) => Err("OpenRouter sign-in was interrupted.".to_string()),
+                Err(_) => Err("OpenRouter sign-in timed out.".to_string()),
+            },
+            _ = cancelled => {
+                let _ = stop_server.send(());
+                return;
+            }
+        };
+        let _ = stop_server.send(());
+        let _ = match result {
+            Ok(()) => flow_handle.emit("openrouter-oauth-finished", ()),
+            Err(message) => flow_handle.emit("openrouter-oauth-failed", OAuthFailed { message }),
+        };
+    });
+    *handle.state::<OpenRouterOAuth>().cancel.lock().unwrap_or_else(|e| e.into_inner()) = Some(cancel);
+
+    if let Err(e) = handle.opener().open_url(&auth_url, None::<&str>) {
+        log::error!("Failed to open the browser for OpenRouter sign-in: {}", e);
+    }
+    Ok(OAuthStart { auth_url })
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn cancel_openrouter_oauth(handle: tauri::AppHandle) -> Result<(), String> {
+    let oauth = handle.state::<OpenRouterOAuth>();
+    if let Some(cancel) = oauth.cancel.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        let _ = cancel.send(());
+    }
+    Ok(())
+}
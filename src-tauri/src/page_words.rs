@@ -0,0 +1,109 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::lookup_history::load_lookup_history;
+use crate::vocab_index::{lemma_candidates, VocabularyIndex};
+use crate::word_lists::{load_imported_word_sets, load_word_set, IGNORED_WORDS_FILE, KNOWN_WORDS_FILE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordStatus {
+    InVocabulary,
+    Known,
+    LookedUp,
+    Ignored,
+    Unknown,
+}
+
+// A word token with UTF-16 offsets into the page text, matching JavaScript string indices.
+#[derive(Debug, Clone, Serialize)]
+pub struct PageWord {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub status: WordStatus,
+    // Imported word lists (see `import_wordlist`) that contain this word.
+    pub lists: Vec<String>,
+}
+
+pub struct WordToken<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Splits text on Unicode word boundaries, keeping only tokens that contain a letter.
+pub fn tokenize_words(text: &str) -> Vec<WordToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut utf16_offset = 0;
+    let mut byte_offset = 0;
+    for (start, word) in text.split_word_bound_indices() {
+        utf16_offset += text[byte_offset..start].encode_utf16().count();
+        let length = word.encode_utf16().count();
+        if word.chars().any(char::is_alphabetic) {
+            tokens.push(WordToken {
+                text: word,
+                start: utf16_offset,
+                end: utf16_offset + length,
+            });
+        }
+        utf16_offset += length;
+        byte_offset = start + word.len();
+    }
+    tokens
+}
+
+pub fn matches_any(word: &str, set: &HashSet<String>) -> bool {
+    lemma_candidates(word).iter().any(|form| set.contains(form))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn classify_page_words(
+    handle: tauri::AppHandle,
+    index: tauri::State<'_, VocabularyIndex>,
+    book_id: String,
+    page_text: String,
+    wordlists: Option<Vec<String>>,
+) -> Result<Vec<PageWord>, String> {
+    let imported = load_imported_word_sets(&handle, wordlists.as_deref())?;
+    let ignored = load_word_set(&handle, IGNORED_WORDS_FILE)?;
+    let known = load_word_set(&handle, KNOWN_WORDS_FILE)?;
+    // Lookups made while reading this book, or without a book attached.
+    let looked_up: HashSet<String> = load_lookup_history(&handle)?
+        .records
+        .into_iter()
+        .filter(|r| r.book_id.as_deref().is_none_or(|id| id == book_id))
+        .map(|r| r.text.to_lowercase())
+        .collect();
+
+    let mut words = Vec::new();
+    for token in tokenize_words(&page_text) {
+        let lower = token.text.to_lowercase();
+        let status = if ignored.contains(&lower) {
+            WordStatus::Ignored
+        } else if index.find(&handle, token.text)?.is_some() {
+            WordStatus::InVocabulary
+        } else if matches_any(token.text, &known) {
+            WordStatus::Known
+        } else if matches_any(token.text, &looked_up) {
+            WordStatus::LookedUp
+        } else {
+            WordStatus::Unknown
+        };
+        let mut lists: Vec<String> = imported
+            .iter()
+            .filter(|(_, set)| matches_any(token.text, set))
+            .map(|(name, _)| name.clone())
+            .collect();
+        lists.sort();
+        words.push(PageWord {
+            text: token.text.to_string(),
+            start: token.start,
+            end: token.end,
+            status,
+            lists,
+        });
+    }
+    Ok(words)
+}
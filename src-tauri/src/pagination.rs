@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::articles::ARTICLE_FILE_TYPE;
+use crate::book_text::{load_book_text, save_book_text, BookPage, BookText};
+use crate::quarantine::parse_or_quarantine;
+use crate::{book_data_file_path, update_recent_books};
+
+// Documents whose pages are made here rather than taken from the file.
+const PAGINATED_FILE_TYPES: &[&str] = &[ARTICLE_FILE_TYPE];
+// `chars_per_page` is for text at this size; larger text fits less on a page.
+const BASE_FONT_SIZE: f32 = 16.0;
+const MIN_CHARS_PER_PAGE: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaginationSettings {
+    // Characters a page holds at `BASE_FONT_SIZE`.
+    chars_per_page: usize,
+    // Font size, in pixels, the reader lays the pages out at.
+    font_size: f32,
+}
+
+impl Default for PaginationSettings {
+    fn default() -> Self {
+        Self { chars_per_page: 2000, font_size: BASE_FONT_SIZE }
+    }
+}
+
+impl PaginationSettings {
+    fn capacity(&self) -> usize {
+        let scale = (BASE_FONT_SIZE / self.font_size.clamp(8.0, 64.0)).powi(2);
+        ((self.chars_per_page as f32 * scale) as usize).max(MIN_CHARS_PER_PAGE)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Repagination {
+    settings: PaginationSettings,
+    total_pages: u32,
+    last_page: u32,
+    // New page for each old page, in old page order, for moving positions the reader keeps
+    // (bookmarks, highlights) to the new pages.
+    page_map: Vec<u32>,
+}
+
+fn settings_file_path(handle: &tauri::AppHandle, book_id: &str) -> Result<PathBuf, String> {
+    book_data_file_path(handle, "pagination", book_id)
+}
+
+fn load_pagination_settings(handle: &tauri::AppHandle, book_id: &str) -> Result<PaginationSettings, String> {
+    let path = settings_file_path(handle, book_id)?;
+    if !path.exists() {
+        return Ok(PaginationSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_pagination_settings(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    settings: &PaginationSettings,
+) -> Result<(), String> {
+    let path = settings_file_path(handle, book_id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// Packs the paragraphs, in order, into pages of about `capacity` characters. Paragraphs are
+// never split, so their sids, and the translations cached under them, stay the same; one
+// longer than a page gets a page to itself. The same text and settings always give the same
+// pages. A page's title moves with its first paragraph.
+fn paginate(text: &BookText, capacity: usize) -> (Vec<BookPage>, HashMap<String, u32>) {
+    let mut pages: Vec<BookPage> = Vec::new();
+    let mut used = 0;
+    let mut page_of_sid = HashMap::new();
+    for old in &text.pages {
+        let mut extracted = old.extracted.iter();
+        for (index, paragraph) in old.paragraphs.iter().enumerate() {
+            let length = paragraph.text.chars().count();
+            let title = if index == 0 { old.title.clone() } else { None };
+            let starts_page = match pages.last() {
+                None => true,
+                Some(page) => !page.paragraphs.is_empty() && (used + length > capacity || title.is_some()),
+            };
+            if starts_page {
+                pages.push(BookPage {
+                    page: pages.len() as u32 + 1,
+                    title: None,
+                    paragraphs: Vec::new(),
+                    extracted: Vec::new(),
+                });
+                used = 0;
+            }
+            if let Some(page) = pages.last_mut() {
+                page.title = page.title.take().or(title);
+                page_of_sid.insert(paragraph.sid.clone(), page.page);
+                page.paragraphs.push(paragraph.clone());
+                used += length;
+                // The extracted paragraphs up to this one go with it, including any cleanup dropped.
+                if old.extracted.iter().any(|p| p.sid == paragraph.sid) {
+                    for source in extracted.by_ref() {
+                        page.extracted.push(source.clone());
+                        if source.sid == paragraph.sid {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(page) = pages.last_mut() {
+            page.extracted.extend(extracted.cloned());
+        }
+    }
+    (pages, page_of_sid)
+}
+
+// The page each paragraph of a document paged here is on now. Paragraphs keep their sids, and
+// the translations cached under them, when pages change, so a sid's `p<page>` part only tells
+// the page it was first stored on. None for PDF and EPUB books, whose sids stay accurate.
+pub fn paragraph_pages(handle: &tauri::AppHandle, book_id: &str) -> Option<HashMap<String, u32>> {
+    let paginated = handle
+        .state::<AppState>()
+        .recent_books
+        .read(handle, |data| {
+            data.books.iter().any(|b| b.id == book_id && PAGINATED_FILE_TYPES.contains(&b.file_type.as_str()))
+        })
+        .unwrap_or(false);
+    if !paginated {
+        return None;
+    }
+    let text = load_book_text(handle, book_id).ok()?;
+    Some(text.pages.iter().flat_map(|page| page.paragraphs.iter().map(|p| (p.sid.clone(), page.page))).collect())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_pagination_settings(handle: tauri::AppHandle, book_id: String) -> Result<PaginationSettings, String> {
+    load_pagination_settings(&handle, &book_id)
+}
+
+// Pages an article again with new settings and moves the reading position to
+// the page now holding the paragraph it was at. PDF and EPUB pages come from the file and are
+// left alone.
+#[tauri::command(rename_all = "camelCase")]
+pub fn repaginate_book(
+    handle: tauri::AppHandle,
+    book_id: String,
+    settings: PaginationSettings,
+) -> Result<Repagination, String> {
+    let book = handle
+        .state::<AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+        .ok_or_else(|| format!("Book not found: {}", book_id))?;
+    if !PAGINATED_FILE_TYPES.contains(&book.file_type.as_str()) {
+        return Err(format!("{} pages come from the file and cannot be changed.", book.file_type.to_uppercase()));
+    }
+    let mut text = load_book_text(&handle, &book_id)?;
+    text.pages.sort_by_key(|p| p.page);
+    let (pages, page_of_sid) = paginate(&text, settings.capacity());
+    if pages.is_empty() {
+        return Err(format!("No extracted text stored for book: {}", book_id));
+    }
+    let total_pages = pages.len() as u32;
+    // An old page maps to the new page holding its first paragraph; an empty one to the page
+    // of the one before it.
+    let mut page_map = Vec::new();
+    let mut previous = 1;
+    for old in 1..=text.pages.last().map_or(0, |p| p.page) {
+        let first = text.page(old).and_then(|p| p.paragraphs.first());
+        previous = first.and_then(|p| page_of_sid.get(&p.sid).copied()).unwrap_or(previous);
+        page_map.push(previous);
+    }
+    let last_page = page_map.get(book.last_page.max(1) as usize - 1).copied().unwrap_or(total_pages);
+
+    text.pages = pages;
+    save_book_text(&handle, &book_id, &text)?;
+    save_pagination_settings(&handle, &book_id, &settings)?;
+    update_recent_books(&handle, |data| {
+        if let Some(book) = data.books.iter_mut().find(|b| b.id == book_id) {
+            book.total_pages = total_pages;
+            book.last_page = last_page;
+            if book.progress < 100.0 {
+                book.progress = last_page as f32 / total_pages as f32 * 100.0;
+            }
+        }
+    })?;
+    Ok(Repagination { settings, total_pages, last_page, page_map })
+}
@@ -0,0 +1,271 @@
+use rhai::module_resolvers::FileModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Module, ModuleResolver, Position, Scope, Shared};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::book_text::load_book_text;
+use crate::private_books;
+use crate::quota;
+use crate::restricted_mode;
+use crate::{app_config_dir, ensure_cloud_allowed, load_openrouter_credentials, request_openrouter};
+
+const PLUGIN_EXTENSION: &str = "rhai";
+// Limits that stop a runaway script without getting in the way of real ones.
+const MAX_OPERATIONS: u64 = 100_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_STRING_SIZE: usize = 16 * 1024 * 1024;
+const MAX_COLLECTION_SIZE: usize = 1_000_000;
+// Model requests from plugins use this unless the sampling preset says otherwise.
+const PLUGIN_TEMPERATURE: f32 = 0.3;
+
+#[derive(Debug, Serialize)]
+pub struct PluginInfo {
+    name: String,
+    // The comment lines the script starts with.
+    description: Option<String>,
+    path: String,
+}
+
+fn plugins_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("plugins"))
+}
+
+// Files a plugin writes go here, one folder per plugin.
+fn exports_dir(handle: &tauri::AppHandle, plugin: &str) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("plugin_exports").join(plugin))
+}
+
+fn description(script: &str) -> Option<String> {
+    let lines: Vec<&str> = script
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.starts_with("//"))
+        .map(|line| line.trim_start_matches('/').trim())
+        .collect();
+    let text = lines.join(" ").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn plugin_path(handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid || name.starts_with('.') {
+        return Err(format!("Invalid plugin name: {}", name));
+    }
+    let path = plugins_dir(handle)?.join(format!("{}.{}", name, PLUGIN_EXTENSION));
+    if !path.is_file() {
+        return Err(format!("Plugin not found: {}", name));
+    }
+    Ok(path)
+}
+
+fn script_error(message: impl Into<String>) -> Box<EvalAltResult> {
+    message.into().into()
+}
+
+// Resolves `import` to scripts in the plugins folder. Paths are resolved, links included, and
+// anything that ends up outside the folder ("../", absolute paths) is not found.
+struct PluginModules {
+    dir: PathBuf,
+    files: FileModuleResolver,
+}
+
+impl PluginModules {
+    fn new(dir: PathBuf) -> Result<Self, String> {
+        let dir = dir.canonicalize().map_err(|e| e.to_string())?;
+        Ok(Self { files: FileModuleResolver::new_with_path(&dir), dir })
+    }
+}
+
+impl ModuleResolver for PluginModules {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        source: Option<&str>,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        let file = self.files.get_file_path(path, None).canonicalize().ok();
+        match file.as_deref().filter(|file| file.starts_with(&self.dir)).and_then(Path::to_str) {
+            Some(file) => self.files.resolve(engine, source, file, pos),
+            None => Err(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos).into()),
+        }
+    }
+}
+
+// Private books, unlocked or not, never reach a model through a plugin; other books only
+// when they may be sent to cloud providers.
+fn ensure_plugin_may_send(handle: &tauri::AppHandle, book_id: &str) -> Result<(), String> {
+    if private_books::is_private(handle, book_id)? {
+        return Err(format!("The plugin read private book {}; it cannot send anything to a model.", book_id));
+    }
+    ensure_cloud_allowed(handle, book_id)
+}
+
+// Only a bare file name is accepted, so a plugin cannot write outside its exports folder.
+fn write_export(handle: &tauri::AppHandle, plugin: &str, file_name: &str, content: &str) -> Result<String, String> {
+    let bare = Path::new(file_name).file_name().is_some_and(|name| name == file_name);
+    if !bare || file_name.starts_with('.') {
+        return Err(format!("Invalid export file name: {}", file_name));
+    }
+    let dir = exports_dir(handle, plugin)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(file_name);
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// The engine with the API plugins get, and nothing more: the library, books' extracted text,
+// the model the user picked and the plugin's own exports folder. Scripts have no other file,
+// network or process access.
+fn engine(handle: &tauri::AppHandle, plugin: &str, model: Option<String>) -> Result<Engine, String> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+    engine.disable_symbol("eval");
+    // `import` loads other scripts from the plugins folder only.
+    engine.set_module_resolver(PluginModules::new(plugins_dir(handle)?)?);
+    let name = plugin.to_string();
+    engine.on_print(move |text| log::info!("[plugin {}] {}", name, text));
+    let name = plugin.to_string();
+    engine.on_debug(move |text, _, position| log::debug!("[plugin {}] {} {}", name, position, text));
+
+    // Books whose text the script has read; it may not send anything to a model once it has
+    // read a private one.
+    let read_books: Arc<Mutex<HashSet<String>>> = Arc::default();
+
+    // Like the recent list, private books are left out until unlocked.
+    let app = handle.clone();
+    engine.register_fn("books", move || -> Result<Dynamic, Box<EvalAltResult>> {
+        let unlocked = private_books::is_unlocked(&app);
+        let books = app.state::<AppState>().recent_books.read(&app, |data| {
+            data.books
+                .iter()
+                .filter(|b| !b.private_lock || unlocked)
+                .map(|b| {
+                    serde_json::json!({
+                        "id": b.id,
+                        "title": b.title,
+                        "author": b.author,
+                        "file_type": b.file_type,
+                        "total_pages": b.total_pages,
+                        "last_page": b.last_page,
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        rhai::serde::to_dynamic(books.map_err(script_error)?)
+    });
+
+    let (app, read) = (handle.clone(), read_books.clone());
+    engine.register_fn("book_pages", move |book_id: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        let text = load_book_text(&app, book_id).map_err(script_error)?;
+        if let Ok(mut read) = read.lock() {
+            read.insert(book_id.to_string());
+        }
+        let pages: Vec<serde_json::Value> = text
+            .pages
+            .iter()
+            .map(|page| {
+                let paragraphs: Vec<&str> = page.paragraphs.iter().map(|p| p.text.as_str()).collect();
+                serde_json::json!({ "page": page.page, "title": page.title, "text": paragraphs.join("\n\n") })
+            })
+            .collect();
+        rhai::serde::to_dynamic(pages)
+    });
+
+    let (app, read) = (handle.clone(), read_books.clone());
+    engine.register_fn("page_text", move |book_id: &str, page: i64| -> Result<String, Box<EvalAltResult>> {
+        let text = load_book_text(&app, book_id).map_err(script_error)?;
+        if let Ok(mut read) = read.lock() {
+            read.insert(book_id.to_string());
+        }
+        let page = u32::try_from(page).ok().and_then(|page| text.page(page));
+        let paragraphs: Vec<&str> = page.iter().flat_map(|p| &p.paragraphs).map(|p| p.text.as_str()).collect();
+        Ok(paragraphs.join("\n\n"))
+    });
+
+    let (app, read) = (handle.clone(), read_books);
+    engine.register_fn("llm", move |system_prompt: &str, prompt: &str| -> Result<String, Box<EvalAltResult>> {
+        let model = model.as_deref().ok_or_else(|| script_error("No model was given to run this plugin with."))?;
+        let books: Vec<String> = read.lock().map(|read| read.iter().cloned().collect()).unwrap_or_default();
+        for book_id in &books {
+            ensure_plugin_may_send(&app, book_id).map_err(script_error)?;
+        }
+        let credentials = load_openrouter_credentials(&app).map_err(script_error)?.for_feature(quota::FEATURE_PLUGINS);
+        tauri::async_runtime::block_on(request_openrouter(
+            &credentials,
+            model,
+            PLUGIN_TEMPERATURE,
+            system_prompt,
+            prompt,
+        ))
+        .map_err(script_error)
+    });
+
+    let (app, name) = (handle.clone(), plugin.to_string());
+    engine.register_fn("write_export", move |file_name: &str, content: &str| -> Result<String, Box<EvalAltResult>> {
+        write_export(&app, &name, file_name, content).map_err(script_error)
+    });
+    Ok(engine)
+}
+
+fn run(
+    handle: &tauri::AppHandle,
+    name: &str,
+    input: serde_json::Value,
+    model: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let script = fs::read_to_string(plugin_path(handle, name)?).map_err(|e| e.to_string())?;
+    let engine = engine(handle, name, model)?;
+    let ast = engine.compile(&script).map_err(|e| format!("Plugin {} failed to compile: {}", name, e))?;
+    let mut scope = Scope::new();
+    scope.push_constant("input", rhai::serde::to_dynamic(input).map_err(|e| e.to_string())?);
+    let output: Dynamic =
+        engine.eval_ast_with_scope(&mut scope, &ast).map_err(|e| format!("Plugin {} failed: {}", name, e))?;
+    rhai::serde::from_dynamic(&output).map_err(|e| format!("Plugin {} returned a value that is not JSON: {}", name, e))
+}
+
+// Rhai scripts (`<name>.rhai`) in the plugins folder, which is made on first call so users know
+// where to put them.
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_plugins(handle: tauri::AppHandle) -> Result<Vec<PluginInfo>, String> {
+    let dir = plugins_dir(&handle)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut plugins = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())?.filter_map(Result::ok) {
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != PLUGIN_EXTENSION) {
+            continue;
+        }
+        let Some(name) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let script = fs::read_to_string(&path).unwrap_or_default();
+        plugins.push(PluginInfo { name, description: description(&script), path: path.to_string_lossy().to_string() });
+    }
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+// Runs a plugin with `input` (any JSON, seen by the script as `input`) and returns the value
+// the script ends with. Scripts can list books with `books()`, read extracted text with
+// `book_pages(id)` and `page_text(id, page)`, ask `model` with `llm(system_prompt, prompt)` and
+// save files with `write_export(file_name, content)`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn run_plugin(
+    handle: tauri::AppHandle,
+    name: String,
+    input: serde_json::Value,
+    model: Option<String>,
+) -> Result<serde_json::Value, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Plugins")?;
+    tauri::async_runtime::spawn_blocking(move || run(&handle, &name, input, model)).await.map_err(|e| e.to_string())?
+}
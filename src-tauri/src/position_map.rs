@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::book_formats::page_at_progress;
+use crate::book_text::load_book_text;
+use crate::epub::{chapter_text, spine_hrefs};
+use crate::{app_state, book_data_file_path, RecentBook};
+
+// Runs of this many words are matched between the two texts; shorter runs repeat too often.
+const SHINGLE_WORDS: usize = 6;
+// Shingles tried per PDF page, spread over the page.
+const SHINGLES_PER_PAGE: usize = 5;
+
+// Where a PDF's pages start in its EPUB's running text, counted in words. Built from the
+// page text the reader mirrors for the PDF and the EPUB's spine chapters.
+#[derive(Debug, Serialize, Deserialize)]
+struct PositionMap {
+    pdf_path: String,
+    epub_path: String,
+    // PDF pages whose text was available when the map was built.
+    pdf_pages_seen: usize,
+    pdf_total_pages: u32,
+    total_words: usize,
+    // Spine href and the word its chapter starts at.
+    chapters: Vec<(String, usize)>,
+    // PDF page and the EPUB word it starts at, both increasing.
+    anchors: Vec<(u32, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct EpubPosition {
+    pub href: Option<String>,
+    // Fraction of the chapter read, 0..1.
+    pub chapter_progress: Option<f32>,
+    // Position in the whole book, 0..100.
+    pub percent: Option<f32>,
+    // Only the spine step is read from a CFI; the rest needs the rendered document.
+    pub cfi: Option<String>,
+    // Whether the position came from matching text rather than from the percentage alone.
+    #[serde(skip_deserializing)]
+    pub aligned: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PdfPosition {
+    pub page: u32,
+    aligned: bool,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect()
+}
+
+fn shingle_hash(words: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    words.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn map_file_path(handle: &tauri::AppHandle, book_id: &str) -> Result<PathBuf, String> {
+    book_data_file_path(handle, "position_maps", book_id)
+}
+
+// The PDF and EPUB files of a book with several formats.
+fn format_pair(book: &RecentBook) -> Option<(String, u32, String)> {
+    let mut files = vec![(book.file_type.as_str(), book.file_path.as_str(), book.total_pages)];
+    files.extend(book.formats.iter().map(|f| (f.file_type.as_str(), f.file_path.as_str(), f.total_pages)));
+    let (_, pdf, pdf_pages) = files.iter().find(|(kind, _, _)| *kind == "pdf")?;
+    let (_, epub, _) = files.iter().find(|(kind, _, _)| *kind == "epub")?;
+    Some((pdf.to_string(), *pdf_pages, epub.to_string()))
+}
+
+fn build_map(handle: &tauri::AppHandle, book: &RecentBook) -> Result<PositionMap, String> {
+    let (pdf_path, pdf_total_pages, epub_path) =
+        format_pair(book).ok_or_else(|| "The book needs both a PDF and an EPUB format.".to_string())?;
+    let pdf_text = load_book_text(handle, &book.id)?;
+
+    let mut epub_words = Vec::new();
+    let mut chapters = Vec::new();
+    for href in spine_hrefs(Path::new(&epub_path))? {
+        let text = chapter_text(Path::new(&epub_path), &href).unwrap_or_default();
+        chapters.push((href, epub_words.len()));
+        epub_words.extend(words(&text));
+    }
+
+    // Only shingles that occur once in the EPUB are trusted
+    let mut index: HashMap<u64, Option<usize>> = HashMap::new();
+    for (position, window) in epub_words.windows(SHINGLE_WORDS).enumerate() {
+        index.entry(shingle_hash(window)).and_modify(|p| *p = None).or_insert(Some(position));
+    }
+
+    let mut anchors: Vec<(u32, usize)> = Vec::new();
+    for page in &pdf_text.pages {
+        let page_words: Vec<String> = page.paragraphs.iter().flat_map(|p| words(&p.text)).collect();
+        if page_words.len() < SHINGLE_WORDS {
+            continue;
+        }
+        let step = ((page_words.len() - SHINGLE_WORDS) / SHINGLES_PER_PAGE).max(1);
+        let start = (0..=page_words.len() - SHINGLE_WORDS).step_by(step).find_map(|offset| {
+            let position = index.get(&shingle_hash(&page_words[offset..offset + SHINGLE_WORDS])).copied()??;
+            position.checked_sub(offset)
+        });
+        // A match before the previous page's is a repeated passage, not this page
+        if let Some(start) = start.filter(|s| anchors.last().is_none_or(|(_, last)| s > last)) {
+            anchors.push((page.page, start));
+        }
+    }
+
+    Ok(PositionMap {
+        pdf_path,
+        epub_path,
+        pdf_pages_seen: pdf_text.pages.len(),
+        pdf_total_pages,
+        total_words: epub_words.len(),
+        chapters,
+        anchors,
+    })
+}
+
+// The stored map, rebuilt when the formats changed or more PDF text has come in since.
+fn load_map(handle: &tauri::AppHandle, book_id: &str) -> Result<PositionMap, String> {
+    let book = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+        .ok_or_else(|| format!("Book not found: {}", book_id))?;
+    let path = map_file_path(handle, book_id)?;
+    if path.exists() {
+        let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        if let Ok(map) = serde_json::from_str::<PositionMap>(&data) {
+            let pages_seen = load_book_text(handle, book_id)?.pages.len();
+            let current = format_pair(&book).is_some_and(|(pdf, _, epub)| pdf == map.pdf_path && epub == map.epub_path);
+            if current && map.pdf_pages_seen == pages_seen {
+                return Ok(map);
+            }
+        }
+    }
+    let map = build_map(handle, &book)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string(&map).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())?;
+    Ok(map)
+}
+
+impl PositionMap {
+    // EPUB word at the start of a PDF page, interpolated between the nearest matched pages.
+    fn word_at_page(&self, page: u32) -> Option<usize> {
+        let after = self.anchors.iter().position(|(p, _)| *p >= page);
+        let (before, after) = match after {
+            Some(i) if self.anchors[i].0 == page => return Some(self.anchors[i].1),
+            Some(0) => ((0, 0), self.anchors[0]),
+            Some(i) => (self.anchors[i - 1], self.anchors[i]),
+            None => (*self.anchors.last()?, (self.pdf_total_pages.max(page) + 1, self.total_words)),
+        };
+        let span = after.0 as f64 - before.0 as f64;
+        let offset = (page as f64 - before.0 as f64) / span * (after.1 as f64 - before.1 as f64);
+        Some(before.1 + offset.max(0.0) as usize)
+    }
+
+    // PDF page containing an EPUB word, interpolated the same way.
+    fn page_at_word(&self, word: usize) -> Option<u32> {
+        let after = self.anchors.iter().position(|(_, w)| *w > word);
+        let (before, after) = match after {
+            Some(0) => ((1, 0), self.anchors[0]),
+            Some(i) => (self.anchors[i - 1], self.anchors[i]),
+            None => (*self.anchors.last()?, (self.pdf_total_pages + 1, self.total_words.max(word + 1))),
+        };
+        let span = (after.1 as f64 - before.1 as f64).max(1.0);
+        let page = before.0 as f64 + (word as f64 - before.1 as f64) / span * (after.0 as f64 - before.0 as f64);
+        Some((page as u32).clamp(1, self.pdf_total_pages.max(1)))
+    }
+
+    fn chapter_bounds(&self, index: usize) -> (usize, usize) {
+        let start = self.chapters[index].1;
+        let end = self.chapters.get(index + 1).map_or(self.total_words, |(_, s)| *s);
+        (start, end)
+    }
+
+    fn position_at_word(&self, word: usize, aligned: bool) -> EpubPosition {
+        let index = self.chapters.iter().rposition(|(_, start)| *start <= word).unwrap_or(0);
+        let (start, end) = if self.chapters.is_empty() { (0, self.total_words) } else { self.chapter_bounds(index) };
+        EpubPosition {
+            href: self.chapters.get(index).map(|(href, _)| href.clone()),
+            chapter_progress: Some((word - start.min(word)) as f32 / (end - start).max(1) as f32),
+            percent: Some(word as f32 / self.total_words.max(1) as f32 * 100.0),
+            cfi: None,
+            aligned,
+        }
+    }
+
+    // EPUB word for a position given as chapter and progress, CFI spine step, or percentage.
+    fn word_at_position(&self, position: &EpubPosition) -> Option<usize> {
+        let chapter = position
+            .href
+            .as_deref()
+            .map(|href| href.split('#').next().unwrap_or(href))
+            .and_then(|href| self.chapters.iter().position(|(h, _)| h == href))
+            .or_else(|| position.cfi.as_deref().and_then(cfi_spine_index));
+        if let Some(index) = chapter.filter(|i| *i < self.chapters.len()) {
+            let (start, end) = self.chapter_bounds(index);
+            let progress = position.chapter_progress.unwrap_or(0.0).clamp(0.0, 1.0);
+            return Some(start + ((end - start) as f32 * progress) as usize);
+        }
+        position.percent.map(|percent| (percent.clamp(0.0, 100.0) / 100.0 * self.total_words as f32) as usize)
+    }
+}
+
+// Spine index from a CFI's second step, e.g. 6 in `epubcfi(/6/14[ch5]!/4/2)` names the
+// seventh spine item (steps count in twos from 2).
+fn cfi_spine_index(cfi: &str) -> Option<usize> {
+    let path = cfi.trim().trim_start_matches("epubcfi(").split('!').next()?;
+    let step: String = path.split('/').nth(2)?.chars().take_while(char::is_ascii_digit).collect();
+    (step.parse::<usize>().ok()? / 2).checked_sub(1)
+}
+
+pub fn pdf_page_to_epub(handle: &tauri::AppHandle, book_id: &str, page: u32) -> Result<EpubPosition, String> {
+    let map = load_map(handle, book_id)?;
+    Ok(match map.word_at_page(page) {
+        Some(word) => map.position_at_word(word, true),
+        None => {
+            let fraction = page.saturating_sub(1) as f32 / map.pdf_total_pages.max(1) as f32;
+            map.position_at_word((fraction * map.total_words as f32) as usize, false)
+        }
+    })
+}
+
+// Where a PDF page's text starts in the book's EPUB format. Falls back to the same
+// percentage through the book (`aligned: false`) until enough PDF text has been extracted.
+#[tauri::command(rename_all = "camelCase")]
+pub fn map_pdf_to_epub(handle: tauri::AppHandle, book_id: String, page: u32) -> Result<EpubPosition, String> {
+    pdf_page_to_epub(&handle, &book_id, page)
+}
+
+pub fn epub_to_pdf_page(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    position: &EpubPosition,
+) -> Result<PdfPosition, String> {
+    let map = load_map(handle, book_id)?;
+    let word = map
+        .word_at_position(position)
+        .ok_or_else(|| "Position needs a chapter href, CFI or percentage.".to_string())?;
+    Ok(match map.page_at_word(word) {
+        Some(page) => PdfPosition { page, aligned: true },
+        None => {
+            let percent = word as f32 / map.total_words.max(1) as f32 * 100.0;
+            PdfPosition { page: page_at_progress(percent, map.pdf_total_pages), aligned: false }
+        }
+    })
+}
+
+// The PDF page holding an EPUB position, given as chapter href and progress, CFI or percentage.
+#[tauri::command(rename_all = "camelCase")]
+pub fn map_epub_to_pdf(
+    handle: tauri::AppHandle,
+    book_id: String,
+    position: EpubPosition,
+) -> Result<PdfPosition, String> {
+    epub_to_pdf_page(&handle, &book_id, &position)
+}
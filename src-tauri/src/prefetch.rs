@@ -0,0 +1,207 @@
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+use crate::book_text::{load_book_text, BookPage, BookText};
+use crate::footnotes::{page_footnotes, Footnote};
+use crate::hooks::{self, HookEvent};
+use crate::jobs::JobRegistry;
+use crate::progress;
+use crate::quota;
+use crate::{
+    cache_target, cached_unit_translation, read_cache, translate_sentences, unit_cache_key, CachedTranslations,
+    InFlightTranslations, TargetLanguage, TranslateSentence,
+};
+
+const MAX_LOOKAHEAD: u32 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+struct PrefetchError {
+    book_id: String,
+    page: u32,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageCoverage {
+    page: u32,
+    // Chapter or section title, for grouping pages into chapters.
+    title: Option<String>,
+    sentences: usize,
+    cached: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranslationCoverage {
+    sentences: usize,
+    cached: usize,
+    pages: Vec<PageCoverage>,
+    // Pages with sentences still to translate, for a job to work through.
+    incomplete_pages: Vec<u32>,
+}
+
+fn prefetch_job_id(book_id: &str) -> String {
+    format!("prefetch:{}", book_id)
+}
+
+// The translation units on a page: its paragraphs, except tables and code. Footnotes go
+// under their own sids in place of the paragraphs their bodies were extracted as.
+fn page_units(text: &BookText, book_id: &str, page: &BookPage) -> Vec<TranslateSentence> {
+    let footnotes: Vec<Footnote> = page_footnotes(text, book_id, page.page)
+        .into_iter()
+        .filter(|note| note.page == page.page)
+        .collect();
+    page.paragraphs
+        .iter()
+        .filter(|p| !p.text.trim().is_empty() && !p.kind.is_verbatim())
+        .filter(|p| !footnotes.iter().any(|note| note.source_sid == p.sid))
+        .map(|p| (p.sid.clone(), p.text.clone()))
+        .chain(footnotes.iter().map(|note| (note.sid.clone(), note.text.clone())))
+        .map(|(sid, text)| TranslateSentence { sid, text, ..Default::default() })
+        .collect()
+}
+
+// Sentences on `page` that are neither cached nor already being translated.
+fn pending_sentences(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    page: u32,
+    model: &str,
+    target_language: &TargetLanguage,
+) -> Result<Vec<TranslateSentence>, String> {
+    let text = load_book_text(handle, book_id)?;
+    let Some(book_page) = text.page(page) else {
+        return Ok(Vec::new());
+    };
+    let units = page_units(&text, book_id, book_page);
+    let target_code = cache_target(handle, &target_language.tag());
+
+    let in_flight = handle.state::<InFlightTranslations>();
+    read_cache(handle, |cache| {
+        units
+            .into_iter()
+            .filter(|unit| {
+                !is_cached(cache, unit, model, &target_code)
+                    && !in_flight.contains(&unit_cache_key(unit, model, &target_code))
+            })
+            .collect()
+    })
+}
+
+// Whether a unit's translation is cached, whole or, from sentence mode, sentence by sentence.
+fn is_cached(cache: &CachedTranslations, unit: &TranslateSentence, model: &str, target_code: &str) -> bool {
+    cached_unit_translation(cache, unit, model, target_code).is_some()
+}
+
+async fn run_prefetch(
+    handle: tauri::AppHandle,
+    book_id: String,
+    pages: Vec<u32>,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+) {
+    let mut total = 0;
+    let job_id = prefetch_job_id(&book_id);
+    for (index, page) in pages.iter().enumerate() {
+        let page = *page;
+        let result = async {
+            let missing = pending_sentences(&handle, &book_id, page, &model, &target_language)?;
+            if missing.is_empty() {
+                return Ok(0);
+            }
+            let jobs = handle.state::<JobRegistry>();
+            let _slot = jobs.acquire_provider_slot().await?;
+            let translated = translate_sentences(&handle, &model, temperature, &target_language, missing).await?;
+            Ok::<usize, String>(translated.len())
+        }
+        .await;
+
+        match result {
+            Ok(translated) => {
+                total += translated;
+                let message = Some(format!("Page {}", page));
+                progress::emit(&handle, &job_id, "prefetch", index as u64 + 1, Some(pages.len() as u64), message);
+            }
+            Err(message) if quota::is_quota_error(&message) => {
+                quota::announce_paused(&handle, &job_id, &message);
+                return;
+            }
+            Err(message) => {
+                let _ = handle.emit(
+                    "prefetch-error",
+                    PrefetchError {
+                        book_id: book_id.clone(),
+                        page,
+                        message,
+                    },
+                );
+                return;
+            }
+        }
+    }
+    if total > 0 {
+        let data = serde_json::json!({ "kind": "prefetch", "book_id": book_id, "pages": pages, "translated": total });
+        hooks::fire(&handle, HookEvent::TranslationJobCompleted, &book_id, data);
+    }
+}
+
+// Quietly translates the pages after `current_page` in the background.
+// Calling it again for the same book (e.g. after a jump) cancels the previous run.
+#[tauri::command(rename_all = "camelCase")]
+pub fn prefetch_translations(
+    handle: tauri::AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+    book_id: String,
+    current_page: u32,
+    lookahead: u32,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+) -> Result<(), String> {
+    let lookahead = lookahead.min(MAX_LOOKAHEAD);
+    if lookahead == 0 {
+        jobs.cancel(&prefetch_job_id(&book_id));
+        return Ok(());
+    }
+    let pages: Vec<u32> = (current_page + 1..=current_page + lookahead).collect();
+    let job = run_prefetch(handle.clone(), book_id.clone(), pages, model, temperature, target_language);
+    jobs.spawn(&handle, prefetch_job_id(&book_id), "prefetch", job);
+    Ok(())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn cancel_prefetch(jobs: tauri::State<'_, JobRegistry>, book_id: String) -> Result<bool, String> {
+    Ok(jobs.cancel(&prefetch_job_id(&book_id)))
+}
+
+// How much of the book is translated by `model` into `target_language` already, page by page:
+// what can be read offline, and what is left to translate.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_translation_coverage(
+    handle: tauri::AppHandle,
+    book_id: String,
+    model: String,
+    target_language: TargetLanguage,
+) -> Result<TranslationCoverage, String> {
+    let text = load_book_text(&handle, &book_id)?;
+    let target_code = cache_target(&handle, &target_language.tag());
+    let units: Vec<(&BookPage, Vec<TranslateSentence>)> =
+        text.pages.iter().map(|page| (page, page_units(&text, &book_id, page))).collect();
+    let pages: Vec<PageCoverage> = read_cache(&handle, |cache| {
+        units
+            .into_iter()
+            .map(|(page, units)| PageCoverage {
+                page: page.page,
+                title: page.title.clone(),
+                sentences: units.len(),
+                cached: units.iter().filter(|unit| is_cached(cache, unit, &model, &target_code)).count(),
+            })
+            .collect()
+    })?;
+    Ok(TranslationCoverage {
+        sentences: pages.iter().map(|p| p.sentences).sum(),
+        cached: pages.iter().map(|p| p.cached).sum(),
+        incomplete_pages: pages.iter().filter(|p| p.cached < p.sentences).map(|p| p.page).collect(),
+        pages,
+    })
+}
@@ -0,0 +1,385 @@
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::activity_log::{self, ActivityEvent};
+use crate::app_state::{self, AppState};
+use crate::cloud_policy;
+use crate::encryption::{decrypt, encrypt, is_encrypted, keychain_delete, keychain_get, keychain_set};
+use crate::lookup_history::{self, LookupRecord};
+use crate::profiles::ActiveProfile;
+use crate::quarantine::parse_or_quarantine;
+use crate::restricted_mode;
+use crate::sync_conflicts::with_write_lock;
+use crate::{
+    app_config_dir, read_cache, read_recent_books_file, update_cache, update_recent_books, CachedTranslations,
+};
+
+// Sealed books live under this folder, one subfolder per book.
+const VAULT_DIR: &str = "private_books";
+const PRIVATE_BOOKS_FILE: &str = "private_books.json";
+// Encrypted with the passphrase when it is first set, to check it on unlock.
+const VERIFIER_FILE: &str = "verifier.enc";
+const VERIFIER_TEXT: &[u8] = b"pdfread-private-books";
+// A book's translation cache entries, lookups and activity log events, in its vault folder.
+const CACHE_VAULT_FILE: &str = "translation_cache.enc";
+const LOOKUPS_VAULT_FILE: &str = "lookup_history.enc";
+const ACTIVITY_VAULT_FILE: &str = "activity_log.enc";
+// While private books are unlocked, the passphrase is also kept in the keychain under this
+// account (per profile), so a session cut short by a crash can be sealed on the next start.
+const SESSION_KEYCHAIN_ACCOUNT: &str = "private-books-session";
+
+// The passphrase while private books are unlocked; never written anywhere.
+#[derive(Default)]
+pub struct PrivateBooks {
+    passphrase: Mutex<Option<String>>,
+}
+
+// Which books are private. Kept apart from the recent books, which are trimmed and rebuilt when
+// a book is opened again, so a private book's vault is unsealed on every unlock for good.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PrivateBooksData {
+    books: BTreeSet<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrivateBooksState {
+    unlocked: bool,
+    // Whether a passphrase has been set up.
+    has_passphrase: bool,
+    locked_books: usize,
+    // Books whose vault could not be opened on unlock; their data stays sealed.
+    unreadable_books: Vec<String>,
+}
+
+fn vault_root(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join(VAULT_DIR))
+}
+
+fn session_passphrase(handle: &tauri::AppHandle) -> Option<String> {
+    handle.state::<PrivateBooks>().passphrase.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn session_account(handle: &tauri::AppHandle) -> Result<String, String> {
+    let profile = handle.state::<ActiveProfile>().current().ok_or_else(|| "No active profile.".to_string())?;
+    Ok(format!("{}:{}", SESSION_KEYCHAIN_ACCOUNT, profile))
+}
+
+pub fn is_unlocked(handle: &tauri::AppHandle) -> bool {
+    session_passphrase(handle).is_some()
+}
+
+fn private_books_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join(PRIVATE_BOOKS_FILE))
+}
+
+// The first time, the private books are taken from the recent list and from the vaults on disk,
+// which still hold books that have dropped off the list.
+pub fn read_private_books_file(handle: &tauri::AppHandle) -> Result<PrivateBooksData, String> {
+    let path = private_books_file_path(handle)?;
+    if !path.exists() {
+        let mut books: BTreeSet<String> = read_recent_books_file(handle)?
+            .books
+            .iter()
+            .filter(|b| b.private_lock)
+            .map(|b| b.id.clone())
+            .collect();
+        if let Ok(entries) = fs::read_dir(vault_root(handle)?) {
+            for entry in entries.filter_map(Result::ok).filter(|entry| entry.path().is_dir()) {
+                books.insert(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        return Ok(PrivateBooksData { books });
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn write_private_books_file(handle: &tauri::AppHandle, data: &PrivateBooksData) -> Result<(), String> {
+    let path = private_books_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    with_write_lock(&path, || fs::write(&path, data).map_err(|e| e.to_string()))
+}
+
+fn private_book_ids(handle: &tauri::AppHandle) -> Result<Vec<String>, String> {
+    handle.state::<AppState>().private_books.read(handle, |data| data.books.iter().cloned().collect())
+}
+
+pub fn is_private(handle: &tauri::AppHandle, book_id: &str) -> Result<bool, String> {
+    handle.state::<AppState>().private_books.read(handle, |data| data.books.contains(book_id))
+}
+
+// Marks a book private without sealing anything, for when it takes over the private flag of
+// another entry (see `book_formats`).
+pub fn mark_private(handle: &tauri::AppHandle, book_id: &str) -> Result<(), String> {
+    handle.state::<AppState>().private_books.update(handle, |data| {
+        data.books.insert(book_id.to_string());
+    })
+}
+
+// Checks the passphrase against the verifier, setting it up on first use.
+fn check_passphrase(handle: &tauri::AppHandle, passphrase: &str) -> Result<(), String> {
+    if passphrase.trim().is_empty() {
+        return Err("A passphrase is required.".to_string());
+    }
+    let path = vault_root(handle)?.join(VERIFIER_FILE);
+    if !path.exists() {
+        fs::create_dir_all(vault_root(handle)?).map_err(|e| e.to_string())?;
+        return fs::write(&path, encrypt(passphrase, VERIFIER_TEXT)?).map_err(|e| e.to_string());
+    }
+    let data = fs::read(&path).map_err(|e| e.to_string())?;
+    match is_encrypted(&data).then(|| decrypt(passphrase, &data)) {
+        Some(Ok(text)) if text == VERIFIER_TEXT => Ok(()),
+        _ => Err("Wrong passphrase for private books.".to_string()),
+    }
+}
+
+// Per-book data files (`<dir>/<book_id>.json` in the config folder), by folder name.
+fn book_data_files(handle: &tauri::AppHandle, book_id: &str) -> Result<Vec<(String, PathBuf)>, String> {
+    let root = app_config_dir(handle)?;
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&root).map_err(|e| e.to_string())?.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path().join(format!("{}.json", book_id));
+        if name != VAULT_DIR && entry.path().is_dir() && path.is_file() {
+            files.push((name, path));
+        }
+    }
+    Ok(files)
+}
+
+fn write_sealed(path: &Path, passphrase: &str, plaintext: &[u8]) -> Result<(), String> {
+    fs::write(path, encrypt(passphrase, plaintext)?).map_err(|e| e.to_string())
+}
+
+fn read_sealed<T: DeserializeOwned + Default>(vault: &Path, file: &str, passphrase: &str) -> Result<T, String> {
+    let path = vault.join(file);
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let data = decrypt(passphrase, &fs::read(&path).map_err(|e| e.to_string())?)?;
+    serde_json::from_slice(&data).map_err(|e| e.to_string())
+}
+
+fn write_sealed_json<T: Serialize>(vault: &Path, file: &str, passphrase: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    write_sealed(&vault.join(file), passphrase, json.as_bytes())
+}
+
+// Moves a book's cached translations, lookups, activity and data files into its vault,
+// encrypted. Each is written to the vault before it is removed from where it was, so a failed
+// write loses nothing.
+fn seal(handle: &tauri::AppHandle, passphrase: &str, book_id: &str) -> Result<(), String> {
+    let vault = vault_root(handle)?.join(book_id);
+    fs::create_dir_all(&vault).map_err(|e| e.to_string())?;
+    let until = Utc::now();
+
+    let prefix = format!("{}|", book_id);
+    let mut sealed: CachedTranslations = read_sealed(&vault, CACHE_VAULT_FILE, passphrase)?;
+    let keys = read_cache(handle, |cache| {
+        let keys: Vec<String> = cache.entries.keys().filter(|key| key.starts_with(&prefix)).cloned().collect();
+        for key in &keys {
+            sealed.entries.insert(key.clone(), cache.entries[key].clone());
+            if let Some(generation) = cache.generations.get(key) {
+                sealed.generations.insert(key.clone(), generation.clone());
+            }
+        }
+        keys
+    })?;
+    write_sealed_json(&vault, CACHE_VAULT_FILE, passphrase, &sealed)?;
+    if !keys.is_empty() {
+        update_cache(handle, |cache| {
+            for key in &keys {
+                cache.entries.remove(key);
+                cache.generations.remove(key);
+            }
+        })?;
+    }
+
+    let mut lookups: Vec<LookupRecord> = read_sealed(&vault, LOOKUPS_VAULT_FILE, passphrase)?;
+    lookups.extend(lookup_history::book_records(handle, book_id)?);
+    write_sealed_json(&vault, LOOKUPS_VAULT_FILE, passphrase, &lookups)?;
+    lookup_history::remove_book_records(handle, book_id, until)?;
+
+    let mut events: Vec<ActivityEvent> = read_sealed(&vault, ACTIVITY_VAULT_FILE, passphrase)?;
+    events.extend(activity_log::book_events(handle, book_id)?);
+    write_sealed_json(&vault, ACTIVITY_VAULT_FILE, passphrase, &events)?;
+    activity_log::remove_book_events(handle, book_id, until)?;
+
+    for (dir, path) in book_data_files(handle, book_id)? {
+        let data = fs::read(&path).map_err(|e| e.to_string())?;
+        write_sealed(&vault.join(format!("{}.enc", dir)), passphrase, &data)?;
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Puts a sealed book's data back in place for the session. Everything is decrypted before
+// anything is restored, so a damaged vault is left whole.
+fn unseal(handle: &tauri::AppHandle, passphrase: &str, book_id: &str) -> Result<(), String> {
+    let vault = vault_root(handle)?.join(book_id);
+    if !vault.is_dir() {
+        return Ok(());
+    }
+    let sealed: CachedTranslations = read_sealed(&vault, CACHE_VAULT_FILE, passphrase)?;
+    let lookups: Vec<LookupRecord> = read_sealed(&vault, LOOKUPS_VAULT_FILE, passphrase)?;
+    let events: Vec<ActivityEvent> = read_sealed(&vault, ACTIVITY_VAULT_FILE, passphrase)?;
+    let mut files = Vec::new();
+    for entry in fs::read_dir(&vault).map_err(|e| e.to_string())?.filter_map(Result::ok) {
+        let path = entry.path();
+        let Some(dir) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let reserved = [CACHE_VAULT_FILE, LOOKUPS_VAULT_FILE, ACTIVITY_VAULT_FILE];
+        if path.extension().is_none_or(|ext| ext != "enc") || reserved.iter().any(|f| path.ends_with(f)) {
+            continue;
+        }
+        files.push((dir, decrypt(passphrase, &fs::read(&path).map_err(|e| e.to_string())?)?));
+    }
+
+    update_cache(handle, |cache| {
+        // Anything cached since the book was sealed is newer and kept.
+        for (key, translation) in sealed.entries {
+            cache.entries.entry(key).or_insert(translation);
+        }
+        for (key, generation) in sealed.generations {
+            cache.generations.entry(key).or_insert(generation);
+        }
+    })?;
+    lookup_history::restore_records(handle, lookups)?;
+    activity_log::restore_events(handle, events)?;
+    let root = app_config_dir(handle)?;
+    for (dir, data) in files {
+        let target_dir = root.join(&dir);
+        fs::create_dir_all(&target_dir).map_err(|e| e.to_string())?;
+        fs::write(target_dir.join(format!("{}.json", book_id)), data).map_err(|e| e.to_string())?;
+    }
+    // The shared cache must hold the entries before the vault copy goes.
+    app_state::flush(handle)?;
+    fs::remove_dir_all(&vault).map_err(|e| e.to_string())
+}
+
+// Seals every private book and forgets the passphrase. Called on quit, so a session never
+// outlives the app; a session cut short by a crash is sealed by `recover` on the next start.
+// A book that fails to seal does not keep the others open; the failures are reported after,
+// and the session is kept in the keychain so the next start tries again.
+pub fn lock(handle: &tauri::AppHandle) -> Result<(), String> {
+    let Some(passphrase) = session_passphrase(handle) else {
+        return Ok(());
+    };
+    let mut failures = Vec::new();
+    for book_id in private_book_ids(handle)? {
+        if let Err(e) = seal(handle, &passphrase, &book_id) {
+            failures.push(format!("{}: {}", book_id, e));
+        }
+    }
+    *handle.state::<PrivateBooks>().passphrase.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    // The shared cache file still holds what was just sealed until it is written again.
+    app_state::flush(handle)?;
+    if !failures.is_empty() {
+        return Err(format!("Some private books could not be sealed: {}", failures.join("; ")));
+    }
+    keychain_delete(&session_account(handle)?)
+}
+
+// Seals what an unlocked session left in plaintext when the app did not get to quit cleanly.
+// Runs at startup, once the stores are loaded.
+pub fn recover(handle: &tauri::AppHandle) -> Result<(), String> {
+    let Some(passphrase) = keychain_get(&session_account(handle)?)? else {
+        return Ok(());
+    };
+    *handle.state::<PrivateBooks>().passphrase.lock().unwrap_or_else(|e| e.into_inner()) = Some(passphrase);
+    lock(handle)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_private_books_state(handle: tauri::AppHandle) -> Result<PrivateBooksState, String> {
+    let unlocked = is_unlocked(&handle);
+    Ok(PrivateBooksState {
+        unlocked,
+        has_passphrase: vault_root(&handle)?.join(VERIFIER_FILE).exists(),
+        locked_books: if unlocked { 0 } else { private_book_ids(&handle)?.len() },
+        unreadable_books: Vec::new(),
+    })
+}
+
+// Unlocks private books for this session: they show in the library again and their data is
+// readable until `lock_private_books` or quit.
+#[tauri::command(rename_all = "camelCase")]
+pub fn unlock_private_books(handle: tauri::AppHandle, passphrase: String) -> Result<PrivateBooksState, String> {
+    check_passphrase(&handle, &passphrase)?;
+    let mut unreadable_books = Vec::new();
+    if !is_unlocked(&handle) {
+        // Without a way to seal the books again after a crash, they stay locked.
+        keychain_set(&session_account(&handle)?, &passphrase)
+            .map_err(|e| format!("Private books cannot be unlocked without the system keychain: {}", e))?;
+        // Set first, so whatever gets unsealed is sealed again by the next lock.
+        *handle.state::<PrivateBooks>().passphrase.lock().unwrap_or_else(|e| e.into_inner()) = Some(passphrase.clone());
+        for book_id in private_book_ids(&handle)? {
+            if let Err(e) = unseal(&handle, &passphrase, &book_id) {
+                log::error!("Failed to unseal private book {}: {}", book_id, e);
+                unreadable_books.push(book_id);
+            }
+        }
+    }
+    Ok(PrivateBooksState { unreadable_books, ..get_private_books_state(handle)? })
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn lock_private_books(handle: tauri::AppHandle) -> Result<PrivateBooksState, String> {
+    lock(&handle)?;
+    get_private_books_state(handle)
+}
+
+// Marks a book private, or no longer private, with the private books passphrase (set up the
+// first time). While locked, a private book is left out of the recent books and the activity
+// timeline, cloud requests for it are refused, and its cached translations, lookups, activity
+// events and data files are kept encrypted. The response and gloss caches are shared across
+// books and not sealed; with cloud requests refused, nothing new reaches them while locked.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_book_private_lock(
+    handle: tauri::AppHandle,
+    book_id: String,
+    private: bool,
+    passphrase: String,
+) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing private books")?;
+    check_passphrase(&handle, &passphrase)?;
+    // A book that has dropped off the recent list is still known by its cloud policy.
+    let listed = handle
+        .state::<AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.iter().any(|b| b.id == book_id))?;
+    if !listed && cloud_policy::policy(&handle, &book_id)?.is_none() {
+        return Err(format!("Book not found: {}", book_id));
+    }
+    let unlocked = is_unlocked(&handle);
+    if !private && !unlocked {
+        unseal(&handle, &passphrase, &book_id)?;
+    }
+    handle.state::<AppState>().private_books.update(&handle, |data| {
+        if private {
+            data.books.insert(book_id.clone());
+        } else {
+            data.books.remove(&book_id);
+        }
+    })?;
+    update_recent_books(&handle, |data| {
+        if let Some(book) = data.books.iter_mut().find(|b| b.id == book_id) {
+            book.private_lock = private;
+        }
+    })?;
+    if private && !unlocked {
+        seal(&handle, &passphrase, &book_id)?;
+        app_state::flush(&handle)?;
+    }
+    Ok(())
+}
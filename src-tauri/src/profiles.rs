@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{Emitter, Manager};
+
+use crate::app_state::AppState;
+use crate::data_root;
+use crate::encryption;
+use crate::jobs::JobRegistry;
+use crate::private_books;
+use crate::restricted_mode;
+use crate::undo::UndoJournal;
+use crate::vocab_index::VocabularyIndex;
+use crate::word_lists::{IGNORED_WORDS_FILE, KNOWN_WORDS_FILE};
+
+const PROFILES_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+const DEFAULT_PROFILE: &str = "default";
+// What a data root from before profiles may hold that belongs to the profile. Only these move;
+// anything else found there (quarantined files, sync tool leftovers, unrelated files) stays put,
+// as do the entries every profile shares: the profile list, the data location file, the
+// OpenRouter key, restricted mode and the library folder of copied books.
+const PROFILE_ENTRIES: &[&str] = &[
+    "activity_log.jsonl",
+    "alignments",
+    "audiobooks.json",
+    "book_text",
+    "citations",
+    "cloud_policy.json",
+    "conflicts",
+    "entities",
+    "export_templates",
+    "feeds.json",
+    "gloss_cache.json",
+    "hooks.json",
+    IGNORED_WORDS_FILE,
+    "koreader_sync.json",
+    KNOWN_WORDS_FILE,
+    "language_rules.json",
+    "lookup_history.json",
+    "maintenance.json",
+    "model_catalog.json",
+    "onboarding.json",
+    "pagination",
+    "pending_jobs.json",
+    "plugin_exports",
+    "plugins",
+    "position_maps",
+    "private_books",
+    "private_books.json",
+    "reading_activity.json",
+    "readings",
+    "recent_books.json",
+    "response_cache.json",
+    "review_reminders.json",
+    "running_lines",
+    "settings.json",
+    "skip_ranges",
+    "srs.json",
+    "story",
+    "text_cleanup",
+    "translation_cache.json",
+    "translation_ratings.json",
+    "transliteration_cache.json",
+    "usage.json",
+    "vocabulary.json",
+    "wordlists",
+];
+const MAX_NAME_CHARS: usize = 40;
+
+// The profile whose folder `app_config_dir` points into. Empty until `init` runs.
+#[derive(Default)]
+pub struct ActiveProfile {
+    name: RwLock<Option<String>>,
+}
+
+impl ActiveProfile {
+    pub fn current(&self) -> Option<String> {
+        self.name.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn set(&self, name: String) {
+        *self.name.write().unwrap_or_else(|e| e.into_inner()) = Some(name);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    name: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfilesData {
+    active: String,
+    profiles: Vec<Profile>,
+}
+
+pub fn profile_dir(root: &Path, name: &str) -> PathBuf {
+    root.join(PROFILES_DIR).join(name)
+}
+
+fn profiles_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(data_root(handle)?.join(PROFILES_FILE))
+}
+
+fn load_profiles(handle: &tauri::AppHandle) -> Result<Option<ProfilesData>, String> {
+    let path = profiles_file_path(handle)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map(Some).map_err(|e| e.to_string())
+}
+
+fn save_profiles(handle: &tauri::AppHandle, data: &ProfilesData) -> Result<(), String> {
+    let path = profiles_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// Data from before profiles existed sits directly in the data root; it becomes the default
+// profile. The known profile entries move into the profile's folder.
+fn migrate_single_profile(root: &Path) -> Result<(), String> {
+    let target = profile_dir(root, DEFAULT_PROFILE);
+    fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+    for name in PROFILE_ENTRIES {
+        let path = root.join(name);
+        if !path.exists() {
+            continue;
+        }
+        fs::rename(&path, target.join(name))
+            .map_err(|e| format!("Failed to move {} into the default profile: {}", name, e))?;
+    }
+    Ok(())
+}
+
+// Picks the active profile, moving single-profile data into the default profile on the
+// first run with profiles. Runs after `data_dir::init` and before the stores load.
+pub fn init(handle: &tauri::AppHandle) -> Result<(), String> {
+    let data = match load_profiles(handle)? {
+        Some(data) => data,
+        None => {
+            migrate_single_profile(&data_root(handle)?)?;
+            let data = ProfilesData {
+                active: DEFAULT_PROFILE.to_string(),
+                profiles: vec![Profile { name: DEFAULT_PROFILE.to_string(), created_at: Utc::now() }],
+            };
+            save_profiles(handle, &data)?;
+            data
+        }
+    };
+    let names: Vec<String> = data.profiles.iter().map(|p| p.name.clone()).collect();
+    if let Err(e) = encryption::split_shared_passphrase(&names) {
+        log::error!("Failed to give each profile its own cache passphrase: {}", e);
+    }
+    handle.state::<ActiveProfile>().set(data.active);
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("Profile names must be 1 to {} characters long.", MAX_NAME_CHARS));
+    }
+    if name.starts_with('.') || !name.chars().all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.')) {
+        return Err("Profile names may only use letters, digits, spaces, dots, dashes and underscores.".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn profiles_data(handle: &tauri::AppHandle) -> Result<ProfilesData, String> {
+    load_profiles(handle)?.ok_or_else(|| "Profiles are not set up yet.".to_string())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn list_profiles(handle: tauri::AppHandle) -> Result<ProfilesData, String> {
+    profiles_data(&handle)
+}
+
+// Adds an empty profile: its own settings, vocabulary, reading progress and caches.
+#[tauri::command(rename_all = "camelCase")]
+pub fn create_profile(handle: tauri::AppHandle, name: String) -> Result<ProfilesData, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Creating profiles")?;
+    let name = validate_name(&name)?;
+    let mut data = profiles_data(&handle)?;
+    if data.profiles.iter().any(|p| p.name.eq_ignore_ascii_case(&name)) {
+        return Err(format!("A profile named {} already exists.", name));
+    }
+    fs::create_dir_all(profile_dir(&data_root(&handle)?, &name)).map_err(|e| e.to_string())?;
+    data.profiles.push(Profile { name, created_at: Utc::now() });
+    save_profiles(&handle, &data)?;
+    Ok(data)
+}
+
+// Makes `name` the active profile. Unsaved changes are written to the old profile first and
+// its background jobs stop; the frontend reloads its state on `profile-switched`.
+#[tauri::command(rename_all = "camelCase")]
+pub fn switch_profile(
+    handle: tauri::AppHandle,
+    jobs: tauri::State<'_, JobRegistry>,
+    name: String,
+) -> Result<ProfilesData, String> {
+    // A fresh profile starts from default settings, without the usage caps.
+    restricted_mode::ensure_unrestricted(&handle, "Switching profiles")?;
+    let mut data = profiles_data(&handle)?;
+    let name = data
+        .profiles
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+        .map(|p| p.name.clone())
+        .ok_or_else(|| format!("No profile named {}.", name.trim()))?;
+    if handle.state::<ActiveProfile>().current().as_deref() == Some(name.as_str()) {
+        return Ok(data);
+    }
+
+    jobs.cancel_all();
+    // Sealed with the old profile's data; the passphrase must not carry over to the new one.
+    private_books::lock(&handle)?;
+    handle.state::<AppState>().switch_files(&handle, || {
+        handle.state::<ActiveProfile>().set(name.clone());
+        Ok(())
+    })?;
+    handle.state::<VocabularyIndex>().invalidate();
+    handle.state::<UndoJournal>().clear();
+    data.active = name;
+    save_profiles(&handle, &data)?;
+    let _ = handle.emit("profile-switched", data.active.clone());
+    Ok(data)
+}
@@ -0,0 +1,32 @@
+use serde::Serialize;
+use tauri::Emitter;
+
+// Every progress report goes out under this name; the frontend can also listen to one kind
+// only with `progress:<kind>`, e.g. `progress:epub_translation`.
+pub const PROGRESS_EVENT: &str = "progress";
+
+// Progress of a long operation, the same shape for every kind so one progress UI can show
+// them all. `current == total` means the operation is done; `total` is None when unknown
+// (downloads without a Content-Length).
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub job_id: String,
+    pub kind: String,
+    pub current: u64,
+    pub total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+pub fn emit(
+    handle: &tauri::AppHandle,
+    job_id: &str,
+    kind: &str,
+    current: u64,
+    total: Option<u64>,
+    message: Option<String>,
+) {
+    let event = ProgressEvent { job_id: job_id.to_string(), kind: kind.to_string(), current, total, message };
+    let _ = handle.emit(&format!("{}:{}", PROGRESS_EVENT, kind), event.clone());
+    let _ = handle.emit(PROGRESS_EVENT, event);
+}
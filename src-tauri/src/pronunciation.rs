@@ -0,0 +1,209 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use crate::page_words::tokenize_words;
+use crate::settings::load_settings;
+
+// whisper.cpp's command-line program; older builds and Homebrew formulas name it differently.
+const WHISPER_PROGRAMS: &[&str] = &["whisper-cli", "whisper-cpp"];
+// Heard words at least this close to the target word count as that word, said imperfectly.
+const MISPRONOUNCED_MIN_ACCURACY: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordStatus {
+    Correct,
+    Mispronounced,
+    Missed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WordScore {
+    word: String,
+    // The recognized word aligned with this one, if any.
+    heard: Option<String>,
+    // 1 for an exact match, down to 0 for a word not heard at all.
+    accuracy: f64,
+    status: WordStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PronunciationScore {
+    transcript: String,
+    // Mean of the word accuracies.
+    accuracy: f64,
+    words: Vec<WordScore>,
+    // Recognized words that match nothing in the target.
+    extra_words: Vec<String>,
+}
+
+fn words(text: &str) -> Vec<String> {
+    tokenize_words(text).iter().map(|token| token.text.to_lowercase()).collect()
+}
+
+// whisper.cpp marks non-speech as "[BLANK_AUDIO]", "(music)" and the like.
+fn strip_annotations(text: &str) -> String {
+    let mut depth = 0usize;
+    let mut stripped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+// Share of characters that survive from one word to the other.
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
+
+// Aligns the heard words with the target words at the least total cost, where leaving a word
+// out costs 1 and pairing two costs how unlike they are. Returns, per target word, the heard
+// word paired with it, and the heard words paired with none.
+fn align(target: &[String], heard: &[String]) -> (Vec<Option<usize>>, Vec<usize>) {
+    let (n, m) = (target.len(), heard.len());
+    let pair_cost = |i: usize, j: usize| 1.0 - similarity(&target[i], &heard[j]);
+    let mut cost = vec![vec![0.0; m + 1]; n + 1];
+    for (i, row) in cost.iter_mut().enumerate() {
+        row[0] = i as f64;
+    }
+    for (j, cell) in cost[0].iter_mut().enumerate() {
+        *cell = j as f64;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let paired = cost[i - 1][j - 1] + pair_cost(i - 1, j - 1);
+            cost[i][j] = paired.min(cost[i - 1][j] + 1.0).min(cost[i][j - 1] + 1.0);
+        }
+    }
+
+    let mut matched = vec![None; n];
+    let mut extra = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && (cost[i][j] - cost[i - 1][j - 1] - pair_cost(i - 1, j - 1)).abs() < 1e-9 {
+            matched[i - 1] = Some(j - 1);
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && (cost[i][j] - cost[i - 1][j] - 1.0).abs() < 1e-9 {
+            i -= 1;
+        } else {
+            j -= 1;
+            extra.push(j);
+        }
+    }
+    extra.reverse();
+    (matched, extra)
+}
+
+fn score(text: &str, transcript: String) -> PronunciationScore {
+    let target = words(text);
+    let heard = words(&transcript);
+    let (matched, extra) = align(&target, &heard);
+    let words: Vec<WordScore> = target
+        .into_iter()
+        .zip(matched)
+        .map(|(word, heard_index)| {
+            let heard = heard_index.map(|j| heard[j].clone());
+            let accuracy = heard.as_deref().map_or(0.0, |h| similarity(&word, h));
+            let status = if accuracy >= 1.0 {
+                WordStatus::Correct
+            } else if accuracy >= MISPRONOUNCED_MIN_ACCURACY {
+                WordStatus::Mispronounced
+            } else {
+                WordStatus::Missed
+            };
+            WordScore { word, heard, accuracy, status }
+        })
+        .collect();
+    let total: f64 = words.iter().map(|w| w.accuracy).sum();
+    let accuracy = if words.is_empty() { 0.0 } else { total / words.len() as f64 };
+    PronunciationScore {
+        transcript,
+        accuracy,
+        words,
+        extra_words: extra.into_iter().map(|j| heard[j].clone()).collect(),
+    }
+}
+
+fn transcribe(model: &str, audio: &Path, language: &str) -> Result<String, String> {
+    for program in WHISPER_PROGRAMS {
+        let output = Command::new(program)
+            .args(["-m", model, "-l", language, "--no-timestamps", "--no-prints", "-f"])
+            .arg(audio)
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                let transcript = strip_annotations(&String::from_utf8_lossy(&output.stdout));
+                return Ok(transcript.split_whitespace().collect::<Vec<_>>().join(" "));
+            }
+            Ok(output) => {
+                let message = String::from_utf8_lossy(&output.stderr);
+                return Err(format!("Speech recognition failed: {}", message.trim()));
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to run {}: {}", program, e)),
+        }
+    }
+    Err("Pronunciation practice needs whisper.cpp's whisper-cli on the PATH.".to_string())
+}
+
+// Transcribes a recording of the learner saying `text` with a local whisper.cpp model and
+// scores each word of `text` against what was recognized. `audio` is a WAV file (16 kHz mono
+// works best); `language` defaults to detecting it from the audio.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn score_pronunciation(
+    handle: tauri::AppHandle,
+    text: String,
+    audio: Vec<u8>,
+    language: Option<String>,
+) -> Result<PronunciationScore, String> {
+    if words(&text).is_empty() {
+        return Err("Nothing to practice: the text has no words.".to_string());
+    }
+    if !audio.starts_with(b"RIFF") || audio.get(8..12) != Some(b"WAVE".as_slice()) {
+        return Err("The recording must be a WAV file.".to_string());
+    }
+    let model = load_settings(&handle)?
+        .speech_recognition_model
+        .filter(|path| Path::new(path).exists())
+        .ok_or_else(|| "Choose a whisper.cpp model file in settings to practice pronunciation.".to_string())?;
+    let language = language.unwrap_or_else(|| "auto".to_string());
+
+    let path = std::env::temp_dir().join(format!("pdfread-pronunciation-{}.wav", Utc::now().format("%Y%m%d%H%M%S%f")));
+    fs::write(&path, &audio).map_err(|e| e.to_string())?;
+    let recording = path.clone();
+    let transcript = tauri::async_runtime::spawn_blocking(move || transcribe(&model, &recording, &language))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+    let _ = fs::remove_file(&path);
+    Ok(score(&text, transcript?))
+}
@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::error::Category;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::app_config_dir;
+use crate::app_state::AppState;
+use crate::encryption;
+use crate::vocab_index::VocabularyIndex;
+
+const QUARANTINE_DIR: &str = "quarantine";
+// Cut points tried, from the end, when salvaging a damaged file.
+const MAX_SALVAGE_ATTEMPTS: usize = 200;
+
+// Serializes updates to the quarantine list; loaders of different stores can fail at once.
+#[derive(Default)]
+pub struct Quarantine {
+    lock: Mutex<()>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedFile {
+    id: String,
+    original_path: String,
+    quarantined_path: String,
+    quarantined_at: DateTime<Utc>,
+    error: String,
+    #[serde(default)]
+    recovered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct QuarantineData {
+    files: Vec<QuarantinedFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCandidate {
+    #[serde(flatten)]
+    file: QuarantinedFile,
+    size: u64,
+    // Bytes of it that `recover_quarantined_file` would put back; None when nothing parses.
+    salvageable_bytes: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataRecoveryReport {
+    files: Vec<RecoveryCandidate>,
+}
+
+fn quarantine_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join(QUARANTINE_DIR))
+}
+
+fn quarantine_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(quarantine_dir(handle)?.join("quarantine.json"))
+}
+
+fn load_quarantine(handle: &tauri::AppHandle) -> Result<QuarantineData, String> {
+    let path = quarantine_file_path(handle)?;
+    if !path.exists() {
+        return Ok(QuarantineData::default());
+    }
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map_err(|e| e.to_string())
+}
+
+fn save_quarantine(handle: &tauri::AppHandle, data: &QuarantineData) -> Result<(), String> {
+    let path = quarantine_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// Moves a file that failed to parse into the quarantine folder under a timestamped name.
+fn quarantine(handle: &tauri::AppHandle, path: &Path, error: &str) -> Result<QuarantinedFile, String> {
+    let state = handle.state::<Quarantine>();
+    let _lock = state.lock.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = quarantine_dir(handle)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let now = Utc::now();
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let id = format!("{}-{}", now.format("%Y%m%dT%H%M%S%3f"), name);
+    let target = dir.join(&id);
+    fs::rename(path, &target).map_err(|e| format!("Failed to quarantine {}: {}", path.display(), e))?;
+
+    let file = QuarantinedFile {
+        id,
+        original_path: path.to_string_lossy().into_owned(),
+        quarantined_path: target.to_string_lossy().into_owned(),
+        quarantined_at: now,
+        error: error.to_string(),
+        recovered_at: None,
+    };
+    let mut data = load_quarantine(handle)?;
+    data.files.push(file.clone());
+    save_quarantine(handle, &data)?;
+    Ok(file)
+}
+
+// Parses a store file. One that is not valid JSON (damaged, or cut short mid-write) is
+// quarantined and the store starts fresh; `data-quarantined` tells the frontend to point the
+// user to the recovery report. Valid JSON of the wrong shape, e.g. written by another version,
+// is an error and the file stays where it is, so nothing in it is lost.
+pub fn parse_or_quarantine<T: DeserializeOwned + Default>(
+    handle: &tauri::AppHandle,
+    path: &Path,
+    data: &str,
+) -> Result<T, String> {
+    let error = match serde_json::from_str(data) {
+        Ok(value) => return Ok(value),
+        Err(e) if matches!(e.classify(), Category::Syntax | Category::Eof) => e.to_string(),
+        Err(e) => return Err(format!("{} is not in the expected format: {}", path.display(), e)),
+    };
+    let file = quarantine(handle, path, &error).map_err(|e| format!("{} ({})", error, e))?;
+    log::warn!("Quarantined {}: {}", file.original_path, error);
+    let _ = handle.emit("data-quarantined", file);
+    Ok(T::default())
+}
+
+fn closers(stack: &[char]) -> String {
+    stack.iter().rev().map(|&open| if open == '{' { '}' } else { ']' }).collect()
+}
+
+// The longest prefix of damaged JSON that still parses once its open objects and arrays are
+// closed, cut between elements: a file truncated mid-write keeps everything before the
+// damage. None when the text is valid JSON already or when no cut parses.
+fn salvage(text: &str) -> Option<String> {
+    if serde_json::from_str::<serde_json::Value>(text).is_ok() {
+        return None;
+    }
+    let mut stack = Vec::new();
+    let mut cuts = Vec::new();
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => stack.push(c),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ',' if !stack.is_empty() => cuts.push((i, closers(&stack))),
+            _ => {}
+        }
+    }
+    cuts.iter().rev().take(MAX_SALVAGE_ATTEMPTS).find_map(|(cut, closers)| {
+        let candidate = format!("{}{}", &text[..*cut], closers);
+        serde_json::from_str::<serde_json::Value>(&candidate).is_ok().then_some(candidate)
+    })
+}
+
+fn read_quarantined(handle: &tauri::AppHandle, path: &Path) -> Result<String, String> {
+    encryption::read_store_file(handle, path)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_data_recovery_report(handle: tauri::AppHandle) -> Result<DataRecoveryReport, String> {
+    let files = load_quarantine(&handle)?
+        .files
+        .into_iter()
+        .rev()
+        .map(|file| {
+            let path = Path::new(&file.quarantined_path);
+            let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            let salvageable_bytes = read_quarantined(&handle, path).ok().and_then(|text| salvage(&text)).map(|s| s.len());
+            RecoveryCandidate { file, size, salvageable_bytes }
+        })
+        .collect();
+    Ok(DataRecoveryReport { files })
+}
+
+// Puts what can be salvaged from a quarantined file back in its place, replacing whatever was
+// saved there since the fresh start. Stores reload from disk on their next use.
+#[tauri::command(rename_all = "camelCase")]
+pub fn recover_quarantined_file(handle: tauri::AppHandle, id: String) -> Result<QuarantinedFile, String> {
+    let mut data = load_quarantine(&handle)?;
+    let file = data
+        .files
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or_else(|| format!("No quarantined file: {}", id))?;
+    let quarantined = Path::new(&file.quarantined_path);
+    let text = read_quarantined(&handle, quarantined)?;
+    let salvaged = salvage(&text).ok_or_else(|| "Nothing in this file could be salvaged.".to_string())?;
+
+    handle.state::<AppState>().unload_all(&handle)?;
+    let original = Path::new(&file.original_path);
+    let was_encrypted = fs::read(quarantined).map(|bytes| encryption::is_encrypted(&bytes)).unwrap_or(false);
+    if was_encrypted {
+        encryption::write_store_file(&handle, original, &salvaged)?;
+    } else {
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(original, salvaged).map_err(|e| e.to_string())?;
+    }
+    handle.state::<VocabularyIndex>().invalidate();
+
+    file.recovered_at = Some(Utc::now());
+    let file = file.clone();
+    save_quarantine(&handle, &data)?;
+    Ok(file)
+}
+
+// Deletes a quarantined file for good.
+#[tauri::command(rename_all = "camelCase")]
+pub fn discard_quarantined_file(handle: tauri::AppHandle, id: String) -> Result<(), String> {
+    let mut data = load_quarantine(&handle)?;
+    let Some(index) = data.files.iter().position(|f| f.id == id) else {
+        return Err(format!("No quarantined file: {}", id));
+    };
+    let file = data.files.remove(index);
+    if let Err(e) = fs::remove_file(&file.quarantined_path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            return Err(e.to_string());
+        }
+    }
+    save_quarantine(&handle, &data)
+}
@@ -0,0 +1,265 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{Emitter, Manager};
+
+use crate::app_state::AppState;
+use crate::quarantine::parse_or_quarantine;
+use crate::settings::{load_settings, UsageCap, UsageCaps};
+use crate::sync_conflicts::with_write_lock;
+use crate::{app_config_dir, coded_error, ERR_QUOTA_EXCEEDED};
+
+// Features usage is counted under, besides the response cache's chat, summary and
+// explanation.
+pub const FEATURE_TRANSLATION: &str = "translation";
+pub const FEATURE_ALTERNATIVES: &str = "alternatives";
+pub const FEATURE_ENTITIES: &str = "entities";
+pub const FEATURE_GLOSS: &str = "gloss";
+pub const FEATURE_TRANSLITERATION: &str = "transliteration";
+pub const FEATURE_SETUP: &str = "setup";
+pub const FEATURE_PLUGINS: &str = "plugins";
+pub const FEATURE_OTHER: &str = "other";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Usage {
+    requests: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    // US dollars, when OpenRouter reported it.
+    cost: f64,
+    // Summed over requests; over `requests` it gives the average.
+    #[serde(default)]
+    latency_ms: u64,
+}
+
+impl Usage {
+    fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, other: &Usage) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+        self.latency_ms += other.latency_ms;
+    }
+
+    fn remove(&mut self, other: &Usage) {
+        self.requests = self.requests.saturating_sub(other.requests);
+        self.prompt_tokens = self.prompt_tokens.saturating_sub(other.prompt_tokens);
+        self.completion_tokens = self.completion_tokens.saturating_sub(other.completion_tokens);
+        self.cost = (self.cost - other.cost).max(0.0);
+        self.latency_ms = self.latency_ms.saturating_sub(other.latency_ms);
+    }
+
+    fn over(&self, cap: &UsageCap) -> bool {
+        cap.monthly_tokens.is_some_and(|limit| self.tokens() >= limit)
+            || cap.monthly_spend.is_some_and(|limit| self.cost >= limit)
+    }
+
+    // Whether `request` on top of this usage would go past the cap. A request that lands exactly
+    // on it still fits; once the cap is reached nothing more does.
+    fn over_with(&self, request: &Usage, cap: &UsageCap) -> bool {
+        self.over(cap)
+            || cap.monthly_tokens.is_some_and(|limit| self.tokens() + request.tokens() > limit)
+            || cap.monthly_spend.is_some_and(|limit| self.cost + request.cost > limit)
+    }
+}
+
+// OpenRouter's `usage` block on a completion.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ReportedUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    cost: Option<f64>,
+}
+
+impl ReportedUsage {
+    pub fn cost(&self) -> Option<f64> {
+        self.cost
+    }
+
+    // For a cost looked up after the fact, when the completion did not include it.
+    pub fn set_cost(&mut self, cost: Option<f64>) {
+        self.cost = self.cost.or(cost);
+    }
+}
+
+// Usage per calendar month ("2026-10") and feature.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UsageData {
+    months: BTreeMap<String, BTreeMap<String, Usage>>,
+    // Estimates of the requests under way, by feature; never written.
+    #[serde(skip)]
+    reserved: BTreeMap<String, Usage>,
+}
+
+// A request's estimated usage, counted against the caps from `check` until the request is
+// done; `record` then adds what it actually used.
+pub struct Reservation {
+    handle: tauri::AppHandle,
+    feature: String,
+    usage: Usage,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let result = self.handle.state::<AppState>().usage.update(&self.handle, |data| {
+            if let Some(reserved) = data.reserved.get_mut(&self.feature) {
+                reserved.remove(&self.usage);
+            }
+        });
+        if let Err(e) = result {
+            log::error!("Failed to release usage reservation: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JobPaused {
+    job_id: String,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageSummary {
+    month: String,
+    total: Usage,
+    features: BTreeMap<String, Usage>,
+    caps: UsageCaps,
+    // "overall" and/or the features whose cap is reached.
+    exceeded: Vec<String>,
+}
+
+fn usage_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("usage.json"))
+}
+
+pub fn read_usage_file(handle: &tauri::AppHandle) -> Result<UsageData, String> {
+    let path = usage_file_path(handle)?;
+    if !path.exists() {
+        return Ok(UsageData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn write_usage_file(handle: &tauri::AppHandle, data: &UsageData) -> Result<(), String> {
+    let path = usage_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    with_write_lock(&path, || fs::write(&path, data).map_err(|e| e.to_string()))
+}
+
+fn current_month() -> String {
+    Utc::now().format("%Y-%m").to_string()
+}
+
+fn month_usage(handle: &tauri::AppHandle, month: &str) -> Result<BTreeMap<String, Usage>, String> {
+    handle
+        .state::<AppState>()
+        .usage
+        .read(handle, |data| data.months.get(month).cloned().unwrap_or_default())
+}
+
+fn total(features: &BTreeMap<String, Usage>) -> Usage {
+    features.values().fold(Usage::default(), |mut total, usage| {
+        total.add(usage);
+        total
+    })
+}
+
+fn exceeded(caps: &UsageCaps, features: &BTreeMap<String, Usage>) -> Vec<String> {
+    let mut exceeded = Vec::new();
+    if total(features).over(&caps.overall) {
+        exceeded.push("overall".to_string());
+    }
+    for (feature, cap) in &caps.features {
+        if features.get(feature).is_some_and(|usage| usage.over(cap)) {
+            exceeded.push(feature.clone());
+        }
+    }
+    exceeded
+}
+
+// Fails with QUOTA_EXCEEDED when this request would take this month's usage past the overall
+// cap or the feature's cap, counting the requests still under way. Otherwise reserves
+// `estimated_tokens` for this request, priced at this month's average cost per token, until the
+// reservation is dropped. Checking and reserving happen under one lock, so concurrent requests
+// cannot all slip under.
+pub fn check(handle: &tauri::AppHandle, feature: &str, estimated_tokens: u64) -> Result<Reservation, String> {
+    let caps = load_settings(handle)?.usage_caps;
+    let month = current_month();
+    let reserved = handle.state::<AppState>().usage.update(handle, |data| {
+        let mut features = data.months.get(&month).cloned().unwrap_or_default();
+        let spent = total(&features);
+        for (name, reserved) in &data.reserved {
+            features.entry(name.clone()).or_default().add(reserved);
+        }
+        let cost_per_token = if spent.tokens() > 0 { spent.cost / spent.tokens() as f64 } else { 0.0 };
+        let usage = Usage {
+            requests: 1,
+            prompt_tokens: estimated_tokens,
+            cost: estimated_tokens as f64 * cost_per_token,
+            ..Default::default()
+        };
+        if total(&features).over_with(&usage, &caps.overall) {
+            return Err(coded_error(ERR_QUOTA_EXCEEDED, "The monthly usage limit has been reached."));
+        }
+        let used = features.get(feature).cloned().unwrap_or_default();
+        if caps.features.get(feature).is_some_and(|cap| used.over_with(&usage, cap)) {
+            let message = format!("The monthly usage limit for {} has been reached.", feature);
+            return Err(coded_error(ERR_QUOTA_EXCEEDED, &message));
+        }
+        data.reserved.entry(feature.to_string()).or_default().add(&usage);
+        Ok(usage)
+    })??;
+    Ok(Reservation { handle: handle.clone(), feature: feature.to_string(), usage: reserved })
+}
+
+pub fn is_quota_error(message: &str) -> bool {
+    message.starts_with(ERR_QUOTA_EXCEEDED)
+}
+
+// Background jobs stopped by a cap announce it with `job-paused` instead of their error event;
+// resumable jobs stay pending for when the cap is raised or the month turns.
+pub fn announce_paused(handle: &tauri::AppHandle, job_id: &str, message: &str) {
+    let paused = JobPaused { job_id: job_id.to_string(), message: message.to_string() };
+    let _ = handle.emit("job-paused", paused);
+}
+
+// Adds one completion to this month's usage; requests without a usage block still count.
+pub fn record(handle: &tauri::AppHandle, feature: &str, reported: &ReportedUsage, latency_ms: u64) {
+    let usage = Usage {
+        requests: 1,
+        prompt_tokens: reported.prompt_tokens,
+        completion_tokens: reported.completion_tokens,
+        cost: reported.cost.unwrap_or(0.0),
+        latency_ms,
+    };
+    let result = handle.state::<AppState>().usage.update(handle, |data| {
+        let month = data.months.entry(current_month()).or_default();
+        month.entry(feature.to_string()).or_default().add(&usage);
+    });
+    if let Err(e) = result {
+        log::error!("Failed to record usage: {}", e);
+    }
+}
+
+// Usage for `month` ("2026-10"; this month when not given) against the caps in settings.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_usage_summary(handle: tauri::AppHandle, month: Option<String>) -> Result<UsageSummary, String> {
+    let month = month.unwrap_or_else(current_month);
+    let caps = load_settings(&handle)?.usage_caps;
+    let features = month_usage(&handle, &month)?;
+    let exceeded = if month == current_month() { exceeded(&caps, &features) } else { Vec::new() };
+    Ok(UsageSummary { month, total: total(&features), features, caps, exceeded })
+}
@@ -0,0 +1,287 @@
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use serde::Deserialize;
+use std::fs;
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+use crate::hash_source_text;
+
+const PADDING: f32 = 96.0;
+const ACCENT_BAR_WIDTH: f32 = 16.0;
+const ATTRIBUTION_SIZE: f32 = 30.0;
+const LINE_SPACING: f32 = 1.3;
+// Longer selections are cut off; a card is not the place for a whole page.
+const MAX_QUOTE_CHARS: usize = 600;
+const MIN_WIDTH: u32 = 480;
+const MAX_WIDTH: u32 = 2400;
+
+// Serif fonts that ship with each system; `QuoteCardStyle::font_path` overrides them, e.g.
+// with a CJK font for quotes they have no glyphs for.
+#[cfg(target_os = "macos")]
+const FONT_CANDIDATES: &[&str] = &[
+    "/System/Library/Fonts/Supplemental/Georgia.ttf",
+    "/System/Library/Fonts/Supplemental/Times New Roman.ttf",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+];
+#[cfg(target_os = "windows")]
+const FONT_CANDIDATES: &[&str] =
+    &["C:\\Windows\\Fonts\\georgia.ttf", "C:\\Windows\\Fonts\\times.ttf", "C:\\Windows\\Fonts\\arial.ttf"];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSerif.ttf",
+    "/usr/share/fonts/TTF/DejaVuSerif.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSerif.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSerif-Regular.ttf",
+    "/usr/share/fonts/noto/NotoSerif-Regular.ttf",
+];
+
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteCardTheme {
+    #[default]
+    Light,
+    Dark,
+    Sepia,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuoteBookMetadata {
+    title: String,
+    #[serde(default)]
+    author: Option<String>,
+    // "#rrggbb" picked from the cover; without one a color is derived from the title.
+    #[serde(default)]
+    accent_color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct QuoteCardStyle {
+    theme: QuoteCardTheme,
+    // Pixels; the height follows from the text.
+    width: u32,
+    font_path: Option<String>,
+}
+
+impl Default for QuoteCardStyle {
+    fn default() -> Self {
+        Self { theme: QuoteCardTheme::Light, width: 1080, font_path: None }
+    }
+}
+
+type Rgb = [u8; 3];
+
+// Background, quote and attribution colors.
+fn palette(theme: QuoteCardTheme) -> (Rgb, Rgb, Rgb) {
+    match theme {
+        QuoteCardTheme::Light => ([0xfa, 0xfa, 0xf7], [0x1f, 0x1f, 0x1f], [0x6b, 0x6b, 0x6b]),
+        QuoteCardTheme::Dark => ([0x1c, 0x1c, 0x1e], [0xf2, 0xf2, 0xf2], [0xa0, 0xa0, 0xa0]),
+        QuoteCardTheme::Sepia => ([0xf4, 0xec, 0xd8], [0x3b, 0x2f, 0x22], [0x7a, 0x6a, 0x55]),
+    }
+}
+
+fn parse_hex_color(color: &str) -> Option<Rgb> {
+    let hex = color.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// A stable, reasonably saturated color per title, for books without a cover accent.
+fn title_color(title: &str) -> Rgb {
+    let hash = hash_source_text(title);
+    let hue = u32::from_str_radix(&hash[..4], 16).unwrap_or(0) as f32 / 65536.0 * 360.0;
+    let (chroma, lightness) = (0.55, 0.45);
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+// The font file at `font_path`, or the first system serif font found, parsed and as bytes.
+pub fn load_font(font_path: Option<&str>) -> Result<(FontVec, Vec<u8>), String> {
+    let paths: Vec<&str> = match font_path {
+        Some(path) => vec![path],
+        None => FONT_CANDIDATES.to_vec(),
+    };
+    for path in paths {
+        if let Ok(data) = fs::read(path) {
+            let font = FontVec::try_from_vec(data.clone()).map_err(|_| format!("Not a usable font file: {}", path))?;
+            return Ok((font, data));
+        }
+    }
+    Err("No font found; choose a font file.".to_string())
+}
+
+pub fn text_width(font: &FontVec, size: f32, text: &str) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// Greedy word wrap. Words wider than a line, and text written without spaces, are broken
+// between characters.
+pub fn wrap(font: &FontVec, size: f32, text: &str, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if text_width(font, size, &candidate) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            line.push(c);
+            if text_width(font, size, &line) > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, c.to_string()));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn line_height(font: &FontVec, size: f32) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    (scaled.ascent() - scaled.descent() + scaled.line_gap()) * LINE_SPACING
+}
+
+// Draws one line of text with its baseline at `baseline`, blending glyph coverage over the
+// (opaque) background.
+fn draw_text(pixmap: &mut Pixmap, font: &FontVec, size: f32, text: &str, x: f32, baseline: f32, color: Rgb) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let data = pixmap.data_mut();
+    let mut caret = x;
+    let mut previous: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(size, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let (px, py) = (bounds.min.x as i32 + gx as i32, bounds.min.y as i32 + gy as i32);
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+            let offset = ((py * width + px) * 4) as usize;
+            let alpha = coverage.clamp(0.0, 1.0);
+            for (channel, &value) in color.iter().enumerate() {
+                let dst = &mut data[offset + channel];
+                *dst = (value as f32 * alpha + *dst as f32 * (1.0 - alpha)).round() as u8;
+            }
+        });
+    }
+}
+
+fn truncate_quote(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_QUOTE_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_QUOTE_CHARS).collect();
+    // Back up to a word boundary when there is one nearby.
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > cut.len() * 4 / 5 => cut[..space].to_string(),
+        _ => cut,
+    };
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace()))
+}
+
+// Renders a selection as a PNG quote card: the quote, then the book's title and author, with
+// an accent bar in the cover's color. Done here rather than on a canvas in the webview.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_quote_card(
+    text: String,
+    book_metadata: QuoteBookMetadata,
+    style: Option<QuoteCardStyle>,
+) -> Result<Vec<u8>, String> {
+    let quote = truncate_quote(&text);
+    if quote.is_empty() {
+        return Err("Select some text for the quote card.".to_string());
+    }
+    let style = style.unwrap_or_default();
+    let (font, _) = load_font(style.font_path.as_deref())?;
+    let (background, foreground, muted) = palette(style.theme);
+    let accent = book_metadata
+        .accent_color
+        .as_deref()
+        .and_then(parse_hex_color)
+        .unwrap_or_else(|| title_color(&book_metadata.title));
+
+    let width = style.width.clamp(MIN_WIDTH, MAX_WIDTH) as f32;
+    let text_left = PADDING + ACCENT_BAR_WIDTH + PADDING / 2.0;
+    let text_width = width - text_left - PADDING;
+    // Short quotes are set large; long ones smaller so the card stays a sensible shape.
+    let quote_size = match quote.chars().count() {
+        0..=120 => 56.0,
+        121..=300 => 44.0,
+        _ => 34.0,
+    };
+    let quote_lines = wrap(&font, quote_size, &format!("“{}”", quote), text_width);
+    let title = match book_metadata.author.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        Some(author) => format!("— {}, {}", book_metadata.title.trim(), author),
+        None => format!("— {}", book_metadata.title.trim()),
+    };
+    let attribution_lines = wrap(&font, ATTRIBUTION_SIZE, &title, text_width);
+
+    let quote_line = line_height(&font, quote_size);
+    let attribution_line = line_height(&font, ATTRIBUTION_SIZE);
+    let text_height = quote_line * quote_lines.len() as f32
+        + ATTRIBUTION_SIZE * 1.5
+        + attribution_line * attribution_lines.len() as f32;
+    let height = (text_height + PADDING * 2.0).ceil();
+
+    let mut pixmap =
+        Pixmap::new(width as u32, height as u32).ok_or_else(|| "The quote card is too large.".to_string())?;
+    pixmap.fill(Color::from_rgba8(background[0], background[1], background[2], 255));
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(accent[0], accent[1], accent[2], 255);
+    if let Some(bar) = Rect::from_xywh(PADDING, PADDING, ACCENT_BAR_WIDTH, height - PADDING * 2.0) {
+        pixmap.fill_rect(bar, &paint, Transform::identity(), None);
+    }
+
+    let ascent = |size: f32| font.as_scaled(PxScale::from(size)).ascent();
+    let mut top = PADDING;
+    for line in &quote_lines {
+        draw_text(&mut pixmap, &font, quote_size, line, text_left, top + ascent(quote_size), foreground);
+        top += quote_line;
+    }
+    top += ATTRIBUTION_SIZE * 1.5;
+    for line in &attribution_lines {
+        draw_text(&mut pixmap, &font, ATTRIBUTION_SIZE, line, text_left, top + ascent(ATTRIBUTION_SIZE), muted);
+        top += attribution_line;
+    }
+
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
@@ -0,0 +1,552 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::book_text::load_book_text;
+use crate::language::detect_language;
+use crate::segmentation::segment;
+use crate::settings::{self, TranslationGranularity};
+use crate::skip_ranges::load_skip_ranges;
+use crate::{translate_sentences, TargetLanguage, TranslateSentence};
+
+// Sentences synthesized ahead of the one being spoken.
+const BUFFER_AHEAD: usize = 3;
+// How often the player checks whether the sentence finished or was skipped.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Rate of the system voices at speed 1.0.
+const BASE_WORDS_PER_MINUTE: f32 = 175.0;
+const MIN_SPEED: f32 = 0.5;
+const MAX_SPEED: f32 = 2.5;
+
+#[derive(Debug, Clone)]
+pub struct Utterance {
+    pub sid: String,
+    pub page: u32,
+    pub text: String,
+    // System voice name; None for the default voice.
+    pub voice: Option<String>,
+    // Speaks the translation of `text` rather than `text`; it is looked up or fetched just
+    // before synthesis.
+    pub translated: bool,
+}
+
+// Model and language translated utterances are translated with.
+#[derive(Debug, Clone)]
+pub struct Translator {
+    pub model: String,
+    pub temperature: f32,
+    pub target_language: TargetLanguage,
+}
+
+// A synthesized utterance; its audio file is removed along with it.
+struct Clip {
+    index: usize,
+    speed: f32,
+    path: PathBuf,
+}
+
+impl Drop for Clip {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct Session {
+    id: u64,
+    book_id: String,
+    utterances: Vec<Utterance>,
+    // The utterance being spoken, or the next one to be.
+    position: usize,
+    speed: f32,
+    translator: Option<Translator>,
+    ahead: VecDeque<Clip>,
+    playing: Option<(Clip, Child)>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Some((_, mut player)) = self.playing.take() {
+            let _ = player.kill();
+        }
+    }
+}
+
+impl Session {
+    fn state(&self) -> ReadAloudState {
+        let current = self.utterances.get(self.position);
+        ReadAloudState {
+            book_id: self.book_id.clone(),
+            sid: current.map(|u| u.sid.clone()),
+            page: current.map(|u| u.page),
+            index: self.position,
+            total: self.utterances.len(),
+            speed: self.speed,
+        }
+    }
+
+    // Drops the buffered clips and whatever is playing, e.g. before jumping elsewhere.
+    fn interrupt(&mut self) {
+        self.ahead.clear();
+        if let Some((_, mut player)) = self.playing.take() {
+            let _ = player.kill();
+        }
+    }
+}
+
+// The one read-aloud session; starting another replaces it.
+#[derive(Default)]
+pub struct ReadAloud {
+    session: Mutex<Option<Session>>,
+    next_id: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadAloudState {
+    book_id: String,
+    sid: Option<String>,
+    page: Option<u32>,
+    index: usize,
+    total: usize,
+    speed: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NowSpeaking {
+    book_id: String,
+    sid: String,
+    page: u32,
+    index: usize,
+    // Whether the sentence's translation is being spoken rather than the sentence.
+    translated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReadAloudFinished {
+    book_id: String,
+    // Set when synthesis or playback failed; otherwise the end was reached.
+    error: Option<String>,
+}
+
+enum Step {
+    Wait,
+    Speaking(NowSpeaking),
+    Finished,
+    Failed(String),
+}
+
+#[cfg(target_os = "macos")]
+const AUDIO_EXTENSION: &str = "aiff";
+#[cfg(not(target_os = "macos"))]
+const AUDIO_EXTENSION: &str = "wav";
+
+#[cfg(not(target_os = "windows"))]
+fn words_per_minute(speed: f32) -> u32 {
+    (BASE_WORDS_PER_MINUTE * speed).round() as u32
+}
+
+#[cfg(target_os = "macos")]
+fn synthesis_command(voice: Option<&str>, speed: f32, output: &Path) -> Command {
+    let mut command = Command::new("say");
+    command.arg("-r").arg(words_per_minute(speed).to_string()).arg("-o").arg(output).args(["-f", "-"]);
+    if let Some(voice) = voice {
+        command.arg("-v").arg(voice);
+    }
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn player_command(path: &Path) -> Command {
+    let mut command = Command::new("afplay");
+    command.arg(path);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn powershell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+#[cfg(target_os = "windows")]
+fn synthesis_command(voice: Option<&str>, speed: f32, output: &Path) -> Command {
+    // SAPI rates run from -10 to 10, where 10 is about three times the normal rate.
+    let rate = (10.0 * speed.ln() / 3f32.ln()).round().clamp(-10.0, 10.0);
+    let voice = voice.map(|v| format!("$s.SelectVoice({});", powershell_quote(v))).unwrap_or_default();
+    // stdin is read in the console code page otherwise, which mangles anything outside it.
+    let script = format!(
+        "[Console]::InputEncoding = [System.Text.Encoding]::UTF8; \
+         Add-Type -AssemblyName System.Speech; $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+         $s.Rate = {}; {} $s.SetOutputToWaveFile({}); $s.Speak([Console]::In.ReadToEnd()); $s.Dispose()",
+        rate,
+        voice,
+        powershell_quote(&output.to_string_lossy())
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command"]).arg(script);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn player_command(path: &Path) -> Command {
+    let script = format!("(New-Object Media.SoundPlayer {}).PlaySync()", powershell_quote(&path.to_string_lossy()));
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command"]).arg(script);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn synthesis_command(voice: Option<&str>, speed: f32, output: &Path) -> Command {
+    let mut command = Command::new("espeak-ng");
+    command.arg("-s").arg(words_per_minute(speed).to_string()).arg("-w").arg(output).arg("--stdin");
+    if let Some(voice) = voice {
+        command.arg("-v").arg(voice);
+    }
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn player_command(path: &Path) -> Command {
+    let mut command = Command::new("aplay");
+    command.arg("-q").arg(path);
+    command
+}
+
+fn clip_path(session_id: u64, index: usize) -> PathBuf {
+    std::env::temp_dir().join("pdfread-read-aloud").join(format!("{}-{}.{}", session_id, index, AUDIO_EXTENSION))
+}
+
+// Renders the utterance to an audio file with the system speech synthesizer. The text goes
+// through stdin so it never has to be quoted for a command line.
+fn synthesize(utterance: &Utterance, speed: f32, output: &Path) -> Result<(), String> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut command = synthesis_command(utterance.voice.as_deref(), speed, output);
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run the speech synthesizer {:?}: {}", command.get_program(), e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(utterance.text.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() || !output.exists() {
+        return Err(format!("Speech synthesis failed for sentence {}", utterance.sid));
+    }
+    Ok(())
+}
+
+// The translation of the utterance's text, through the translation cache.
+async fn translation_of(
+    handle: &tauri::AppHandle,
+    translator: &Translator,
+    utterance: &Utterance,
+) -> Result<String, String> {
+    let sentence = TranslateSentence { sid: utterance.sid.clone(), text: utterance.text.clone(), ..Default::default() };
+    let translated = translate_sentences(
+        handle,
+        &translator.model,
+        translator.temperature,
+        &translator.target_language,
+        vec![sentence],
+    )
+    .await?;
+    translated
+        .into_iter()
+        .next()
+        .map(|item| item.translation)
+        .ok_or_else(|| format!("No translation came back for sentence {}", utterance.sid))
+}
+
+fn with_session<T>(handle: &tauri::AppHandle, id: u64, f: impl FnOnce(&mut Session) -> T) -> Option<T> {
+    let state = handle.state::<ReadAloud>();
+    let mut session = state.session.lock().unwrap_or_else(|e| e.into_inner());
+    session.as_mut().filter(|s| s.id == id).map(f)
+}
+
+fn with_current<T>(handle: &tauri::AppHandle, f: impl FnOnce(&mut Session) -> T) -> Result<T, String> {
+    let state = handle.state::<ReadAloud>();
+    let mut session = state.session.lock().unwrap_or_else(|e| e.into_inner());
+    session.as_mut().map(f).ok_or_else(|| "Nothing is being read aloud.".to_string())
+}
+
+fn finish(handle: &tauri::AppHandle, id: u64, error: Option<String>) {
+    let state = handle.state::<ReadAloud>();
+    let mut session = state.session.lock().unwrap_or_else(|e| e.into_inner());
+    if session.as_ref().is_some_and(|s| s.id == id) {
+        if let Some(session) = session.take() {
+            let _ = handle.emit("read-aloud-finished", ReadAloudFinished { book_id: session.book_id.clone(), error });
+        }
+    }
+}
+
+// Keeps up to BUFFER_AHEAD clips synthesized past the utterance being spoken. A clip made for
+// a position or speed that changed while it was being synthesized is thrown away.
+async fn synthesize_ahead(handle: tauri::AppHandle, id: u64) {
+    loop {
+        let next = with_session(&handle, id, |s| {
+            let index = s.ahead.back().map_or(s.position + usize::from(s.playing.is_some()), |clip| clip.index + 1);
+            if s.ahead.len() >= BUFFER_AHEAD {
+                return None;
+            }
+            s.utterances.get(index).map(|u| (index, u.clone(), s.speed, s.translator.clone()))
+        });
+        let Some(next) = next else {
+            return;
+        };
+        let Some((index, mut utterance, speed, translator)) = next else {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        };
+
+        let result = async {
+            if let (true, Some(translator)) = (utterance.translated, &translator) {
+                utterance.text = translation_of(&handle, translator, &utterance).await?;
+            }
+            let path = clip_path(id, index);
+            let output = path.clone();
+            tauri::async_runtime::spawn_blocking(move || synthesize(&utterance, speed, &output))
+                .await
+                .map_err(|e| e.to_string())??;
+            Ok::<PathBuf, String>(path)
+        }
+        .await;
+        let clip = match result {
+            Ok(path) => Clip { index, speed, path },
+            Err(e) => {
+                finish(&handle, id, Some(e));
+                return;
+            }
+        };
+        with_session(&handle, id, |s| {
+            let expected = s.ahead.back().map_or(s.position + usize::from(s.playing.is_some()), |c| c.index + 1);
+            if clip.index == expected && clip.speed == s.speed {
+                s.ahead.push_back(clip);
+            }
+        });
+    }
+}
+
+fn advance(session: &mut Session) -> Step {
+    if let Some((_, player)) = &mut session.playing {
+        if let Ok(None) = player.try_wait() {
+            return Step::Wait;
+        }
+        session.playing = None;
+        session.position += 1;
+    }
+    let Some(utterance) = session.utterances.get(session.position).cloned() else {
+        return Step::Finished;
+    };
+    if session.ahead.front().is_none_or(|clip| clip.index != session.position) {
+        return Step::Wait;
+    }
+    let Some(clip) = session.ahead.pop_front() else {
+        return Step::Wait;
+    };
+    let mut command = player_command(&clip.path);
+    match command.stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+        Ok(player) => {
+            let event = NowSpeaking {
+                book_id: session.book_id.clone(),
+                sid: utterance.sid,
+                page: utterance.page,
+                index: session.position,
+                translated: utterance.translated,
+            };
+            session.playing = Some((clip, player));
+            Step::Speaking(event)
+        }
+        Err(e) => Step::Failed(format!("Failed to play speech with {:?}: {}", command.get_program(), e)),
+    }
+}
+
+async fn play(handle: tauri::AppHandle, id: u64) {
+    loop {
+        let Some(step) = with_session(&handle, id, advance) else {
+            return;
+        };
+        match step {
+            Step::Wait => tokio::time::sleep(POLL_INTERVAL).await,
+            Step::Speaking(event) => {
+                let _ = handle.emit("now-speaking", event);
+            }
+            Step::Finished => return finish(&handle, id, None),
+            Step::Failed(e) => return finish(&handle, id, Some(e)),
+        }
+    }
+}
+
+fn check_speed(speed: f32) -> Result<f32, String> {
+    if !speed.is_finite() {
+        return Err(format!("Invalid speed: {}", speed));
+    }
+    Ok(speed.clamp(MIN_SPEED, MAX_SPEED))
+}
+
+// The book's paragraphs from `from_page` on, each with its sentences; tables, code and skipped pages
+// left out. Sentences of a paragraph that splits get sids `<sid>#s<n>`, as in sentence-level translation.
+fn book_paragraphs(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    from_page: u32,
+) -> Result<Vec<(Utterance, Vec<Utterance>)>, String> {
+    let text = load_book_text(handle, book_id)?;
+    let sample: Vec<&str> = text.pages.iter().flat_map(|p| &p.paragraphs).take(50).map(|p| p.text.as_str()).collect();
+    let lang = detect_language(&sample.join(" ")).unwrap_or_else(|| "en".to_string());
+
+    let skipped = load_skip_ranges(handle, book_id)?;
+    let mut paragraphs = Vec::new();
+    for page in text.pages.iter().filter(|p| p.page >= from_page && !skipped.skips_page(p.page)) {
+        for paragraph in page.paragraphs.iter().filter(|p| !p.kind.is_verbatim()) {
+            let whole = Utterance {
+                sid: paragraph.sid.clone(),
+                page: page.page,
+                text: paragraph.text.clone(),
+                voice: None,
+                translated: false,
+            };
+            let sentences = segment(&paragraph.text, &lang, None);
+            let split = sentences.len() > 1;
+            let sentences = sentences
+                .into_iter()
+                .enumerate()
+                .map(|(index, sentence)| Utterance {
+                    sid: if split { format!("{}#s{}", paragraph.sid, index) } else { paragraph.sid.clone() },
+                    text: sentence.text,
+                    ..whole.clone()
+                })
+                .collect();
+            paragraphs.push((whole, sentences));
+        }
+    }
+    Ok(paragraphs)
+}
+
+// The book's sentences from `from_page` on.
+pub fn book_utterances(handle: &tauri::AppHandle, book_id: &str, from_page: u32) -> Result<Vec<Utterance>, String> {
+    Ok(book_paragraphs(handle, book_id, from_page)?.into_iter().flat_map(|(_, sentences)| sentences).collect())
+}
+
+// Starts reading `utterances` aloud, stopping any session already running.
+pub fn start(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    utterances: Vec<Utterance>,
+    speed: f32,
+    translator: Option<Translator>,
+) -> Result<ReadAloudState, String> {
+    if utterances.is_empty() {
+        return Err(format!("No extracted text to read in book: {}", book_id));
+    }
+    let state = handle.state::<ReadAloud>();
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+    let session = Session {
+        id,
+        book_id: book_id.to_string(),
+        utterances,
+        position: 0,
+        speed: check_speed(speed)?,
+        translator,
+        ahead: VecDeque::new(),
+        playing: None,
+    };
+    let snapshot = session.state();
+    *state.session.lock().unwrap_or_else(|e| e.into_inner()) = Some(session);
+    tauri::async_runtime::spawn(synthesize_ahead(handle.clone(), id));
+    tauri::async_runtime::spawn(play(handle.clone(), id));
+    Ok(snapshot)
+}
+
+// Reads the book aloud sentence by sentence from `from_page` with the system voice, emitting
+// "now-speaking" with each sentence's sid as it starts so the reader can highlight it.
+#[tauri::command(rename_all = "camelCase")]
+pub fn start_read_aloud(
+    handle: tauri::AppHandle,
+    book_id: String,
+    from_page: u32,
+    speed: Option<f32>,
+) -> Result<ReadAloudState, String> {
+    let utterances = book_utterances(&handle, &book_id, from_page)?;
+    start(&handle, &book_id, utterances, speed.unwrap_or(1.0), None)
+}
+
+// Shadowing practice: each sentence from `from_page` is spoken and then its translation, in
+// separate voices. Translations come from the cache, or are fetched just ahead of playback
+// when missing. "now-speaking" tells the two apart with `translated`.
+#[tauri::command(rename_all = "camelCase")]
+pub fn start_bilingual_read_aloud(
+    handle: tauri::AppHandle,
+    book_id: String,
+    from_page: u32,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+    source_voice: Option<String>,
+    translation_voice: Option<String>,
+    speed: Option<f32>,
+) -> Result<ReadAloudState, String> {
+    // Translations are spoken per unit of the reader's translation granularity, so the ones the
+    // reader already cached are reused: each sentence with sentence-level translation, otherwise
+    // the whole paragraph after its last sentence.
+    let by_sentence = settings::load_settings(&handle)?.translation_granularity == TranslationGranularity::Sentence;
+    let mut utterances = Vec::new();
+    for (paragraph, sentences) in book_paragraphs(&handle, &book_id, from_page)? {
+        for sentence in sentences {
+            let translation = Utterance { voice: translation_voice.clone(), translated: true, ..sentence.clone() };
+            utterances.push(Utterance { voice: source_voice.clone(), ..sentence });
+            if by_sentence {
+                utterances.push(translation);
+            }
+        }
+        if !by_sentence {
+            utterances.push(Utterance { voice: translation_voice.clone(), translated: true, ..paragraph });
+        }
+    }
+    let translator = Translator { model, temperature, target_language };
+    start(&handle, &book_id, utterances, speed.unwrap_or(1.0), Some(translator))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn stop_read_aloud(handle: tauri::AppHandle) -> Result<(), String> {
+    let state = handle.state::<ReadAloud>();
+    state.session.lock().unwrap_or_else(|e| e.into_inner()).take();
+    Ok(())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_read_aloud_state(handle: tauri::AppHandle) -> Result<Option<ReadAloudState>, String> {
+    let state = handle.state::<ReadAloud>();
+    let session = state.session.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(session.as_ref().map(Session::state))
+}
+
+// Takes effect from the next sentence; the buffered ones are synthesized again.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_read_aloud_speed(handle: tauri::AppHandle, speed: f32) -> Result<ReadAloudState, String> {
+    let speed = check_speed(speed)?;
+    with_current(&handle, |s| {
+        s.speed = speed;
+        s.ahead.clear();
+        s.state()
+    })
+}
+
+// Moves `by` sentences (1 when not given; negative goes back) and speaks from there.
+#[tauri::command(rename_all = "camelCase")]
+pub fn skip_read_aloud_sentence(handle: tauri::AppHandle, by: Option<i64>) -> Result<ReadAloudState, String> {
+    with_current(&handle, |s| {
+        let target = (s.position as i64 + by.unwrap_or(1)).clamp(0, s.utterances.len() as i64);
+        s.interrupt();
+        s.position = target as usize;
+        s.state()
+    })
+}
@@ -0,0 +1,217 @@
+use crate::epub::{attribute, html_blocks};
+use crate::structure::BlockKind;
+
+// Elements that are page furniture rather than the article.
+const FURNITURE_TAGS: &[&str] = &["nav", "header", "footer", "aside", "form", "noscript", "iframe", "button", "dialog"];
+// Containers whose class or id says they hold comments, sharing buttons and the like.
+const FURNITURE_MARKERS: &[&str] = &[
+    "comment", "sidebar", "share", "social", "related", "promo", "newsletter", "subscribe", "cookie", "banner",
+    "advert", "sponsor", "footer", "menu", "breadcrumb", "popup", "modal",
+];
+const STRAY_LABELS: &[&str] = &[
+    "advertisement", "share", "tweet", "subscribe", "sign up", "sign in", "log in", "read more", "comments", "print",
+    "email", "follow us", "related",
+];
+const MARKED_TAGS: &[&str] = &["div", "section", "ul", "ol", "p", "span", "table"];
+
+pub struct ArticleBlock {
+    pub kind: BlockKind,
+    pub text: String,
+}
+
+// An image of the article, absolute URL, placed before the block at `position`.
+pub struct ArticleImage {
+    pub url: String,
+    pub position: usize,
+}
+
+pub struct Article {
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    pub blocks: Vec<ArticleBlock>,
+    pub images: Vec<ArticleImage>,
+}
+
+// Stands in for an image among the blocks, followed by the image's index.
+const IMAGE_MARKER: char = '\u{FFFC}';
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+// Byte range of the element opening at `start` (a `<`), through its matching close tag.
+fn element_end(html: &str, start: usize, name: &str) -> usize {
+    let mut depth = 0;
+    let mut position = start;
+    while let Some(offset) = html[position..].find('<') {
+        let open = position + offset;
+        let Some(close) = html[open..].find('>').map(|c| open + c) else {
+            return html.len();
+        };
+        let tag = &html[open + 1..close];
+        if tag_name(tag) == name {
+            if tag.starts_with('/') {
+                depth -= 1;
+            } else if !tag.ends_with('/') {
+                depth += 1;
+            }
+            if depth == 0 {
+                return close + 1;
+            }
+        }
+        position = close + 1;
+    }
+    html.len()
+}
+
+fn is_furniture(tag: &str) -> bool {
+    let name = tag_name(tag);
+    if FURNITURE_TAGS.contains(&name.as_str()) {
+        return true;
+    }
+    if !MARKED_TAGS.contains(&name.as_str()) {
+        return false;
+    }
+    let labels = format!(
+        "{} {}",
+        attribute(tag, "class").unwrap_or_default(),
+        attribute(tag, "id").unwrap_or_default()
+    )
+    .to_lowercase();
+    let hidden = attribute(tag, "aria-hidden").as_deref() == Some("true") || attribute(tag, "hidden").is_some();
+    hidden || FURNITURE_MARKERS.iter().any(|marker| labels.contains(marker))
+}
+
+fn strip_furniture(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut position = 0;
+    while let Some(offset) = html[position..].find('<') {
+        let open = position + offset;
+        let Some(close) = html[open..].find('>').map(|c| open + c) else {
+            break;
+        };
+        let tag = &html[open + 1..close];
+        if !tag.starts_with('/') && !tag.starts_with('!') && is_furniture(tag) {
+            out.push_str(&html[position..open]);
+            position = if tag.ends_with('/') { close + 1 } else { element_end(html, open, &tag_name(tag)) };
+            continue;
+        }
+        out.push_str(&html[position..=close]);
+        position = close + 1;
+    }
+    out.push_str(&html[position..]);
+    out
+}
+
+// Inner markup of the elements named `name`, the longest first.
+fn elements<'a>(html: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let lower = html.to_ascii_lowercase();
+    let mut position = 0;
+    while let Some(offset) = lower[position..].find(&format!("<{}", name)) {
+        let open = position + offset;
+        let Some(close) = html[open..].find('>').map(|c| open + c) else {
+            break;
+        };
+        if tag_name(&html[open + 1..close]) != name {
+            position = close + 1;
+            continue;
+        }
+        let end = element_end(html, open, name);
+        let inner_end = html[..end].rfind('<').filter(|i| *i > close).unwrap_or(end);
+        found.push(&html[close + 1..inner_end]);
+        position = end;
+    }
+    found.sort_by_key(|inner| std::cmp::Reverse(inner.len()));
+    found
+}
+
+fn meta_content(html: &str, key: &str) -> Option<String> {
+    html.split("<meta").skip(1).find_map(|rest| {
+        let tag = rest.split('>').next()?;
+        let named = attribute(tag, "property").or_else(|| attribute(tag, "name"))?;
+        (named == key).then(|| attribute(tag, "content")).flatten()
+    })
+}
+
+fn decode(text: &str) -> Option<String> {
+    let text = html_escape::decode_html_entities(text).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// Replaces each `<img>` with a marker paragraph, collecting the image URLs. Lazy-loading
+// pages keep the real source in `data-src`.
+fn mark_images(html: &str, base: Option<&reqwest::Url>, urls: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut position = 0;
+    while let Some(offset) = html[position..].find('<') {
+        let open = position + offset;
+        let Some(close) = html[open..].find('>').map(|c| open + c) else {
+            break;
+        };
+        out.push_str(&html[position..open]);
+        let tag = &html[open + 1..close];
+        if tag_name(tag) == "img" {
+            let source = attribute(tag, "data-src").or_else(|| attribute(tag, "src"));
+            let absolute = source.filter(|src| !src.starts_with("data:")).and_then(|src| match base {
+                Some(base) => base.join(&html_escape::decode_html_entities(&src)).ok().map(|url| url.to_string()),
+                None => src.starts_with("http").then_some(src),
+            });
+            if let Some(url) = absolute {
+                out.push_str(&format!("<p>{}{}</p>", IMAGE_MARKER, urls.len()));
+                urls.push(url);
+            }
+        } else {
+            out.push_str(&html[open..=close]);
+        }
+        position = close + 1;
+    }
+    out.push_str(&html[position..]);
+    out
+}
+
+// A short line such as "Share this" or "Advertisement" left behind by the page's widgets.
+fn is_stray_label(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    text.split_whitespace().count() < 4 && STRAY_LABELS.iter().any(|label| lower.contains(label))
+}
+
+// Pulls the readable article out of a web page: the longest `<article>` (or `<main>`, or the
+// body), without navigation, comment sections and other page furniture. A heuristic in the
+// spirit of Mozilla's Readability rather than a port of it.
+pub fn extract_article(html: &str, base: Option<&reqwest::Url>) -> Article {
+    let title = meta_content(html, "og:title")
+        .or_else(|| elements(html, "title").first().and_then(|t| decode(t)))
+        .and_then(|t| decode(&t));
+    let byline = meta_content(html, "author").and_then(|a| decode(&a));
+
+    let cleaned = strip_furniture(html);
+    let content = ["article", "main", "body"]
+        .iter()
+        .find_map(|name| elements(&cleaned, name).first().map(|inner| inner.to_string()))
+        .unwrap_or(cleaned);
+
+    let mut urls = Vec::new();
+    let content = mark_images(&content, base, &mut urls);
+
+    let mut blocks: Vec<ArticleBlock> = Vec::new();
+    let mut images = Vec::new();
+    for (kind, text) in html_blocks(&content) {
+        let image = text.strip_prefix(IMAGE_MARKER).and_then(|index| index.parse::<usize>().ok());
+        match image.and_then(|index| urls.get(index)) {
+            Some(url) => images.push(ArticleImage { url: url.clone(), position: blocks.len() }),
+            None if kind.is_verbatim() || !is_stray_label(&text) => blocks.push(ArticleBlock { kind, text }),
+            None => {}
+        }
+    }
+    // The page title usually repeats as the article's heading
+    if blocks.first().is_some_and(|b| Some(&b.text) == title.as_ref()) {
+        blocks.remove(0);
+        images.iter_mut().for_each(|image| image.position = image.position.saturating_sub(1));
+    }
+    Article { title, byline, blocks, images }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_config_dir;
+use crate::app_state::AppState;
+use crate::quarantine::parse_or_quarantine;
+
+// A page turn further apart than this from the previous one starts a new session.
+const SESSION_GAP_MINUTES: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingSession {
+    pub book_id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub pages: u32,
+}
+
+impl ReadingSession {
+    pub fn minutes(&self) -> i64 {
+        (self.ended_at - self.started_at).num_minutes()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReadingActivity {
+    pub sessions: Vec<ReadingSession>,
+    // Sentences sent for translation, by day.
+    pub translated: BTreeMap<NaiveDate, u32>,
+}
+
+fn activity_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("reading_activity.json"))
+}
+
+pub fn read_reading_activity_file(handle: &tauri::AppHandle) -> Result<ReadingActivity, String> {
+    let path = activity_file_path(handle)?;
+    if !path.exists() {
+        return Ok(ReadingActivity::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn write_reading_activity_file(handle: &tauri::AppHandle, activity: &ReadingActivity) -> Result<(), String> {
+    let path = activity_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string(activity).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+pub fn load_reading_activity(handle: &tauri::AppHandle) -> Result<ReadingActivity, String> {
+    handle.state::<AppState>().reading_activity.get(handle)
+}
+
+fn update_activity(handle: &tauri::AppHandle, f: impl FnOnce(&mut ReadingActivity)) -> Result<(), String> {
+    handle.state::<AppState>().reading_activity.update(handle, f)
+}
+
+// Extends the book's current session, or starts a new one after a break.
+pub fn record_page_turn(handle: &tauri::AppHandle, book_id: &str) -> Result<(), String> {
+    let now = Utc::now();
+    update_activity(handle, |activity| {
+        match activity.sessions.last_mut() {
+            Some(session)
+                if session.book_id == book_id && now - session.ended_at < Duration::minutes(SESSION_GAP_MINUTES) =>
+            {
+                session.ended_at = now;
+                session.pages += 1;
+            }
+            _ => activity.sessions.push(ReadingSession {
+                book_id: book_id.to_string(),
+                started_at: now,
+                ended_at: now,
+                pages: 1,
+            }),
+        }
+    })
+}
+
+pub fn record_translations(handle: &tauri::AppHandle, count: usize) -> Result<(), String> {
+    if count == 0 {
+        return Ok(());
+    }
+    update_activity(handle, |activity| {
+        *activity.translated.entry(Utc::now().date_naive()).or_default() += count as u32;
+    })
+}
@@ -0,0 +1,232 @@
+use chrono::{Datelike, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::Manager;
+
+use crate::lookup_history::load_lookup_history;
+use crate::reading_activity::load_reading_activity;
+use crate::{app_state, load_vocabulary, BookStatus};
+
+const MONTH_NAMES: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const TOP_LOOKUPS: usize = 10;
+
+#[derive(Debug, Serialize)]
+pub struct FinishedBook {
+    id: String,
+    title: String,
+    author: Option<String>,
+    finished_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BookTime {
+    id: String,
+    title: String,
+    minutes: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LookupCount {
+    text: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct MonthStats {
+    sessions: usize,
+    minutes: i64,
+    pages: u32,
+    words_added: usize,
+    sentences_translated: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadingReport {
+    year: i32,
+    sessions: usize,
+    reading_minutes: i64,
+    pages_turned: u32,
+    longest_session_minutes: i64,
+    days_read: usize,
+    books_finished: Vec<FinishedBook>,
+    most_read_books: Vec<BookTime>,
+    words_added: usize,
+    vocabulary_total: usize,
+    lookups: usize,
+    top_lookups: Vec<LookupCount>,
+    sentences_translated: u32,
+    months: Vec<MonthStats>,
+    markdown: String,
+    html: String,
+}
+
+fn render_markdown(report: &ReadingReport) -> String {
+    let mut out = format!("# {} in reading\n\n", report.year);
+    out.push_str(&format!(
+        "- **{}** hours over **{}** sessions on **{}** days\n- **{}** pages turned, longest session **{}** min\n",
+        report.reading_minutes / 60,
+        report.sessions,
+        report.days_read,
+        report.pages_turned,
+        report.longest_session_minutes
+    ));
+    out.push_str(&format!(
+        "- **{}** new words ({} in total), **{}** lookups\n- **{}** sentences translated\n",
+        report.words_added, report.vocabulary_total, report.lookups, report.sentences_translated
+    ));
+
+    out.push_str(&format!("\n## Books finished ({})\n\n", report.books_finished.len()));
+    for book in &report.books_finished {
+        match &book.author {
+            Some(author) => out.push_str(&format!("- {} — {} ({})\n", book.title, author, book.finished_at)),
+            None => out.push_str(&format!("- {} ({})\n", book.title, book.finished_at)),
+        }
+    }
+    if !report.most_read_books.is_empty() {
+        out.push_str("\n## Most time spent with\n\n");
+        for book in &report.most_read_books {
+            out.push_str(&format!("- {}: {} min\n", book.title, book.minutes));
+        }
+    }
+    if !report.top_lookups.is_empty() {
+        out.push_str("\n## Most looked-up\n\n");
+        for lookup in &report.top_lookups {
+            out.push_str(&format!("- {} ×{}\n", lookup.text, lookup.count));
+        }
+    }
+    out.push_str("\n## By month\n\n| Month | Sessions | Minutes | Pages | New words | Translated |\n");
+    out.push_str("|---|---|---|---|---|---|\n");
+    for (name, month) in MONTH_NAMES.iter().zip(&report.months) {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            name, month.sessions, month.minutes, month.pages, month.words_added, month.sentences_translated
+        ));
+    }
+    out
+}
+
+fn render_html(report: &ReadingReport) -> String {
+    let mut out = format!(
+        "<section class=\"reading-report\"><h1>{} in reading</h1><ul>\
+         <li><strong>{}</strong> hours over <strong>{}</strong> sessions</li>\
+         <li><strong>{}</strong> pages turned</li>\
+         <li><strong>{}</strong> books finished</li>\
+         <li><strong>{}</strong> new words</li>\
+         <li><strong>{}</strong> sentences translated</li></ul>",
+        report.year,
+        report.reading_minutes / 60,
+        report.sessions,
+        report.pages_turned,
+        report.books_finished.len(),
+        report.words_added,
+        report.sentences_translated
+    );
+    if !report.books_finished.is_empty() {
+        out.push_str("<h2>Books finished</h2><ol>");
+        for book in &report.books_finished {
+            out.push_str(&format!("<li>{}</li>", html_escape::encode_text(&book.title)));
+        }
+        out.push_str("</ol>");
+    }
+    out.push_str("</section>");
+    out
+}
+
+// A year-in-review built from the activity log, book states, vocabulary and lookup history.
+#[tauri::command(rename_all = "camelCase")]
+pub fn generate_reading_report(handle: tauri::AppHandle, year: Option<i32>) -> Result<ReadingReport, String> {
+    let year = year.unwrap_or_else(|| Utc::now().year());
+    let activity = load_reading_activity(&handle)?;
+    let books = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.clone())?;
+    let vocabulary = load_vocabulary(&handle)?;
+    let history = load_lookup_history(&handle)?;
+    let mut months: Vec<MonthStats> = (0..12).map(|_| MonthStats::default()).collect();
+
+    let sessions: Vec<_> = activity.sessions.iter().filter(|s| s.started_at.year() == year).collect();
+    let mut days: Vec<_> = sessions.iter().map(|s| s.started_at.date_naive()).collect();
+    days.dedup();
+    let mut minutes_by_book: HashMap<&str, i64> = HashMap::new();
+    for session in &sessions {
+        let month = &mut months[session.started_at.month0() as usize];
+        month.sessions += 1;
+        month.minutes += session.minutes();
+        month.pages += session.pages;
+        *minutes_by_book.entry(&session.book_id).or_default() += session.minutes();
+    }
+    let mut most_read_books: Vec<BookTime> = minutes_by_book
+        .into_iter()
+        .filter(|(_, minutes)| *minutes > 0)
+        .map(|(id, minutes)| BookTime {
+            id: id.to_string(),
+            title: books.iter().find(|b| b.id == id).map_or_else(|| id.to_string(), |b| b.title.clone()),
+            minutes,
+        })
+        .collect();
+    most_read_books.sort_by_key(|book| std::cmp::Reverse(book.minutes));
+    most_read_books.truncate(5);
+
+    let mut finished: Vec<_> = books
+        .iter()
+        .filter(|b| matches!(b.status, BookStatus::Finished | BookStatus::Archived))
+        .filter_map(|b| b.finished_at.filter(|at| at.year() == year).map(|at| (at, b)))
+        .collect();
+    finished.sort_by_key(|(at, _)| *at);
+    let books_finished = finished
+        .into_iter()
+        .map(|(at, b)| FinishedBook {
+            id: b.id.clone(),
+            title: b.title.clone(),
+            author: b.author.clone(),
+            finished_at: at.format("%Y-%m-%d").to_string(),
+        })
+        .collect();
+
+    let mut words_added = 0;
+    for entry in vocabulary.entries.iter().filter(|e| e.added_at.year() == year) {
+        words_added += 1;
+        months[entry.added_at.month0() as usize].words_added += 1;
+    }
+    let mut sentences_translated = 0;
+    for (day, count) in activity.translated.iter().filter(|(day, _)| day.year() == year) {
+        sentences_translated += count;
+        months[day.month0() as usize].sentences_translated += count;
+    }
+
+    let lookups: Vec<_> = history.records.iter().filter(|r| r.looked_up_at.year() == year).collect();
+    let mut lookup_counts: HashMap<String, usize> = HashMap::new();
+    for record in &lookups {
+        *lookup_counts.entry(record.text.to_lowercase()).or_default() += 1;
+    }
+    let mut top_lookups: Vec<LookupCount> = lookup_counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(text, count)| LookupCount { text, count })
+        .collect();
+    top_lookups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.text.cmp(&b.text)));
+    top_lookups.truncate(TOP_LOOKUPS);
+
+    let mut report = ReadingReport {
+        year,
+        sessions: sessions.len(),
+        reading_minutes: sessions.iter().map(|s| s.minutes()).sum(),
+        pages_turned: sessions.iter().map(|s| s.pages).sum(),
+        longest_session_minutes: sessions.iter().map(|s| s.minutes()).max().unwrap_or(0),
+        days_read: days.len(),
+        books_finished,
+        most_read_books,
+        words_added,
+        vocabulary_total: vocabulary.entries.len(),
+        lookups: lookups.len(),
+        top_lookups,
+        sentences_translated,
+        months,
+        markdown: String::new(),
+        html: String::new(),
+    };
+    report.markdown = render_markdown(&report);
+    report.html = render_html(&report);
+    Ok(report)
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::book_text::load_book_text;
+use crate::encryption::{read_store_file, write_store_file};
+use crate::quarantine::parse_or_quarantine;
+use crate::transliteration::{transliterate_locally, transliterate_text, ReadingToken, TransliterationScheme};
+use crate::{book_data_file_path, ensure_cloud_allowed};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParagraphReadings {
+    pub sid: String,
+    pub tokens: Vec<ReadingToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageReadings {
+    pub page: u32,
+    pub scheme: TransliterationScheme,
+    pub paragraphs: Vec<ParagraphReadings>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct BookReadings {
+    pub pages: HashMap<u32, PageReadings>,
+}
+
+fn readings_file_path(handle: &tauri::AppHandle, book_id: &str) -> Result<PathBuf, String> {
+    book_data_file_path(handle, "readings", book_id)
+}
+
+pub fn load_book_readings(handle: &tauri::AppHandle, book_id: &str) -> Result<BookReadings, String> {
+    let path = readings_file_path(handle, book_id)?;
+    if !path.exists() {
+        return Ok(BookReadings::default());
+    }
+    let data = read_store_file(handle, &path)?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_book_readings(handle: &tauri::AppHandle, book_id: &str, readings: &BookReadings) -> Result<(), String> {
+    let path = readings_file_path(handle, book_id)?;
+    let data = serde_json::to_string(readings).map_err(|e| e.to_string())?;
+    write_store_file(handle, &path, &data)
+}
+
+// Renders tokens as HTML ruby markup, the form EPUB readers display as furigana/pinyin.
+pub fn render_ruby_html(tokens: &[ReadingToken]) -> String {
+    tokens
+        .iter()
+        .map(|token| {
+            if token.reading.is_empty() || token.reading == token.text {
+                html_escape::encode_text(&token.text).to_string()
+            } else {
+                format!(
+                    "<ruby>{}<rt>{}</rt></ruby>",
+                    html_escape::encode_text(&token.text),
+                    html_escape::encode_text(&token.reading)
+                )
+            }
+        })
+        .collect()
+}
+
+// Generates reading annotations for every paragraph on the page and stores them, so
+// revisiting the page (or exporting the book) needs no further model calls.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn annotate_page_readings(
+    handle: tauri::AppHandle,
+    model: String,
+    book_id: String,
+    page: u32,
+    scheme: TransliterationScheme,
+) -> Result<PageReadings, String> {
+    let mut readings = load_book_readings(&handle, &book_id)?;
+    if let Some(existing) = readings.pages.get(&page) {
+        if existing.scheme == scheme {
+            return Ok(existing.clone());
+        }
+    }
+
+    let text = load_book_text(&handle, &book_id)?;
+    let book_page = text
+        .page(page)
+        .ok_or_else(|| format!("No extracted text for page {} of book {}", page, book_id))?;
+
+    if book_page
+        .paragraphs
+        .iter()
+        .any(|p| transliterate_locally(&p.text, scheme).is_none())
+    {
+        ensure_cloud_allowed(&handle, &book_id)?;
+    }
+    let mut paragraphs = Vec::new();
+    for paragraph in &book_page.paragraphs {
+        let tokens = transliterate_text(&handle, &model, &paragraph.text, scheme).await?;
+        paragraphs.push(ParagraphReadings {
+            sid: paragraph.sid.clone(),
+            tokens,
+        });
+    }
+
+    let page_readings = PageReadings {
+        page,
+        scheme,
+        paragraphs,
+    };
+    // Reload in case another page was annotated meanwhile.
+    readings = load_book_readings(&handle, &book_id)?;
+    readings.pages.insert(page, page_readings.clone());
+    save_book_readings(&handle, &book_id, &readings)?;
+    Ok(page_readings)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_page_readings(handle: tauri::AppHandle, book_id: String, page: u32) -> Result<Option<PageReadings>, String> {
+    Ok(load_book_readings(&handle, &book_id)?.pages.remove(&page))
+}
+
+// Ruby HTML for each annotated paragraph of the page, keyed by sid.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_page_readings_html(
+    handle: tauri::AppHandle,
+    book_id: String,
+    page: u32,
+) -> Result<HashMap<String, String>, String> {
+    let readings = load_book_readings(&handle, &book_id)?;
+    Ok(readings
+        .pages
+        .get(&page)
+        .map(|page_readings| {
+            page_readings
+                .paragraphs
+                .iter()
+                .map(|p| (p.sid.clone(), render_ruby_html(&p.tokens)))
+                .collect()
+        })
+        .unwrap_or_default())
+}
@@ -0,0 +1,64 @@
+use serde::Serialize;
+use std::cmp::Ordering;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::book_level::{estimate, BookLevel, KnownWords};
+use crate::BookStatus;
+
+// Share of unknown running words that makes for comprehensible input: enough new words to
+// learn from, few enough to follow the text without a dictionary.
+const SWEET_SPOT_MIN_PERCENT: f64 = 2.0;
+const SWEET_SPOT_MAX_PERCENT: f64 = 5.0;
+
+#[derive(Debug, Serialize)]
+pub struct BookRecommendation {
+    title: String,
+    author: Option<String>,
+    #[serde(flatten)]
+    level: BookLevel,
+    // How far the unknown-word share is from the sweet spot, in percentage points; 0 inside it.
+    distance: f64,
+}
+
+fn distance_from_sweet_spot(unknown_percent: f64) -> f64 {
+    if unknown_percent < SWEET_SPOT_MIN_PERCENT {
+        SWEET_SPOT_MIN_PERCENT - unknown_percent
+    } else if unknown_percent > SWEET_SPOT_MAX_PERCENT {
+        unknown_percent - SWEET_SPOT_MAX_PERCENT
+    } else {
+        0.0
+    }
+}
+
+// Library books not finished or set aside, best comprehensible-input fit first: those whose
+// unknown-word share is inside the sweet spot, then the ones closest to it. Books without
+// extracted text cannot be measured and are left out.
+#[tauri::command(rename_all = "camelCase")]
+pub fn recommend_next_books(handle: tauri::AppHandle, count: usize) -> Result<Vec<BookRecommendation>, String> {
+    let books = handle.state::<AppState>().recent_books.read(&handle, |data| data.books.clone())?;
+    let known = KnownWords::load(&handle)?;
+    let middle = (SWEET_SPOT_MIN_PERCENT + SWEET_SPOT_MAX_PERCENT) / 2.0;
+
+    let mut recommendations = Vec::new();
+    for book in books.into_iter().filter(|b| b.status == BookStatus::Reading) {
+        let Some(level) = estimate(&handle, &book.id, &known)? else {
+            continue;
+        };
+        recommendations.push(BookRecommendation {
+            title: book.title,
+            author: book.author,
+            distance: distance_from_sweet_spot(level.unknown_word_percent),
+            level,
+        });
+    }
+    recommendations.sort_by(|a, b| {
+        a.distance.partial_cmp(&b.distance).unwrap_or(Ordering::Equal).then_with(|| {
+            let a_middle = (a.level.unknown_word_percent - middle).abs();
+            let b_middle = (b.level.unknown_word_percent - middle).abs();
+            a_middle.partial_cmp(&b_middle).unwrap_or(Ordering::Equal)
+        })
+    });
+    recommendations.truncate(count);
+    Ok(recommendations)
+}
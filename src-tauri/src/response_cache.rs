@@ -0,0 +1,170 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::quarantine::parse_or_quarantine;
+use crate::{app_config_dir, encryption, hash_source_text, request_openrouter, OpenRouterCredentials};
+
+pub const FEATURE_CHAT: &str = "chat";
+pub const FEATURE_SUMMARY: &str = "summary";
+pub const FEATURE_EXPLANATION: &str = "explanation";
+// Past this, the oldest responses make room for new ones.
+const MAX_ENTRIES: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    feature: String,
+    model: String,
+    content: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResponseCacheData {
+    entries: HashMap<String, CachedResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCacheStats {
+    feature: String,
+    entries: usize,
+    expired: usize,
+}
+
+// Summaries and explanations of a fixed text do not go stale; chat answers are kept shorter
+// since readers tend to re-ask the same question only within a session or two.
+fn feature_ttl(feature: &str) -> Duration {
+    match feature {
+        FEATURE_CHAT => Duration::days(7),
+        _ => Duration::days(30),
+    }
+}
+
+pub fn response_cache_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("response_cache.json"))
+}
+
+pub fn read_response_cache_file(handle: &tauri::AppHandle) -> Result<ResponseCacheData, String> {
+    let path = response_cache_file_path(handle)?;
+    if !path.exists() {
+        return Ok(ResponseCacheData::default());
+    }
+    let data = encryption::read_store_file(handle, &path)?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+// Encrypted like the translation cache, since prompts carry book text.
+pub fn write_response_cache_file(handle: &tauri::AppHandle, cache: &ResponseCacheData) -> Result<(), String> {
+    let path = response_cache_file_path(handle)?;
+    let data = serde_json::to_string_pretty(cache).map_err(|e| e.to_string())?;
+    encryption::write_store_file(handle, &path, &data)
+}
+
+fn response_cache_key(feature: &str, model: &str, temperature: f32, system_prompt: &str, user_prompt: &str) -> String {
+    let prompt_hash = hash_source_text(&format!("{}\n{}\n{}", temperature, system_prompt, user_prompt));
+    format!("{}:{}:{}", feature, model, prompt_hash)
+}
+
+// `request_openrouter`, but answered from the response cache when the same feature has sent
+// the same prompts to the same model within its TTL.
+pub async fn request_openrouter_cached(
+    handle: &tauri::AppHandle,
+    feature: &str,
+    credentials: &OpenRouterCredentials,
+    model: &str,
+    temperature: f32,
+    system_prompt: &str,
+    user_prompt: &str,
+) -> Result<String, String> {
+    let key = response_cache_key(feature, model, temperature, system_prompt, user_prompt);
+    let now = Utc::now();
+    let store = &handle.state::<AppState>().response_cache;
+    // The cache only saves requests; when it cannot be read or written, the request goes ahead.
+    let cached = store.read(handle, |cache| {
+        cache.entries.get(&key).filter(|entry| entry.expires_at > now).map(|entry| entry.content.clone())
+    });
+    match cached {
+        Ok(Some(content)) => return Ok(content),
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to read response cache: {}", e),
+    }
+
+    let credentials = credentials.for_feature(feature);
+    let content = request_openrouter(&credentials, model, temperature, system_prompt, user_prompt).await?;
+
+    let response = CachedResponse {
+        feature: feature.to_string(),
+        model: model.to_string(),
+        content: content.clone(),
+        created_at: now,
+        expires_at: now + feature_ttl(feature),
+    };
+    let stored = store.update(handle, |cache| {
+        cache.entries.retain(|_, entry| entry.expires_at > now);
+        cache.entries.insert(key, response);
+        let excess = cache.entries.len().saturating_sub(MAX_ENTRIES);
+        if excess > 0 {
+            let mut by_age: Vec<(DateTime<Utc>, String)> =
+                cache.entries.iter().map(|(key, entry)| (entry.created_at, key.clone())).collect();
+            by_age.sort();
+            for (_, key) in by_age.into_iter().take(excess) {
+                cache.entries.remove(&key);
+            }
+        }
+    });
+    if let Err(e) = stored {
+        log::error!("Failed to cache response: {}", e);
+    }
+    Ok(content)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_response_cache_stats(handle: tauri::AppHandle) -> Result<Vec<ResponseCacheStats>, String> {
+    let now = Utc::now();
+    let mut stats: Vec<ResponseCacheStats> = Vec::new();
+    let entries: Vec<(String, DateTime<Utc>)> = handle.state::<AppState>().response_cache.read(&handle, |cache| {
+        cache.entries.values().map(|entry| (entry.feature.clone(), entry.expires_at)).collect()
+    })?;
+    for (feature, expires_at) in entries {
+        let index = match stats.iter().position(|s| s.feature == feature) {
+            Some(index) => index,
+            None => {
+                stats.push(ResponseCacheStats { feature, entries: 0, expired: 0 });
+                stats.len() - 1
+            }
+        };
+        stats[index].entries += 1;
+        if expires_at <= now {
+            stats[index].expired += 1;
+        }
+    }
+    stats.sort_by(|a, b| a.feature.cmp(&b.feature));
+    Ok(stats)
+}
+
+// Drops cached responses, optionally only those of one feature and/or model. With
+// `expired_only`, entries still within their TTL are kept. Returns how many were removed.
+#[tauri::command(rename_all = "camelCase")]
+pub fn clear_response_cache(
+    handle: tauri::AppHandle,
+    feature: Option<String>,
+    model: Option<String>,
+    expired_only: Option<bool>,
+) -> Result<usize, String> {
+    let now = Utc::now();
+    let expired_only = expired_only.unwrap_or(false);
+    handle.state::<AppState>().response_cache.update(&handle, |cache| {
+        let before = cache.entries.len();
+        cache.entries.retain(|_, entry| {
+            let matches = feature.as_deref().is_none_or(|f| entry.feature == f)
+                && model.as_deref().is_none_or(|m| entry.model == m)
+                && (!expired_only || entry.expires_at <= now);
+            !matches
+        });
+        before - cache.entries.len()
+    })
+}
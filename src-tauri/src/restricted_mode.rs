@@ -0,0 +1,162 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::encryption::{keychain_delete, keychain_get, keychain_set};
+use crate::{coded_error, data_root, hash_source_text, ERR_RESTRICTED};
+
+// Kept at the data root rather than in a profile or the settings, so neither switching
+// profiles nor saving or importing settings turns it off.
+const RESTRICTED_MODE_FILE: &str = "restricted_mode.json";
+// The PIN's salt and hash are also kept in the keychain while restricted mode is on, so
+// deleting or breaking the file does not turn it off.
+const KEYCHAIN_ACCOUNT: &str = "restricted-mode-pin";
+const MIN_PIN_CHARS: usize = 4;
+// Wrong PINs allowed before each further attempt has to wait, doubling from BASE_DELAY.
+const FREE_ATTEMPTS: u32 = 3;
+const BASE_DELAY: Duration = Duration::from_secs(5);
+const MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct RestrictedModeData {
+    enabled: bool,
+    pin_salt: String,
+    pin_hash: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestrictedModeState {
+    enabled: bool,
+}
+
+// Wrong PINs entered since the last right one, for the backoff. Kept in memory only.
+#[derive(Default)]
+pub struct PinAttempts {
+    failures: Mutex<(u32, Option<Instant>)>,
+}
+
+fn restricted_mode_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(data_root(handle)?.join(RESTRICTED_MODE_FILE))
+}
+
+fn load_restricted_mode(handle: &tauri::AppHandle) -> Result<RestrictedModeData, String> {
+    let path = restricted_mode_file_path(handle)?;
+    if !path.exists() {
+        return Ok(RestrictedModeData::default());
+    }
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map_err(|e| e.to_string())
+}
+
+fn save_restricted_mode(handle: &tauri::AppHandle, data: &RestrictedModeData) -> Result<(), String> {
+    let path = restricted_mode_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// The PIN's salt and hash from the keychain, when restricted mode was turned on there.
+fn keychain_pin() -> Option<(String, String)> {
+    match keychain_get(KEYCHAIN_ACCOUNT) {
+        Ok(marker) => marker?.split_once(':').map(|(salt, hash)| (salt.to_string(), hash.to_string())),
+        Err(e) => {
+            log::error!("Failed to read restricted mode from keychain: {}", e);
+            None
+        }
+    }
+}
+
+fn pin_hash(salt: &str, pin: &str) -> String {
+    hash_source_text(&format!("{}:{}", salt, pin))
+}
+
+// A file that cannot be read counts as restricted, so breaking it does not unlock anything,
+// and so does a missing or disabled file while the keychain still says restricted mode is on.
+pub fn is_restricted(handle: &tauri::AppHandle) -> bool {
+    match load_restricted_mode(handle) {
+        Ok(data) => data.enabled || keychain_pin().is_some(),
+        Err(e) => {
+            log::error!("Failed to read restricted mode state: {}", e);
+            true
+        }
+    }
+}
+
+// Refuses `what` while restricted mode is on. Called at the top of every command that sends
+// free-form prompts; changes settings, hooks, keys, accounts or profiles; or changes what gets
+// sent and to where. Translation, lookups and locking or unlocking private books never call it.
+pub fn ensure_unrestricted(handle: &tauri::AppHandle, what: &str) -> Result<(), String> {
+    if is_restricted(handle) {
+        return Err(coded_error(ERR_RESTRICTED, &format!("{} is turned off in restricted mode.", what)));
+    }
+    Ok(())
+}
+
+// Checks `pin` against the stored hash, refusing attempts that come too soon after wrong ones.
+fn check_pin(handle: &tauri::AppHandle, salt: &str, hash: &str, pin: &str) -> Result<(), String> {
+    let attempts = handle.state::<PinAttempts>();
+    let mut failures = attempts.failures.lock().unwrap_or_else(|e| e.into_inner());
+    if let (count, Some(last)) = *failures {
+        let delay = count
+            .checked_sub(FREE_ATTEMPTS)
+            .map_or(Duration::ZERO, |extra| BASE_DELAY.saturating_mul(1 << extra.min(16)).min(MAX_DELAY));
+        let wait = delay.saturating_sub(last.elapsed());
+        if !wait.is_zero() {
+            return Err(format!("Too many wrong PINs; try again in {} seconds.", wait.as_secs().max(1)));
+        }
+    }
+    if pin_hash(salt, pin) != hash {
+        *failures = (failures.0 + 1, Some(Instant::now()));
+        return Err("The PIN is not correct.".to_string());
+    }
+    *failures = (0, None);
+    Ok(())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_restricted_mode(handle: tauri::AppHandle) -> Result<RestrictedModeState, String> {
+    Ok(RestrictedModeState { enabled: is_restricted(&handle) })
+}
+
+// Turns restricted mode on; the same PIN is needed to turn it off again.
+#[tauri::command(rename_all = "camelCase")]
+pub fn enable_restricted_mode(handle: tauri::AppHandle, pin: String) -> Result<RestrictedModeState, String> {
+    ensure_unrestricted(&handle, "Changing the PIN")?;
+    let pin = pin.trim();
+    if pin.chars().count() < MIN_PIN_CHARS {
+        return Err(format!("The PIN must be at least {} characters long.", MIN_PIN_CHARS));
+    }
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let pin_salt: String = salt.iter().map(|b| format!("{:02x}", b)).collect();
+    let data = RestrictedModeData { enabled: true, pin_hash: pin_hash(&pin_salt, pin), pin_salt };
+    // Without a keychain the file alone has to do.
+    if let Err(e) = keychain_set(KEYCHAIN_ACCOUNT, &format!("{}:{}", data.pin_salt, data.pin_hash)) {
+        log::error!("Failed to keep restricted mode in keychain: {}", e);
+    }
+    save_restricted_mode(&handle, &data)?;
+    Ok(RestrictedModeState { enabled: true })
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn disable_restricted_mode(handle: tauri::AppHandle, pin: String) -> Result<RestrictedModeState, String> {
+    if !is_restricted(&handle) {
+        return Ok(RestrictedModeState { enabled: false });
+    }
+    // The keychain copy is preferred: the file may have been tampered with.
+    let data = load_restricted_mode(&handle).unwrap_or_default();
+    let (salt, hash) = keychain_pin()
+        .or_else(|| data.enabled.then(|| (data.pin_salt.clone(), data.pin_hash.clone())))
+        .ok_or_else(|| "Restricted mode state is unreadable; the PIN cannot be checked.".to_string())?;
+    check_pin(&handle, &salt, &hash, pin.trim())?;
+    save_restricted_mode(&handle, &RestrictedModeData::default())?;
+    keychain_delete(KEYCHAIN_ACCOUNT)?;
+    Ok(RestrictedModeState { enabled: false })
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::app_config_dir;
+use crate::settings::{load_settings, ReviewReminderSettings};
+use crate::srs::due_count;
+
+// How often the scheduler wakes to see whether a reminder is due.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+const DEFAULT_SNOOZE_MINUTES: u32 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReminderState {
+    last_notified_at: Option<DateTime<Utc>>,
+    snoozed_until: Option<DateTime<Utc>>,
+}
+
+fn reminder_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("review_reminders.json"))
+}
+
+fn load_state(handle: &tauri::AppHandle) -> Result<ReminderState, String> {
+    let path = reminder_file_path(handle)?;
+    if !path.exists() {
+        return Ok(ReminderState::default());
+    }
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map_err(|e| e.to_string())
+}
+
+fn save_state(handle: &tauri::AppHandle, state: &ReminderState) -> Result<(), String> {
+    let path = reminder_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn in_quiet_hours(settings: &ReviewReminderSettings, hour: u32) -> bool {
+    let (start, end) = (settings.quiet_start_hour, settings.quiet_end_hour);
+    if start <= end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+// Shows the reminder when it is enabled, outside quiet hours, not snoozed, the last one is
+// `every_hours` old and enough words are due.
+fn check(handle: &tauri::AppHandle) -> Result<(), String> {
+    let settings = load_settings(handle)?.review_reminders;
+    if !settings.enabled || in_quiet_hours(&settings, Local::now().hour()) {
+        return Ok(());
+    }
+    let mut state = load_state(handle)?;
+    let now = Utc::now();
+    if state.snoozed_until.is_some_and(|until| until > now) {
+        return Ok(());
+    }
+    let every = Duration::hours(settings.every_hours.max(1) as i64);
+    if state.last_notified_at.is_some_and(|last| now - last < every) {
+        return Ok(());
+    }
+    let due = due_count(handle)?;
+    if due == 0 || due < settings.min_due {
+        return Ok(());
+    }
+
+    let body = if due == 1 { "1 word due for review".to_string() } else { format!("{} words due for review", due) };
+    handle.notification().builder().title("PDFRead").body(body).show().map_err(|e| e.to_string())?;
+    state.last_notified_at = Some(now);
+    state.snoozed_until = None;
+    save_state(handle, &state)
+}
+
+pub fn start(handle: &tauri::AppHandle) {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if let Err(e) = check(&handle) {
+                log::error!("Failed to check review reminders: {}", e);
+            }
+        }
+    });
+}
+
+// Holds reminders off for `minutes` (an hour when not given).
+#[tauri::command(rename_all = "camelCase")]
+pub fn snooze_review_reminder(handle: tauri::AppHandle, minutes: Option<u32>) -> Result<ReminderState, String> {
+    let minutes = minutes.unwrap_or(DEFAULT_SNOOZE_MINUTES);
+    let mut state = load_state(&handle)?;
+    state.snoozed_until = Some(Utc::now() + Duration::minutes(minutes as i64));
+    save_state(&handle, &state)?;
+    Ok(state)
+}
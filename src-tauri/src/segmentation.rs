@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{extract_doc_id, hash_source_text, translation_cache_key, update_cache, TranslateSentence};
+
+// Hex digits of the content hash used in a sid; plenty to keep one book's sentences apart.
+const SID_HASH_LEN: usize = 12;
+
+// Words that end in a period without ending the sentence, by language.
+const ABBREVIATIONS_EN: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "st", "jr", "sr", "vs", "etc", "e.g", "i.e", "cf", "fig", "no", "vol", "pp",
+    "ch", "approx", "inc", "ltd", "co",
+];
+const ABBREVIATIONS_DE: &[&str] = &["dr", "prof", "nr", "bzw", "usw", "vgl", "ca", "z.b", "d.h", "s", "str"];
+const ABBREVIATIONS_FR: &[&str] = &["m", "mme", "mlle", "dr", "p", "etc", "cf", "av", "env"];
+const ABBREVIATIONS_ES: &[&str] = &["sr", "sra", "srta", "dr", "dra", "p", "etc", "pág", "ud", "uds"];
+
+// A sentence with UTF-16 offsets into the original text, matching JavaScript string indices.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextSegment {
+    pub sid: String,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn abbreviations(lang: &str) -> &'static [&'static str] {
+    match lang.split(['-', '_']).next().unwrap_or("").to_lowercase().as_str() {
+        "de" => ABBREVIATIONS_DE,
+        "fr" => ABBREVIATIONS_FR,
+        "es" => ABBREVIATIONS_ES,
+        _ => ABBREVIATIONS_EN,
+    }
+}
+
+// The form sids are hashed from: NFC, soft hyphens and zero-width characters dropped,
+// whitespace collapsed. Extraction differences that don't change the words keep the sid.
+pub fn normalize_text(text: &str) -> String {
+    let cleaned: String = text
+        .nfc()
+        .filter(|c| !matches!(c, '\u{00AD}' | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{FEFF}'))
+        .collect();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// True when the segment ends in something like "Dr." or "J." that rarely ends a sentence.
+fn ends_with_abbreviation(segment: &str, abbreviations: &[&str]) -> bool {
+    let trimmed = segment.trim_end();
+    let Some(without_period) = trimmed.strip_suffix('.') else {
+        return false;
+    };
+    let last_word = without_period
+        .rsplit(|c: char| c.is_whitespace() || c == '(' || c == '"')
+        .next()
+        .unwrap_or("");
+    let mut letters = last_word.chars();
+    let is_initial = matches!((letters.next(), letters.next()), (Some(c), None) if c.is_uppercase());
+    is_initial || abbreviations.contains(&last_word.to_lowercase().as_str())
+}
+
+// Sentence boundaries as byte ranges: Unicode sentence breaks, re-joined after abbreviations
+// and initials, with pieces that hold no letters or digits folded into their neighbour.
+fn sentence_ranges(text: &str, lang: &str) -> Vec<(usize, usize)> {
+    let abbreviations = abbreviations(lang);
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut join_next = false;
+    for (start, piece) in text.split_sentence_bound_indices() {
+        let end = start + piece.len();
+        let has_content = piece.chars().any(char::is_alphanumeric);
+        match ranges.last_mut() {
+            Some(last) if join_next || !has_content => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+        if let Some(&(range_start, range_end)) = ranges.last() {
+            join_next = ends_with_abbreviation(&text[range_start..range_end], abbreviations);
+        }
+    }
+    ranges
+}
+
+// Content-derived sid: the same sentence gets the same id no matter how the frontend
+// splits the page. Repeats within one text are told apart by occurrence.
+fn content_sid(doc_id: Option<&str>, normalized: &str, occurrence: usize) -> String {
+    let hash = hash_source_text(normalized);
+    let mut sid = match doc_id {
+        Some(doc_id) => format!("{}:{}", doc_id, &hash[..SID_HASH_LEN]),
+        None => hash[..SID_HASH_LEN].to_string(),
+    };
+    if occurrence > 1 {
+        sid.push_str(&format!("-{}", occurrence));
+    }
+    sid
+}
+
+pub fn segment(text: &str, lang: &str, doc_id: Option<&str>) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut utf16_offset = 0;
+    let mut byte_offset = 0;
+    for (start, end) in sentence_ranges(text, lang) {
+        let raw = &text[start..end];
+        let leading = raw.len() - raw.trim_start().len();
+        let sentence = raw.trim();
+        utf16_offset += text[byte_offset..start + leading].encode_utf16().count();
+        byte_offset = start + leading;
+        if sentence.is_empty() {
+            continue;
+        }
+
+        let normalized = normalize_text(sentence);
+        let occurrence = occurrences.entry(normalized.clone()).or_insert(0);
+        *occurrence += 1;
+        let length = sentence.encode_utf16().count();
+        segments.push(TextSegment {
+            sid: content_sid(doc_id, &normalized, *occurrence),
+            text: sentence.to_string(),
+            start: utf16_offset,
+            end: utf16_offset + length,
+        });
+        utf16_offset += length;
+        byte_offset += sentence.len();
+    }
+    segments
+}
+
+// Splits text into sentences with stable, content-derived sids. `lang` picks the
+// abbreviation rules; `doc_id` prefixes the sids so they work as translation cache keys.
+#[tauri::command(rename_all = "camelCase")]
+pub fn segment_text(text: String, lang: String, doc_id: Option<String>) -> Result<Vec<TextSegment>, String> {
+    Ok(segment(&text, &lang, doc_id.as_deref()))
+}
+
+// Moves a book's cached translations onto new sids. Old entries are matched by the hash of
+// their source text, so translations survive a change in how sentences are split or named.
+// Moved entries are removed under their old key. Returns how many were rekeyed.
+#[tauri::command(rename_all = "camelCase")]
+pub fn rekey_translation_cache(
+    handle: tauri::AppHandle,
+    book_id: String,
+    sentences: Vec<TranslateSentence>,
+) -> Result<usize, String> {
+    let new_sids: HashSet<&str> = sentences.iter().map(|s| s.sid.as_str()).collect();
+    if sentences.iter().any(|s| extract_doc_id(&s.sid) != book_id) {
+        return Err(format!("Every sid must belong to book {}.", book_id));
+    }
+
+    update_cache(&handle, |cache| {
+        // Source hash -> (old key, model, target) of entries under sids that are going away.
+        let mut by_source: HashMap<String, Vec<(String, String, String)>> = HashMap::new();
+        for key in cache.entries.keys() {
+            let parts: Vec<&str> = key.splitn(4, '|').collect();
+            let [doc_id, sid, source_hash, rest] = parts[..] else {
+                continue;
+            };
+            let Some((model, target)) = rest.rsplit_once('|') else {
+                continue;
+            };
+            if doc_id != book_id || new_sids.contains(sid) {
+                continue;
+            }
+            by_source
+                .entry(source_hash.to_string())
+                .or_default()
+                .push((key.clone(), model.to_string(), target.to_string()));
+        }
+
+        let mut rekeyed = 0;
+        let mut moved: HashSet<String> = HashSet::new();
+        for sentence in &sentences {
+            let hashes = [
+                hash_source_text(&sentence.text),
+                hash_source_text(&normalize_text(&sentence.text)),
+            ];
+            let Some(old_entries) = hashes.iter().find_map(|hash| by_source.get(hash)) else {
+                continue;
+            };
+            for (old_key, model, target) in old_entries {
+                let new_key = translation_cache_key(&sentence.sid, &sentence.text, model, target);
+                if cache.entries.contains_key(&new_key) {
+                    continue;
+                }
+                if let Some(translation) = cache.entries.get(old_key).cloned() {
+                    if let Some(generation) = cache.generations.get(old_key).cloned() {
+                        cache.generations.insert(new_key.clone(), generation);
+                    }
+                    cache.entries.insert(new_key, translation);
+                    moved.insert(old_key.clone());
+                    rekeyed += 1;
+                }
+            }
+        }
+
+        for old_key in &moved {
+            cache.entries.remove(old_key);
+            cache.generations.remove(old_key);
+        }
+        rekeyed
+    })
+}
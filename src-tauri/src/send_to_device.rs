@@ -0,0 +1,204 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::Manager;
+
+use crate::encryption::{keychain_delete, keychain_get, keychain_set};
+use crate::restricted_mode;
+use crate::settings::{load_settings, SmtpSecurity, SmtpSettings};
+use crate::{app_state, ensure_cloud_allowed};
+
+const KEYCHAIN_ACCOUNT: &str = "smtp-password";
+// Send to Kindle refuses larger attachments.
+const MAX_EMAIL_BYTES: u64 = 50 * 1024 * 1024;
+// Formats Send to Kindle accepts by email.
+const EMAIL_FORMATS: &[&str] = &["epub", "pdf", "txt", "html", "docx"];
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeviceTarget {
+    // A Send-to-Kindle style address; the one in settings when `to` is not given.
+    Email { to: Option<String> },
+    // A mounted e-reader, or any folder on one.
+    Usb { path: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SentBook {
+    destination: String,
+    file_name: String,
+    // Set when the file was converted for the device first.
+    converted_to: Option<String>,
+}
+
+// What a mounted reader takes and where books go on it.
+struct Device {
+    name: &'static str,
+    formats: &'static [&'static str],
+    books_dir: PathBuf,
+}
+
+// Stores the SMTP password in the system keychain, or removes it when `password` is None.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_smtp_password(handle: tauri::AppHandle, password: Option<String>) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing the SMTP password")?;
+    match password.filter(|p| !p.is_empty()) {
+        Some(password) => keychain_set(KEYCHAIN_ACCOUNT, &password),
+        None => keychain_delete(KEYCHAIN_ACCOUNT),
+    }
+}
+
+fn content_type(extension: &str) -> &'static str {
+    match extension {
+        "epub" => "application/epub+zip",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "html" => "text/html",
+        "azw3" => "application/vnd.amazon.ebook",
+        "mobi" => "application/x-mobipocket-ebook",
+        _ => "application/octet-stream",
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase()
+}
+
+// Recognizes a Kindle or Kobo by the folders they keep at their root, walking up from `path`.
+fn detect_device(path: &Path) -> Device {
+    for root in path.ancestors() {
+        if root.join("documents").is_dir() && root.join("system").is_dir() {
+            let books_dir = root.join("documents");
+            return Device { name: "Kindle", formats: &["azw3", "mobi", "pdf", "txt"], books_dir };
+        }
+        if root.join(".kobo").is_dir() {
+            return Device { name: "Kobo", formats: &["epub", "kepub", "pdf", "mobi", "txt"], books_dir: path.into() };
+        }
+    }
+    Device { name: "folder", formats: &[], books_dir: path.into() }
+}
+
+// Converts with Calibre's `ebook-convert`, the only converter we can count on being around.
+fn convert(source: &Path, format: &str) -> Result<PathBuf, String> {
+    let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("book");
+    let output = std::env::temp_dir().join(format!("{}.{}", stem, format));
+    let status = Command::new("ebook-convert")
+        .arg(source)
+        .arg(&output)
+        .status()
+        .map_err(|_| format!("Converting to {} needs Calibre's ebook-convert on the PATH.", format.to_uppercase()))?;
+    if !status.success() || !output.exists() {
+        return Err(format!("ebook-convert could not convert the book to {}.", format.to_uppercase()));
+    }
+    Ok(output)
+}
+
+// The file in a format the destination accepts, converting when it takes none we have.
+fn file_for(source: &Path, formats: &[&str]) -> Result<(PathBuf, Option<String>), String> {
+    if formats.is_empty() || formats.contains(&extension(source).as_str()) {
+        return Ok((source.to_path_buf(), None));
+    }
+    let format = formats[0];
+    Ok((convert(source, format)?, Some(format.to_string())))
+}
+
+fn send_email(smtp: &SmtpSettings, password: String, to: &str, file: &Path, title: &str) -> Result<(), String> {
+    let size = fs::metadata(file).map_err(|e| e.to_string())?.len();
+    if size > MAX_EMAIL_BYTES {
+        return Err(format!("The file is {} MB; e-mail delivery allows at most 50 MB.", size / (1024 * 1024)));
+    }
+    let file_name = file.file_name().and_then(|n| n.to_str()).unwrap_or("book").to_string();
+    let attachment = Attachment::new(file_name).body(
+        fs::read(file).map_err(|e| e.to_string())?,
+        ContentType::parse(content_type(&extension(file))).map_err(|e| e.to_string())?,
+    );
+    let from: Mailbox = smtp.from.parse().map_err(|e| format!("Invalid sender address: {}", e))?;
+    let to: Mailbox = to.parse().map_err(|e| format!("Invalid device address: {}", e))?;
+    let message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(title)
+        .multipart(MultiPart::mixed().singlepart(SinglePart::plain(String::new())).singlepart(attachment))
+        .map_err(|e| e.to_string())?;
+
+    let builder = match smtp.security {
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&smtp.host),
+        SmtpSecurity::Tls => SmtpTransport::relay(&smtp.host),
+    }
+    .map_err(|e| e.to_string())?;
+    let mut builder = builder.credentials(Credentials::new(smtp.username.clone(), password));
+    if let Some(port) = smtp.port {
+        builder = builder.port(port);
+    }
+    builder.build().send(&message).map_err(|e| format!("Sending failed: {}", e))?;
+    Ok(())
+}
+
+fn copy_to_device(path: &Path, file: &Path) -> Result<(String, PathBuf), String> {
+    let device = detect_device(path);
+    fs::create_dir_all(&device.books_dir).map_err(|e| e.to_string())?;
+    let (file, _) = file_for(file, device.formats)?;
+    let name = file.file_name().ok_or_else(|| "The book has no file name.".to_string())?;
+    fs::copy(&file, device.books_dir.join(name)).map_err(|e| e.to_string())?;
+    Ok((device.name.to_string(), file))
+}
+
+// Sends a book to an e-reader: by e-mail (Send to Kindle and the like) over the SMTP server
+// in settings, or by copying it onto a mounted device. Books the device cannot read are
+// converted first. `file_path` sends another file for the book instead, such as an export.
+// Mail leaves the machine, so books kept off the cloud can only be copied.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn send_book_to_device(
+    handle: tauri::AppHandle,
+    book_id: String,
+    target: DeviceTarget,
+    file_path: Option<String>,
+) -> Result<SentBook, String> {
+    let book = handle
+        .state::<app_state::AppState>()
+        .recent_books
+        .read(&handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+        .ok_or_else(|| format!("Book not found: {}", book_id))?;
+    let source = PathBuf::from(file_path.unwrap_or(book.file_path));
+    if !source.is_file() {
+        return Err(format!("File not found: {}", source.display()));
+    }
+    if matches!(target, DeviceTarget::Email { .. }) {
+        ensure_cloud_allowed(&handle, &book_id)?;
+    }
+    let settings = load_settings(&handle)?;
+
+    tauri::async_runtime::spawn_blocking(move || match target {
+        DeviceTarget::Email { to } => {
+            let to = to
+                .or(settings.device_email)
+                .filter(|to| !to.trim().is_empty())
+                .ok_or_else(|| "No device e-mail address is set.".to_string())?;
+            let smtp = settings.smtp.ok_or_else(|| "Set up an SMTP server in settings first.".to_string())?;
+            let password = keychain_get(KEYCHAIN_ACCOUNT)?.ok_or_else(|| "The SMTP password is not set.".to_string())?;
+            let (file, converted_to) = file_for(&source, EMAIL_FORMATS)?;
+            send_email(&smtp, password, &to, &file, &book.title)?;
+            Ok(SentBook {
+                destination: to,
+                file_name: file.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string(),
+                converted_to,
+            })
+        }
+        DeviceTarget::Usb { path } => {
+            let (device, file) = copy_to_device(Path::new(&path), &source)?;
+            let converted_to = (file != source).then(|| extension(&file));
+            Ok(SentBook {
+                destination: format!("{} ({})", device, path),
+                file_name: file.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string(),
+                converted_to,
+            })
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
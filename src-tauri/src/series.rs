@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::Manager;
+
+use crate::epub::{attribute, read_package_document};
+use crate::{app_state, update_recent_books, BookStatus, RecentBook};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BookSeries {
+    pub name: String,
+    // Position in reading order; fractional for novellas between volumes (2.5).
+    pub index: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesVolume {
+    id: String,
+    title: String,
+    index: Option<f32>,
+    status: BookStatus,
+    progress: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesView {
+    name: String,
+    volumes: Vec<SeriesVolume>,
+    // The first volume after the last one finished that is not yet finished or dropped.
+    next: Option<SeriesVolume>,
+}
+
+fn parse_index(text: &str) -> Option<f32> {
+    text.trim().parse().ok().filter(|index: &f32| index.is_finite())
+}
+
+// Series recorded in an EPUB's package document, either by Calibre (`calibre:series`) or as
+// an EPUB 3 `belongs-to-collection` of type series.
+pub fn epub_series(path: &Path) -> Option<BookSeries> {
+    let opf = read_package_document(path).ok()?;
+    let metas: Vec<(&str, &str)> = opf
+        .split("<meta ")
+        .skip(1)
+        .filter_map(|rest| {
+            let (tag, after) = rest.split_once('>')?;
+            Some((tag, after.split('<').next().unwrap_or("")))
+        })
+        .collect();
+
+    let calibre = |name: &str| {
+        metas
+            .iter()
+            .find(|(tag, _)| attribute(tag, "name").as_deref() == Some(name))
+            .and_then(|(tag, _)| attribute(tag, "content"))
+    };
+    if let Some(name) = calibre("calibre:series").filter(|name| !name.trim().is_empty()) {
+        let index = calibre("calibre:series_index").as_deref().and_then(parse_index);
+        return Some(BookSeries { name: name.trim().to_string(), index });
+    }
+
+    let (tag, name) =
+        metas.iter().find(|(tag, _)| attribute(tag, "property").as_deref() == Some("belongs-to-collection"))?;
+    let refines = attribute(tag, "id").map(|id| format!("#{}", id));
+    let refinement = |property: &str| {
+        metas
+            .iter()
+            .find(|(tag, _)| {
+                refines.is_some()
+                    && attribute(tag, "refines") == refines
+                    && attribute(tag, "property").as_deref() == Some(property)
+            })
+            .map(|(_, value)| value.trim())
+    };
+    if refinement("collection-type").is_some_and(|kind| kind != "series") || name.trim().is_empty() {
+        return None;
+    }
+    Some(BookSeries { name: name.trim().to_string(), index: refinement("group-position").and_then(parse_index) })
+}
+
+// Fills in the series from the book file unless one is known or was set by hand.
+pub fn fill_series(book: &mut RecentBook) {
+    let metadata = &mut book.metadata;
+    let pinned = metadata.overridden.iter().any(|name| name == "series");
+    if metadata.series.is_none() && !pinned && book.file_type == "epub" {
+        metadata.series = epub_series(Path::new(&book.file_path));
+    }
+}
+
+// Sets or, with no name, clears the book's series. A hand-set series is kept by enrichment.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_book_series(
+    handle: tauri::AppHandle,
+    book_id: String,
+    name: Option<String>,
+    index: Option<f32>,
+) -> Result<(), String> {
+    if index.is_some_and(|index| !index.is_finite() || index < 0.0) {
+        return Err(format!("Invalid series index: {:?}", index));
+    }
+    update_recent_books(&handle, |data| {
+        let book = data
+            .books
+            .iter_mut()
+            .find(|b| b.id == book_id)
+            .ok_or_else(|| format!("Book not found: {}", book_id))?;
+        let metadata = &mut book.metadata;
+        metadata.series = name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .map(|name| BookSeries { name, index });
+        if !metadata.overridden.iter().any(|name| name == "series") {
+            metadata.overridden.push("series".to_string());
+        }
+        Ok(())
+    })?
+}
+
+// The library's volumes of a series (matched case-insensitively) in reading order, with
+// volumes lacking an index last by title.
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_series(handle: tauri::AppHandle, name: String) -> Result<SeriesView, String> {
+    let wanted = name.trim().to_lowercase();
+    let (names, mut volumes): (Vec<String>, Vec<SeriesVolume>) =
+        handle.state::<app_state::AppState>().recent_books.read(&handle, |data| {
+            data.books
+                .iter()
+                .filter_map(|b| {
+                    let series = b.metadata.series.as_ref()?;
+                    let volume = SeriesVolume {
+                        id: b.id.clone(),
+                        title: b.title.clone(),
+                        index: series.index,
+                        status: b.status,
+                        progress: b.progress,
+                    };
+                    (series.name.to_lowercase() == wanted).then(|| (series.name.clone(), volume))
+                })
+                .unzip()
+        })?;
+    volumes.sort_by(|a, b| match (a.index, b.index) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.title.cmp(&b.title),
+    });
+
+    let done = |v: &SeriesVolume| matches!(v.status, BookStatus::Finished | BookStatus::Archived);
+    let next = volumes.iter().rposition(done).and_then(|last| {
+        volumes[last + 1..]
+            .iter()
+            .find(|v| !done(v) && v.status != BookStatus::Abandoned)
+            .cloned()
+    });
+    // Spelled as the library spells it
+    let name = names.into_iter().next().unwrap_or(name);
+    Ok(SeriesView { name, volumes, next })
+}
@@ -0,0 +1,269 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_config_dir;
+use crate::app_state::AppState;
+use crate::model_params::{check_top_p, ParameterPreset};
+use crate::quarantine::parse_or_quarantine;
+use crate::restricted_mode;
+use crate::sync_conflicts::with_write_lock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationCachePolicy {
+    // Only translations made by the requested model count as cached.
+    #[default]
+    Exact,
+    // A translation by another model is returned right away and re-translated in the background.
+    StaleWhileRevalidate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    // Imported books are read from where they are.
+    #[default]
+    Link,
+    // Imported books are copied into the library folder first.
+    Copy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationGranularity {
+    // Each unit the reader sends (usually a paragraph) is translated as one piece.
+    #[default]
+    Paragraph,
+    // Units are split into sentences, translated and cached one by one, and joined again.
+    Sentence,
+    // Units are translated one by one with their neighbours attached as context.
+    Window,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    #[default]
+    StartTls,
+    // Implicit TLS, usually port 465.
+    Tls,
+}
+
+// Outgoing mail server for sending books to e-readers. The password is kept in the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: String,
+    pub from: String,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+// A monthly limit on OpenRouter use; unset fields mean no limit.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct UsageCap {
+    pub monthly_tokens: Option<u64>,
+    // In US dollars, as OpenRouter reports cost.
+    pub monthly_spend: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct UsageCaps {
+    pub overall: UsageCap,
+    // Keyed by feature, e.g. "translation" or "chat"; see `quota`.
+    pub features: BTreeMap<String, UsageCap>,
+}
+
+// When and how often to be reminded of vocabulary due for review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReviewReminderSettings {
+    pub enabled: bool,
+    // Hours between reminders while words stay due.
+    pub every_hours: u32,
+    // Local hours (0-23) between which no reminder is shown; the range may wrap midnight.
+    pub quiet_start_hour: u32,
+    pub quiet_end_hour: u32,
+    // Fewer due words than this are not worth a reminder.
+    pub min_due: usize,
+}
+
+impl Default for ReviewReminderSettings {
+    fn default() -> Self {
+        Self { enabled: false, every_hours: 24, quiet_start_hour: 22, quiet_end_hour: 8, min_due: 1 }
+    }
+}
+
+// Backend-owned settings. Every field has a default so older settings files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    // Only used by builds with the `updater` feature.
+    pub update_channel: UpdateChannel,
+    // Encrypts the translation, response and transliteration caches and the per-book annotations
+    // (alignments, citations, entities, readings, story). Book text and layout data stay plaintext.
+    pub encrypt_cache: bool,
+    // When set, translation batches are also sent to this model and the first valid reply wins.
+    pub race_model: Option<String>,
+    pub translation_cache_policy: TranslationCachePolicy,
+    pub translation_granularity: TranslationGranularity,
+    // Earlier translations of the same chapter included in each prompt as fixed context, so
+    // pronouns, tense and terms carry across batches. 0 leaves them out.
+    pub continuation_context: usize,
+    // Frontend-owned preferences (prompt templates, glossaries, styles, hotkeys) kept
+    // as-is so they travel with exported profiles.
+    pub ui_preferences: serde_json::Value,
+    // Sent as OpenRouter's `user` and `metadata` fields, e.g. to tell classroom seats apart.
+    pub openrouter_user: Option<String>,
+    pub openrouter_metadata: BTreeMap<String, String>,
+    // Sampling preset; when set it overrides each feature's temperature and `top_p`.
+    pub parameter_preset: Option<ParameterPreset>,
+    pub top_p: Option<f32>,
+    // How dropped and downloaded books enter the library, and where copies go (default:
+    // `library` in the data directory).
+    pub import_mode: ImportMode,
+    pub library_folder: Option<String>,
+    // Where `send_book_to_device` mails books, e.g. a Send to Kindle address.
+    pub smtp: Option<SmtpSettings>,
+    pub device_email: Option<String>,
+    // Hard limits for shared deployments (classrooms, families); requests past them fail with
+    // QUOTA_EXCEEDED.
+    pub usage_caps: UsageCaps,
+    pub review_reminders: ReviewReminderSettings,
+    // whisper.cpp model (a ggml .bin file) used to transcribe pronunciation practice.
+    pub speech_recognition_model: Option<String>,
+    // Model features use cached results only and never reach the network.
+    pub offline_mode: bool,
+    // Upper limit on answer tokens per request; translation batches are sized to stay under it.
+    pub max_output_tokens: Option<u32>,
+    // The application `open_book_externally` may use besides the system default. Only
+    // `set_external_app` changes it, after checking it is an installed application.
+    pub external_app: Option<String>,
+}
+
+// Bumped when the profile layout changes incompatibly.
+const PROFILE_FORMAT_VERSION: u32 = 1;
+
+// Field names that hold credentials; never written to an exported profile.
+const SECRET_FIELD_MARKERS: &[&str] = &["apikey", "api_key", "secret", "password", "access_token"];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SettingsProfile {
+    format_version: u32,
+    exported_at: DateTime<Utc>,
+    settings: AppSettings,
+}
+
+fn settings_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("settings.json"))
+}
+
+pub fn read_settings_file(handle: &tauri::AppHandle) -> Result<AppSettings, String> {
+    let path = settings_file_path(handle)?;
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+pub fn write_settings_file(handle: &tauri::AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let path = settings_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    with_write_lock(&path, || fs::write(&path, data).map_err(|e| e.to_string()))
+}
+
+pub fn load_settings(handle: &tauri::AppHandle) -> Result<AppSettings, String> {
+    handle.state::<AppState>().settings.get(handle)
+}
+
+pub fn save_settings(handle: &tauri::AppHandle, settings: &AppSettings) -> Result<(), String> {
+    handle.state::<AppState>().settings.set(settings.clone())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_app_settings(handle: tauri::AppHandle) -> Result<AppSettings, String> {
+    load_settings(&handle)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn save_app_settings(handle: tauri::AppHandle, settings: AppSettings) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing settings")?;
+    if let Some(top_p) = settings.top_p {
+        check_top_p(top_p)?;
+    }
+    let mut settings = settings;
+    settings.external_app = load_settings(&handle)?.external_app;
+    save_settings(&handle, &settings)
+}
+
+fn strip_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|name, _| {
+                let name = name.to_lowercase();
+                !SECRET_FIELD_MARKERS.iter().any(|marker| name.contains(marker))
+            });
+            map.values_mut().for_each(strip_secrets);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+// Writes the current settings to `path` as a shareable profile. The API key lives in its
+// own file and is never included.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_settings(handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    let mut settings = load_settings(&handle)?;
+    strip_secrets(&mut settings.ui_preferences);
+    let profile = SettingsProfile {
+        format_version: PROFILE_FORMAT_VERSION,
+        exported_at: Utc::now(),
+        settings,
+    };
+    let data = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// Replaces the current settings with a profile written by `export_settings`. Cache
+// encryption is tied to this machine's keychain, so the local choice is kept.
+#[tauri::command(rename_all = "camelCase")]
+pub fn import_settings(handle: tauri::AppHandle, path: String) -> Result<AppSettings, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing settings")?;
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let profile: SettingsProfile =
+        serde_json::from_str(&data).map_err(|e| format!("Not a valid settings profile: {}", e))?;
+    if profile.format_version > PROFILE_FORMAT_VERSION {
+        return Err(format!(
+            "This profile was exported by a newer version of PDFRead (format {}).",
+            profile.format_version
+        ));
+    }
+
+    let mut settings = profile.settings;
+    strip_secrets(&mut settings.ui_preferences);
+    let current = load_settings(&handle)?;
+    settings.encrypt_cache = current.encrypt_cache;
+    settings.external_app = current.external_app;
+    save_settings(&handle, &settings)?;
+    Ok(settings)
+}
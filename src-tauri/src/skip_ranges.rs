@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::book_data_file_path;
+use crate::book_text::load_book_text;
+use crate::epub::{chapter_blocks, spine_hrefs};
+use crate::quarantine::parse_or_quarantine;
+use crate::restricted_mode;
+
+// Headings of front and back matter not worth translating, counting or reading aloud.
+const SKIPPED_HEADINGS: &[&str] = &[
+    "copyright", "contents", "table of contents", "index", "bibliography", "references", "works cited",
+    "acknowledgments", "acknowledgements", "about the author", "about the authors", "also by", "other books by",
+    "dedication", "colophon", "title page", "half title", "inhaltsverzeichnis", "literaturverzeichnis",
+    "table des matières", "bibliographie", "índice", "bibliografía", "目录", "目次", "参考文献", "索引", "版权",
+];
+// Chapter files named for front and back matter, as EPUB tools usually name them.
+const SKIPPED_FILE_STEMS: &[&str] = &["copyright", "toc", "contents", "index", "bibliography", "colophon", "titlepage"];
+// Only short first blocks are read as a heading.
+const MAX_HEADING_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SkipRange {
+    // Pages of the extracted text, both ends included.
+    Pages { first_page: u32, last_page: u32, label: Option<String> },
+    // An EPUB chapter, by spine href; whole-book EPUB translation goes by chapter, not page.
+    Chapter { href: String, label: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SkipRanges {
+    pub ranges: Vec<SkipRange>,
+}
+
+impl SkipRanges {
+    pub fn skips_page(&self, page: u32) -> bool {
+        self.ranges.iter().any(|range| match range {
+            SkipRange::Pages { first_page, last_page, .. } => (*first_page..=*last_page).contains(&page),
+            SkipRange::Chapter { .. } => false,
+        })
+    }
+
+    pub fn skips_chapter(&self, href: &str) -> bool {
+        let chapter = href.split('#').next().unwrap_or(href);
+        self.ranges.iter().any(|range| match range {
+            SkipRange::Chapter { href, .. } => href.split('#').next().unwrap_or(href) == chapter,
+            SkipRange::Pages { .. } => false,
+        })
+    }
+}
+
+fn skip_ranges_file_path(handle: &tauri::AppHandle, book_id: &str) -> Result<PathBuf, String> {
+    book_data_file_path(handle, "skip_ranges", book_id)
+}
+
+pub fn load_skip_ranges(handle: &tauri::AppHandle, book_id: &str) -> Result<SkipRanges, String> {
+    let path = skip_ranges_file_path(handle, book_id)?;
+    if !path.exists() {
+        return Ok(SkipRanges::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_skip_ranges(handle: &tauri::AppHandle, book_id: &str, ranges: &SkipRanges) -> Result<(), String> {
+    let path = skip_ranges_file_path(handle, book_id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(ranges).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// The skipped heading a title reads as, ignoring case, numbering and trailing punctuation.
+fn skipped_heading(title: &str) -> Option<String> {
+    let heading = title
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '.' | ' '))
+        .trim_end_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace())
+        .to_lowercase();
+    SKIPPED_HEADINGS.contains(&heading.as_str()).then(|| title.trim().to_string())
+}
+
+// Runs of pages under a skipped heading, each up to the next titled page.
+fn suggest_pages(handle: &tauri::AppHandle, book_id: &str) -> Result<Vec<SkipRange>, String> {
+    let mut text = load_book_text(handle, book_id)?;
+    text.pages.sort_by_key(|p| p.page);
+    let mut ranges = Vec::new();
+    let mut open: Option<(u32, u32, String)> = None;
+    for page in &text.pages {
+        if let Some(title) = &page.title {
+            if let Some((first_page, last_page, label)) = open.take() {
+                ranges.push(SkipRange::Pages { first_page, last_page, label: Some(label) });
+            }
+            open = skipped_heading(title).map(|label| (page.page, page.page, label));
+        } else if let Some((_, last_page, _)) = open.as_mut() {
+            *last_page = page.page;
+        }
+    }
+    if let Some((first_page, last_page, label)) = open {
+        ranges.push(SkipRange::Pages { first_page, last_page, label: Some(label) });
+    }
+    Ok(ranges)
+}
+
+// Spine chapters whose file name or opening heading marks them as front or back matter.
+fn suggest_chapters(epub_path: &str, book_id: &str) -> Result<Vec<SkipRange>, String> {
+    let mut ranges = Vec::new();
+    for href in spine_hrefs(Path::new(epub_path))? {
+        let chapter = href.split('#').next().unwrap_or(&href);
+        let stem = Path::new(chapter).file_stem().map(|s| s.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let heading = chapter_blocks(epub_path, book_id, &href)?
+            .into_iter()
+            .find(|block| !block.text.trim().is_empty())
+            .filter(|block| block.text.chars().count() <= MAX_HEADING_CHARS)
+            .and_then(|block| skipped_heading(&block.text));
+        let label = heading.or_else(|| SKIPPED_FILE_STEMS.contains(&stem.as_str()).then(|| stem.clone()));
+        if label.is_some() {
+            ranges.push(SkipRange::Chapter { href, label });
+        }
+    }
+    Ok(ranges)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_skip_ranges(handle: tauri::AppHandle, book_id: String) -> Result<Vec<SkipRange>, String> {
+    Ok(load_skip_ranges(&handle, &book_id)?.ranges)
+}
+
+// Sets the parts of a book that whole-book translation, statistics and read-aloud leave out.
+// Reading and translating a page by hand is unaffected.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_skip_ranges(handle: tauri::AppHandle, book_id: String, ranges: Vec<SkipRange>) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing skip ranges")?;
+    for range in &ranges {
+        if let SkipRange::Pages { first_page, last_page, .. } = range {
+            if *first_page == 0 || first_page > last_page {
+                return Err(format!("Invalid page range: {}-{}", first_page, last_page));
+            }
+        }
+    }
+    save_skip_ranges(&handle, &book_id, &SkipRanges { ranges })
+}
+
+// Ranges worth skipping, found from the outline: pages titled as copyright, contents, index,
+// bibliography and the like, and for EPUBs the spine chapters that hold them. Nothing is saved;
+// the user reviews them and passes the ones to keep to `set_skip_ranges`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn suggest_skip_ranges(handle: tauri::AppHandle, book_id: String) -> Result<Vec<SkipRange>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let book = handle
+            .state::<AppState>()
+            .recent_books
+            .read(&handle, |data| data.books.iter().find(|b| b.id == book_id).cloned())?
+            .ok_or_else(|| format!("Book not found: {}", book_id))?;
+        let mut ranges = suggest_pages(&handle, &book_id)?;
+        let epub_path = std::iter::once((book.file_type.as_str(), book.file_path.as_str()))
+            .chain(book.formats.iter().map(|f| (f.file_type.as_str(), f.file_path.as_str())))
+            .find(|(kind, _)| *kind == "epub")
+            .map(|(_, path)| path.to_string());
+        if let Some(path) = epub_path {
+            ranges.extend(suggest_chapters(&path, &book_id)?);
+        }
+        Ok(ranges)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,140 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::quarantine::parse_or_quarantine;
+use crate::{app_config_dir, load_vocabulary, VocabularyEntry};
+
+const INITIAL_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+// Grades below this (of 0-5) count as forgotten and restart the card.
+const PASSING_GRADE: u8 = 3;
+
+// SM-2 scheduling state of one vocabulary word. Words without one are new and due now.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewState {
+    due_at: DateTime<Utc>,
+    interval_days: u32,
+    ease: f64,
+    repetitions: u32,
+    last_reviewed_at: DateTime<Utc>,
+}
+
+// Keyed by the lowercased word, like vocabulary lookups.
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct SrsData {
+    cards: BTreeMap<String, ReviewState>,
+}
+
+// Serializes read-modify-write of the review file.
+#[derive(Default)]
+pub struct ReviewLock {
+    lock: Mutex<()>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DueReview {
+    #[serde(flatten)]
+    entry: VocabularyEntry,
+    // None for words never reviewed.
+    review: Option<ReviewState>,
+}
+
+fn srs_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("srs.json"))
+}
+
+fn load_srs(handle: &tauri::AppHandle) -> Result<SrsData, String> {
+    let path = srs_file_path(handle)?;
+    if !path.exists() {
+        return Ok(SrsData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_srs(handle: &tauri::AppHandle, data: &SrsData) -> Result<(), String> {
+    let path = srs_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn due_entries(handle: &tauri::AppHandle) -> Result<Vec<DueReview>, String> {
+    let cards = load_srs(handle)?.cards;
+    let now = Utc::now();
+    let mut due: Vec<DueReview> = load_vocabulary(handle)?
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let review = cards.get(&entry.word.to_lowercase()).cloned();
+            DueReview { entry, review }
+        })
+        .filter(|d| d.review.as_ref().is_none_or(|r| r.due_at <= now))
+        .collect();
+    // Overdue reviews first, oldest due first; new words after them in the order they were saved.
+    due.sort_by_key(|d| (d.review.is_none(), d.review.as_ref().map(|r| r.due_at), d.entry.added_at));
+    Ok(due)
+}
+
+// Vocabulary words due for review now, new ones included.
+pub fn due_count(handle: &tauri::AppHandle) -> Result<usize, String> {
+    Ok(due_entries(handle)?.len())
+}
+
+// The next state after a review graded 0 (blackout) to 5 (perfect), per SM-2.
+fn schedule(previous: Option<&ReviewState>, grade: u8, now: DateTime<Utc>) -> ReviewState {
+    let (interval, ease, repetitions) =
+        previous.map_or((0, INITIAL_EASE, 0), |r| (r.interval_days, r.ease, r.repetitions));
+    let quality = grade as f64;
+    let ease = (ease + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)).max(MIN_EASE);
+    let (interval_days, repetitions) = if grade < PASSING_GRADE {
+        (1, 0)
+    } else {
+        let interval = match repetitions {
+            0 => 1,
+            1 => 6,
+            _ => (interval as f64 * ease).round() as u32,
+        };
+        (interval, repetitions + 1)
+    };
+    ReviewState {
+        due_at: now + Duration::days(interval_days as i64),
+        interval_days,
+        ease,
+        repetitions,
+        last_reviewed_at: now,
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_due_reviews(handle: tauri::AppHandle, limit: Option<usize>) -> Result<Vec<DueReview>, String> {
+    let mut due = due_entries(&handle)?;
+    due.truncate(limit.unwrap_or(usize::MAX));
+    Ok(due)
+}
+
+// Records a review of `word` graded 0-5 and schedules its next one.
+#[tauri::command(rename_all = "camelCase")]
+pub fn record_review(handle: tauri::AppHandle, word: String, grade: u8) -> Result<ReviewState, String> {
+    if grade > 5 {
+        return Err(format!("Grade must be 0 to 5, got {}.", grade));
+    }
+    let key = word.trim().to_lowercase();
+    if !load_vocabulary(&handle)?.entries.iter().any(|e| e.word.to_lowercase() == key) {
+        return Err(format!("Not in vocabulary: {}", word.trim()));
+    }
+    let lock = handle.state::<ReviewLock>();
+    let _lock = lock.lock.lock().unwrap_or_else(|e| e.into_inner());
+    let mut data = load_srs(&handle)?;
+    let state = schedule(data.cards.get(&key), grade, Utc::now());
+    data.cards.insert(key, state.clone());
+    save_srs(&handle, &data)?;
+    Ok(state)
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+use crate::book_text::{load_book_text, BookPage};
+use crate::encryption::{read_store_file, write_store_file};
+use crate::quarantine::parse_or_quarantine;
+use crate::response_cache::{request_openrouter_cached, FEATURE_CHAT, FEATURE_SUMMARY};
+use crate::restricted_mode;
+use crate::{
+    arxiv, book_data_file_path, ensure_cloud_allowed, extract_json_object, load_openrouter_credentials,
+    truncate_for_error, OpenRouterCredentials,
+};
+
+// Used when the book has no chapter titles to split on.
+const PAGES_PER_SECTION: usize = 10;
+// Chat gets the text of the current page and this many pages before it.
+const CHAT_CONTEXT_PAGES: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryCharacter {
+    name: String,
+    description: String,
+    #[serde(default)]
+    relationships: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorySection {
+    start_page: u32,
+    end_page: u32,
+    title: Option<String>,
+    summary: String,
+    #[serde(default)]
+    events: Vec<String>,
+    // Everyone known as of the end of this section, so later sections never leak backwards.
+    #[serde(default)]
+    characters: Vec<StoryCharacter>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct StoryData {
+    sections: Vec<StorySection>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoryContext {
+    up_to_page: u32,
+    sections: Vec<StorySection>,
+    characters: Vec<StoryCharacter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SectionResponse {
+    summary: String,
+    #[serde(default)]
+    events: Vec<String>,
+    #[serde(default)]
+    characters: Vec<StoryCharacter>,
+}
+
+fn load_story(handle: &tauri::AppHandle, book_id: &str) -> Result<StoryData, String> {
+    let path = book_data_file_path(handle, "story", book_id)?;
+    if !path.exists() {
+        return Ok(StoryData::default());
+    }
+    let data = read_store_file(handle, &path)?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_story(handle: &tauri::AppHandle, book_id: &str, story: &StoryData) -> Result<(), String> {
+    let path = book_data_file_path(handle, "story", book_id)?;
+    let data = serde_json::to_string_pretty(story).map_err(|e| e.to_string())?;
+    write_store_file(handle, &path, &data)
+}
+
+// Splits pages into sections at chapter-title changes, or every PAGES_PER_SECTION pages.
+fn split_sections(pages: &[BookPage]) -> Vec<&[BookPage]> {
+    let has_titles = pages.iter().any(|p| p.title.is_some());
+    let mut sections = Vec::new();
+    let mut start = 0;
+    for index in 1..=pages.len() {
+        let boundary = index == pages.len()
+            || if has_titles {
+                pages[index].title.is_some() && pages[index].title != pages[index - 1].title
+            } else {
+                index - start >= PAGES_PER_SECTION
+            };
+        if boundary {
+            sections.push(&pages[start..index]);
+            start = index;
+        }
+    }
+    sections
+}
+
+fn pages_text(pages: &[BookPage]) -> String {
+    pages
+        .iter()
+        .flat_map(|p| p.paragraphs.iter().map(|para| para.text.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn build_section_system_prompt() -> String {
+    [
+        "You are a careful reading companion that tracks a book's story as it is read.",
+        "Summarize only what happens in the given section; never guess at later events.",
+        "Output STRICT JSON ONLY.",
+        "No markdown, no explanations, no extra text.",
+    ]
+    .join(" ")
+}
+
+fn build_section_prompt(previous: Option<&StorySection>, text: &str) -> String {
+    let previous_context = match previous {
+        Some(section) => format!(
+            "Story so far: {}\nKnown characters: {}\n",
+            section.summary,
+            serde_json::to_string(&section.characters).unwrap_or_else(|_| "[]".to_string())
+        ),
+        None => String::new(),
+    };
+    format!(
+        r#"{}Summarize the next section of the book.
+Return JSON in this exact format:
+{{"summary": "story so far, including this section, in a short paragraph", "events": ["key plot event in this section"], "characters": [{{"name": "...", "description": "who they are so far", "relationships": ["relation to another character"]}}]}}
+- characters: the full updated list of characters known so far
+Section text:
+{}"#,
+        previous_context, text
+    )
+}
+
+async fn summarize_section(
+    handle: &tauri::AppHandle,
+    credentials: &OpenRouterCredentials,
+    model: &str,
+    previous: Option<&StorySection>,
+    pages: &[BookPage],
+) -> Result<StorySection, String> {
+    let system_prompt = build_section_system_prompt();
+    let user_prompt = build_section_prompt(previous, &pages_text(pages));
+    let content = request_openrouter_cached(
+        handle,
+        FEATURE_SUMMARY,
+        credentials,
+        model,
+        0.2,
+        &system_prompt,
+        &user_prompt,
+    )
+    .await?;
+    let json_content = extract_json_object(&content);
+    let parsed: SectionResponse = serde_json::from_str(&json_content).map_err(|e| {
+        format!("Failed to parse story JSON: {} (content: {})", e, truncate_for_error(&json_content))
+    })?;
+    Ok(StorySection {
+        start_page: pages.first().map(|p| p.page).unwrap_or(0),
+        end_page: pages.last().map(|p| p.page).unwrap_or(0),
+        title: pages.first().and_then(|p| p.title.clone()),
+        summary: parsed.summary,
+        events: parsed.events,
+        characters: parsed.characters,
+    })
+}
+
+// Summarizes any finished sections up to `up_to_page` that are not stored yet and returns
+// the story as known at that page. Sections past the page are never read.
+pub async fn build_story_context(
+    handle: &tauri::AppHandle,
+    model: &str,
+    book_id: &str,
+    up_to_page: u32,
+) -> Result<StoryContext, String> {
+    let text = load_book_text(handle, book_id)?;
+    let mut story = load_story(handle, book_id)?;
+    let known_end = story.sections.last().map(|s| s.end_page).unwrap_or(0);
+
+    let pending: Vec<&[BookPage]> = split_sections(&text.pages)
+        .into_iter()
+        .filter(|section| {
+            let end = section.last().map(|p| p.page).unwrap_or(0);
+            end > known_end && end <= up_to_page
+        })
+        .collect();
+
+    if !pending.is_empty() {
+        ensure_cloud_allowed(handle, book_id)?;
+        let credentials = load_openrouter_credentials(handle)?;
+        for pages in pending {
+            let section = summarize_section(handle, &credentials, model, story.sections.last(), pages).await?;
+            story.sections.push(section);
+            save_story(handle, book_id, &story)?;
+        }
+    }
+
+    let sections: Vec<StorySection> = story
+        .sections
+        .into_iter()
+        .filter(|s| s.end_page <= up_to_page)
+        .collect();
+    let characters = sections.last().map(|s| s.characters.clone()).unwrap_or_default();
+    Ok(StoryContext {
+        up_to_page,
+        sections,
+        characters,
+    })
+}
+
+// The stored story summary as of `max_page`, without summarizing anything new.
+pub fn stored_story_summary(handle: &tauri::AppHandle, book_id: &str, max_page: u32) -> Result<Option<String>, String> {
+    Ok(load_story(handle, book_id)?
+        .sections
+        .into_iter()
+        .rfind(|s| s.end_page <= max_page)
+        .map(|s| s.summary))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_story_context(
+    handle: tauri::AppHandle,
+    model: String,
+    book_id: String,
+    up_to_page: u32,
+) -> Result<StoryContext, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Story summaries")?;
+    build_story_context(&handle, &model, &book_id, up_to_page).await
+}
+
+// Answers a question about the book using only what the reader has already read.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn chat_with_book(
+    handle: tauri::AppHandle,
+    model: String,
+    book_id: String,
+    current_page: u32,
+    question: String,
+) -> Result<String, String> {
+    restricted_mode::ensure_unrestricted(&handle, "Chat")?;
+    ensure_cloud_allowed(&handle, &book_id)?;
+    let story = build_story_context(&handle, &model, &book_id, current_page).await?;
+    let text = load_book_text(&handle, &book_id)?;
+    let recent: Vec<BookPage> = text
+        .pages
+        .into_iter()
+        .filter(|p| p.page <= current_page && p.page + CHAT_CONTEXT_PAGES >= current_page)
+        .collect();
+
+    let story_so_far = story.sections.last().map(|s| s.summary.as_str()).unwrap_or("");
+    let characters = serde_json::to_string(&story.characters).unwrap_or_else(|_| "[]".to_string());
+    let system_prompt = format!(
+        "You are a helpful reading assistant. The reader is on page {}. Answer using only the story so far and the recent pages provided. Never reveal or speculate about anything after page {}; if the question needs later material, say that it has not happened yet in their reading.",
+        current_page, current_page
+    );
+    let mut user_prompt = format!(
+        "Story so far:\n{}\n\nCharacters:\n{}\n\nRecent pages:\n{}\n\n---\n\nQuestion: {}",
+        story_so_far,
+        characters,
+        pages_text(&recent),
+        question
+    );
+    if let Some(preamble) = arxiv::chat_preamble(&handle, &book_id)? {
+        user_prompt = format!("{}\n\n{}", preamble, user_prompt);
+    }
+
+    let credentials = load_openrouter_credentials(&handle)?;
+    request_openrouter_cached(&handle, FEATURE_CHAT, &credentials, &model, 0.3, &system_prompt, &user_prompt).await
+}
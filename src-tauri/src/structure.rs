@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+// Words that open a caption when followed by a number, e.g. "Figure 3.", "Tab. 2:", "图 1".
+const CAPTION_LABELS: &[&str] = &[
+    "figure", "fig", "table", "tab", "chart", "plate", "illustration", "abbildung", "abb", "tabelle", "tableau",
+    "tabla", "figura", "图", "表",
+];
+// A line needs at least this many cells to be read as a table row.
+const MIN_TABLE_CELLS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    #[default]
+    Body,
+    // Figure and table captions: translated like body text, but kept apart from it.
+    Caption,
+    // Tabular data: passed through untranslated.
+    Table,
+    // Program listings, only ever from markup (`<pre>`, `<code>`): passed through untranslated.
+    Code,
+}
+
+impl BlockKind {
+    // Blocks kept exactly as extracted: never reflowed, translated or sent to a model.
+    pub fn is_verbatim(self) -> bool {
+        matches!(self, BlockKind::Table | BlockKind::Code)
+    }
+
+    pub fn is_body(&self) -> bool {
+        *self == BlockKind::Body
+    }
+}
+
+fn is_caption(text: &str) -> bool {
+    let lower = text.trim_start().to_lowercase();
+    CAPTION_LABELS.iter().any(|label| {
+        let Some(rest) = lower.strip_prefix(label) else {
+            return false;
+        };
+        let number = rest
+            .trim_start_matches(['.', ' ', '\u{00A0}'])
+            .split(|c: char| c.is_whitespace() || matches!(c, ':' | '：'))
+            .next()
+            .unwrap_or("")
+            .trim_end_matches('.');
+        let arabic = number.starts_with(|c: char| c.is_ascii_digit())
+            && number.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '-'));
+        let roman = !number.is_empty() && number.chars().all(|c| matches!(c, 'i' | 'v' | 'x'));
+        arabic || roman
+    })
+}
+
+// Cells of one line: runs separated by tabs or by two or more spaces.
+fn table_cells(line: &str) -> usize {
+    line.split('\t')
+        .flat_map(|part| part.split("  "))
+        .filter(|cell| !cell.trim().is_empty())
+        .count()
+}
+
+fn is_numeric_token(token: &str) -> bool {
+    let token = token.trim_matches(|c: char| matches!(c, '%' | '$' | '€' | '£' | '(' | ')' | '+'));
+    !token.is_empty() && token.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '–'))
+}
+
+// Several lines laid out in columns: most of them split into cells, or some of them and the
+// text mostly numbers. Prose full of years and figures has no columns and stays body text.
+fn is_table(text: &str) -> bool {
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.len() < 2 {
+        return false;
+    }
+    let columned = lines.iter().filter(|line| table_cells(line) >= MIN_TABLE_CELLS).count();
+    if columned == 0 {
+        return false;
+    }
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let numeric = tokens.iter().filter(|token| is_numeric_token(token)).count();
+    columned * 2 >= lines.len() || numeric * 2 >= tokens.len()
+}
+
+// Classifies a block of extracted text as it came out of extraction, before reflow joins its
+// lines. Only for whole page blocks: a single sentence has no layout to go by. Code is never
+// guessed from text; prose with parentheses and semicolons reads much like it.
+pub fn classify_block(text: &str) -> BlockKind {
+    if is_caption(text) {
+        BlockKind::Caption
+    } else if is_table(text) {
+        BlockKind::Table
+    } else {
+        BlockKind::Body
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn classify_blocks(blocks: Vec<String>) -> Result<Vec<BlockKind>, String> {
+    Ok(blocks.iter().map(|block| classify_block(block)).collect())
+}
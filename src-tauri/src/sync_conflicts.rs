@@ -0,0 +1,241 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::vocab_index::VocabularyIndex;
+use crate::vocab_merge::{merge_vocabulary_entries, VocabularyMergeStrategy};
+use crate::{
+    app_config_dir, app_state, recent_books_file_path, vocabulary_file_path, RecentBooksData, VocabularyData,
+};
+
+// A lock older than this was left behind by a crash or a machine that went offline.
+const LOCK_STALE_AFTER_SECS: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockInfo {
+    host: String,
+    pid: u32,
+    acquired_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ConflictMergeReport {
+    // Conflict copies merged and moved to `<config>/conflicts`.
+    files: Vec<String>,
+    vocabulary_added: usize,
+    books_updated: usize,
+}
+
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+// Runs `write` while holding `<file>.lock`. The lock is created with `create_new`, so two instances
+// can't both take it; a fresh lock from another process means that one is writing right now and
+// the write is refused and, for the in-memory stores, retried by the next flush. A stale lock is
+// removed and taken over. This only keeps instances on the same machine apart: a sync client
+// delivers the lock file late if at all, so writes from other machines are not prevented and end
+// up as conflict copies, merged below.
+pub fn with_write_lock(path: &Path, write: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    let lock = lock_path(path);
+    if let Some(parent) = lock.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let info = LockInfo {
+        host: host_name(),
+        pid: std::process::id(),
+        acquired_at: Utc::now(),
+    };
+    let data = serde_json::to_string(&info).map_err(|e| e.to_string())?;
+
+    // One retry: a stale lock is removed and the create attempted again.
+    let mut retried = false;
+    let owned = loop {
+        match OpenOptions::new().write(true).create_new(true).open(&lock) {
+            Ok(mut file) => {
+                if let Err(e) = file.write_all(data.as_bytes()) {
+                    let _ = fs::remove_file(&lock);
+                    return Err(e.to_string());
+                }
+                break true;
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => match lock_holder(&lock) {
+                // Another writer in this process already holds it.
+                Some(holder) if holder.host == info.host && holder.pid == info.pid => break false,
+                Some(holder) if retried || !is_stale(holder.acquired_at) => {
+                    return Err(format!(
+                        "{} is being written by {} (pid {}); will retry.",
+                        path.display(),
+                        holder.host,
+                        holder.pid
+                    ));
+                }
+                // A lock that can't be read yet was probably just created; judge it by its age.
+                None if retried || lock_modified(&lock).is_some_and(|at| !is_stale(at)) => {
+                    return Err(format!("{} is being written; will retry.", path.display()));
+                }
+                _ => {
+                    let _ = fs::remove_file(&lock);
+                    retried = true;
+                }
+            },
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+
+    let result = write();
+    if owned {
+        let _ = fs::remove_file(&lock);
+    }
+    result
+}
+
+fn lock_holder(lock: &Path) -> Option<LockInfo> {
+    fs::read_to_string(lock)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+}
+
+fn lock_modified(lock: &Path) -> Option<DateTime<Utc>> {
+    fs::metadata(lock).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from)
+}
+
+fn is_stale(acquired_at: DateTime<Utc>) -> bool {
+    Utc::now() - acquired_at >= Duration::seconds(LOCK_STALE_AFTER_SECS)
+}
+
+// Whether `stem` is the name a sync client gives a conflicting copy of `base`:
+// "vocabulary (conflicted copy 2024-01-01).json" or "vocabulary (Ann's conflicted copy 2024-01-01).json"
+// (Dropbox), "vocabulary.sync-conflict-20240101-120000-ABCDEFG.json" (Syncthing), "vocabulary 2.json"
+// (iCloud), "vocabulary-DESKTOP-AB12.json" (OneDrive). Anything else next to the file is left alone.
+fn is_conflict_stem(stem: &str, base: &str) -> bool {
+    let Some(rest) = stem.strip_prefix(base) else {
+        return false;
+    };
+    if let Some(note) = rest.strip_prefix(" (").and_then(|note| note.strip_suffix(')')) {
+        return note == "conflicted copy" || note.contains("conflicted copy ");
+    }
+    if let Some(stamp) = rest.strip_prefix(".sync-conflict-") {
+        let mut parts = stamp.splitn(3, '-');
+        let digits = |part: Option<&str>, len: usize| {
+            part.is_some_and(|p| p.len() == len && p.chars().all(|c| c.is_ascii_digit()))
+        };
+        return digits(parts.next(), 8) && digits(parts.next(), 6);
+    }
+    if let Some(number) = rest.strip_prefix(' ') {
+        return !number.is_empty() && number.len() <= 3 && number.chars().all(|c| c.is_ascii_digit());
+    }
+    if let Some(machine) = rest.strip_prefix('-') {
+        return !machine.is_empty()
+            && machine.chars().any(|c| c.is_ascii_uppercase())
+            && machine.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-');
+    }
+    false
+}
+
+pub fn conflict_copies(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(base), Some(extension)) = (path.parent(), path.file_stem(), path.extension()) else {
+        return Vec::new();
+    };
+    let base = base.to_string_lossy();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut copies: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            candidate.extension() == Some(extension)
+                && candidate
+                    .file_stem()
+                    .is_some_and(|stem| is_conflict_stem(&stem.to_string_lossy(), &base))
+        })
+        .collect();
+    copies.sort();
+    copies
+}
+
+fn read_conflict_copy<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let data = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&data) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::warn!("Skipping unreadable conflict copy {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// Moves a merged copy out of the config folder, keeping it around in case the merge
+// needs to be checked by hand.
+fn archive_conflict_copy(handle: &tauri::AppHandle, path: &Path) -> Result<String, String> {
+    let dir = app_config_dir(handle)?.join("conflicts");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let target = dir.join(format!("{}-{}", Utc::now().format("%Y%m%d%H%M%S"), name));
+    fs::rename(path, &target).map_err(|e| e.to_string())?;
+    Ok(name)
+}
+
+// Books are matched by id; whichever copy was opened last holds the current position.
+fn merge_recent_books(into: &mut RecentBooksData, other: RecentBooksData) -> usize {
+    let mut updated = 0;
+    for book in other.books {
+        match into.books.iter_mut().find(|b| b.id == book.id) {
+            Some(existing) if book.last_opened_at > existing.last_opened_at => {
+                *existing = book;
+                updated += 1;
+            }
+            Some(_) => {}
+            None => {
+                into.books.push(book);
+                updated += 1;
+            }
+        }
+    }
+    updated
+}
+
+// Folds conflict copies of the vocabulary and recent-books files into the live stores and
+// archives them. Runs at startup and on every background flush.
+pub fn merge_pending_conflicts(handle: &tauri::AppHandle) -> Result<ConflictMergeReport, String> {
+    let state = handle.state::<app_state::AppState>();
+    let mut report = ConflictMergeReport::default();
+
+    for copy in conflict_copies(&vocabulary_file_path(handle)?) {
+        if let Some(other) = read_conflict_copy::<VocabularyData>(&copy) {
+            let merged = state.vocabulary.update(handle, |vocab| {
+                merge_vocabulary_entries(&mut vocab.entries, other.entries, VocabularyMergeStrategy::Union)
+            })?;
+            report.vocabulary_added += merged.added.len();
+            report.files.push(archive_conflict_copy(handle, &copy)?);
+        }
+    }
+    if report.vocabulary_added > 0 {
+        handle.state::<VocabularyIndex>().invalidate();
+    }
+    for copy in conflict_copies(&recent_books_file_path(handle)?) {
+        if let Some(other) = read_conflict_copy::<RecentBooksData>(&copy) {
+            report.books_updated += state.recent_books.update(handle, |data| merge_recent_books(data, other))?;
+            report.files.push(archive_conflict_copy(handle, &copy)?);
+        }
+    }
+    Ok(report)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn merge_sync_conflicts(handle: tauri::AppHandle) -> Result<ConflictMergeReport, String> {
+    merge_pending_conflicts(&handle)
+}
@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+
+use crate::book_data_file_path;
+use crate::book_text::{clean_stored_pages, BookText};
+use crate::quarantine::parse_or_quarantine;
+use crate::restricted_mode;
+use crate::segmentation::{segment, TextSegment};
+
+// Running headers and footers are looked for among this many lines at each edge of a page.
+const EDGE_LINES: usize = 2;
+// Below this many pages a repeated line is as likely to be content as a running header.
+const RUNNING_LINE_MIN_PAGES: usize = 3;
+// Front matter rarely runs past this many pages; a longer "numeral" is a word.
+const MAX_ROMAN_PAGE: u32 = 100;
+
+// Per-book switches for the cleanup applied to extracted text before it is segmented,
+// stored or sent anywhere. Everything is on by default; scanned or unusual layouts can
+// turn single passes off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupOptions {
+    // Joins words split by a hyphen at the end of a line ("transla-\ntion").
+    pub dehyphenate: bool,
+    // Joins hard-wrapped lines into paragraphs; blank lines and indents still break them.
+    pub reflow: bool,
+    // Drops bare page numbers ("12", "- 12 -", "Page 12", "xiv") on the first or last line.
+    pub strip_page_numbers: bool,
+    // Drops running headers and footers: lines repeated at the top or bottom of many pages.
+    pub strip_running_lines: bool,
+    // Share of stored pages a top or bottom line must repeat on to count as running.
+    pub running_line_min_share: f32,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        Self {
+            dehyphenate: true,
+            reflow: true,
+            strip_page_numbers: true,
+            strip_running_lines: true,
+            running_line_min_share: 0.3,
+        }
+    }
+}
+
+// Signatures of the lines found at the edges of each page as it was first stored, kept so
+// detection still sees them after they have been stripped from the stored text.
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct RunningLineData {
+    edges: BTreeMap<u32, Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreparedPage {
+    text: String,
+    segments: Vec<TextSegment>,
+}
+
+pub fn load_cleanup_options(handle: &tauri::AppHandle, book_id: &str) -> Result<CleanupOptions, String> {
+    let path = book_data_file_path(handle, "text_cleanup", book_id)?;
+    if !path.exists() {
+        return Ok(CleanupOptions::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_cleanup_options(handle: &tauri::AppHandle, book_id: &str, options: &CleanupOptions) -> Result<(), String> {
+    let path = book_data_file_path(handle, "text_cleanup", book_id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(options).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn load_running_lines(handle: &tauri::AppHandle, book_id: &str) -> Result<RunningLineData, String> {
+    let path = book_data_file_path(handle, "running_lines", book_id)?;
+    if !path.exists() {
+        return Ok(RunningLineData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_running_lines(handle: &tauri::AppHandle, book_id: &str, running: &RunningLineData) -> Result<(), String> {
+    let path = book_data_file_path(handle, "running_lines", book_id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(running).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn roman_numeral(mut value: u32) -> String {
+    const DIGITS: [(u32, &str); 9] =
+        [(100, "c"), (90, "xc"), (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i")];
+    let mut numeral = String::new();
+    for (digit, letters) in DIGITS {
+        while value >= digit {
+            numeral.push_str(letters);
+            value -= digit;
+        }
+    }
+    numeral
+}
+
+// A well-formed lowercase numeral, so words made of the same letters ("civic", "ill") are not
+// taken for one. Capitals on a line of their own are chapter or part numbers.
+fn is_roman_numeral(text: &str) -> bool {
+    text.len() <= 8 && (1..=MAX_ROMAN_PAGE).any(|value| roman_numeral(value) == text)
+}
+
+// "12", "- 12 -", "[12]", "Page 12", "p. 12", "xiv". Roman numerals only on their own.
+pub fn is_page_number_line(line: &str) -> bool {
+    let trimmed = line.trim_matches(|c: char| matches!(c, '-' | '–' | '[' | ']') || c.is_whitespace());
+    let lower = trimmed.to_lowercase();
+    let number = lower
+        .strip_prefix("page")
+        .or_else(|| lower.strip_prefix("p."))
+        .unwrap_or(&lower)
+        .trim();
+    (!number.is_empty() && number.len() <= 5 && number.chars().all(|c| c.is_ascii_digit())) || is_roman_numeral(trimmed)
+}
+
+// Case and digits ignored, so "Chapter 3 · 41" and "CHAPTER 3 · 42" compare equal.
+fn line_signature(line: &str) -> String {
+    let lower: String = line
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_digit() { '#' } else { c })
+        .collect();
+    lower.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn edge_signatures(lines: &[&str]) -> Vec<String> {
+    let lines: Vec<&str> = lines.iter().copied().filter(|line| !line.trim().is_empty()).collect();
+    let mut edges: Vec<&str> = lines.iter().take(EDGE_LINES).copied().collect();
+    let tail_start = lines.len().saturating_sub(EDGE_LINES).max(edges.len());
+    edges.extend(&lines[tail_start..]);
+    let mut signatures: Vec<String> = edges.into_iter().map(line_signature).collect();
+    signatures.sort();
+    signatures.dedup();
+    signatures
+}
+
+// Signatures that repeat at the page edges on enough pages to be running headers or footers.
+fn running_signatures(running: &RunningLineData, min_share: f32) -> HashSet<String> {
+    let threshold = RUNNING_LINE_MIN_PAGES.max((running.edges.len() as f32 * min_share).ceil() as usize);
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for signature in running.edges.values().flatten() {
+        *counts.entry(signature.as_str()).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(signature, count)| *count >= threshold && signature.chars().any(char::is_alphabetic))
+        .map(|(signature, _)| signature.to_string())
+        .collect()
+}
+
+// Removes up to EDGE_LINES items from each end of `items` while they are running lines.
+fn strip_running_edges<T>(items: &mut Vec<T>, running: &HashSet<String>, text: impl Fn(&T) -> &str) {
+    let is_running = |item: &T| running.contains(&line_signature(text(item)));
+    let leading = items.iter().take(EDGE_LINES).take_while(|item| is_running(item)).count();
+    items.drain(..leading);
+    let trailing = items.iter().rev().take(EDGE_LINES).take_while(|item| is_running(item)).count();
+    items.truncate(items.len() - trailing);
+}
+
+// Records the edge lines of newly stored pages, then strips running headers and footers
+// from every page of the book. Pages stored before a line crossed the threshold are
+// cleaned up here as well.
+pub fn strip_running_paragraphs(
+    handle: &tauri::AppHandle,
+    book_id: &str,
+    text: &mut BookText,
+    new_pages: &[u32],
+    options: &CleanupOptions,
+) -> Result<(), String> {
+    if !options.strip_running_lines {
+        return Ok(());
+    }
+    let mut running = load_running_lines(handle, book_id)?;
+    for page in text.pages.iter().filter(|p| new_pages.contains(&p.page)) {
+        let lines: Vec<&str> = page.paragraphs.iter().map(|p| p.text.as_str()).collect();
+        running.edges.insert(page.page, edge_signatures(&lines));
+    }
+    save_running_lines(handle, book_id, &running)?;
+
+    let signatures = running_signatures(&running, options.running_line_min_share);
+    if signatures.is_empty() {
+        return Ok(());
+    }
+    for page in &mut text.pages {
+        strip_running_edges(&mut page.paragraphs, &signatures, |p| p.text.as_str());
+    }
+    Ok(())
+}
+
+fn strip_page_number_lines(lines: &mut Vec<&str>) {
+    while lines.first().is_some_and(|line| line.trim().is_empty() || is_page_number_line(line)) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|line| line.trim().is_empty() || is_page_number_line(line)) {
+        lines.pop();
+    }
+}
+
+// The previous line ends in a hyphen after a letter and the next one carries on in lowercase.
+fn is_split_word(previous: &str, next: &str) -> bool {
+    let Some(stem) = previous.strip_suffix(['-', '\u{00AD}']) else {
+        return false;
+    };
+    stem.chars().last().is_some_and(char::is_alphabetic) && next.chars().next().is_some_and(char::is_lowercase)
+}
+
+fn ends_sentence(line: &str) -> bool {
+    line.ends_with(['.', '!', '?', ':', '"', '\u{201D}', '\u{3002}', '\u{FF01}', '\u{FF1F}'])
+}
+
+pub fn clean_text(text: &str, options: &CleanupOptions) -> String {
+    let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    if options.strip_page_numbers {
+        strip_page_number_lines(&mut lines);
+    }
+
+    let mut output = String::new();
+    let mut previous: Option<&str> = None;
+    for line in lines {
+        let content = line.trim_start();
+        let indented = content.len() < line.len();
+        match previous {
+            None => {}
+            Some(_) if content.is_empty() => {
+                previous = Some("");
+                continue;
+            }
+            Some("") => output.push_str("\n\n"),
+            Some(prev) if options.dehyphenate && is_split_word(prev, content) => {
+                output.pop();
+            }
+            Some(prev) if options.reflow && !(indented && ends_sentence(prev)) => output.push(' '),
+            Some(_) => output.push('\n'),
+        }
+        if content.is_empty() && previous.is_none() {
+            continue;
+        }
+        output.push_str(content);
+        previous = Some(content);
+    }
+    output
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_cleanup_options(handle: tauri::AppHandle, book_id: String) -> Result<CleanupOptions, String> {
+    load_cleanup_options(&handle, &book_id)
+}
+
+// The running headers and footers currently detected for a book, as signatures
+// (lowercased, digits shown as '#').
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_running_lines(handle: tauri::AppHandle, book_id: String) -> Result<Vec<String>, String> {
+    let options = load_cleanup_options(&handle, &book_id)?;
+    let running = load_running_lines(&handle, &book_id)?;
+    let mut signatures: Vec<String> = running_signatures(&running, options.running_line_min_share)
+        .into_iter()
+        .collect();
+    signatures.sort();
+    Ok(signatures)
+}
+
+// Saves the options and cleans the stored pages again with them, from the text as extracted,
+// so turning a pass off brings back what it removed.
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_cleanup_options(handle: tauri::AppHandle, book_id: String, options: CleanupOptions) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing text cleanup")?;
+    save_cleanup_options(&handle, &book_id, &options)?;
+    clean_stored_pages(&handle, &book_id, &options)
+}
+
+// Cleans one page of extracted text with the book's options and splits it into sentences.
+// Segment offsets refer to the returned, cleaned text.
+#[tauri::command(rename_all = "camelCase")]
+pub fn prepare_page_text(
+    handle: tauri::AppHandle,
+    book_id: String,
+    text: String,
+    lang: String,
+) -> Result<PreparedPage, String> {
+    let options = load_cleanup_options(&handle, &book_id)?;
+    let text = if options.strip_running_lines {
+        let signatures = running_signatures(&load_running_lines(&handle, &book_id)?, options.running_line_min_share);
+        let mut lines: Vec<&str> = text.lines().collect();
+        strip_running_edges(&mut lines, &signatures, |line| line);
+        clean_text(&lines.join("\n"), &options)
+    } else {
+        clean_text(&text, &options)
+    };
+    let segments = segment(&text, &lang, Some(&book_id));
+    Ok(PreparedPage { text, segments })
+}
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::quarantine::parse_or_quarantine;
+use crate::quota;
+use crate::restricted_mode;
+use crate::{
+    app_config_dir, build_system_prompt, cache_target, ensure_cloud_allowed, extract_doc_id, language_rules,
+    load_openrouter_credentials, math, parse_translation_json, read_cache, request_openrouter_completion,
+    translation_cache_key, update_cache, TargetLanguage, TranslationResult,
+};
+
+// Ratings at or below this (on a 1-5 scale) drop the cached translation so it is redone.
+const PURGE_RATING_THRESHOLD: u8 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TranslationRating {
+    sid: String,
+    rating: u8,
+    rated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct TranslationRatingsData {
+    ratings: Vec<TranslationRating>,
+}
+
+fn ratings_file_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("translation_ratings.json"))
+}
+
+fn load_ratings(handle: &tauri::AppHandle) -> Result<TranslationRatingsData, String> {
+    let path = ratings_file_path(handle)?;
+    if !path.exists() {
+        return Ok(TranslationRatingsData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_ratings(handle: &tauri::AppHandle, data: &TranslationRatingsData) -> Result<(), String> {
+    let path = ratings_file_path(handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+// Removes every cached translation of `sid`, whatever model or language produced it.
+fn purge_cached_sentence(handle: &tauri::AppHandle, sid: &str) -> Result<usize, String> {
+    let prefix = format!("{}|{}|", extract_doc_id(sid), sid);
+    update_cache(handle, |cache| {
+        let before = cache.entries.len();
+        cache.entries.retain(|key, _| !key.starts_with(&prefix));
+        cache.generations.retain(|key, _| !key.starts_with(&prefix));
+        before - cache.entries.len()
+    })
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn rate_translation(handle: tauri::AppHandle, sid: String, rating: u8) -> Result<bool, String> {
+    if !(1..=5).contains(&rating) {
+        return Err("Rating must be between 1 and 5.".to_string());
+    }
+    let mut data = load_ratings(&handle)?;
+    data.ratings.retain(|r| r.sid != sid);
+    data.ratings.push(TranslationRating {
+        sid: sid.clone(),
+        rating,
+        rated_at: Utc::now(),
+    });
+    save_ratings(&handle, &data)?;
+
+    if rating <= PURGE_RATING_THRESHOLD {
+        return Ok(purge_cached_sentence(&handle, &sid)? > 0);
+    }
+    Ok(false)
+}
+
+fn build_retranslate_prompt(
+    target_language: &TargetLanguage,
+    sid: &str,
+    text: &str,
+    previous: Option<&str>,
+    instruction: &str,
+) -> String {
+    let payload = serde_json::json!([{ "sid": sid, "text": text }]);
+    let mut prompt = format!(
+        "Target language: {} ({})\nTranslation style: faithful, clear, readable\n",
+        target_language.label, target_language.prompt_tag()
+    );
+    if let Some(previous) = previous {
+        prompt.push_str(&format!("Previous translation (rejected by the reader): {}\n", previous));
+    }
+    if !instruction.trim().is_empty() {
+        prompt.push_str(&format!("Reader instruction: {}\n", instruction.trim()));
+    }
+    prompt.push_str(&format!("Input JSON: {}", payload));
+    prompt
+}
+
+// Translates one sentence again, honouring a free-form instruction, and replaces the cached result.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn retranslate_sentence(
+    handle: tauri::AppHandle,
+    model: String,
+    temperature: f32,
+    target_language: TargetLanguage,
+    sid: String,
+    text: String,
+    instruction: String,
+) -> Result<TranslationResult, String> {
+    if !instruction.trim().is_empty() {
+        restricted_mode::ensure_unrestricted(&handle, "Retranslating with instructions")?;
+    }
+    ensure_cloud_allowed(&handle, extract_doc_id(&sid))?;
+    let key = translation_cache_key(&sid, &text, &model, &cache_target(&handle, &target_language.tag()));
+    let previous = read_cache(&handle, |cache| cache.entries.get(&key).cloned())?;
+
+    let credentials = load_openrouter_credentials(&handle)?.for_feature(quota::FEATURE_TRANSLATION).pin_temperature();
+    let (protected_text, math_spans) = math::protect_math(&text);
+    let system_prompt = build_system_prompt();
+    let mut user_prompt =
+        build_retranslate_prompt(&target_language, &sid, &protected_text, previous.as_deref(), &instruction);
+    user_prompt.push_str(&language_rules::prompt_section(&handle, &target_language.tag()));
+    let completion =
+        request_openrouter_completion(&credentials, &model, temperature, &system_prompt, &user_prompt).await?;
+
+    let mut translation = parse_translation_json(&completion.content)
+        .map_err(|e| format!("Failed to parse OpenRouter JSON: {}", e))?
+        .into_iter()
+        .find(|item| item.sid == sid)
+        .ok_or_else(|| "OpenRouter returned no translation for this sentence.".to_string())?;
+
+    translation.translation = math::restore_math(&translation.translation, &math_spans);
+    translation.generation = Some(completion.generation);
+
+    update_cache(&handle, |cache| {
+        if let Some(generation) = &translation.generation {
+            cache.generations.insert(key.clone(), generation.clone());
+        }
+        cache.entries.insert(key, translation.translation.clone());
+    })?;
+    Ok(translation)
+}
@@ -0,0 +1,302 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::quarantine::parse_or_quarantine;
+use crate::quota;
+use crate::{
+    app_config_dir, encryption, ensure_cloud_allowed, extract_json_object, hash_source_text,
+    load_openrouter_credentials, request_openrouter, truncate_for_error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransliterationScheme {
+    Pinyin,
+    Romaji,
+    Romanization,
+}
+
+impl TransliterationScheme {
+    fn as_str(self) -> &'static str {
+        match self {
+            TransliterationScheme::Pinyin => "pinyin",
+            TransliterationScheme::Romaji => "romaji",
+            TransliterationScheme::Romanization => "romanization",
+        }
+    }
+
+    fn prompt_description(self) -> &'static str {
+        match self {
+            TransliterationScheme::Pinyin => "Hanyu Pinyin with tone marks",
+            TransliterationScheme::Romaji => "Hepburn romaji (for kanji, the reading in context)",
+            TransliterationScheme::Romanization => "the standard Latin-script romanization for this language",
+        }
+    }
+}
+
+// One token of the source text with its reading, e.g. {"text": "漢字", "reading": "kanji"}.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingToken {
+    pub text: String,
+    pub reading: String,
+}
+
+// Past this, the oldest readings make room for new ones.
+const MAX_ENTRIES: usize = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TransliterationCacheData {
+    entries: HashMap<String, Vec<ReadingToken>>,
+    // When each entry was cached; entries from before this was kept have none and go first.
+    #[serde(default)]
+    added_at: HashMap<String, DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransliterationResponse {
+    tokens: Vec<ReadingToken>,
+}
+
+pub fn transliteration_cache_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join("transliteration_cache.json"))
+}
+
+pub fn read_transliteration_cache_file(handle: &tauri::AppHandle) -> Result<TransliterationCacheData, String> {
+    let path = transliteration_cache_path(handle)?;
+    if !path.exists() {
+        return Ok(TransliterationCacheData::default());
+    }
+    let data = encryption::read_store_file(handle, &path)?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+// Encrypted like the translation cache, since entries carry book text.
+pub fn write_transliteration_cache_file(
+    handle: &tauri::AppHandle,
+    cache: &TransliterationCacheData,
+) -> Result<(), String> {
+    let path = transliteration_cache_path(handle)?;
+    let data = serde_json::to_string_pretty(cache).map_err(|e| e.to_string())?;
+    encryption::write_store_file(handle, &path, &data)
+}
+
+const CYRILLIC_TO_LATIN: &[(char, &str)] = &[
+    ('а', "a"), ('б', "b"), ('в', "v"), ('г', "g"), ('д', "d"), ('е', "e"), ('ё', "yo"),
+    ('ж', "zh"), ('з', "z"), ('и', "i"), ('й', "y"), ('к', "k"), ('л', "l"), ('м', "m"),
+    ('н', "n"), ('о', "o"), ('п', "p"), ('р', "r"), ('с', "s"), ('т', "t"), ('у', "u"),
+    ('ф', "f"), ('х', "kh"), ('ц', "ts"), ('ч', "ch"), ('ш', "sh"), ('щ', "shch"), ('ъ', ""),
+    ('ы', "y"), ('ь', ""), ('э', "e"), ('ю', "yu"), ('я', "ya"), ('і', "i"), ('ї', "yi"),
+    ('є', "ye"), ('ґ', "g"), ('ў', "w"),
+];
+
+const KANA_TO_ROMAJI: &[(&str, &str)] = &[
+    ("きゃ", "kya"), ("きゅ", "kyu"), ("きょ", "kyo"), ("しゃ", "sha"), ("しゅ", "shu"), ("しょ", "sho"),
+    ("ちゃ", "cha"), ("ちゅ", "chu"), ("ちょ", "cho"), ("にゃ", "nya"), ("にゅ", "nyu"), ("にょ", "nyo"),
+    ("ひゃ", "hya"), ("ひゅ", "hyu"), ("ひょ", "hyo"), ("みゃ", "mya"), ("みゅ", "myu"), ("みょ", "myo"),
+    ("りゃ", "rya"), ("りゅ", "ryu"), ("りょ", "ryo"), ("ぎゃ", "gya"), ("ぎゅ", "gyu"), ("ぎょ", "gyo"),
+    ("じゃ", "ja"), ("じゅ", "ju"), ("じょ", "jo"), ("びゃ", "bya"), ("びゅ", "byu"), ("びょ", "byo"),
+    ("ぴゃ", "pya"), ("ぴゅ", "pyu"), ("ぴょ", "pyo"),
+    ("あ", "a"), ("い", "i"), ("う", "u"), ("え", "e"), ("お", "o"),
+    ("か", "ka"), ("き", "ki"), ("く", "ku"), ("け", "ke"), ("こ", "ko"),
+    ("さ", "sa"), ("し", "shi"), ("す", "su"), ("せ", "se"), ("そ", "so"),
+    ("た", "ta"), ("ち", "chi"), ("つ", "tsu"), ("て", "te"), ("と", "to"),
+    ("な", "na"), ("に", "ni"), ("ぬ", "nu"), ("ね", "ne"), ("の", "no"),
+    ("は", "ha"), ("ひ", "hi"), ("ふ", "fu"), ("へ", "he"), ("ほ", "ho"),
+    ("ま", "ma"), ("み", "mi"), ("む", "mu"), ("め", "me"), ("も", "mo"),
+    ("や", "ya"), ("ゆ", "yu"), ("よ", "yo"),
+    ("ら", "ra"), ("り", "ri"), ("る", "ru"), ("れ", "re"), ("ろ", "ro"),
+    ("わ", "wa"), ("を", "wo"), ("ん", "n"),
+    ("が", "ga"), ("ぎ", "gi"), ("ぐ", "gu"), ("げ", "ge"), ("ご", "go"),
+    ("ざ", "za"), ("じ", "ji"), ("ず", "zu"), ("ぜ", "ze"), ("ぞ", "zo"),
+    ("だ", "da"), ("ぢ", "ji"), ("づ", "zu"), ("で", "de"), ("ど", "do"),
+    ("ば", "ba"), ("び", "bi"), ("ぶ", "bu"), ("べ", "be"), ("ぼ", "bo"),
+    ("ぱ", "pa"), ("ぴ", "pi"), ("ぷ", "pu"), ("ぺ", "pe"), ("ぽ", "po"),
+    ("ぁ", "a"), ("ぃ", "i"), ("ぅ", "u"), ("ぇ", "e"), ("ぉ", "o"),
+    ("ゃ", "ya"), ("ゅ", "yu"), ("ょ", "yo"),
+];
+
+fn is_cyrillic(c: char) -> bool {
+    ('\u{0400}'..='\u{04FF}').contains(&c)
+}
+
+fn is_kana(c: char) -> bool {
+    ('\u{3041}'..='\u{30FF}').contains(&c)
+}
+
+fn romanize_cyrillic_word(word: &str) -> String {
+    let mut output = String::new();
+    for c in word.chars() {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        match CYRILLIC_TO_LATIN.iter().find(|(cyr, _)| *cyr == lower) {
+            Some((_, latin)) if c.is_uppercase() => {
+                let mut chars = latin.chars();
+                if let Some(first) = chars.next() {
+                    output.extend(first.to_uppercase());
+                    output.push_str(chars.as_str());
+                }
+            }
+            Some((_, latin)) => output.push_str(latin),
+            None => output.push(c),
+        }
+    }
+    output
+}
+
+fn kana_to_romaji(text: &str) -> String {
+    // Katakana sits exactly 0x60 above hiragana, so fold it first.
+    let hiragana: String = text
+        .chars()
+        .map(|c| match c {
+            '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect();
+
+    let mut output = String::new();
+    let mut rest = hiragana.as_str();
+    let mut double_next = false;
+    while let Some(c) = rest.chars().next() {
+        if c == 'っ' {
+            double_next = true;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if c == 'ー' {
+            if let Some(last) = output.chars().last() {
+                output.push(last);
+            }
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let matched = KANA_TO_ROMAJI
+            .iter()
+            .filter(|(kana, _)| rest.starts_with(kana))
+            .max_by_key(|(kana, _)| kana.len());
+        match matched {
+            Some((kana, romaji)) => {
+                if double_next {
+                    output.push_str(&romaji[..1]);
+                }
+                output.push_str(romaji);
+                rest = &rest[kana.len()..];
+            }
+            None => {
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        double_next = false;
+    }
+    output
+}
+
+// Handles the cases we can do without a model: Cyrillic romanization and kana-only romaji.
+pub fn transliterate_locally(text: &str, scheme: TransliterationScheme) -> Option<Vec<ReadingToken>> {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let words = text.split_whitespace();
+    match scheme {
+        TransliterationScheme::Romanization if letters.iter().all(|c| is_cyrillic(*c) || c.is_ascii()) => Some(
+            words
+                .map(|word| ReadingToken {
+                    text: word.to_string(),
+                    reading: romanize_cyrillic_word(word),
+                })
+                .collect(),
+        ),
+        TransliterationScheme::Romaji if letters.iter().all(|c| is_kana(*c)) => Some(
+            words
+                .map(|word| ReadingToken {
+                    text: word.to_string(),
+                    reading: kana_to_romaji(word),
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn build_transliteration_system_prompt() -> String {
+    [
+        "You are a transliteration engine.",
+        "Split the text into words and give the reading of each one.",
+        "Output STRICT JSON ONLY.",
+        "No markdown, no explanations, no extra text.",
+    ]
+    .join(" ")
+}
+
+fn build_transliteration_prompt(text: &str, scheme: TransliterationScheme) -> String {
+    format!(
+        r#"Transliterate this text using {}: "{}"
+Return JSON in this exact format:
+{{"tokens": [{{"text": "original word", "reading": "reading"}}]}}
+- tokens must cover the whole text in order; punctuation tokens use the punctuation itself as reading"#,
+        scheme.prompt_description(),
+        text
+    )
+}
+
+// Returns the cached readings for `text`, or produces them locally / via the model.
+pub async fn transliterate_text(
+    handle: &tauri::AppHandle,
+    model: &str,
+    text: &str,
+    scheme: TransliterationScheme,
+) -> Result<Vec<ReadingToken>, String> {
+    if let Some(tokens) = transliterate_locally(text, scheme) {
+        return Ok(tokens);
+    }
+
+    let key = format!("{}|{}|{}", scheme.as_str(), model, hash_source_text(text));
+    let store = &handle.state::<AppState>().transliteration_cache;
+    if let Some(tokens) = store.read(handle, |cache| cache.entries.get(&key).cloned())? {
+        return Ok(tokens);
+    }
+
+    let credentials =
+        load_openrouter_credentials(handle)?.for_feature(quota::FEATURE_TRANSLITERATION).pin_temperature();
+    let system_prompt = build_transliteration_system_prompt();
+    let user_prompt = build_transliteration_prompt(text, scheme);
+    let content = request_openrouter(&credentials, model, 0.0, &system_prompt, &user_prompt).await?;
+    let json_content = extract_json_object(&content);
+    let parsed: TransliterationResponse = serde_json::from_str(&json_content).map_err(|e| {
+        format!("Failed to parse transliteration JSON: {} (content: {})", e, truncate_for_error(&json_content))
+    })?;
+
+    store.update(handle, |cache| {
+        cache.added_at.insert(key.clone(), Utc::now());
+        cache.entries.insert(key, parsed.tokens.clone());
+        let excess = cache.entries.len().saturating_sub(MAX_ENTRIES);
+        if excess > 0 {
+            let mut by_age: Vec<(Option<DateTime<Utc>>, String)> =
+                cache.entries.keys().map(|key| (cache.added_at.get(key).copied(), key.clone())).collect();
+            by_age.sort();
+            for (_, key) in by_age.into_iter().take(excess) {
+                cache.entries.remove(&key);
+                cache.added_at.remove(&key);
+            }
+        }
+    })?;
+    Ok(parsed.tokens)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn transliterate(
+    handle: tauri::AppHandle,
+    model: String,
+    text: String,
+    scheme: TransliterationScheme,
+    book_id: String,
+) -> Result<Vec<ReadingToken>, String> {
+    if transliterate_locally(&text, scheme).is_none() {
+        ensure_cloud_allowed(&handle, &book_id)?;
+    }
+    transliterate_text(&handle, &model, &text, scheme).await
+}
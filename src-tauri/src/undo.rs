@@ -0,0 +1,165 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::app_state::AppState;
+use crate::language_rules;
+use crate::vocab_index::VocabularyIndex;
+use crate::{update_recent_books, RecentBook, VocabularyEntry};
+
+// Operations kept for undo; older ones drop off.
+const MAX_OPERATIONS: usize = 50;
+
+// A destructive operation and what it takes to reverse it. Each one keeps the state from
+// before and after so it can be undone and redone without the command that made it.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    // The removed entries with their positions in the vocabulary, in ascending order.
+    RemoveVocabularyWord { word: String, removed: Vec<(usize, VocabularyEntry)> },
+    RemoveRecentBook { position: usize, book: Box<RecentBook> },
+    // Rules stored under any spelling of `language` before and after the edit.
+    SetLanguageRules { language: String, before: Vec<(String, Vec<String>)>, after: Vec<(String, Vec<String>)> },
+}
+
+#[derive(Debug, Serialize)]
+pub struct JournalEntry {
+    kind: &'static str,
+    label: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoState {
+    // What undo and redo would reverse or repeat next, if anything.
+    undo: Option<JournalEntry>,
+    redo: Option<JournalEntry>,
+}
+
+impl Operation {
+    fn entry(&self) -> JournalEntry {
+        match self {
+            Operation::RemoveVocabularyWord { word, .. } => JournalEntry {
+                kind: "remove_vocabulary_word",
+                label: format!("Remove \"{}\" from vocabulary", word),
+            },
+            Operation::RemoveRecentBook { book, .. } => {
+                JournalEntry { kind: "remove_recent_book", label: format!("Remove {}", book.title) }
+            }
+            Operation::SetLanguageRules { language, .. } => {
+                JournalEntry { kind: "set_language_rules", label: format!("Edit rules for {}", language) }
+            }
+        }
+    }
+
+    fn undo(&self, handle: &tauri::AppHandle) -> Result<(), String> {
+        match self {
+            Operation::RemoveVocabularyWord { removed, .. } => {
+                handle.state::<AppState>().vocabulary.update(handle, |vocab| {
+                    for (position, entry) in removed {
+                        let position = (*position).min(vocab.entries.len());
+                        vocab.entries.insert(position, entry.clone());
+                    }
+                })?;
+                handle.state::<VocabularyIndex>().invalidate();
+                Ok(())
+            }
+            Operation::RemoveRecentBook { position, book } => update_recent_books(handle, |data| {
+                if !data.books.iter().any(|b| b.id == book.id) {
+                    let position = (*position).min(data.books.len());
+                    data.books.insert(position, (**book).clone());
+                }
+            }),
+            Operation::SetLanguageRules { language, before, .. } => {
+                language_rules::replace_rules(handle, language, before.clone())
+            }
+        }
+    }
+
+    fn redo(&self, handle: &tauri::AppHandle) -> Result<(), String> {
+        match self {
+            Operation::RemoveVocabularyWord { word, .. } => {
+                let word = word.to_lowercase();
+                handle
+                    .state::<AppState>()
+                    .vocabulary
+                    .update(handle, |vocab| vocab.entries.retain(|e| e.word.to_lowercase() != word))?;
+                handle.state::<VocabularyIndex>().invalidate();
+                Ok(())
+            }
+            Operation::RemoveRecentBook { book, .. } => {
+                update_recent_books(handle, |data| data.books.retain(|b| b.id != book.id))
+            }
+            Operation::SetLanguageRules { language, after, .. } => {
+                language_rules::replace_rules(handle, language, after.clone())
+            }
+        }
+    }
+}
+
+// Undo and redo stacks for this session. Nothing is persisted; switching profiles clears it.
+#[derive(Default)]
+pub struct UndoJournal {
+    stacks: Mutex<(Vec<Operation>, Vec<Operation>)>,
+}
+
+impl UndoJournal {
+    pub fn clear(&self) {
+        *self.stacks.lock().unwrap_or_else(|e| e.into_inner()) = (Vec::new(), Vec::new());
+    }
+}
+
+// Journals an operation that just happened; anything that could be redone is dropped.
+pub fn record(handle: &tauri::AppHandle, operation: Operation) {
+    let journal = handle.state::<UndoJournal>();
+    let mut stacks = journal.stacks.lock().unwrap_or_else(|e| e.into_inner());
+    let (undo, redo) = &mut *stacks;
+    redo.clear();
+    undo.push(operation);
+    if undo.len() > MAX_OPERATIONS {
+        undo.remove(0);
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_undo_state(journal: tauri::State<'_, UndoJournal>) -> Result<UndoState, String> {
+    let stacks = journal.stacks.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(UndoState {
+        undo: stacks.0.last().map(Operation::entry),
+        redo: stacks.1.last().map(Operation::entry),
+    })
+}
+
+// Reverses the most recent journaled operation; None when there is nothing to undo.
+#[tauri::command(rename_all = "camelCase")]
+pub fn undo_last_operation(
+    handle: tauri::AppHandle,
+    journal: tauri::State<'_, UndoJournal>,
+) -> Result<Option<JournalEntry>, String> {
+    let Some(operation) = journal.stacks.lock().unwrap_or_else(|e| e.into_inner()).0.pop() else {
+        return Ok(None);
+    };
+    if let Err(e) = operation.undo(&handle) {
+        journal.stacks.lock().unwrap_or_else(|e| e.into_inner()).0.push(operation);
+        return Err(e);
+    }
+    let entry = operation.entry();
+    journal.stacks.lock().unwrap_or_else(|e| e.into_inner()).1.push(operation);
+    Ok(Some(entry))
+}
+
+// Repeats the most recently undone operation; None when there is nothing to redo.
+#[tauri::command(rename_all = "camelCase")]
+pub fn redo_last_operation(
+    handle: tauri::AppHandle,
+    journal: tauri::State<'_, UndoJournal>,
+) -> Result<Option<JournalEntry>, String> {
+    let Some(operation) = journal.stacks.lock().unwrap_or_else(|e| e.into_inner()).1.pop() else {
+        return Ok(None);
+    };
+    if let Err(e) = operation.redo(&handle) {
+        journal.stacks.lock().unwrap_or_else(|e| e.into_inner()).1.push(operation);
+        return Err(e);
+    }
+    let entry = operation.entry();
+    journal.stacks.lock().unwrap_or_else(|e| e.into_inner()).0.push(operation);
+    Ok(Some(entry))
+}
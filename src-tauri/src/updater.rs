@@ -0,0 +1,99 @@
+use serde::Serialize;
+use tauri::{Emitter, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::progress;
+use crate::restricted_mode;
+use crate::settings::{load_settings, save_settings, UpdateChannel};
+
+// The minisign public key releases are signed with, given at build time. The key in
+// `tauri.updater.conf.json` is left empty and replaced by this one.
+const PUBKEY: &str = env!("PDFREAD_UPDATER_PUBKEY");
+const _: () = assert!(!PUBKEY.is_empty(), "PDFREAD_UPDATER_PUBKEY is empty");
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/everettjf/PDFRead/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/everettjf/PDFRead/releases/download/beta/latest.json";
+
+#[derive(Debug, Serialize)]
+pub struct UpdateInfo {
+    version: String,
+    current_version: String,
+    notes: Option<String>,
+    channel: UpdateChannel,
+}
+
+pub fn plugin<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R, tauri_plugin_updater::Config> {
+    tauri_plugin_updater::Builder::new().pubkey(PUBKEY).build()
+}
+
+fn channel_endpoint(channel: UpdateChannel) -> &'static str {
+    match channel {
+        UpdateChannel::Stable => STABLE_ENDPOINT,
+        UpdateChannel::Beta => BETA_ENDPOINT,
+    }
+}
+
+async fn find_update(handle: &tauri::AppHandle) -> Result<(Option<Update>, UpdateChannel), String> {
+    let channel = load_settings(handle)?.update_channel;
+    let endpoint = Url::parse(channel_endpoint(channel)).map_err(|e| e.to_string())?;
+    let updater = handle
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|e| e.to_string())?
+        .build()
+        .map_err(|e| e.to_string())?;
+    let update = updater.check().await.map_err(|e| e.to_string())?;
+    Ok((update, channel))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_update_channel(handle: tauri::AppHandle) -> Result<UpdateChannel, String> {
+    Ok(load_settings(&handle)?.update_channel)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn set_update_channel(handle: tauri::AppHandle, channel: UpdateChannel) -> Result<(), String> {
+    restricted_mode::ensure_unrestricted(&handle, "Changing settings")?;
+    let mut settings = load_settings(&handle)?;
+    settings.update_channel = channel;
+    save_settings(&handle, &settings)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn check_for_updates(handle: tauri::AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let (update, channel) = find_update(&handle).await?;
+    Ok(update.map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        channel,
+    }))
+}
+
+// Downloads and installs the newest release for the selected channel, then restarts.
+// Reports download progress and emits `update-installed` when done.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn install_update(handle: tauri::AppHandle) -> Result<(), String> {
+    let (update, _) = find_update(&handle).await?;
+    let update = update.ok_or_else(|| "No update available.".to_string())?;
+
+    let mut downloaded: u64 = 0;
+    let progress_handle = handle.clone();
+    let finished_handle = handle.clone();
+    update
+        .download_and_install(
+            move |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                progress::emit(&progress_handle, "update", "update_download", downloaded, content_length, None);
+            },
+            move || {
+                let _ = finished_handle.emit("update-installed", ());
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    handle.restart()
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::library_import::{import_book_bytes, ImportedBook};
+use crate::progress;
+
+// Larger downloads are almost certainly not a single book.
+const MAX_DOWNLOAD_BYTES: u64 = 512 * 1024 * 1024;
+// Progress is reported at most this often, in bytes.
+const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+// A server that does not answer, or stops sending, for this long fails the download. Slow but
+// steady downloads of large books are not cut off.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+// `filename` from a Content-Disposition header, or the last segment of the URL's path.
+fn download_file_name(url: &reqwest::Url, disposition: Option<&str>) -> Option<String> {
+    let from_header = disposition.and_then(|value| {
+        let name = value.split(';').map(str::trim).find_map(|part| part.strip_prefix("filename="))?;
+        Some(name.trim_matches('"').to_string())
+    });
+    let name = from_header.or_else(|| url.path_segments()?.next_back().map(str::to_string))?;
+    // Only the final component, whatever the server sent
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default().trim().to_string();
+    (!name.is_empty() && name != "." && name != "..").then_some(name)
+}
+
+fn accepted_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    mime.is_empty()
+        || matches!(
+            mime.as_str(),
+            "application/pdf" | "application/x-pdf" | "application/epub+zip" | "application/octet-stream"
+                | "binary/octet-stream" | "application/zip" | "application/download" | "application/force-download"
+        )
+}
+
+// Downloads the book at `url` into the library folder, reporting progress along the way,
+// and registers it. Web pages and other non-book content are rejected by content type and
+// again by the file's first bytes.
+pub async fn download_book(
+    handle: &tauri::AppHandle,
+    url: &str,
+    file_name: Option<String>,
+) -> Result<ImportedBook, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Only http and https links can be imported: {}", url));
+    }
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut response = client.get(parsed.clone()).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Download failed: {}", response.status()));
+    }
+    let header = |name: reqwest::header::HeaderName| {
+        response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+    };
+    let content_type = header(reqwest::header::CONTENT_TYPE).unwrap_or_default();
+    if !accepted_content_type(&content_type) {
+        return Err(format!("The link points to {} rather than a PDF or EPUB file.", content_type));
+    }
+    let disposition = header(reqwest::header::CONTENT_DISPOSITION);
+    let total = response.content_length();
+    if total.is_some_and(|total| total > MAX_DOWNLOAD_BYTES) {
+        return Err("The file is too large to import.".to_string());
+    }
+
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+    let mut reported = 0;
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        bytes.extend_from_slice(&chunk);
+        let downloaded = bytes.len() as u64;
+        if downloaded > MAX_DOWNLOAD_BYTES {
+            return Err("The file is too large to import.".to_string());
+        }
+        if downloaded - reported >= PROGRESS_STEP_BYTES || Some(downloaded) == total {
+            reported = downloaded;
+            let job_id = format!("download:{}", url);
+            progress::emit(handle, &job_id, "book_download", downloaded, total, Some(url.to_string()));
+        }
+    }
+
+    let file_name = file_name.or_else(|| download_file_name(&parsed, disposition.as_deref()));
+    let book = import_book_bytes(handle, &bytes, file_name, None)?;
+    let _ = handle.emit("book-imported", book.clone());
+    Ok(book)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_book_from_url(handle: tauri::AppHandle, url: String) -> Result<ImportedBook, String> {
+    download_book(&handle, &url, None).await
+}
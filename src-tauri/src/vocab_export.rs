@@ -0,0 +1,134 @@
+use serde::Deserialize;
+
+use crate::language::primary_language;
+use crate::{load_vocabulary, vocabulary_markdown, VocabularyEntry};
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VocabularyExportFormat {
+    Markdown,
+    // Quizlet's "Import from Word, Excel, Google Docs, etc." box: one card per line.
+    Quizlet,
+    // Memrise course CSV with term, definition and pronunciation columns.
+    Memrise,
+}
+
+// What goes between the term and the definition of a Quizlet card.
+#[derive(Debug, Clone, Copy, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QuizletSeparator {
+    #[default]
+    Tab,
+    Comma,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct VocabularyExportOptions {
+    // Only words in this language; "zh" also matches entries saved as "zh-CN".
+    language: Option<String>,
+    include_part_of_speech: bool,
+    quizlet_separator: QuizletSeparator,
+    // Memrise only: start with a term,definition,pronunciation header row.
+    include_header: bool,
+}
+
+impl Default for VocabularyExportOptions {
+    fn default() -> Self {
+        Self {
+            language: None,
+            include_part_of_speech: true,
+            quizlet_separator: QuizletSeparator::default(),
+            include_header: true,
+        }
+    }
+}
+
+// All definitions on one line, e.g. "n. a place to live; v. to shelter".
+pub fn definition_line(entry: &VocabularyEntry, include_part_of_speech: bool) -> String {
+    let definitions: Vec<String> = entry
+        .definitions
+        .iter()
+        .map(|d| match d.pos.trim() {
+            pos if include_part_of_speech && !pos.is_empty() => format!("{} {}", pos, d.meanings.trim()),
+            _ => d.meanings.trim().to_string(),
+        })
+        .collect();
+    collapse_lines(&definitions.join("; "))
+}
+
+// Line breaks would start a new card or row in both formats.
+fn collapse_lines(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn quizlet(entries: &[VocabularyEntry], options: &VocabularyExportOptions) -> String {
+    let separator = match options.quizlet_separator {
+        QuizletSeparator::Tab => "\t",
+        QuizletSeparator::Comma => ",",
+    };
+    entries
+        .iter()
+        .map(|entry| {
+            // Quizlet has no field for the pronunciation; it goes with the term.
+            let term = match entry.phonetic.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+                Some(phonetic) => format!("{} {}", entry.word, phonetic),
+                None => entry.word.clone(),
+            };
+            // Quizlet splits each line at the first separator, so only the term must not hold one.
+            let term = collapse_lines(&term).replace(separator, " ");
+            format!("{}{}{}\n", term, separator, definition_line(entry, options.include_part_of_speech))
+        })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn memrise(entries: &[VocabularyEntry], options: &VocabularyExportOptions) -> String {
+    let mut csv = String::new();
+    if options.include_header {
+        csv.push_str("term,definition,pronunciation\n");
+    }
+    for entry in entries {
+        let row = [
+            collapse_lines(&entry.word),
+            definition_line(entry, options.include_part_of_speech),
+            entry.phonetic.as_deref().map(collapse_lines).unwrap_or_default(),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// Vocabulary entries, only those in `language` when given.
+pub fn filtered_entries(handle: &tauri::AppHandle, language: Option<&str>) -> Result<Vec<VocabularyEntry>, String> {
+    let mut entries = load_vocabulary(handle)?.entries;
+    if let Some(lang) = language.map(primary_language) {
+        entries.retain(|e| e.source_lang.as_deref().is_some_and(|code| primary_language(code) == lang));
+    }
+    Ok(entries)
+}
+
+// The vocabulary in `format`, returned as text for the frontend to save.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_vocabulary(
+    handle: tauri::AppHandle,
+    format: VocabularyExportFormat,
+    options: Option<VocabularyExportOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let entries = filtered_entries(&handle, options.language.as_deref())?;
+    Ok(match format {
+        VocabularyExportFormat::Markdown => vocabulary_markdown(&entries),
+        VocabularyExportFormat::Quizlet => quizlet(&entries, &options),
+        VocabularyExportFormat::Memrise => memrise(&entries, &options),
+    })
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::load_vocabulary;
+
+// A handful of common irregular forms; regular inflections are folded by suffix rules.
+const IRREGULAR_FORMS: &[(&str, &str)] = &[
+    ("went", "go"),
+    ("gone", "go"),
+    ("was", "be"),
+    ("were", "be"),
+    ("been", "be"),
+    ("had", "have"),
+    ("did", "do"),
+    ("done", "do"),
+    ("made", "make"),
+    ("said", "say"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("took", "take"),
+    ("taken", "take"),
+    ("came", "come"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("thought", "think"),
+    ("brought", "bring"),
+    ("bought", "buy"),
+    ("caught", "catch"),
+    ("taught", "teach"),
+    ("found", "find"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("got", "get"),
+    ("gotten", "get"),
+    ("left", "leave"),
+    ("felt", "feel"),
+    ("kept", "keep"),
+    ("told", "tell"),
+    ("wrote", "write"),
+    ("written", "write"),
+    ("ran", "run"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("children", "child"),
+    ("men", "man"),
+    ("women", "woman"),
+    ("people", "person"),
+    ("feet", "foot"),
+    ("teeth", "tooth"),
+    ("mice", "mouse"),
+    ("geese", "goose"),
+    ("lives", "life"),
+    ("wives", "wife"),
+    ("knives", "knife"),
+    ("leaves", "leaf"),
+    ("better", "good"),
+    ("best", "good"),
+    ("worse", "bad"),
+    ("worst", "bad"),
+];
+
+pub fn normalize_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'' && c != '-')
+        .trim_matches(|c: char| c == '\'' || c == '-')
+        .to_lowercase()
+}
+
+fn undouble(stem: &str) -> Option<String> {
+    let bytes = stem.as_bytes();
+    let len = bytes.len();
+    if len >= 3 && bytes[len - 1] == bytes[len - 2] && !b"aeiousl".contains(&bytes[len - 1]) {
+        Some(stem[..len - 1].to_string())
+    } else {
+        None
+    }
+}
+
+fn irregular_lemma(word: &str) -> Option<&'static str> {
+    IRREGULAR_FORMS.iter().find(|(form, _)| *form == word).map(|(_, lemma)| *lemma)
+}
+
+// Possible dictionary forms of a (lowercased) word, including the word itself.
+// Over-generation is fine: candidates are only used to probe the vocabulary index.
+pub fn lemma_candidates(word: &str) -> Vec<String> {
+    let word = normalize_word(word);
+    let mut candidates = vec![word.clone()];
+    if word.chars().count() < 3 || !word.is_ascii() {
+        return candidates;
+    }
+
+    if let Some(lemma) = irregular_lemma(&word) {
+        candidates.push(lemma.to_string());
+    }
+
+    let mut push_stem = |stem: &str, extra_e: bool| {
+        if stem.len() < 2 {
+            return;
+        }
+        candidates.push(stem.to_string());
+        if let Some(single) = undouble(stem) {
+            candidates.push(single);
+        }
+        if extra_e {
+            candidates.push(format!("{}e", stem));
+        }
+    };
+
+    if let Some(stem) = word.strip_suffix("'s") {
+        push_stem(stem, false);
+    }
+    if let Some(stem) = word.strip_suffix("ies") {
+        push_stem(&format!("{}y", stem), false);
+    } else if let Some(stem) = word.strip_suffix("es") {
+        push_stem(stem, false);
+        push_stem(&format!("{}e", stem), false);
+    } else if let Some(stem) = word.strip_suffix('s') {
+        if !stem.ends_with('s') {
+            push_stem(stem, false);
+        }
+    }
+    if let Some(stem) = word.strip_suffix("ied") {
+        push_stem(&format!("{}y", stem), false);
+    } else if let Some(stem) = word.strip_suffix("ed") {
+        push_stem(stem, true);
+    }
+    if let Some(stem) = word.strip_suffix("ing") {
+        push_stem(stem, true);
+    }
+    if let Some(stem) = word.strip_suffix("ier") {
+        push_stem(&format!("{}y", stem), false);
+    } else if let Some(stem) = word.strip_suffix("er") {
+        push_stem(stem, true);
+    }
+    if let Some(stem) = word.strip_suffix("iest") {
+        push_stem(&format!("{}y", stem), false);
+    } else if let Some(stem) = word.strip_suffix("est") {
+        push_stem(stem, true);
+    }
+    if let Some(stem) = word.strip_suffix("ly") {
+        push_stem(stem, false);
+    }
+
+    candidates.dedup();
+    candidates
+}
+
+// In-memory index over the vocabulary file, keyed by every lemma form of each saved word, so a
+// saved "running" is found from "runs" or "ran" through their shared "run". A saved word as
+// written wins over another's lemma guess. Built lazily on first use and dropped whenever the
+// vocabulary changes.
+#[derive(Default)]
+pub struct VocabularyIndex {
+    entries: RwLock<Option<HashMap<String, String>>>,
+}
+
+impl VocabularyIndex {
+    pub fn invalidate(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            *entries = None;
+        }
+    }
+
+    fn ensure_loaded(&self, handle: &tauri::AppHandle) -> Result<(), String> {
+        if self.entries.read().map_err(|e| e.to_string())?.is_some() {
+            return Ok(());
+        }
+        let vocab = load_vocabulary(handle)?;
+        let mut index = HashMap::new();
+        for entry in &vocab.entries {
+            index.entry(normalize_word(&entry.word)).or_insert_with(|| entry.word.clone());
+        }
+        for entry in &vocab.entries {
+            for form in lemma_candidates(&entry.word) {
+                index.entry(form).or_insert_with(|| entry.word.clone());
+            }
+        }
+        *self.entries.write().map_err(|e| e.to_string())? = Some(index);
+        Ok(())
+    }
+
+    // Returns the saved vocabulary word sharing a lemma form with `word`; the word itself is
+    // tried first, so an exact match wins.
+    pub fn find(&self, handle: &tauri::AppHandle, word: &str) -> Result<Option<String>, String> {
+        self.ensure_loaded(handle)?;
+        let entries = self.entries.read().map_err(|e| e.to_string())?;
+        let Some(index) = entries.as_ref() else {
+            return Ok(None);
+        };
+        Ok(lemma_candidates(word)
+            .iter()
+            .find_map(|form| index.get(form).cloned()))
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn check_words_in_vocabulary(
+    handle: tauri::AppHandle,
+    index: tauri::State<'_, VocabularyIndex>,
+    words: Vec<String>,
+) -> Result<Vec<bool>, String> {
+    words
+        .iter()
+        .map(|word| Ok(index.find(&handle, word)?.is_some()))
+        .collect()
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use tauri::Manager;
+
+use crate::vocab_index::{normalize_word, VocabularyIndex};
+use crate::{app_state, VocabularyData, VocabularyEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VocabularyMergeStrategy {
+    // Matching entries get the definitions they are missing from the other file.
+    #[default]
+    Union,
+    // Matching entries are left alone; only new words are added.
+    KeepLocal,
+    // Matching entries take the other file's phonetic and definitions.
+    PreferOther,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct VocabularyMergeReport {
+    pub added: Vec<String>,
+    merged: Vec<String>,
+    skipped: Vec<String>,
+}
+
+// Folds `other` into `entries`. Words are matched as written, ignoring case and surrounding
+// punctuation; inflected forms stay separate entries, since guessing at lemmas would fold
+// different words together. A matched entry keeps the earlier `added_at` of the two.
+pub fn merge_vocabulary_entries(
+    entries: &mut Vec<VocabularyEntry>,
+    other: Vec<VocabularyEntry>,
+    strategy: VocabularyMergeStrategy,
+) -> VocabularyMergeReport {
+    let mut words: HashMap<String, usize> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        words.entry(normalize_word(&entry.word)).or_insert(index);
+    }
+
+    let mut report = VocabularyMergeReport::default();
+    for incoming in other {
+        let word = normalize_word(&incoming.word);
+        let Some(index) = words.get(&word).copied() else {
+            words.insert(word, entries.len());
+            report.added.push(incoming.word.clone());
+            entries.push(incoming);
+            continue;
+        };
+
+        let existing = &mut entries[index];
+        let mut changed = incoming.added_at < existing.added_at;
+        existing.added_at = existing.added_at.min(incoming.added_at);
+        match strategy {
+            VocabularyMergeStrategy::KeepLocal => {}
+            VocabularyMergeStrategy::PreferOther => {
+                changed = true;
+                existing.phonetic = incoming.phonetic.or(existing.phonetic.take());
+                existing.definitions = incoming.definitions;
+            }
+            VocabularyMergeStrategy::Union => {
+                for definition in incoming.definitions {
+                    let known = existing
+                        .definitions
+                        .iter()
+                        .any(|d| d.pos == definition.pos && d.meanings == definition.meanings);
+                    if !known {
+                        existing.definitions.push(definition);
+                        changed = true;
+                    }
+                }
+                if existing.phonetic.is_none() && incoming.phonetic.is_some() {
+                    existing.phonetic = incoming.phonetic;
+                    changed = true;
+                }
+            }
+        }
+        if existing.source_lang.is_none() {
+            existing.source_lang = incoming.source_lang;
+        }
+        if existing.target_lang.is_none() {
+            existing.target_lang = incoming.target_lang;
+        }
+
+        if changed {
+            report.merged.push(existing.word.clone());
+        } else {
+            report.skipped.push(incoming.word);
+        }
+    }
+    report
+}
+
+// Merges another vocabulary.json, e.g. one exported on a second machine, into this one.
+#[tauri::command(rename_all = "camelCase")]
+pub fn merge_vocabulary(
+    handle: tauri::AppHandle,
+    other_path: String,
+    strategy: Option<VocabularyMergeStrategy>,
+) -> Result<VocabularyMergeReport, String> {
+    let data = fs::read_to_string(&other_path).map_err(|e| e.to_string())?;
+    let other: VocabularyData =
+        serde_json::from_str(&data).map_err(|e| format!("Not a valid vocabulary file: {}", e))?;
+
+    let report = handle.state::<app_state::AppState>().vocabulary.update(&handle, |vocab| {
+        merge_vocabulary_entries(&mut vocab.entries, other.entries, strategy.unwrap_or_default())
+    })?;
+    if !report.added.is_empty() || !report.merged.is_empty() {
+        handle.state::<VocabularyIndex>().invalidate();
+    }
+    Ok(report)
+}
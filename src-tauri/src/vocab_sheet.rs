@@ -0,0 +1,226 @@
+use ab_glyph::{Font, FontVec};
+use chrono::Local;
+use printpdf::{Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point, Rgb};
+use serde::Deserialize;
+use std::io::Cursor;
+
+use crate::quote_card::{load_font, text_width, wrap};
+use crate::vocab_export::{definition_line, filtered_entries};
+
+// A4, in millimetres.
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 18.0;
+const CELL_PADDING: f32 = 2.0;
+// Font sizes in points.
+const TITLE_SIZE: f32 = 16.0;
+const LABEL_SIZE: f32 = 9.0;
+const WORD_SIZE: f32 = 11.0;
+const MEANING_SIZE: f32 = 10.0;
+const LINE_SPACING: f32 = 1.35;
+// Rows get at least this much height when there is a column to write answers in.
+const MIN_TEST_ROW_HEIGHT: f32 = 11.0;
+const MM_PER_PT: f32 = 25.4 / 72.0;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct VocabularyPdfOptions {
+    // Heading on the first page; "Vocabulary" when not given.
+    title: Option<String>,
+    // Only words in this language, as in `export_vocabulary`.
+    language: Option<String>,
+    include_part_of_speech: bool,
+    // A blank "Test yourself" column, to write meanings in with the meaning column covered.
+    include_test_column: bool,
+    // For scripts the system serif font has no glyphs for.
+    font_path: Option<String>,
+}
+
+impl Default for VocabularyPdfOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            language: None,
+            include_part_of_speech: true,
+            include_test_column: false,
+            font_path: None,
+        }
+    }
+}
+
+struct Column {
+    label: &'static str,
+    x: f32,
+    width: f32,
+}
+
+// Where the sheet is being written; `y` is the top of the next row, up from the page bottom.
+struct Sheet {
+    doc: PdfDocumentReference,
+    font: IndirectFontRef,
+    metrics: FontVec,
+    layer: PdfLayerReference,
+    columns: Vec<Column>,
+    y: f32,
+    page: usize,
+}
+
+impl Sheet {
+    fn line_height(size: f32) -> f32 {
+        size * LINE_SPACING * MM_PER_PT
+    }
+
+    fn units_per_em(&self) -> f32 {
+        self.metrics.units_per_em().unwrap_or(1000.0)
+    }
+
+    fn ascent(&self, size: f32) -> f32 {
+        size * MM_PER_PT * self.metrics.ascent_unscaled() / self.units_per_em()
+    }
+
+    // Text measuring works in ab_glyph's scale, which is relative to the font's height
+    // rather than its em.
+    fn glyph_scale(&self, size: f32) -> f32 {
+        size * self.metrics.height_unscaled() / self.units_per_em()
+    }
+
+    fn wrap(&self, size: f32, text: &str, width: f32) -> Vec<String> {
+        wrap(&self.metrics, self.glyph_scale(size), text, width / MM_PER_PT)
+    }
+
+    fn width(&self, size: f32, text: &str) -> f32 {
+        text_width(&self.metrics, self.glyph_scale(size), text) * MM_PER_PT
+    }
+
+    fn text(&self, text: &str, size: f32, x: f32, baseline: f32, gray: f32) {
+        self.layer.set_fill_color(Color::Rgb(Rgb::new(gray, gray, gray, None)));
+        self.layer.use_text(text, size, Mm(x), Mm(baseline), &self.font);
+    }
+
+    // Lines set top down from `top`; returns the height they took.
+    fn lines(&self, lines: &[String], size: f32, x: f32, top: f32, gray: f32) -> f32 {
+        let mut baseline = top - self.ascent(size);
+        for line in lines {
+            self.text(line, size, x, baseline, gray);
+            baseline -= Self::line_height(size);
+        }
+        lines.len() as f32 * Self::line_height(size)
+    }
+
+    fn rule(&self, from: (f32, f32), to: (f32, f32), gray: f32) {
+        self.layer.set_outline_color(Color::Rgb(Rgb::new(gray, gray, gray, None)));
+        self.layer.set_outline_thickness(0.4);
+        self.layer.add_line(Line {
+            points: vec![(Point::new(Mm(from.0), Mm(from.1)), false), (Point::new(Mm(to.0), Mm(to.1)), false)],
+            is_closed: false,
+        });
+    }
+
+    // Column labels under a rule, repeated at the top of every page.
+    fn header_row(&mut self) {
+        for column in &self.columns {
+            self.text(column.label, LABEL_SIZE, column.x + CELL_PADDING, self.y - self.ascent(LABEL_SIZE), 0.4);
+        }
+        self.y -= Self::line_height(LABEL_SIZE) + CELL_PADDING;
+        self.rule((MARGIN, self.y), (PAGE_WIDTH - MARGIN, self.y), 0.2);
+    }
+
+    fn page_number(&self) {
+        let label = self.page.to_string();
+        let x = (PAGE_WIDTH - self.width(LABEL_SIZE, &label)) / 2.0;
+        self.text(&label, LABEL_SIZE, x, MARGIN / 2.0, 0.4);
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.page += 1;
+        self.y = PAGE_HEIGHT - MARGIN;
+        self.page_number();
+        self.header_row();
+    }
+}
+
+// A printable A4 worksheet of the vocabulary: each word with its pronunciation beside its
+// meanings, optionally with a blank column for testing yourself. Returns the PDF's bytes.
+#[tauri::command(rename_all = "camelCase")]
+pub fn export_vocabulary_pdf(
+    handle: tauri::AppHandle,
+    options: Option<VocabularyPdfOptions>,
+) -> Result<Vec<u8>, String> {
+    let options = options.unwrap_or_default();
+    let entries = filtered_entries(&handle, options.language.as_deref())?;
+    if entries.is_empty() {
+        return Err("There is no vocabulary to export.".to_string());
+    }
+    let (metrics, font_data) = load_font(options.font_path.as_deref())?;
+    let title = options.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).unwrap_or("Vocabulary");
+
+    let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+    let font = doc.add_external_font(Cursor::new(font_data)).map_err(|e| e.to_string())?;
+    let content_width = PAGE_WIDTH - MARGIN * 2.0;
+    let widths: Vec<(&'static str, f32)> = if options.include_test_column {
+        vec![("Word", 0.28), ("Meaning", 0.48), ("Test yourself", 0.24)]
+    } else {
+        vec![("Word", 0.32), ("Meaning", 0.68)]
+    };
+    let mut x = MARGIN;
+    let columns = widths
+        .into_iter()
+        .map(|(label, share)| {
+            let column = Column { label, x, width: content_width * share };
+            x += column.width;
+            column
+        })
+        .collect();
+    let mut sheet = Sheet {
+        layer: doc.get_page(page).get_layer(layer),
+        doc,
+        font,
+        metrics,
+        columns,
+        y: PAGE_HEIGHT - MARGIN,
+        page: 1,
+    };
+
+    sheet.lines(&[title.to_string()], TITLE_SIZE, MARGIN, sheet.y, 0.0);
+    sheet.y -= Sheet::line_height(TITLE_SIZE);
+    let subtitle = format!("{} words · {}", entries.len(), Local::now().format("%Y-%m-%d"));
+    sheet.lines(&[subtitle], LABEL_SIZE, MARGIN, sheet.y, 0.4);
+    sheet.y -= Sheet::line_height(LABEL_SIZE) * 2.0;
+    sheet.page_number();
+    sheet.header_row();
+
+    for entry in &entries {
+        let (word_column, meaning_column) = (&sheet.columns[0], &sheet.columns[1]);
+        let word_lines = sheet.wrap(WORD_SIZE, &entry.word, word_column.width - CELL_PADDING * 2.0);
+        let phonetic = entry.phonetic.as_deref().map(str::trim).filter(|p| !p.is_empty());
+        let phonetic_lines = phonetic.map_or_else(Vec::new, |p| sheet.wrap(LABEL_SIZE, p, word_column.width));
+        let meaning = definition_line(entry, options.include_part_of_speech);
+        let meaning_lines = sheet.wrap(MEANING_SIZE, &meaning, meaning_column.width - CELL_PADDING * 2.0);
+
+        let word_height = word_lines.len() as f32 * Sheet::line_height(WORD_SIZE)
+            + phonetic_lines.len() as f32 * Sheet::line_height(LABEL_SIZE);
+        let meaning_height = meaning_lines.len() as f32 * Sheet::line_height(MEANING_SIZE);
+        let mut row_height = word_height.max(meaning_height) + CELL_PADDING * 2.0;
+        if options.include_test_column {
+            row_height = row_height.max(MIN_TEST_ROW_HEIGHT);
+        }
+        if sheet.y - row_height < MARGIN {
+            sheet.new_page();
+        }
+
+        let (word_x, meaning_x) = (sheet.columns[0].x, sheet.columns[1].x);
+        let top = sheet.y - CELL_PADDING;
+        let used = sheet.lines(&word_lines, WORD_SIZE, word_x + CELL_PADDING, top, 0.0);
+        sheet.lines(&phonetic_lines, LABEL_SIZE, word_x + CELL_PADDING, top - used, 0.4);
+        sheet.lines(&meaning_lines, MEANING_SIZE, meaning_x + CELL_PADDING, top, 0.1);
+        sheet.y -= row_height;
+        if let Some(test_column) = sheet.columns.get(2) {
+            sheet.rule((test_column.x, sheet.y + row_height), (test_column.x, sheet.y), 0.7);
+        }
+        sheet.rule((MARGIN, sheet.y), (PAGE_WIDTH - MARGIN, sheet.y), 0.7);
+    }
+
+    sheet.doc.save_to_bytes().map_err(|e| e.to_string())
+}
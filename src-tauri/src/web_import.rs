@@ -0,0 +1,216 @@
+use chrono::Utc;
+use std::io::{Cursor, Write};
+use tauri::Emitter;
+use zip::write::SimpleFileOptions;
+
+use crate::language::detect_language;
+use crate::library_import::{import_book_bytes, ImportedBook};
+use crate::readability::{extract_article, Article};
+use crate::structure::BlockKind;
+use crate::update_recent_books;
+
+// Images beyond these limits are left out of the saved page.
+const MAX_IMAGES: usize = 40;
+const MAX_IMAGE_BYTES: usize = 8 * 1024 * 1024;
+
+struct PageImage {
+    href: String,
+    media_type: &'static str,
+    bytes: Vec<u8>,
+    position: usize,
+}
+
+fn image_type(bytes: &[u8]) -> Option<(&'static str, &'static str)> {
+    if bytes.starts_with(b"\x89PNG") {
+        Some(("image/png", "png"))
+    } else if bytes.starts_with(b"\xFF\xD8\xFF") {
+        Some(("image/jpeg", "jpg"))
+    } else if bytes.starts_with(b"GIF8") {
+        Some(("image/gif", "gif"))
+    } else if bytes.len() > 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some(("image/webp", "webp"))
+    } else {
+        None
+    }
+}
+
+// Downloads the article's images; ones that fail or are not PNG, JPEG, GIF or WebP are
+// dropped rather than failing the import.
+async fn fetch_images(client: &reqwest::Client, article: &Article) -> Vec<PageImage> {
+    let mut images = Vec::new();
+    for image in article.images.iter().take(MAX_IMAGES) {
+        let bytes = match client.get(&image.url).send().await {
+            Ok(response) if response.status().is_success() => response.bytes().await.ok(),
+            _ => None,
+        };
+        let Some(bytes) = bytes.filter(|b| b.len() <= MAX_IMAGE_BYTES) else {
+            continue;
+        };
+        if let Some((media_type, extension)) = image_type(&bytes) {
+            images.push(PageImage {
+                href: format!("images/{}.{}", images.len() + 1, extension),
+                media_type,
+                bytes: bytes.to_vec(),
+                position: image.position,
+            });
+        }
+    }
+    images
+}
+
+fn chapter_xhtml(article: &Article, title: &str, url: &str, language: &str, images: &[PageImage]) -> String {
+    let mut body = format!("<h1>{}</h1>\n", html_escape::encode_text(title));
+    if let Some(byline) = &article.byline {
+        body.push_str(&format!("<p class=\"byline\">{}</p>\n", html_escape::encode_text(byline)));
+    }
+    for (index, block) in article.blocks.iter().enumerate() {
+        for image in images.iter().filter(|image| image.position == index) {
+            body.push_str(&format!("<figure><img src=\"{}\" alt=\"\"/></figure>\n", image.href));
+        }
+        let text = html_escape::encode_text(&block.text);
+        body.push_str(&match block.kind {
+            BlockKind::Code | BlockKind::Table => format!("<pre>{}</pre>\n", text),
+            BlockKind::Caption => format!("<p class=\"caption\">{}</p>\n", text),
+            BlockKind::Body => format!("<p>{}</p>\n", text),
+        });
+    }
+    for image in images.iter().filter(|image| image.position >= article.blocks.len()) {
+        body.push_str(&format!("<figure><img src=\"{}\" alt=\"\"/></figure>\n", image.href));
+    }
+    body.push_str(&format!(
+        "<p class=\"source\">Saved from <a href=\"{}\">{}</a> on {}.</p>\n",
+        html_escape::encode_double_quoted_attribute(url),
+        html_escape::encode_text(url),
+        Utc::now().format("%Y-%m-%d")
+    ));
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+         <head><meta charset=\"utf-8\"/><title>{title}</title></head>\n<body>\n{body}</body>\n</html>\n",
+        lang = language,
+        title = html_escape::encode_text(title),
+        body = body
+    )
+}
+
+fn package_opf(article: &Article, title: &str, url: &str, language: &str, images: &[PageImage]) -> String {
+    let creator = article
+        .byline
+        .as_ref()
+        .map(|byline| format!("<dc:creator>{}</dc:creator>", html_escape::encode_text(byline)))
+        .unwrap_or_default();
+    let image_items: String = images
+        .iter()
+        .enumerate()
+        .map(|(index, image)| {
+            format!("<item id=\"image{}\" href=\"{}\" media-type=\"{}\"/>\n", index + 1, image.href, image.media_type)
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"uid\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <dc:identifier id=\"uid\">{url}</dc:identifier>\n<dc:title>{title}</dc:title>\n{creator}\n\
+         <dc:language>{language}</dc:language>\n<dc:source>{url}</dc:source>\n\
+         <meta property=\"dcterms:modified\">{modified}</meta>\n</metadata>\n\
+         <manifest>\n<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"article\" href=\"article.xhtml\" media-type=\"application/xhtml+xml\"/>\n{images}</manifest>\n\
+         <spine>\n<itemref idref=\"article\"/>\n</spine>\n</package>\n",
+        url = html_escape::encode_text(url),
+        title = html_escape::encode_text(title),
+        creator = creator,
+        language = language,
+        modified = Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        images = image_items
+    )
+}
+
+fn nav_xhtml(title: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\n\
+         <head><title>{title}</title></head>\n<body>\n<nav epub:type=\"toc\"><ol>\
+         <li><a href=\"article.xhtml\">{title}</a></li></ol></nav>\n</body>\n</html>\n",
+        title = html_escape::encode_text(title)
+    )
+}
+
+// An EPUB 3 package holding the article as one chapter with its images.
+fn build_epub(article: &Article, title: &str, url: &str, images: &[PageImage]) -> Result<Vec<u8>, String> {
+    let sample: String = article.blocks.iter().take(20).map(|b| b.text.as_str()).collect::<Vec<_>>().join(" ");
+    let language = detect_language(&sample).unwrap_or_else(|| "und".to_string());
+    let mut buffer = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buffer);
+        // The mimetype entry comes first and uncompressed, as readers sniff for it
+        let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let container = "<?xml version=\"1.0\"?>\n\
+             <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+             <rootfiles><rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\
+             </rootfiles>\n</container>\n";
+        let mut entries: Vec<(String, Vec<u8>, SimpleFileOptions)> = vec![
+            ("mimetype".into(), b"application/epub+zip".to_vec(), stored),
+            ("META-INF/container.xml".into(), container.as_bytes().to_vec(), deflated),
+            ("OEBPS/content.opf".into(), package_opf(article, title, url, &language, images).into_bytes(), deflated),
+            ("OEBPS/nav.xhtml".into(), nav_xhtml(title).into_bytes(), deflated),
+            (
+                "OEBPS/article.xhtml".into(),
+                chapter_xhtml(article, title, url, &language, images).into_bytes(),
+                deflated,
+            ),
+        ];
+        for image in images {
+            entries.push((format!("OEBPS/{}", image.href), image.bytes.clone(), stored));
+        }
+        for (name, bytes, options) in entries {
+            zip.start_file(name, options).map_err(|e| e.to_string())?;
+            zip.write_all(&bytes).map_err(|e| e.to_string())?;
+        }
+        zip.finish().map_err(|e| e.to_string())?;
+    }
+    Ok(buffer.into_inner())
+}
+
+fn file_name_for(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' { c } else { '_' })
+        .take(80)
+        .collect();
+    let name = name.trim();
+    format!("{}.epub", if name.is_empty() { "web-page" } else { name })
+}
+
+// Saves a web page as a book: its readable article (without navigation, comments and other
+// furniture) is packaged with its images as an EPUB in the library folder, where translation
+// and lookup work on it like on any other book.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_web_page(handle: tauri::AppHandle, url: String) -> Result<ImportedBook, String> {
+    let base = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(base.scheme(), "http" | "https") {
+        return Err(format!("Only http and https pages can be saved: {}", url));
+    }
+    let client = reqwest::Client::new();
+    let response = client.get(base.clone()).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Fetching the page failed: {}", response.status()));
+    }
+    let html = response.text().await.map_err(|e| e.to_string())?;
+    let article = extract_article(&html, Some(&base));
+    if article.blocks.is_empty() {
+        return Err(format!("No readable text found at {}", url));
+    }
+    let title = article.title.clone().unwrap_or_else(|| base.to_string());
+    let images = fetch_images(&client, &article).await;
+    let bytes = build_epub(&article, &title, base.as_str(), &images)?;
+
+    let book = import_book_bytes(&handle, &bytes, Some(file_name_for(&title)), None)?;
+    update_recent_books(&handle, |data| {
+        if let Some(entry) = data.books.iter_mut().find(|b| b.id == book.id) {
+            entry.metadata.source.get_or_insert_with(|| "web".to_string());
+        }
+    })?;
+    let _ = handle.emit("book-imported", book.clone());
+    Ok(book)
+}
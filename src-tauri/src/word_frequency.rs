@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::book_level::CefrLevel;
+use crate::vocab_index::lemma_candidates;
+
+// Built-in English frequency list: one word per line, most common first.
+const COMMON_WORDS_EN: &str = include_str!("data/common_words_en.txt");
+// Built-in English graded list: words under `# A1` to `# C1` headings.
+const GRADED_WORDS_EN: &str = include_str!("data/graded_words_en.txt");
+
+fn ranks() -> &'static HashMap<&'static str, u32> {
+    static RANKS: OnceLock<HashMap<&'static str, u32>> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        let mut ranks = HashMap::new();
+        let words = COMMON_WORDS_EN
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for word in words {
+            let rank = ranks.len() as u32 + 1;
+            ranks.entry(word).or_insert(rank);
+        }
+        ranks
+    })
+}
+
+fn levels() -> &'static HashMap<&'static str, CefrLevel> {
+    static LEVELS: OnceLock<HashMap<&'static str, CefrLevel>> = OnceLock::new();
+    LEVELS.get_or_init(|| {
+        let mut levels = HashMap::new();
+        let mut level = CefrLevel::A1;
+        for line in GRADED_WORDS_EN.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(comment) = line.strip_prefix('#') {
+                level = match comment.trim() {
+                    "A1" => CefrLevel::A1,
+                    "A2" => CefrLevel::A2,
+                    "B1" => CefrLevel::B1,
+                    "B2" => CefrLevel::B2,
+                    "C1" => CefrLevel::C1,
+                    _ => level,
+                };
+                continue;
+            }
+            levels.entry(line).or_insert(level);
+        }
+        levels
+    })
+}
+
+// The level `word` is usually learned at, matching inflected forms through their lemmas.
+// `None` means the word is outside the graded list, C2 or rarer.
+pub fn word_level(word: &str) -> Option<CefrLevel> {
+    lemma_candidates(&word.replace('\u{2019}', "'"))
+        .iter()
+        .filter_map(|form| levels().get(form.as_str()).copied())
+        .min()
+}
+
+// Frequency rank of `word` (1 = most common), matching inflected forms through their
+// lemmas. `None` means the word is outside the list, i.e. rarer than all of it.
+pub fn word_rank(word: &str) -> Option<u32> {
+    lemma_candidates(word)
+        .iter()
+        .filter_map(|form| ranks().get(form.as_str()).copied())
+        .min()
+}
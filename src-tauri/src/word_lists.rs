@@ -0,0 +1,231 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::app_config_dir;
+use crate::quarantine::parse_or_quarantine;
+
+// Words the reader never wants highlighted or quizzed (names, interjections, ...).
+pub const IGNORED_WORDS_FILE: &str = "ignored_words.json";
+// Words the reader already knows well enough not to study.
+pub const KNOWN_WORDS_FILE: &str = "known_words.json";
+// Imported, externally curated lists (CEFR levels, exam lists, a teacher's list) live here.
+const IMPORTED_LISTS_DIR: &str = "wordlists";
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct WordListData {
+    words: Vec<String>,
+}
+
+fn word_list_file_path(handle: &tauri::AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join(file_name))
+}
+
+fn load_word_list(handle: &tauri::AppHandle, file_name: &str) -> Result<WordListData, String> {
+    let path = word_list_file_path(handle, file_name)?;
+    if !path.exists() {
+        return Ok(WordListData::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_or_quarantine(handle, &path, &data)
+}
+
+fn save_word_list(handle: &tauri::AppHandle, file_name: &str, list: &WordListData) -> Result<(), String> {
+    let path = word_list_file_path(handle, file_name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string_pretty(list).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+}
+
+// Lowercased set for membership checks.
+pub fn load_word_set(handle: &tauri::AppHandle, file_name: &str) -> Result<HashSet<String>, String> {
+    Ok(load_word_list(handle, file_name)?
+        .words
+        .into_iter()
+        .map(|w| w.to_lowercase())
+        .collect())
+}
+
+fn add_to_word_list(handle: &tauri::AppHandle, file_name: &str, word: &str) -> Result<(), String> {
+    let word = word.trim().to_lowercase();
+    if word.is_empty() {
+        return Err("Word is empty.".to_string());
+    }
+    let mut list = load_word_list(handle, file_name)?;
+    if list.words.iter().any(|w| w.to_lowercase() == word) {
+        return Ok(());
+    }
+    list.words.push(word);
+    save_word_list(handle, file_name, &list)
+}
+
+fn remove_from_word_list(handle: &tauri::AppHandle, file_name: &str, word: &str) -> Result<(), String> {
+    let word = word.trim().to_lowercase();
+    let mut list = load_word_list(handle, file_name)?;
+    list.words.retain(|w| w.to_lowercase() != word);
+    save_word_list(handle, file_name, &list)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn mark_word_known(handle: tauri::AppHandle, word: String) -> Result<(), String> {
+    // A word can only live in one of the two lists.
+    remove_from_word_list(&handle, IGNORED_WORDS_FILE, &word)?;
+    add_to_word_list(&handle, KNOWN_WORDS_FILE, &word)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn unmark_word_known(handle: tauri::AppHandle, word: String) -> Result<(), String> {
+    remove_from_word_list(&handle, KNOWN_WORDS_FILE, &word)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_known_words(handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    Ok(load_word_list(&handle, KNOWN_WORDS_FILE)?.words)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn mark_word_ignored(handle: tauri::AppHandle, word: String) -> Result<(), String> {
+    remove_from_word_list(&handle, KNOWN_WORDS_FILE, &word)?;
+    add_to_word_list(&handle, IGNORED_WORDS_FILE, &word)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn unmark_word_ignored(handle: tauri::AppHandle, word: String) -> Result<(), String> {
+    remove_from_word_list(&handle, IGNORED_WORDS_FILE, &word)
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_ignored_words(handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    Ok(load_word_list(&handle, IGNORED_WORDS_FILE)?.words)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ImportedWordList {
+    name: String,
+    source_path: String,
+    imported_at: DateTime<Utc>,
+    words: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WordListInfo {
+    name: String,
+    source_path: String,
+    imported_at: DateTime<Utc>,
+    word_count: usize,
+}
+
+fn imported_lists_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app_config_dir(handle)?.join(IMPORTED_LISTS_DIR))
+}
+
+// List names are free text; files are named after a lowercase slug of the name.
+fn imported_list_path(handle: &tauri::AppHandle, list_name: &str) -> Result<PathBuf, String> {
+    let slug: String = list_name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        return Err(format!("Invalid word list name: {}", list_name));
+    }
+    Ok(imported_lists_dir(handle)?.join(format!("{}.json", slug)))
+}
+
+fn load_imported_lists(handle: &tauri::AppHandle) -> Result<Vec<ImportedWordList>, String> {
+    let dir = imported_lists_dir(handle)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut lists: Vec<ImportedWordList> = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            lists.push(serde_json::from_str(&data).map_err(|e| e.to_string())?);
+        }
+    }
+    lists.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(lists)
+}
+
+// Lowercased word sets of the imported lists, keyed by list name. With `names`, only
+// those lists are returned.
+pub fn load_imported_word_sets(
+    handle: &tauri::AppHandle,
+    names: Option<&[String]>,
+) -> Result<HashMap<String, HashSet<String>>, String> {
+    Ok(load_imported_lists(handle)?
+        .into_iter()
+        .filter(|list| names.is_none_or(|names| names.iter().any(|n| n.eq_ignore_ascii_case(&list.name))))
+        .map(|list| (list.name, list.words.into_iter().collect()))
+        .collect())
+}
+
+// One word per line; for CSV/TSV files the first column is used. Blank lines and lines
+// starting with `#` are skipped.
+fn parse_word_list(contents: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split([',', '\t', ';']).next())
+        .map(|word| word.trim().trim_matches('"').to_lowercase())
+        .filter(|word| word.chars().any(char::is_alphabetic))
+        .filter(|word| seen.insert(word.clone()))
+        .collect()
+}
+
+fn word_list_info(list: &ImportedWordList) -> WordListInfo {
+    WordListInfo {
+        name: list.name.clone(),
+        source_path: list.source_path.clone(),
+        imported_at: list.imported_at,
+        word_count: list.words.len(),
+    }
+}
+
+// Imports a word list file under `list_name`, replacing a list of the same name.
+#[tauri::command(rename_all = "camelCase")]
+pub fn import_wordlist(handle: tauri::AppHandle, path: String, list_name: String) -> Result<WordListInfo, String> {
+    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let words = parse_word_list(&contents);
+    if words.is_empty() {
+        return Err(format!("No words found in {}", path));
+    }
+    let list = ImportedWordList {
+        name: list_name.trim().to_string(),
+        source_path: path,
+        imported_at: Utc::now(),
+        words,
+    };
+
+    let list_path = imported_list_path(&handle, &list.name)?;
+    if let Some(parent) = list_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_string(&list).map_err(|e| e.to_string())?;
+    fs::write(list_path, data).map_err(|e| e.to_string())?;
+    Ok(word_list_info(&list))
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn get_wordlists(handle: tauri::AppHandle) -> Result<Vec<WordListInfo>, String> {
+    Ok(load_imported_lists(&handle)?.iter().map(word_list_info).collect())
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub fn remove_wordlist(handle: tauri::AppHandle, list_name: String) -> Result<(), String> {
+    let path = imported_list_path(&handle, &list_name)?;
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
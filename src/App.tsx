@@ -635,6 +635,7 @@ export default function App() {
             model: currentSettings.model,
             targetLanguage: currentSettings.targetLanguage,
             word: text,
+            bookId: docIdRef.current,
           })) as { phonetic?: string; definitions: WordDefinition[] };
 
           // Cache the result
@@ -653,7 +654,7 @@ export default function App() {
             model: currentSettings.model,
             temperature: currentSettings.temperature,
             targetLanguage: currentSettings.targetLanguage,
-            sentences: [{ sid: "text", text }],
+            sentences: [{ sid: `${docIdRef.current}:selection`, text }],
           })) as { sid: string; translation: string }[];
 
           const translation = results[0]?.translation || "Translation failed";
@@ -703,6 +704,7 @@ export default function App() {
           word: word.word,
           phonetic: word.phonetic || null,
           definitions: word.definitions,
+          bookId: docIdRef.current || null,
         });
         setWordTranslation((prev) => prev ? { ...prev, isLiked: true } : null);
       }
@@ -1321,6 +1323,7 @@ export default function App() {
               <EpubViewer
                 ref={epubViewerRef}
                 fileData={epubData}
+                docId={docId}
                 onMetadata={handleEpubMetadata}
                 onParagraphsExtracted={handleEpubParagraphs}
                 onCurrentPageChange={handleEpubPageChange}
@@ -1371,6 +1374,7 @@ export default function App() {
         isOpen={chatOpen}
         onClose={() => setChatOpen(false)}
         model={settings.model}
+        bookId={docId}
         getCurrentPageText={getCurrentPageText}
         getSurroundingPagesText={getSurroundingPagesText}
       />
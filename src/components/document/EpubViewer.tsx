@@ -14,6 +14,7 @@ export type EpubParagraph = {
 
 type EpubViewerProps = {
   fileData: Uint8Array;
+  docId: string;
   onMetadata: (metadata: { title: string; author?: string; coverImage?: string }) => void;
   onParagraphsExtracted: (paragraphs: EpubParagraph[]) => void;
   onCurrentPageChange: (page: number, total: number) => void;
@@ -28,6 +29,7 @@ export type EpubViewerHandle = {
 
 export const EpubViewer = forwardRef<EpubViewerHandle, EpubViewerProps>(function EpubViewer({
   fileData,
+  docId,
   onMetadata,
   onParagraphsExtracted,
   onCurrentPageChange,
@@ -51,6 +53,7 @@ export const EpubViewer = forwardRef<EpubViewerHandle, EpubViewerProps>(function
   const onCurrentPageChangeRef = useRef(onCurrentPageChange);
   const onLoadingProgressRef = useRef(onLoadingProgress);
   const onHrefChangeRef = useRef(onHrefChange);
+  const docIdRef = useRef(docId);
 
   const normalizeHref = useCallback((href: string) => href.split("#")[0], []);
 
@@ -67,6 +70,10 @@ export const EpubViewer = forwardRef<EpubViewerHandle, EpubViewerProps>(function
     [normalizeHref]
   );
 
+  useEffect(() => {
+    docIdRef.current = docId;
+  }, [docId]);
+
   useEffect(() => {
     onMetadataRef.current = onMetadata;
   }, [onMetadata]);
@@ -296,7 +303,7 @@ export const EpubViewer = forwardRef<EpubViewerHandle, EpubViewerProps>(function
           for (const text of textContent) {
             if (!seen.has(text)) {
               seen.add(text);
-              const pid = `epub:${pidCounter++}`;
+              const pid = `${docIdRef.current}:${pidCounter++}`;
               paragraphs.push({
                 pid,
                 source: text,
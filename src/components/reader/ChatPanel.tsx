@@ -7,6 +7,7 @@ type ChatPanelProps = {
   isOpen: boolean;
   onClose: () => void;
   model: string;
+  bookId: string;
   getCurrentPageText: () => string;
   getSurroundingPagesText: () => string;
 };
@@ -48,6 +49,7 @@ export function ChatPanel({
   isOpen,
   onClose,
   model,
+  bookId,
   getCurrentPageText,
   getSurroundingPagesText,
 }: ChatPanelProps) {
@@ -89,6 +91,7 @@ export function ChatPanel({
         model,
         context,
         question: userMessage,
+        bookId,
       });
 
       const assistantMsg: ChatMessage = {
@@ -110,7 +113,7 @@ export function ChatPanel({
     } finally {
       setIsLoading(false);
     }
-  }, [model]);
+  }, [model, bookId]);
 
   const handlePresetQuestion = useCallback((preset: typeof PRESET_QUESTIONS[0]) => {
     const context = preset.label.includes("nearby")
@@ -27,6 +27,8 @@ export type PageDoc = {
 export type TargetLanguage = {
   label: string;
   code: string;
+  script?: string;
+  region?: string;
 };
 
 export type TranslationMode = "window" | "chunk";